@@ -0,0 +1,71 @@
+#ifndef CIRCUIT_SOLVER_H
+#define CIRCUIT_SOLVER_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The circuit was solved, `out` holds the steps.
+ */
+#define CSS_OK 0
+
+/**
+ * The input was null, not UTF-8 or not a circuit.
+ */
+#define CSS_INVALID_INPUT 1
+
+#define CSS_UNKNOWN 2
+
+#define CSS_KNOWN 3
+
+#define CSS_MULTIPLE 4
+
+#define CSS_SYMBOLIC_LIMIT 5
+
+#define CSS_EMPTY_CIRCUIT 6
+
+#define CSS_NO_SOURCE 7
+
+#define CSS_STALE_SOLVER 8
+
+#define CSS_UNSUPPORTED 9
+
+#define CSS_STRICT_MODE 10
+
+/**
+ * The solver panicked, `out` holds the panic message if there was one.
+ */
+#define CSS_PANIC -1
+
+/**
+ * Solve a circuit given as the JSON of a `ContainerSetup` with the node matrix solver
+ *
+ * On success `*out` is set to the JSON list of steps, on failure to the same
+ * `{"errors": [...]}` object the wasm `solve` returns. Returns one of the `CSS_` codes.
+ *
+ * # Safety
+ *
+ * `input` must be null or a nul terminated string and `out` must be null or valid for
+ * writes. The string written to `*out` has to be released with `css_free`.
+ */
+int32_t css_solve_json(const char *input, char **out);
+
+/**
+ * Release a string returned by this library, null is ignored.
+ *
+ * # Safety
+ *
+ * `ptr` must be null or a string returned by this library that was not freed yet.
+ */
+void css_free(char *ptr);
+
+/**
+ * Version of the library, a static string that must not be freed.
+ */
+const char *css_version(void);
+
+#endif /* CIRCUIT_SOLVER_H */
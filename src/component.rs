@@ -1,8 +1,30 @@
 use crate::util::PrettyPrint;
-use serde::{Deserialize, Serialize};
+use crate::validation::StatusError;
+use crate::validation::StatusError::Known;
+use serde::de::{Error, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 /// Possible Component Types
-#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+///
+/// Components are (de)serialized using the stable names and codes below, these
+/// must never change as the frontend depends on them. The legacy variant names
+/// (`"Resistor"`, `"VoltageSrc"`, ...) are still accepted when deserializing.
+///
+/// | Component        | Name                | Code |
+/// |------------------|---------------------|------|
+/// | Ground           | `ground`            | 0    |
+/// | Resistor         | `resistor`          | 1    |
+/// | VoltageSrc       | `voltage_src`       | 2    |
+/// | CurrentSrc       | `current_src`       | 3    |
+/// | DependentVoltage | `dependent_voltage` | 4    |
+/// | DependentCurrent | `dependent_current` | 5    |
+/// | Switch           | `switch`            | 6    |
+/// | Inductor         | `inductor`          | 7    |
+/// | Capacitor        | `capacitor`         | 8    |
+/// | Compound         | `compound`          | 9    |
+#[derive(PartialEq, Debug, Clone)]
 pub enum Component {
     Compound(Simplification),
     Ground,
@@ -16,6 +38,68 @@ pub enum Component {
     Capacitor,
 }
 
+/// Unit of a component value
+///
+/// Displays as the symbol, the alternate form `{:#}` displays the SI name.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum Unit {
+    Volt,
+    Ampere,
+    Ohm,
+    Siemens,
+    Farad,
+    Henry,
+    /// Power, used for results rather than component values
+    Watt,
+    /// Voltage gain of a voltage controlled voltage source
+    VoltPerVolt,
+    /// Transconductance of a voltage controlled current source
+    AmperePerVolt,
+    /// The value has no unit, e.g. the state of a switch
+    None,
+}
+
+impl Unit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Volt => "V",
+            Unit::Ampere => "A",
+            Unit::Ohm => "Ω",
+            Unit::Siemens => "S",
+            Unit::Farad => "F",
+            Unit::Henry => "H",
+            Unit::Watt => "W",
+            Unit::VoltPerVolt => "V/V",
+            Unit::AmperePerVolt => "A/V",
+            Unit::None => "",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Unit::Volt => "volt",
+            Unit::Ampere => "ampere",
+            Unit::Ohm => "ohm",
+            Unit::Siemens => "siemens",
+            Unit::Farad => "farad",
+            Unit::Henry => "henry",
+            Unit::Watt => "watt",
+            Unit::VoltPerVolt => "volt per volt",
+            Unit::AmperePerVolt => "ampere per volt",
+            Unit::None => "",
+        }
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match f.alternate() {
+            true => write!(f, "{}", self.name()),
+            false => write!(f, "{}", self.symbol()),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Simplification {
     None,
@@ -25,14 +109,81 @@ pub enum Simplification {
     Thevinin,
 }
 
+/// Generates `ALL`, `as_str`, `code` and `legacy_str` from one row per variant
+///
+/// The matches are exhaustive, so a variant without a row does not compile and every row
+/// is part of `ALL`. The codes have to count up from 0 in row order.
+macro_rules! component_table {
+    ($($pattern:pat => $component:expr, $name:literal, $legacy:literal, $code:literal;)*) => {
+        impl Component {
+            /// Every component class ordered by its numeric code
+            pub const ALL: [Component; [$($code),*].len()] = [$($component),*];
+
+            /// Stable name used for (de)serialization
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($pattern => $name,)*
+                }
+            }
+
+            /// Stable numeric code, a compact alternative to the name
+            pub const fn code(&self) -> u8 {
+                match self {
+                    $($pattern => $code,)*
+                }
+            }
+
+            /// Name of the enum variant, accepted as an alias when deserializing
+            pub fn legacy_str(&self) -> &'static str {
+                match self {
+                    $($pattern => $legacy,)*
+                }
+            }
+        }
+
+        const _: () = {
+            let mut i: usize = 0;
+            while i < Component::ALL.len() {
+                assert!(Component::ALL[i].code() as usize == i, "Component codes out of order");
+                i += 1;
+            }
+        };
+    };
+}
+
+component_table! {
+    Component::Ground => Component::Ground, "ground", "Ground", 0;
+    Component::Resistor => Component::Resistor, "resistor", "Resistor", 1;
+    Component::VoltageSrc => Component::VoltageSrc, "voltage_src", "VoltageSrc", 2;
+    Component::CurrentSrc => Component::CurrentSrc, "current_src", "CurrentSrc", 3;
+    Component::DependentVoltage => Component::DependentVoltage, "dependent_voltage", "DependentVoltage", 4;
+    Component::DependentCurrent => Component::DependentCurrent, "dependent_current", "DependentCurrent", 5;
+    Component::Switch => Component::Switch, "switch", "Switch", 6;
+    Component::Inductor => Component::Inductor, "inductor", "Inductor", 7;
+    Component::Capacitor => Component::Capacitor, "capacitor", "Capacitor", 8;
+    Component::Compound(_) => Component::Compound(Simplification::None), "compound", "Compound", 9;
+}
+
 impl Component {
-    pub(crate) fn unit_string(&self) -> String {
+    pub fn from_code(code: u64) -> Option<Component> {
+        Component::ALL.into_iter().find(|x| x.code() as u64 == code)
+    }
+
+    /// Unit of the value of an element of this class
+    ///
+    /// Dependent sources report the unit of their gain.
+    pub fn unit(&self) -> Unit {
         match self {
-            Component::Ground => "V".to_string(),
-            Component::Resistor => "Ω".to_string(),
-            Component::VoltageSrc => "V".to_string(),
-            Component::CurrentSrc => "A".to_string(),
-            _ => "Unknown".to_string(),
+            Component::Ground => Unit::Volt,
+            Component::Resistor => Unit::Ohm,
+            Component::VoltageSrc => Unit::Volt,
+            Component::CurrentSrc => Unit::Ampere,
+            Component::DependentVoltage => Unit::VoltPerVolt,
+            Component::DependentCurrent => Unit::AmperePerVolt,
+            Component::Switch => Unit::None,
+            Component::Inductor => Unit::Henry,
+            Component::Capacitor => Unit::Farad,
+            Component::Compound(_) => Unit::Ohm,
         }
     }
 
@@ -43,6 +194,86 @@ impl Component {
             _ => false,
         }
     }
+
+    /// Voltage controlled source, its value is the gain applied to the control voltage.
+    pub(crate) fn is_dependent(&self) -> bool {
+        matches!(
+            self,
+            Component::DependentVoltage | Component::DependentCurrent
+        )
+    }
+}
+
+impl FromStr for Component {
+    type Err = StatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Component::ALL
+            .into_iter()
+            .find(|x| x.as_str() == s || x.legacy_str() == s)
+            .ok_or_else(|| Known(format!("Unknown component class {}", s)))
+    }
+}
+
+impl Serialize for Component {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Only the plain compound has a name, a simplification would be lost on the way back
+        if let Component::Compound(simplification) = self {
+            if *simplification != Simplification::None {
+                return Err(serde::ser::Error::custom(format!(
+                    "Cannot serialize a {:?} compound",
+                    simplification
+                )));
+            }
+        }
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ComponentVisitor)
+    }
+}
+
+/// Accepts either a component name (stable or legacy) or a numeric code
+struct ComponentVisitor;
+
+impl<'de> Visitor<'de> for ComponentVisitor {
+    type Value = Component;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "a component class name or numeric code")
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+        if v < 0 {
+            return Err(E::custom(format!("Unknown component code {}", v)));
+        }
+        self.visit_u64(v as u64)
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        Component::from_code(v).ok_or_else(|| E::custom(format!("Unknown component code {}", v)))
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+        // JavaScript numbers always arrive as floats
+        if v.fract() != 0.0 || v < 0.0 {
+            return Err(E::custom(format!("Unknown component code {}", v)));
+        }
+        self.visit_u64(v as u64)
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        Component::from_str(v).map_err(|_| E::custom(format!("Unknown component class {}", v)))
+    }
 }
 
 impl PrettyPrint for Component {
@@ -52,6 +283,10 @@ impl PrettyPrint for Component {
             Component::Resistor => "Resistor".to_string(),
             Component::VoltageSrc => "Voltage".to_string(),
             Component::CurrentSrc => "Current".to_string(),
+            Component::Capacitor => "Capacitor".to_string(),
+            Component::Inductor => "Inductor".to_string(),
+            Component::DependentVoltage => "Dependent Voltage".to_string(),
+            Component::DependentCurrent => "Dependent Current".to_string(),
             _ => "Unknown".to_string(),
         }
     }
@@ -62,6 +297,10 @@ impl PrettyPrint for Component {
             Component::Resistor => "R".to_string(),
             Component::VoltageSrc => "SRC(V)".to_string(),
             Component::CurrentSrc => "SRC(C)".to_string(),
+            Component::Capacitor => "C".to_string(),
+            Component::Inductor => "L".to_string(),
+            Component::DependentVoltage => "VCVS".to_string(),
+            Component::DependentCurrent => "VCCS".to_string(),
             _ => "Unknown".to_string(),
         }
     }
@@ -77,6 +316,52 @@ mod tests {
         assert_eq!(Component::Resistor.pretty_string(), "Resistor".to_string());
         assert_eq!(Component::VoltageSrc.pretty_string(), "Voltage".to_string());
         assert_eq!(Component::CurrentSrc.pretty_string(), "Current".to_string());
+        assert_eq!(
+            Component::Capacitor.pretty_string(),
+            "Capacitor".to_string()
+        );
+        assert_eq!(Component::Capacitor.basic_string(), "C".to_string());
+        assert_eq!(Component::Inductor.pretty_string(), "Inductor".to_string());
+        assert_eq!(Component::Inductor.basic_string(), "L".to_string());
+        assert_eq!(
+            Component::DependentVoltage.pretty_string(),
+            "Dependent Voltage".to_string()
+        );
+        assert_eq!(
+            Component::DependentVoltage.basic_string(),
+            "VCVS".to_string()
+        );
+        assert_eq!(
+            Component::DependentCurrent.basic_string(),
+            "VCCS".to_string()
+        );
+    }
+
+    #[test]
+    fn test_unit() {
+        let units = [
+            (Unit::Volt, "V", "volt"),
+            (Unit::Ohm, "Ω", "ohm"),
+            (Unit::Volt, "V", "volt"),
+            (Unit::Ampere, "A", "ampere"),
+            (Unit::VoltPerVolt, "V/V", "volt per volt"),
+            (Unit::AmperePerVolt, "A/V", "ampere per volt"),
+            (Unit::None, "", ""),
+            (Unit::Henry, "H", "henry"),
+            (Unit::Farad, "F", "farad"),
+            (Unit::Ohm, "Ω", "ohm"),
+        ];
+        assert_eq!(units.len(), Component::ALL.len());
+        for (component, (unit, symbol, name)) in Component::ALL.iter().zip(units) {
+            assert_eq!(component.unit(), unit);
+            assert_eq!(unit.to_string(), symbol);
+            assert_eq!(format!("{:#}", unit), name);
+        }
+        assert_eq!(
+            Component::Compound(Simplification::Series).unit(),
+            Unit::Ohm
+        );
+        assert_eq!(format!("{:#}", Unit::Siemens), "siemens");
     }
 
     #[test]
@@ -92,4 +377,66 @@ mod tests {
             "CurrentSrc".to_string()
         );
     }
+
+    #[test]
+    fn test_stable_names() {
+        let names = [
+            ("ground", "Ground", 0),
+            ("resistor", "Resistor", 1),
+            ("voltage_src", "VoltageSrc", 2),
+            ("current_src", "CurrentSrc", 3),
+            ("dependent_voltage", "DependentVoltage", 4),
+            ("dependent_current", "DependentCurrent", 5),
+            ("switch", "Switch", 6),
+            ("inductor", "Inductor", 7),
+            ("capacitor", "Capacitor", 8),
+            ("compound", "Compound", 9),
+        ];
+        assert_eq!(names.len(), Component::ALL.len());
+
+        for (i, component) in Component::ALL.iter().enumerate() {
+            let (name, legacy, code) = names[i];
+            assert_eq!(component.as_str(), name);
+            assert_eq!(component.code(), code);
+            assert_eq!(&Component::from_str(name).unwrap(), component);
+            assert_eq!(&Component::from_str(legacy).unwrap(), component);
+            assert_eq!(
+                format!("\"{}\"", name),
+                serde_json::to_string(component).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        for component in Component::ALL {
+            let json = [
+                format!("\"{}\"", component.as_str()),
+                format!("\"{}\"", component.legacy_str()),
+                format!("{}", component.code()),
+                format!("{}.0", component.code()),
+            ];
+            for x in json {
+                assert_eq!(serde_json::from_str::<Component>(&x).unwrap(), component);
+            }
+        }
+
+        assert!(serde_json::from_str::<Component>("\"diode\"").is_err());
+        assert!(serde_json::from_str::<Component>("10").is_err());
+        assert!(serde_json::from_str::<Component>("-1").is_err());
+        assert!(Component::from_str("RESISTOR").is_err());
+    }
+
+    #[test]
+    fn test_serialize_compound() {
+        assert_eq!(
+            serde_json::to_string(&Component::Compound(Simplification::None)).unwrap(),
+            "\"compound\""
+        );
+        let error = serde_json::to_string(&Component::Compound(Simplification::Series));
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "Cannot serialize a Series compound"
+        );
+    }
 }
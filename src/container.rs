@@ -1,21 +1,29 @@
-use crate::component::Component::{Ground, VoltageSrc};
-use crate::component::Simplification;
-use crate::elements::Element;
+use crate::component::Component::{
+    Capacitor, CurrentSrc, DependentCurrent, DependentVoltage, Ground, Inductor, Resistor,
+    VoltageSrc,
+};
+use crate::component::{Component, Simplification};
+use crate::elements::{Control, Element};
+use crate::solvers::node_matrix_solver::{voltage_source_sign, NodeMatrixSolver};
+use crate::solvers::options::{SolveOptions, ZeroOhmStrategy};
+use crate::solvers::solution::Solution;
+use crate::solvers::solver::{NodeSolver, Solver};
 use crate::tools::{Tool, ToolType};
+use crate::topology::Topology;
+use crate::transformation::{Transformation, TransformationKind};
 use crate::util::PrettyPrint;
 use crate::validation::StatusError::Known;
 use crate::validation::{
-    check_duplicates, get_all_internal_status_errors, Status, StatusError, Validation,
-    ValidationResult,
+    check_duplicates, get_all_internal_status_errors, Severity, Status, StatusError, Validation,
+    ValidationResult, Warning, WarningKind,
 };
-use petgraph::graph::UnGraph;
-use petgraph::prelude::NodeIndex;
-use rustworkx_core::connectivity;
+use nalgebra::DMatrix;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
 
 use crate::tools::ToolType::SuperNode;
-use serde::Serialize;
-use std::fmt::{Debug, Formatter};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display, Formatter};
 use std::rc::{Rc, Weak};
 
 /// Representation of a Schematic Container
@@ -27,6 +35,22 @@ pub struct Container {
     tools: Vec<Rc<RefCell<Tool>>>,
     simplifications: Vec<Rc<Simplification>>,
     ground: usize,
+    warnings: Vec<Warning>,
+    ports: Vec<Port>,
+    zero_ohm_strategy: Option<ZeroOhmStrategy>,
+    transformations: Vec<Transformation>,
+    #[serde(skip)]
+    junctions: BTreeMap<usize, Vec<(usize, bool)>>, // Node label to (element, positive side)
+    #[serde(skip)]
+    generation: u64, // Bumped on every change to the circuit, see `generation()`
+}
+
+/// Named pair of nodes used as a port in multi-port analysis
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Port {
+    pub name: String,
+    pub positive: usize,
+    pub negative: usize,
 }
 
 /// Container is a collection of Elements and Tools we are using to solve the circuit
@@ -41,6 +65,12 @@ impl Container {
             tools: Vec::new(),
             simplifications: vec![],
             ground: 0,
+            warnings: vec![],
+            ports: vec![],
+            zero_ohm_strategy: None,
+            transformations: vec![],
+            junctions: BTreeMap::new(),
+            generation: 0,
         }
     }
 
@@ -66,6 +96,7 @@ impl Container {
         }
         element.id = id;
         self.elements.push(Rc::new(RefCell::new(element)));
+        self.generation += 1;
         id
     }
 
@@ -75,17 +106,160 @@ impl Container {
         }
         let id = element.id.clone();
         self.elements.push(Rc::new(RefCell::new(element)));
+        self.generation += 1;
         id
     }
 
-    fn add_tool(&mut self, mut tool: Tool) {
+    /// Add an element between two nodes and return its id
+    ///
+    /// Nodes are labels chosen by the caller, label 0 is ground. The element is connected
+    /// to every element previously added at the same label, with `node_a` on its positive
+    /// side. A ground element is added first if the container has none. Labels are only
+    /// known for elements added through this method and are unrelated to the ids the
+    /// nodes get in `create_nodes`.
+    pub fn add_element_between_nodes(
+        &mut self,
+        class: Component,
+        value: f64,
+        node_a: usize,
+        node_b: usize,
+    ) -> usize {
+        if !self.elements.iter().any(|x| x.borrow().class == Ground) {
+            let mut ground: Element = Element::new(Ground, 0.0, vec![], vec![]);
+            ground.id = self.elements.len();
+            self.ground = self.add_element_core(ground);
+        }
+
+        let id: usize = self.elements.len();
+        // Elements at ground only list the ground element, ground lists all of them
+        let connections = |label: usize| -> Vec<usize> {
+            match label {
+                0 => vec![self.ground],
+                _ => self
+                    .junctions
+                    .get(&label)
+                    .map(|x| x.iter().map(|(element, _)| *element).collect())
+                    .unwrap_or_default(),
+            }
+        };
+        let mut element: Element =
+            Element::new(class, value, connections(node_a), connections(node_b));
+        element.id = id;
+
+        for (label, positive) in [(node_a, true), (node_b, false)] {
+            if label == 0 {
+                self.get_element_by_id(self.ground)
+                    .borrow_mut()
+                    .positive
+                    .push(id);
+                continue;
+            }
+            let attached: Vec<(usize, bool)> = self.junctions.entry(label).or_default().clone();
+            for (other, other_positive) in attached {
+                if other == id {
+                    continue;
+                }
+                let mut other = self.get_element_by_id(other).borrow_mut();
+                match other_positive {
+                    true => other.positive.push(id),
+                    false => other.negative.push(id),
+                }
+            }
+            self.junctions
+                .entry(label)
+                .or_default()
+                .push((id, positive));
+        }
+
+        self.add_element_core(element)
+    }
+
+    /// Copy every element of `other` with `id_offset` added to its id and connections
+    ///
+    /// The copy brings its own ground and is not connected to the existing elements.
+    /// Ids index the element list, so the offset has to be the current element count.
+    /// Returns the ids of the copied elements.
+    pub fn copy_elements_from(
+        &mut self,
+        other: &Container,
+        id_offset: usize,
+    ) -> Result<Vec<usize>, StatusError> {
+        let count: usize = self.elements.len();
+        if id_offset < count {
+            return Err(Known(format!(
+                "Offset {} overlaps the {} elements already present",
+                id_offset, count
+            )));
+        }
+        if id_offset > count {
+            return Err(Known(format!(
+                "Offset {} leaves ids {} to {} without an element",
+                id_offset,
+                count,
+                id_offset - 1
+            )));
+        }
+
+        let mut ids: Vec<usize> = Vec::new();
+        for element in other.get_elements() {
+            let mut element: Element = element.borrow().clone();
+            element.id += id_offset;
+            element.positive.iter_mut().for_each(|x| *x += id_offset);
+            element.negative.iter_mut().for_each(|x| *x += id_offset);
+            ids.push(self.add_element_core(element));
+        }
+        Ok(ids)
+    }
+
+    /// Take an element out of the circuit
+    ///
+    /// The element is detached and every connection to it is dropped, its id stays taken
+    /// so the ids of the other elements do not move. Nodes that were already created are
+    /// created again. Ground can not be removed.
+    pub fn remove_element(&mut self, id: usize) -> Result<(), StatusError> {
+        let element = match self.elements.get(id) {
+            Some(element) => element.borrow(),
+            None => return Err(Known(format!("Element {} does not exist", id))),
+        };
+        if element.class == Ground {
+            return Err(Known("Ground can not be removed".to_string()));
+        }
+        if element.is_detached() {
+            return Err(Known(format!(
+                "{} was already removed",
+                element.pretty_string()
+            )));
+        }
+        drop(element);
+
+        for element in &self.elements {
+            let mut element = element.borrow_mut();
+            element.positive.retain(|x| *x != id);
+            element.negative.retain(|x| *x != id);
+        }
+        {
+            let mut element = self.get_element_by_id(id).borrow_mut();
+            element.positive.clear();
+            element.negative.clear();
+        }
+        for attached in self.junctions.values_mut() {
+            attached.retain(|(element, _)| *element != id);
+        }
+        self.generation += 1;
+        self.rebuild_nodes()
+    }
+
+    fn add_tool(&mut self, mut tool: Tool) -> usize {
         if !self.tools.is_empty() {
             let new_id: usize = self.tools.get(self.tools.len() - 1).unwrap().borrow().id + 1;
             tool.id = new_id;
         } else {
             tool.id = 1;
         }
+        let id: usize = tool.id;
         self.tools.push(Rc::new(RefCell::new(tool)));
+        self.generation += 1;
+        id
     }
 
     pub(crate) fn get_element_by_id(&self, id: usize) -> &Rc<RefCell<Element>> {
@@ -129,6 +303,9 @@ impl Container {
         let mut new_nodes: Vec<Tool> = Vec::new();
 
         for element in &self.elements {
+            if element.borrow().is_detached() {
+                continue;
+            }
             // Need a list of all elements connected to the positive side node.
             let mut node_elements: Vec<Weak<RefCell<Element>>> = element
                 .borrow()
@@ -157,13 +334,262 @@ impl Container {
             new_nodes.push(Tool::create_node(node_elements));
         }
 
-        for node in new_nodes {
-            self.add_tool(node);
+        for mut node in new_nodes {
+            // Repeated membership would count an element twice, e.g. on the G matrix diagonal
+            let duplicates: Vec<usize> = node.dedup_members();
+            let id: usize = self.add_tool(node);
+            for element in duplicates {
+                let message: String = format!(
+                    "Element {} appears more than once in Node {}, the duplicate was removed",
+                    element, id
+                );
+                // Rebuilding the nodes finds the same duplicate again
+                let warning: Warning =
+                    Warning::new(WarningKind::DuplicateMembership, message.clone());
+                if !self.warnings.contains(&warning) {
+                    self.warnings.push(warning);
+                }
+                self.add_transformation(Transformation::new(
+                    TransformationKind::DuplicateMembershipRemoved,
+                    vec![element],
+                    message,
+                ));
+            }
         }
 
         Ok(self)
     }
 
+    /// Counter that changes whenever elements or tools are added or values are changed
+    ///
+    /// Solvers record it when created and refuse to solve once it moved.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Rewrite the zero resistance branches of the circuit with the given strategy
+    ///
+    /// Solvers call this before they use the nodes. Existing nodes and supernodes are
+    /// rebuilt when a branch was rewritten. A rewritten branch is no longer a wire, so
+    /// only the first strategy applied to a container has an effect.
+    pub fn apply_zero_ohm_strategy(
+        &mut self,
+        strategy: ZeroOhmStrategy,
+    ) -> Result<(), StatusError> {
+        let wires: Vec<usize> = self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().is_wire() && !x.borrow().is_detached())
+            .map(|x| x.borrow().id)
+            .collect();
+        strategy.validate()?;
+        if wires.is_empty() {
+            return Ok(());
+        }
+
+        for id in wires {
+            let name: String = self.get_element_by_id(id).pretty_string();
+            let (kind, description) = match strategy {
+                ZeroOhmStrategy::Merge => {
+                    self.merge_wire(id);
+                    (
+                        TransformationKind::ZeroOhmMerged,
+                        format!("Wire {} removed, the nodes at its ends were joined", name),
+                    )
+                }
+                ZeroOhmStrategy::SenseBranch => {
+                    self.get_element_by_id(id).borrow_mut().class = VoltageSrc;
+                    (
+                        TransformationKind::ZeroOhmSenseBranch,
+                        format!("Wire {} replaced by a 0 V source", name),
+                    )
+                }
+                ZeroOhmStrategy::Epsilon(resistance) => {
+                    self.get_element_by_id(id).borrow_mut().value = resistance;
+                    let name: String = self.get_element_by_id(id).pretty_string();
+                    self.warnings.push(Warning::new(
+                        WarningKind::IllConditioned,
+                        format!(
+                            "Wire modelled as {}, the large conductance may leave the system ill conditioned",
+                            name
+                        ),
+                    ));
+                    (
+                        TransformationKind::ZeroOhmEpsilon,
+                        format!("Wire replaced by {}", name),
+                    )
+                }
+            };
+            self.add_transformation(Transformation::new(kind, vec![id], description));
+        }
+        self.zero_ohm_strategy = Some(strategy);
+        self.generation += 1;
+        self.rebuild_nodes()
+    }
+
+    /// Apply every automatic transformation the options allow
+    ///
+    /// Self loops are pruned before the zero resistance branches are rewritten, so a
+    /// shorted wire is dropped instead of becoming a source. Nothing is changed when
+    /// `allow_auto_transform` is off, `required_transformations` then lists what is missing.
+    pub fn apply_auto_transforms(&mut self, options: &SolveOptions) -> Result<(), StatusError> {
+        if !options.allow_auto_transform {
+            return Ok(());
+        }
+        self.prune_self_loops()?;
+        self.apply_zero_ohm_strategy(options.zero_ohm_strategy)
+    }
+
+    /// Take out elements with both terminals on the same node
+    ///
+    /// Such an element carries no current into the rest of the circuit but would still
+    /// be counted on the diagonal of its node. Voltage sources are kept, a shorted source
+    /// is an error and not something to hide.
+    pub fn prune_self_loops(&mut self) -> Result<(), StatusError> {
+        let loops: Vec<usize> = self.self_loops();
+        if loops.is_empty() {
+            return Ok(());
+        }
+
+        for id in loops {
+            for element in &self.elements {
+                let mut element = element.borrow_mut();
+                element.positive.retain(|x| *x != id);
+                element.negative.retain(|x| *x != id);
+            }
+            let name: String = self.get_element_by_id(id).pretty_string();
+            {
+                let mut element = self.get_element_by_id(id).borrow_mut();
+                element.positive.clear();
+                element.negative.clear();
+            }
+            self.add_transformation(Transformation::new(
+                TransformationKind::SelfLoopPruned,
+                vec![id],
+                format!("{} connects a node to itself and was removed", name),
+            ));
+        }
+        self.generation += 1;
+        self.rebuild_nodes()
+    }
+
+    /// Ids of the elements whose terminals connect to exactly the same elements.
+    fn self_loops(&self) -> Vec<usize> {
+        let sorted = |x: &Vec<usize>| -> Vec<usize> {
+            let mut x: Vec<usize> = x.clone();
+            x.sort();
+            x.dedup();
+            x
+        };
+        self.elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| !matches!(x.class, Ground | VoltageSrc | DependentVoltage))
+            .filter(|x| !x.is_detached() && sorted(&x.positive) == sorted(&x.negative))
+            .map(|x| x.id)
+            .collect()
+    }
+
+    /// Describe every transformation the circuit needs or already went through
+    ///
+    /// Used to reject the circuit when `allow_auto_transform` is off.
+    pub fn required_transformations(&self) -> Vec<String> {
+        let mut required: Vec<String> = self
+            .transformations
+            .iter()
+            .map(|x| x.description.clone())
+            .collect();
+        for id in self.self_loops() {
+            required.push(format!(
+                "{} connects a node to itself",
+                self.get_element_by_id(id).pretty_string()
+            ));
+        }
+        for element in self.elements.iter().map(|x| x.borrow()) {
+            if element.is_wire() && !element.is_detached() {
+                required.push(format!(
+                    "{} is a zero resistance branch",
+                    element.pretty_string()
+                ));
+            }
+        }
+        required
+    }
+
+    /// Recreate existing nodes and supernodes after the elements were rewired.
+    fn rebuild_nodes(&mut self) -> Result<(), StatusError> {
+        if !self.tools.is_empty() {
+            let super_nodes: bool = !self.get_tools(SuperNode).is_empty();
+            let meshes: bool = !self.get_tools(ToolType::Mesh).is_empty();
+            self.tools.clear();
+            self.create_nodes()?;
+            if super_nodes {
+                self.create_super_nodes().map_err(Known)?;
+            }
+            if meshes {
+                self.create_meshes().create_super_meshes();
+            }
+        }
+        Ok(())
+    }
+
+    /// Strategy the zero resistance branches were rewritten with, `None` if there were none.
+    pub fn zero_ohm_strategy(&self) -> Option<ZeroOhmStrategy> {
+        self.zero_ohm_strategy
+    }
+
+    /// Connect the elements on either side of a wire directly and detach the wire.
+    fn merge_wire(&mut self, wire: usize) {
+        let (positive, negative) = {
+            let wire = self.get_element_by_id(wire).borrow();
+            (wire.positive.clone(), wire.negative.clone())
+        };
+
+        for (side, other) in [(&positive, &negative), (&negative, &positive)] {
+            for id in side {
+                let mut element = self.get_element_by_id(*id).borrow_mut();
+                let element: &mut Element = &mut element;
+                let own_id: usize = element.id;
+                for connections in [&mut element.positive, &mut element.negative] {
+                    if !connections.contains(&wire) {
+                        continue;
+                    }
+                    connections.retain(|x| *x != wire);
+                    for x in other {
+                        if *x != own_id && !connections.contains(x) {
+                            connections.push(*x);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut wire = self.get_element_by_id(wire).borrow_mut();
+        wire.positive.clear();
+        wire.negative.clear();
+    }
+
+    pub(crate) fn add_warning(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// Warnings recorded while building the circuit
+    pub fn warnings(&self) -> &Vec<Warning> {
+        &self.warnings
+    }
+
+    /// Record a transformation, nodes rebuilt later do not record the same one twice.
+    pub(crate) fn add_transformation(&mut self, transformation: Transformation) {
+        if !self.transformations.contains(&transformation) {
+            self.transformations.push(transformation);
+        }
+    }
+
+    /// Automatic transformations applied to the circuit, in the order they ran
+    pub fn transformations(&self) -> &Vec<Transformation> {
+        &self.transformations
+    }
+
     pub(crate) fn get_calculation_nodes(&self) -> Vec<Rc<RefCell<Tool>>> {
         let nodes: Vec<Rc<RefCell<Tool>>> =
             self.nodes().iter().map(|x| x.upgrade().unwrap()).collect();
@@ -174,26 +600,7 @@ impl Container {
             .collect();
         let mut cleaned: Vec<Rc<RefCell<Tool>>> = nodes
             .into_iter()
-            .filter(|node| {
-                for super_node in &super_nodes {
-                    let super_node_member_ids: Vec<usize> = (super_node
-                        .borrow()
-                        .clone()
-                        .into_iter()
-                        .map(|x| x.id())
-                        .collect::<Vec<usize>>())
-                    .to_vec();
-                    if node
-                        .borrow()
-                        .clone()
-                        .into_iter()
-                        .all(|y| super_node_member_ids.contains(&y.id()))
-                    {
-                        return false;
-                    }
-                }
-                true
-            })
+            .filter(|node| self.get_supernode_for(node.borrow().id).is_none())
             .collect();
         cleaned.extend(super_nodes);
         cleaned
@@ -237,19 +644,29 @@ impl Container {
         Ok(self)
     }
 
+    /// Create a mesh for every independent loop of the circuit
+    ///
+    /// The meshes are the loops of `fundamental_loops`, their members are listed in the
+    /// order the loop is walked with the element closing the loop first. Nothing is created
+    /// while an element is not connected to a node on both terminals.
     pub fn create_meshes(&mut self) -> &mut Self {
-        let graph: UnGraph<i32, ()> = Tool::nodes_to_graph(&self.nodes()).unwrap();
-        let root = Some(self.ground);
-        let x: Vec<Vec<usize>> = connectivity::cycle_basis(&graph, root.map(NodeIndex::new))
-            .into_iter()
-            .map(|res_map| res_map.into_iter().map(|x| x.index()).collect())
-            .collect();
+        let connected: bool = self
+            .elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class != Ground && !x.is_detached())
+            .all(|x| {
+                self.node_at(x.id, &x.positive).is_some()
+                    && self.node_at(x.id, &x.negative).is_some()
+            });
+        if !connected {
+            return self;
+        }
 
-        for mesh in x {
+        for mesh in self.fundamental_loops() {
             self.add_tool(Tool::create_mesh(
                 mesh.iter()
-                    .map(|x| self.get_element_by_id(*x))
-                    .map(|x| Rc::downgrade(x))
+                    .map(|x| Rc::downgrade(self.get_element_by_id(*x)))
                     .collect(),
             ));
         }
@@ -257,7 +674,97 @@ impl Container {
         self
     }
 
-    pub fn create_super_meshes(&mut self) {}
+    /// Merge the meshes that share a current source into supermeshes
+    ///
+    /// Meshes chained through several sources end up in the same supermesh, which keeps
+    /// the sources as members like a supernode does. A source on a single mesh fixes that
+    /// mesh current on its own and gets no supermesh.
+    pub fn create_super_meshes(&mut self) -> &mut Self {
+        let meshes: Vec<Rc<RefCell<Tool>>> = self
+            .tools
+            .iter()
+            .filter(|x| x.borrow().class == ToolType::Mesh)
+            .cloned()
+            .collect();
+
+        // Indices of the meshes merged together
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for source in &self.elements {
+            let source = source.borrow();
+            if source.class != CurrentSrc || source.is_detached() {
+                continue;
+            }
+            let mut merged: Vec<usize> = meshes
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.borrow().member_ids().contains(&source.id))
+                .map(|(i, _)| i)
+                .collect();
+            if merged.len() < 2 {
+                continue;
+            }
+            groups.retain(|group| {
+                if !group.iter().any(|x| merged.contains(x)) {
+                    return true;
+                }
+                merged.extend(group);
+                false
+            });
+            merged.sort();
+            merged.dedup();
+            groups.push(merged);
+        }
+
+        for group in groups {
+            let mut members: Vec<Weak<RefCell<Element>>> = Vec::new();
+            for mesh in group.iter().map(|x| meshes[*x].borrow()) {
+                for element in mesh.members.iter() {
+                    let id: usize = element.upgrade().unwrap().borrow().id;
+                    if !members
+                        .iter()
+                        .any(|x| x.upgrade().unwrap().borrow().id == id)
+                    {
+                        members.push(element.clone());
+                    }
+                }
+            }
+            self.add_tool(Tool::create_supermesh(members));
+        }
+
+        self
+    }
+
+    /// Nodes, supernodes and meshes as owned data
+    ///
+    /// Tools that have not been created yet are created on a copy in the same order as
+    /// the solvers create them, the container itself is left untouched. The circuit does
+    /// not have to be valid, only every connection has to name an existing element.
+    pub fn analyze_topology(&self) -> Result<Topology, StatusError> {
+        // Node creation looks every connection up, it must not run into a missing element
+        let count: usize = self.elements.len();
+        for element in &self.elements {
+            let element = element.borrow();
+            let mut connections = element.positive.iter().chain(element.negative.iter());
+            if let Some(missing) = connections.find(|x| **x >= count) {
+                return Err(Known(format!(
+                    "Element {} is connected to element {} which does not exist",
+                    element.id, missing
+                )));
+            }
+        }
+
+        let mut analyzed: Container = self.clone();
+        if analyzed.nodes().is_empty() {
+            analyzed.create_nodes()?;
+        }
+        if analyzed.get_tools(SuperNode).is_empty() {
+            analyzed.create_super_nodes().map_err(Known)?;
+        }
+        if analyzed.get_tools(ToolType::Mesh).is_empty() && !analyzed.nodes().is_empty() {
+            analyzed.create_meshes();
+        }
+        Ok(Topology::from_container(&analyzed))
+    }
 
     pub fn get_elements(&self) -> &Vec<Rc<RefCell<Element>>> {
         &self.elements
@@ -286,109 +793,1954 @@ impl Container {
             .collect()
     }
 
-    /// Get all the node pairs in the circuit.
+    /// Get the SuperNode that absorbs the Node with the given id
     ///
-    /// Returns a vector of tuples containing the node ids and the element
-    pub fn get_all_node_pairs(&self) -> Vec<(usize, usize, Rc<RefCell<Element>>)> {
-        let mut node_to_node_resistors: Vec<(usize, usize, Rc<RefCell<Element>>)> = Vec::new();
+    /// A Node belongs to a SuperNode when every one of its members is also a SuperNode member.
+    pub fn get_supernode_for(&self, node_id: usize) -> Option<&Rc<RefCell<Tool>>> {
+        self.get_enclosing_tool(node_id, ToolType::Node, SuperNode)
+    }
 
-        for element in self.elements.iter() {
-            if node_to_node_resistors
-                .iter()
-                .any(|x| x.2.borrow().id == element.borrow().id)
-                || element.borrow().class == Ground
-            {
-                continue;
-            }
+    /// Get the SuperMesh that absorbs the Mesh with the given id
+    pub fn get_supermesh_for(&self, mesh_id: usize) -> Option<&Rc<RefCell<Tool>>> {
+        self.get_enclosing_tool(mesh_id, ToolType::Mesh, ToolType::SuperMesh)
+    }
 
-            let tools = self.get_tools_for_element(element.borrow().id);
-            if element.borrow().connected_to_ground() {
-                node_to_node_resistors.push((
-                    tools[0].upgrade().unwrap().borrow().id,
-                    0,
-                    element.clone(),
-                ));
-            } else {
-                node_to_node_resistors.push((
-                    tools[0].upgrade().unwrap().borrow().id,
-                    tools[1].upgrade().unwrap().borrow().id,
-                    element.clone(),
-                ));
-            }
-        }
+    fn get_enclosing_tool(
+        &self,
+        id: usize,
+        class: ToolType,
+        enclosing_class: ToolType,
+    ) -> Option<&Rc<RefCell<Tool>>> {
+        let tool: &Rc<RefCell<Tool>> = self
+            .tools
+            .iter()
+            .find(|x| x.borrow().id == id && x.borrow().class == class)?;
+        let member_ids: Vec<usize> = tool.borrow().member_ids();
 
-        node_to_node_resistors
+        self.tools
+            .iter()
+            .filter(|x| x.borrow().class == enclosing_class)
+            .find(|x| {
+                let enclosing_ids: Vec<usize> = x.borrow().member_ids();
+                member_ids.iter().all(|y| enclosing_ids.contains(y))
+            })
     }
 
-    pub fn get_voltage_sources(&self) -> Vec<Weak<RefCell<Element>>> {
-        self.elements
-            .iter()
-            .filter(|x| x.borrow().class == VoltageSrc)
-            .map(|x| Rc::downgrade(x))
-            .collect()
+    /// Impedance scaling
+    ///
+    /// Returns a copy where every impedance is multiplied by `factor`, resistors and
+    /// inductors grow while capacitors shrink. Sources are unchanged, so voltages driven
+    /// by voltage sources stay the same and currents scale by `1 / factor`.
+    /// Tools are not copied, nodes have to be created again on the result.
+    pub fn scale_all_impedances(&self, factor: f64) -> Container {
+        self.map_values(|element| match element.class {
+            Resistor | Inductor => element.value * factor,
+            Capacitor => element.value / factor,
+            _ => element.value,
+        })
     }
-}
 
-impl Validation for Container {
-    /// Validate the Container and the circuit within are usable.
+    /// Frequency scaling
     ///
-    /// This function will check that the Container is in a valid state to be solved.
-    /// It will make calls to validate functions in the elements themselves and let
-    /// them handle their own internal validation. This will take care of the high
-    /// level validation.
+    /// Returns a copy where inductors and capacitors are divided by `factor` so every
+    /// impedance at `factor * ω` matches the original impedance at `ω`.
+    /// Tools are not copied, nodes have to be created again on the result.
+    pub fn scale_all_frequencies(&self, factor: f64) -> Container {
+        self.map_values(|element| match element.class {
+            Inductor | Capacitor => element.value / factor,
+            _ => element.value,
+        })
+    }
+
+    /// Normalize a second order filter to 1 Ω and 1 rad/s
     ///
-    /// * All Elements have a valid Component, Value, Positive, and Negative
-    /// * No duplicate Elements or Tools
-    /// * Contains at least one source and a single ground
-    /// * No floating Elements, Tools, etc.
-    /// * No shorted or open Elements
-    fn validate(&self) -> ValidationResult {
-        let mut errors: Vec<StatusError> = Vec::new();
+    /// Impedances are scaled so the Thevenin resistance seen by the first voltage source
+    /// is 1 Ω, then frequencies are scaled so the cutoff (or center) frequency
+    /// `1 / sqrt(LC)` is 1 rad/s. Returns the normalized copy with `(R_norm, ω_norm)`, the
+    /// original resistance and frequency in rad/s, which are needed to denormalize the final
+    /// design. Tools are not copied, nodes have to be created again on the result.
+    pub fn normalize_to_1_ohm_1_rad(&self) -> Result<(Container, f64, f64), StatusError> {
+        let values = |class: Component| -> Vec<f64> {
+            self.elements
+                .iter()
+                .filter(|x| x.borrow().class == class)
+                .map(|x| x.borrow().value)
+                .collect()
+        };
+        let (inductors, capacitors) = (values(Inductor), values(Capacitor));
+        if inductors.len() != 1 || capacitors.len() != 1 {
+            return Err(Known(
+                "Normalization needs a second order circuit with one inductor and one capacitor"
+                    .to_string(),
+            ));
+        }
 
-        // Check that all elements and tools are valid individually
-        errors.append(&mut get_all_internal_status_errors(&self.elements));
-        errors.append(&mut get_all_internal_status_errors(&self.tools));
+        let omega: f64 = 1.0 / (inductors[0] * capacitors[0]).sqrt();
+        let resistance: f64 = self.thevenin_resistance()?;
+        let normalized: Container = self
+            .scale_all_impedances(1.0 / resistance)
+            .scale_all_frequencies(1.0 / omega);
 
-        // Check that there are no duplicates in elements or tools
-        errors.append(&mut check_duplicates(&self.elements));
-        errors.append(&mut check_duplicates(&self.tools));
+        Ok((normalized, resistance, omega))
+    }
 
-        // Check that there is at least one source and a single ground
-        if !self.elements.iter().any(|x| x.borrow().class.is_source()) {
-            errors.push(Known("No Sources".parse().unwrap()));
-        }
-        if self
+    /// Resistance seen by the first voltage source
+    ///
+    /// Inductors and capacitors are treated as shorts, the other sources are switched off.
+    /// The shorts are 0 Ω branches, modelled as 0 V sources like any other wire.
+    fn thevenin_resistance(&self) -> Result<f64, StatusError> {
+        let source: usize = match self
             .elements
             .iter()
-            .filter(|x| x.borrow().class == Ground)
-            .count()
-            != 1
+            .find(|x| x.borrow().class == VoltageSrc)
         {
-            errors.push(Known("Multiple Grounds".parse().unwrap()));
-        }
+            Some(source) => source.borrow().id,
+            None => {
+                return Err(Known(
+                    "Thevenin resistance needs a voltage source".to_string(),
+                ))
+            }
+        };
 
-        match errors.len() {
-            0 => Ok(Status::Valid),
-            1 => Err(errors[0].clone()),
-            _ => Err(StatusError::Multiple(errors)),
+        let mut container = Container::new();
+        container.ground = self.ground;
+        for element in &self.elements {
+            let mut copy: Element = element.borrow().clone();
+            match copy.class {
+                Inductor | Capacitor => {
+                    copy.class = Resistor;
+                    copy.value = 0.0;
+                }
+                VoltageSrc => copy.value = if copy.id == source { 1.0 } else { 0.0 },
+                CurrentSrc => copy.value = 0.0,
+                _ => {}
+            }
+            container.add_element_core(copy);
         }
+        container.apply_zero_ohm_strategy(ZeroOhmStrategy::SenseBranch)?;
+        container.create_nodes()?;
+
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(container)));
+        solver.solve()?;
+        let current: f64 = solver.current_through_source(source)?.abs();
+        if current < f64::EPSILON {
+            return Err(Known(format!(
+                "Source {} sees an open circuit, the Thevenin resistance is infinite",
+                source
+            )));
+        }
+        Ok(1.0 / current)
     }
 
-    fn id(&self) -> usize {
-        panic!("Container does not have an id")
+    /// Id of the node formed by an element and the elements on one of its sides
+    ///
+    /// Ground is reported as node 0. `None` if no such node was created.
+    pub(crate) fn node_at(&self, element: usize, side: &[usize]) -> Option<usize> {
+        if side
+            .iter()
+            .any(|x| self.get_element_by_id(*x).borrow().class == Ground)
+        {
+            return Some(0);
+        }
+        self.nodes()
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .find(|node| {
+                let node = node.borrow();
+                node.contains(self.get_element_by_id(element).clone())
+                    && side
+                        .iter()
+                        .all(|x| node.contains(self.get_element_by_id(*x).clone()))
+            })
+            .map(|node| node.borrow().id)
     }
-}
+
+    /// Nodes `(from, to)` at the terminals of an element in the direction of its current
+    ///
+    /// Current is taken to flow through the element from its positive to its negative
+    /// terminal, the same convention as `NodeSolver::current_through_source`. Ground is
+    /// node 0. Panics if the element does not exist or the nodes have not been created.
+    pub fn element_current_direction(&self, element_id: usize) -> (usize, usize) {
+        let element = self.get_element_by_id(element_id).borrow();
+        let node = |side: &Vec<usize>| -> usize {
+            match self.node_at(element_id, side) {
+                Some(id) => id,
+                None => panic!("Element {} is not connected to a node", element_id),
+            }
+        };
+        (node(&element.positive), node(&element.negative))
+    }
+
+    /// Nodes `(positive, negative)` a dependent source reads its control voltage across
+    ///
+    /// An element control is read in the direction of `element_current_direction`, so the
+    /// control voltage is the `NodeSolver::element_voltage` of that element. The nodes have
+    /// to be created first.
+    pub(crate) fn control_nodes(&self, source_id: usize) -> Result<(usize, usize), StatusError> {
+        let source = self.get_element_by_id(source_id).borrow();
+        match source.control {
+            Some(Control::Element(id)) => {
+                let element = match self.elements.get(id) {
+                    Some(x) if x.borrow().class != Ground && !x.borrow().is_detached() => {
+                        x.borrow()
+                    }
+                    _ => {
+                        return Err(Known(format!(
+                            "{} is controlled by element {} which is not a branch",
+                            source.pretty_string(),
+                            id
+                        )))
+                    }
+                };
+                match (
+                    self.node_at(id, &element.positive),
+                    self.node_at(id, &element.negative),
+                ) {
+                    (Some(a), Some(b)) => Ok((a, b)),
+                    _ => Err(Known(format!(
+                        "{} is not connected to a node",
+                        element.pretty_string()
+                    ))),
+                }
+            }
+            Some(Control::Nodes(a, b)) => {
+                let nodes: Vec<usize> = self.node_ids();
+                match [a, b].into_iter().find(|x| *x != 0 && !nodes.contains(x)) {
+                    Some(id) => Err(Known(format!(
+                        "{} is controlled by node {} which does not exist",
+                        source.pretty_string(),
+                        id
+                    ))),
+                    None => Ok((a, b)),
+                }
+            }
+            None => Err(Known(format!(
+                "Dependent source has no control {}",
+                source.pretty_string()
+            ))),
+        }
+    }
+
+    /// Ids of the elements with one terminal on each of two nodes, in either direction
+    ///
+    /// Ground is node 0, passing the same node twice returns the elements shorted across
+    /// it. Elements on a node that was not created are left out, so the nodes have to be
+    /// created first.
+    pub fn get_element_between(&self, node_a: usize, node_b: usize) -> Vec<usize> {
+        self.elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class != Ground && !x.is_detached())
+            .filter(|x| {
+                let a: Option<usize> = self.node_at(x.id, &x.positive);
+                let b: Option<usize> = self.node_at(x.id, &x.negative);
+                (a, b) == (Some(node_a), Some(node_b)) || (a, b) == (Some(node_b), Some(node_a))
+            })
+            .map(|x| x.id)
+            .collect()
+    }
+
+    /// Shunt, series, shunt triples forming a π network, e.g. the C-L-C of a low pass filter
+    ///
+    /// The series element joins two nodes other than ground and each shunt element connects
+    /// one of them to ground. Only resistors, capacitors and inductors take part. The shunt
+    /// at the positive terminal of the series element comes first, triples are in series
+    /// element order. The nodes have to be created first.
+    pub fn identify_pi_networks(&self) -> Vec<(usize, usize, usize)> {
+        let mut networks: Vec<(usize, usize, usize)> = Vec::new();
+        for (series, a, b) in self.passive_branches() {
+            if a == 0 || b == 0 || a == b {
+                continue;
+            }
+            let shunts = |node: usize| -> Vec<usize> {
+                self.passive_branches()
+                    .into_iter()
+                    .filter(|(_, x, y)| (*x, *y) == (node, 0) || (*x, *y) == (0, node))
+                    .map(|(id, _, _)| id)
+                    .collect()
+            };
+            for first in shunts(a) {
+                for second in shunts(b) {
+                    networks.push((first, series, second));
+                }
+            }
+        }
+        networks
+    }
+
+    /// Series, shunt, series triples forming a T network
+    ///
+    /// The shunt element connects the middle node to ground, the series elements lead from
+    /// it to two different nodes other than ground. Only resistors, capacitors and inductors
+    /// take part. The series element with the lower id comes first. The nodes have to be
+    /// created first.
+    pub fn identify_t_networks(&self) -> Vec<(usize, usize, usize)> {
+        let branches: Vec<(usize, usize, usize)> = self.passive_branches();
+        let mut networks: Vec<(usize, usize, usize)> = Vec::new();
+        for (shunt, a, b) in branches.iter() {
+            let middle: usize = match (*a, *b) {
+                (0, x) | (x, 0) if x != 0 => x,
+                _ => continue,
+            };
+            let series: Vec<(usize, usize)> = branches
+                .iter()
+                .filter_map(|(id, x, y)| {
+                    let far: usize = match (*x, *y) {
+                        (x, y) if x == middle => y,
+                        (x, y) if y == middle => x,
+                        _ => return None,
+                    };
+                    (far != 0 && far != middle).then_some((*id, far))
+                })
+                .collect();
+            for (i, (first, p)) in series.iter().enumerate() {
+                for (second, q) in series.iter().skip(i + 1) {
+                    if p != q {
+                        networks.push((*first, *shunt, *second));
+                    }
+                }
+            }
+        }
+        networks.sort();
+        networks
+    }
+
+    /// Replace a resistive π network by the equivalent T network
+    ///
+    /// The triple has to be one of `identify_pi_networks`. Its resistors are rewired in
+    /// place around a new middle node, so the rest of the circuit sees no difference. With
+    /// the shunts `Ra`, `Rc`, the series `Rb` and `Σ = Ra + Rb + Rc` the arms of the T are
+    ///
+    /// * `Ra Rb / Σ` from the node of `Ra`, taken by the first shunt
+    /// * `Rb Rc / Σ` from the node of `Rc`, taken by the second shunt
+    /// * `Ra Rc / Σ` from ground, taken by the series element
+    ///
+    /// Every arm has its positive terminal away from the middle node, except the one to
+    /// ground. Nodes are created again.
+    pub fn apply_pi_to_t_transform(
+        &mut self,
+        pi: (usize, usize, usize),
+    ) -> Result<(), StatusError> {
+        let (first, series, second) = pi;
+        if !self.identify_pi_networks().contains(&pi) {
+            return Err(Known(format!(
+                "Elements {}, {} and {} do not form a π network",
+                first, series, second
+            )));
+        }
+        let mut values: Vec<f64> = Vec::new();
+        for id in [first, series, second] {
+            let element = self.get_element_by_id(id).borrow();
+            if element.class != Resistor {
+                return Err(Known(format!(
+                    "Only resistive π networks can be transformed, {} is not a resistor",
+                    element.pretty_string()
+                )));
+            }
+            values.push(element.value);
+        }
+        let (ra, rb, rc) = (values[0], values[1], values[2]);
+        let sum: f64 = ra + rb + rc;
+        if sum == 0.0 {
+            return Err(Known(
+                "The resistances of the π network add up to 0 Ω".to_string(),
+            ));
+        }
+
+        // Terminal nodes of every branch, the π is moved onto the new middle node
+        let mut terminals: Vec<(usize, usize, usize)> = self.branch_terminals()?;
+        let far = |id: usize| -> usize {
+            let (_, a, b) = terminals.iter().find(|(x, _, _)| *x == id).unwrap();
+            *a.max(b)
+        };
+        let (p, q) = (far(first), far(second));
+        let middle: usize = self.node_ids().into_iter().max().unwrap_or(0) + 1;
+        for (id, a, b) in terminals.iter_mut() {
+            (*a, *b) = match *id {
+                x if x == first => (p, middle),
+                x if x == second => (q, middle),
+                x if x == series => (middle, 0),
+                _ => continue,
+            };
+        }
+
+        self.connect_terminals(&terminals)?;
+        for (id, value) in [
+            (first, ra * rb / sum),
+            (second, rb * rc / sum),
+            (series, ra * rc / sum),
+        ] {
+            self.get_element_by_id(id).borrow_mut().value = value;
+        }
+
+        // Keep the labels of `add_element_between_nodes` in step, the middle gets a new one
+        if let Some(label) = self.junctions.keys().max().map(|x| x + 1) {
+            for attached in self.junctions.values_mut() {
+                attached.retain(|(x, _)| *x != series);
+                for (x, positive) in attached.iter_mut() {
+                    if *x == first || *x == second {
+                        *positive = true;
+                    }
+                }
+            }
+            self.junctions
+                .insert(label, vec![(series, true), (first, false), (second, false)]);
+        }
+        self.generation += 1;
+        self.rebuild_nodes()
+    }
+
+    /// Replace a current source and its shunt resistor by the equivalent Thevenin source
+    ///
+    /// The resistor has to sit across the same two nodes as the source. Both are removed
+    /// and a voltage source of `I R` in series with a resistor of `R` is added in their
+    /// place, around a new middle node. The resistor sits on the node the current source
+    /// drew from and the voltage source on the node it drove, so the rest of the circuit
+    /// sees no difference. A source drawing from its node into ground keeps the resistor on
+    /// ground instead, a voltage source to ground cannot pull its node below ground. Nodes
+    /// are created again.
+    pub fn apply_norton_to_thevenin(
+        &mut self,
+        current_source_id: usize,
+        shunt_resistor_id: usize,
+    ) -> Result<(), StatusError> {
+        let current: f64 =
+            self.transformed_value(current_source_id, CurrentSrc, "current source")?;
+        let resistance: f64 = self.transformed_value(shunt_resistor_id, Resistor, "resistor")?;
+        if resistance == 0.0 {
+            return Err(Known(format!(
+                "Element {} is 0 Ω and shorts the current source",
+                shunt_resistor_id
+            )));
+        }
+
+        let terminals: Vec<(usize, usize, usize)> = self.branch_terminals()?;
+        let nodes = |id: usize| -> (usize, usize) {
+            let (_, a, b) = terminals.iter().find(|(x, _, _)| *x == id).unwrap();
+            (*a, *b)
+        };
+        let (p, n) = nodes(current_source_id);
+        let (a, b) = nodes(shunt_resistor_id);
+        if p == n || ((a, b) != (p, n) && (a, b) != (n, p)) {
+            return Err(Known(format!(
+                "Elements {} and {} are not in parallel",
+                current_source_id, shunt_resistor_id
+            )));
+        }
+
+        // The current source drives its positive terminal. The voltage source floats and
+        // holds its negative terminal above the positive one.
+        let (driven, drawn) = (p, n);
+        let middle: usize = self.node_ids().into_iter().max().unwrap_or(0) + 1;
+        let added: Vec<(Component, f64, usize, usize)> = match driven {
+            0 => vec![
+                (VoltageSrc, current * resistance, drawn, middle),
+                (Resistor, resistance, middle, driven),
+            ],
+            _ => vec![
+                (VoltageSrc, current * resistance, middle, driven),
+                (Resistor, resistance, drawn, middle),
+            ],
+        };
+        self.replace_branches([current_source_id, shunt_resistor_id], added)
+    }
+
+    /// Replace a voltage source and its series resistor by the equivalent Norton source
+    ///
+    /// The two have to meet on a node no other element is connected to. Both are removed
+    /// and a current source of `V / R` in parallel with a resistor of `R` is added across
+    /// their outer nodes, the middle node goes away. Nodes are created again.
+    pub fn apply_thevenin_to_norton(
+        &mut self,
+        voltage_source_id: usize,
+        series_resistor_id: usize,
+    ) -> Result<(), StatusError> {
+        let voltage: f64 =
+            self.transformed_value(voltage_source_id, VoltageSrc, "voltage source")?;
+        let resistance: f64 = self.transformed_value(series_resistor_id, Resistor, "resistor")?;
+        if resistance == 0.0 {
+            return Err(Known(format!(
+                "Element {} is 0 Ω, the Norton source would be infinite",
+                series_resistor_id
+            )));
+        }
+
+        let terminals: Vec<(usize, usize, usize)> = self.branch_terminals()?;
+        let nodes = |id: usize| -> (usize, usize) {
+            let (_, a, b) = terminals.iter().find(|(x, _, _)| *x == id).unwrap();
+            (*a, *b)
+        };
+        let (va, vb) = nodes(voltage_source_id);
+        let (ra, rb) = nodes(series_resistor_id);
+        let alone = |node: usize| -> bool {
+            terminals.iter().all(|(id, a, b)| {
+                *id == voltage_source_id || *id == series_resistor_id || (*a != node && *b != node)
+            })
+        };
+        let middle: Option<usize> = [va, vb]
+            .into_iter()
+            .find(|x| *x != 0 && (*x == ra || *x == rb) && alone(*x));
+        let (middle, drawn, driven) = match middle {
+            Some(x) => (
+                x,
+                if va == x { vb } else { va },
+                if ra == x { rb } else { ra },
+            ),
+            None => (0, 0, 0),
+        };
+        if middle == 0 || va == vb || ra == rb || drawn == driven {
+            return Err(Known(format!(
+                "Elements {} and {} are not in series",
+                voltage_source_id, series_resistor_id
+            )));
+        }
+
+        // The open circuit voltage of the driven node over the other, read off the C row of
+        // the source on the middle node as the node matrix solver forms it
+        let sign: f64 = self
+            .nodes()
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .find(|x| x.borrow().id == middle)
+            .map(|x| {
+                voltage_source_sign(
+                    &x.borrow(),
+                    &self.get_element_by_id(voltage_source_id).borrow(),
+                )
+            })
+            .unwrap_or(1.0);
+        let (driven, drawn) = match sign > 0.0 {
+            true => (driven, drawn),
+            false => (drawn, driven),
+        };
+        // The current source drives its positive terminal
+        let shunt: (usize, usize) = match drawn {
+            0 => (driven, drawn),
+            _ => (drawn, driven),
+        };
+        self.replace_branches(
+            [voltage_source_id, series_resistor_id],
+            vec![
+                (CurrentSrc, voltage / resistance, driven, drawn),
+                (Resistor, resistance, shunt.0, shunt.1),
+            ],
+        )
+    }
+
+    /// Value of an element a transformation replaces, checking it is of the expected class
+    fn transformed_value(
+        &self,
+        id: usize,
+        class: Component,
+        name: &str,
+    ) -> Result<f64, StatusError> {
+        let element = match self.elements.get(id) {
+            Some(element) => element.borrow(),
+            None => return Err(Known(format!("Element {} does not exist", id))),
+        };
+        if element.class != class || element.is_detached() {
+            return Err(Known(format!(
+                "Element {} is not a {} in the circuit",
+                id, name
+            )));
+        }
+        Ok(element.value)
+    }
+
+    /// Detach two elements and add new ones as `(class, value, positive node, negative node)`
+    ///
+    /// The labels of `add_element_between_nodes` follow, nodes without one get a new label.
+    /// Nodes are created again.
+    fn replace_branches(
+        &mut self,
+        removed: [usize; 2],
+        added: Vec<(Component, f64, usize, usize)>,
+    ) -> Result<(), StatusError> {
+        let mut terminals: Vec<(usize, usize, usize)> = self.branch_terminals()?;
+        let old: Vec<(usize, usize, usize)> = terminals
+            .iter()
+            .filter(|(x, _, _)| removed.contains(x))
+            .cloned()
+            .collect();
+        terminals.retain(|(x, _, _)| !removed.contains(x));
+
+        for id in removed {
+            let mut element = self.get_element_by_id(id).borrow_mut();
+            element.positive.clear();
+            element.negative.clear();
+        }
+        let mut new: Vec<(usize, usize, usize)> = Vec::new();
+        for (class, value, a, b) in added {
+            let mut element: Element = Element::new(class, value, vec![], vec![]);
+            element.id = self.elements.len();
+            new.push((self.add_element_core(element), a, b));
+        }
+        terminals.extend(new.iter().cloned());
+        self.connect_terminals(&terminals)?;
+
+        if !self.junctions.is_empty() {
+            let mut labels: BTreeMap<usize, usize> = BTreeMap::new();
+            for (id, a, b) in old.iter() {
+                for (label, attached) in self.junctions.iter() {
+                    if attached.contains(&(*id, true)) {
+                        labels.insert(*a, *label);
+                    }
+                    if attached.contains(&(*id, false)) {
+                        labels.insert(*b, *label);
+                    }
+                }
+            }
+            for attached in self.junctions.values_mut() {
+                attached.retain(|(x, _)| !removed.contains(x));
+            }
+            for (id, a, b) in new {
+                for (node, positive) in [(a, true), (b, false)] {
+                    if node == 0 {
+                        continue;
+                    }
+                    let next: usize = self.junctions.keys().max().unwrap_or(&0) + 1;
+                    let label: usize = *labels.entry(node).or_insert(next);
+                    self.junctions
+                        .entry(label)
+                        .or_default()
+                        .push((id, positive));
+                }
+            }
+        }
+        self.generation += 1;
+        self.rebuild_nodes()
+    }
+
+    /// Every branch as `(id, positive node, negative node)`, ground and removed elements
+    /// are left out
+    fn branch_terminals(&self) -> Result<Vec<(usize, usize, usize)>, StatusError> {
+        let mut terminals: Vec<(usize, usize, usize)> = Vec::new();
+        for element in self.elements.iter().map(|x| x.borrow()) {
+            if element.class == Ground || element.is_detached() {
+                continue;
+            }
+            match (
+                self.node_at(element.id, &element.positive),
+                self.node_at(element.id, &element.negative),
+            ) {
+                (Some(a), Some(b)) => terminals.push((element.id, a, b)),
+                _ => {
+                    return Err(Known(format!(
+                        "{} is not connected to a node",
+                        element.pretty_string()
+                    )))
+                }
+            }
+        }
+        Ok(terminals)
+    }
+
+    /// Set the connections of every branch to the others on the same nodes
+    fn connect_terminals(
+        &mut self,
+        terminals: &[(usize, usize, usize)],
+    ) -> Result<(), StatusError> {
+        let ground: usize = self
+            .elements
+            .iter()
+            .find(|x| x.borrow().class == Ground)
+            .map(|x| x.borrow().id)
+            .ok_or_else(|| Known("Circuit has no ground".to_string()))?;
+        // Elements at ground only list the ground element, ground lists all of them
+        let attached = |node: usize, own: usize| -> Vec<usize> {
+            match node {
+                0 => vec![ground],
+                _ => terminals
+                    .iter()
+                    .filter(|(id, a, b)| *id != own && (*a == node || *b == node))
+                    .map(|(id, _, _)| *id)
+                    .collect(),
+            }
+        };
+        for (id, a, b) in terminals.iter() {
+            let mut element = self.get_element_by_id(*id).borrow_mut();
+            element.positive = attached(*a, *id);
+            element.negative = attached(*b, *id);
+        }
+        self.get_element_by_id(ground).borrow_mut().positive = terminals
+            .iter()
+            .filter(|(_, a, b)| *a == 0 || *b == 0)
+            .map(|(id, _, _)| *id)
+            .collect();
+        Ok(())
+    }
+
+    /// Resistors, capacitors and inductors as `(id, positive node, negative node)`
+    fn passive_branches(&self) -> Vec<(usize, usize, usize)> {
+        self.elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| matches!(x.class, Resistor | Capacitor | Inductor) && !x.is_detached())
+            .filter_map(|x| {
+                let a: usize = self.node_at(x.id, &x.positive)?;
+                let b: usize = self.node_at(x.id, &x.negative)?;
+                Some((x.id, a, b))
+            })
+            .collect()
+    }
+
+    /// Off-diagonal conductance matrix entry `G[i][j]` between two nodes
+    ///
+    /// Only the resistors shared by the two nodes are visited, so a single entry can be
+    /// refreshed after one element changes without forming the whole matrix. The entry is
+    /// the negated sum of the shared conductances and 0 for `node_i == node_j`. Ground is
+    /// not part of G. Panics if either node does not exist.
+    pub fn get_mutual_admittance(&self, node_i: usize, node_j: usize) -> f64 {
+        if node_i == node_j {
+            return 0.0;
+        }
+        let (first, second) = (self.node_by_id(node_i), self.node_by_id(node_j));
+        let first = first.borrow();
+        let second = second.borrow();
+
+        first
+            .members
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .filter(|x| x.borrow().class == Resistor && second.contains(x.clone()))
+            .map(|x| -1.0 / x.borrow().value)
+            .sum()
+    }
+
+    /// Diagonal conductance matrix entry `G[i][i]` of a node
+    ///
+    /// Sum of the conductances of every resistor connected to the node, formed without
+    /// building the whole matrix. Panics if the node does not exist.
+    pub fn get_self_admittance(&self, node_id: usize) -> f64 {
+        self.node_by_id(node_id)
+            .borrow()
+            .members
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .filter(|x| x.borrow().class == Resistor)
+            .map(|x| 1.0 / x.borrow().value)
+            .sum()
+    }
+
+    /// Reduced incidence matrix, one row per branch and one column per non ground node
+    ///
+    /// Every element but ground is a branch, rows follow element ids. Column `j` is the
+    /// node with the `j`th smallest id. A branch has `1` at the node its current leaves
+    /// from and `-1` at the node it enters, see `element_current_direction`. The ground
+    /// column is removed, so `A_r^T * Y * A_r` is the nodal admittance matrix.
+    /// Panics if the nodes have not been created.
+    pub fn compute_reduced_incidence_matrix(&self) -> DMatrix<i8> {
+        let mut nodes: Vec<usize> = self
+            .nodes()
+            .iter()
+            .map(|x| x.upgrade().unwrap().borrow().id)
+            .collect();
+        nodes.sort();
+        let branches: Vec<usize> = self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().class != Ground)
+            .map(|x| x.borrow().id)
+            .collect();
+
+        let mut matrix: DMatrix<i8> = DMatrix::zeros(branches.len(), nodes.len());
+        for (row, id) in branches.iter().enumerate() {
+            let (from, to) = self.element_current_direction(*id);
+            for (node, sign) in [(from, 1), (to, -1)] {
+                if let Some(column) = nodes.iter().position(|x| *x == node) {
+                    matrix[(row, column)] = sign;
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Independent loops of the circuit as ordered lists of element ids
+    ///
+    /// A spanning tree of the nodes is grown from ground, every branch outside the tree
+    /// closes one loop with the tree path between its terminals. That branch is listed
+    /// first, the rest follow in the order the loop is walked. Panics if the nodes have
+    /// not been created.
+    pub fn fundamental_loops(&self) -> Vec<Vec<usize>> {
+        let branches: Vec<(usize, usize, usize)> = self
+            .elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class != Ground && !x.is_detached())
+            .map(|x| {
+                let (from, to) = self.element_current_direction(x.id);
+                (x.id, from, to)
+            })
+            .collect();
+
+        // Node to (parent node, element to the parent), roots have no parent
+        let mut parent: BTreeMap<usize, Option<(usize, usize)>> = BTreeMap::new();
+        let mut tree: Vec<bool> = vec![false; branches.len()];
+        let roots: Vec<usize> = std::iter::once(0)
+            .chain(branches.iter().flat_map(|(_, from, to)| [*from, *to]))
+            .collect();
+        for root in roots {
+            if parent.contains_key(&root) {
+                continue;
+            }
+            parent.insert(root, None);
+            let mut queue: VecDeque<usize> = VecDeque::from([root]);
+            while let Some(node) = queue.pop_front() {
+                for (i, (id, from, to)) in branches.iter().enumerate() {
+                    let other: usize = match node {
+                        x if x == *from => *to,
+                        x if x == *to => *from,
+                        _ => continue,
+                    };
+                    if parent.contains_key(&other) {
+                        continue;
+                    }
+                    parent.insert(other, Some((node, *id)));
+                    tree[i] = true;
+                    queue.push_back(other);
+                }
+            }
+        }
+
+        // Elements from a node up to its root, paired with the node they lead away from
+        let ancestry = |mut node: usize| -> Vec<(usize, usize)> {
+            let mut path: Vec<(usize, usize)> = Vec::new();
+            while let Some(Some((next, element))) = parent.get(&node) {
+                path.push((node, *element));
+                node = *next;
+            }
+            path
+        };
+
+        let mut loops: Vec<Vec<usize>> = Vec::new();
+        for (i, (id, from, to)) in branches.iter().enumerate() {
+            if tree[i] || from == to {
+                continue;
+            }
+            let mut up: Vec<(usize, usize)> = ancestry(*to);
+            let mut down: Vec<(usize, usize)> = ancestry(*from);
+            // Drop the part of the paths shared above the lowest common ancestor
+            while !up.is_empty() && !down.is_empty() && up.last() == down.last() {
+                up.pop();
+                down.pop();
+            }
+
+            let mut mesh: Vec<usize> = vec![*id];
+            mesh.extend(up.iter().map(|(_, element)| *element));
+            mesh.extend(down.iter().rev().map(|(_, element)| *element));
+            loops.push(mesh);
+        }
+        loops
+    }
+
+    pub(crate) fn node_by_id(&self, id: usize) -> Rc<RefCell<Tool>> {
+        match self
+            .nodes()
+            .into_iter()
+            .filter_map(|x| x.upgrade())
+            .find(|x| x.borrow().id == id)
+        {
+            Some(node) => node,
+            None => panic!("Node {} does not exist", id),
+        }
+    }
+
+    /// Graphviz DOT description of the circuit
+    ///
+    /// Nodes are drawn as circles and elements as labeled edges between them, voltage
+    /// sources are drawn bold. Render with `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        let mut container: Container = self.clone();
+        if container.nodes().is_empty() {
+            let _ = container.create_nodes();
+        }
+
+        let mut out: String =
+            String::from("graph {\n    node [shape=circle];\n    0 [label=\"GND\"];\n");
+        for node in container.nodes().iter().filter_map(|x| x.upgrade()) {
+            out.push_str(&format!(
+                "    {} [label=\"N{}\"];\n",
+                node.borrow().id,
+                node.borrow().id
+            ));
+        }
+        for element in container.elements.iter() {
+            let element = element.borrow();
+            if element.class == Ground {
+                continue;
+            }
+            let (from, to) = match (
+                container.node_at(element.id, &element.positive),
+                container.node_at(element.id, &element.negative),
+            ) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+            let style: &str = match element.class {
+                VoltageSrc => ", style=bold",
+                _ => "",
+            };
+            out.push_str(&format!(
+                "    {} -- {} [label=\"{}\"{}];\n",
+                from,
+                to,
+                element.pretty_string(),
+                style
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Copy of the circuit as seen at DC with its nodes created
+    ///
+    /// Inductors are shorts at DC, capacitors are already treated as open circuits. The
+    /// shorts become 0 V sources, so the nodes keep the ids they have in this circuit.
+    fn dc_equivalent(&self) -> Result<Container, StatusError> {
+        let mut container = Container::new();
+        container.ground = self.ground;
+        for element in &self.elements {
+            let mut copy: Element = element.borrow().clone();
+            if copy.class == Inductor {
+                copy.class = Resistor;
+                copy.value = 0.0;
+            }
+            container.add_element_core(copy);
+        }
+        container.apply_zero_ohm_strategy(ZeroOhmStrategy::SenseBranch)?;
+        container.create_nodes()?;
+        Ok(container)
+    }
+
+    /// Copy of the circuit that shares no element with this one, with its nodes created
+    ///
+    /// `clone` shares the elements, a solver that rewrites elements works on this instead.
+    pub(crate) fn deep_copy(&self) -> Result<Container, StatusError> {
+        let mut container: Container = self.clone();
+        container.elements = self
+            .elements
+            .iter()
+            .map(|x| Rc::new(RefCell::new(x.borrow().clone())))
+            .collect();
+        container.tools = vec![];
+        container.create_nodes()?;
+        Ok(container)
+    }
+
+    /// Name a pair of nodes as a port for multi-port analysis
+    ///
+    /// Port names are unique, the nodes are only checked once the port is used.
+    pub fn add_port(
+        &mut self,
+        name: String,
+        pos_node: usize,
+        neg_node: usize,
+    ) -> Result<(), StatusError> {
+        if self.ports.iter().any(|x| x.name == name) {
+            return Err(Known(format!("Port {} already exists", name)));
+        }
+        self.ports.push(Port {
+            name,
+            positive: pos_node,
+            negative: neg_node,
+        });
+        Ok(())
+    }
+
+    pub fn ports(&self) -> &Vec<Port> {
+        &self.ports
+    }
+
+    pub fn get_port(&self, name: &str) -> Result<&Port, StatusError> {
+        self.ports
+            .iter()
+            .find(|x| x.name == name)
+            .ok_or_else(|| Known(format!("Port {} does not exist", name)))
+    }
+
+    /// Check that every port joins two distinct nodes that exist
+    ///
+    /// Node 0 is ground. If the nodes have not been created yet they are created on a
+    /// copy of the container, provided every connection refers to an existing element.
+    pub fn validate_port_definitions(&self) -> Result<(), StatusError> {
+        let mut errors: Vec<StatusError> = self.port_errors();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    fn port_errors(&self) -> Vec<StatusError> {
+        if self.ports.is_empty() {
+            return vec![];
+        }
+
+        let nodes: Option<Vec<usize>> = self.known_node_ids();
+        let mut errors: Vec<StatusError> = Vec::new();
+        for port in self.ports.iter() {
+            if port.positive == port.negative {
+                errors.push(Known(format!(
+                    "Port {} connects node {} to itself",
+                    port.name, port.positive
+                )));
+                continue;
+            }
+            if let Some(nodes) = &nodes {
+                for id in [port.positive, port.negative] {
+                    if id != 0 && !nodes.contains(&id) {
+                        errors.push(Known(format!(
+                            "Port {} uses node {} which does not exist",
+                            port.name, id
+                        )));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Controls of the dependent sources that do not point at a branch or an existing node
+    ///
+    /// Missing controls are reported by `Element::validate`.
+    fn control_errors(&self) -> Vec<StatusError> {
+        let sources: Vec<Element> = self
+            .elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class.is_dependent() && !x.is_detached())
+            .map(|x| x.clone())
+            .collect();
+        if sources.is_empty() {
+            return vec![];
+        }
+
+        let nodes: Option<Vec<usize>> = self.known_node_ids();
+        let mut errors: Vec<StatusError> = Vec::new();
+        for source in sources {
+            match source.control {
+                Some(Control::Element(id)) => {
+                    let branch: bool = self.elements.get(id).is_some_and(|x| {
+                        let x = x.borrow();
+                        x.class != Ground && !x.is_detached()
+                    });
+                    if !branch {
+                        errors.push(Known(format!(
+                            "{} is controlled by element {} which is not a branch",
+                            source.pretty_string(),
+                            id
+                        )));
+                    }
+                }
+                Some(Control::Nodes(a, b)) => {
+                    if let Some(nodes) = &nodes {
+                        for id in [a, b] {
+                            if id != 0 && !nodes.contains(&id) {
+                                errors.push(Known(format!(
+                                    "{} is controlled by node {} which does not exist",
+                                    source.pretty_string(),
+                                    id
+                                )));
+                            }
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+        errors
+    }
+
+    /// Node ids, created on a copy of the container when the nodes do not exist yet
+    ///
+    /// None when a connection refers to a missing element and the nodes can not be created.
+    fn known_node_ids(&self) -> Option<Vec<usize>> {
+        let connected: bool = self.elements.iter().all(|x| {
+            let x = x.borrow();
+            x.positive
+                .iter()
+                .chain(x.negative.iter())
+                .all(|id| *id < self.elements.len())
+        });
+        match (self.nodes().is_empty(), connected) {
+            (false, _) => Some(self.node_ids()),
+            (true, true) => {
+                let mut container: Container = self.clone();
+                container.create_nodes().ok().map(|x| x.node_ids())
+            }
+            (true, false) => None,
+        }
+    }
+
+    pub(crate) fn node_ids(&self) -> Vec<usize> {
+        self.nodes()
+            .iter()
+            .map(|x| x.upgrade().unwrap().borrow().id)
+            .collect()
+    }
+
+    /// `validate` for a circuit that is solved with the given options
+    ///
+    /// Negative resistors pass when `allow_negative_resistance` is set. Inductors fail
+    /// unless `allow_dc_inductors` is set, everything else is checked as usual.
+    pub fn validate_with_options(&self, options: &SolveOptions) -> ValidationResult {
+        let result: ValidationResult = match options.allow_negative_resistance {
+            false => self.validate(),
+            true => {
+                let mut container: Container =
+                    self.map_values(|x| match x.is_negative_resistance() {
+                        true => -x.value,
+                        false => x.value,
+                    });
+                container.ports = self.ports.clone();
+                container.validate()
+            }
+        };
+
+        let inductors: Vec<StatusError> = match options.allow_dc_inductors {
+            true => vec![],
+            false => self
+                .elements
+                .iter()
+                .filter(|x| x.borrow().class == Inductor && !x.borrow().is_detached())
+                .map(|x| Known(dc_inductor_message(&x.borrow())))
+                .collect(),
+        };
+        if inductors.is_empty() {
+            return result;
+        }
+        let mut errors: Vec<StatusError> = match result {
+            Ok(_) => vec![],
+            Err(StatusError::Multiple(list)) => list,
+            Err(error) => vec![error],
+        };
+        errors.extend(inductors);
+        match errors.len() {
+            1 => Err(errors[0].clone()),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    /// Check that the voltage sources do not over-constrain the MNA matrix
+    ///
+    /// Every voltage source adds a row fixing the difference of two node voltages, there
+    /// are only as many independent differences as there are non ground nodes. More sources
+    /// than nodes, or sources that close a loop among themselves, leave the A matrix rank
+    /// deficient. Nodes are created on a copy of the container if there are none yet.
+    pub fn validate_voltage_source_count(&self) -> Result<(), StatusError> {
+        let mut copy: Container;
+        let container: &Container = match self.nodes().is_empty() {
+            false => self,
+            true => {
+                copy = self.clone();
+                copy.create_nodes()?;
+                &copy
+            }
+        };
+
+        let sources: Vec<usize> = self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().class == VoltageSrc && !x.borrow().is_detached())
+            .map(|x| x.borrow().id)
+            .collect();
+        let nodes: usize = container.nodes().len();
+        if sources.len() > nodes {
+            return Err(Known(format!(
+                "Circuit has {} voltage sources but only {} nodes, the MNA matrix would be over-constrained",
+                sources.len(),
+                nodes
+            )));
+        }
+
+        // Group the nodes joined through sources, a source inside one group closes a loop
+        let mut group: BTreeMap<usize, usize> =
+            container.node_ids().into_iter().map(|x| (x, x)).collect();
+        group.insert(0, 0);
+        let mut errors: Vec<StatusError> = Vec::new();
+        for id in sources {
+            let element = self.get_element_by_id(id).borrow();
+            let (a, b) = match (
+                container.node_at(id, &element.positive),
+                container.node_at(id, &element.negative),
+            ) {
+                (Some(a), Some(b)) => (group[&a], group[&b]),
+                _ => continue,
+            };
+            if a == b {
+                errors.push(Known(format!(
+                    "Voltage source {} closes a loop of voltage sources, the MNA matrix would be over-constrained",
+                    element.pretty_string()
+                )));
+                continue;
+            }
+            for x in group.values_mut() {
+                if *x == a {
+                    *x = b;
+                }
+            }
+        }
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    /// DC driving point impedance between two nodes with every independent source zeroed
+    pub fn impedance_at(&self, node_a: usize, node_b: usize) -> Result<f64, StatusError> {
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(self.dc_equivalent()?)));
+        solver.impedance_between(node_a, node_b)
+    }
+
+    /// Power factor of an element at `frequency` in Hz, the cos of the phase between its
+    /// voltage and current
+    ///
+    /// The phase of an ideal resistor, inductor or capacitor does not depend on the circuit,
+    /// a resistor has 1 and an inductor or capacitor 0 at every frequency. A source that
+    /// drives a single series loop of resistors, inductors and capacitors sees
+    /// `Z = R + j(ωL - 1 / (ωC))` and has `R / |Z|`, 0 at DC once a capacitor blocks the
+    /// loop. Any other element needs the phasors of an AC solve and fails.
+    pub fn element_power_factor(
+        &self,
+        element_id: usize,
+        frequency: f64,
+    ) -> Result<f64, StatusError> {
+        let element = match self.elements.get(element_id) {
+            Some(element) => element.borrow(),
+            None => return Err(Known(format!("Element {} does not exist", element_id))),
+        };
+        if element.is_detached() {
+            return Err(Known(format!(
+                "{} was removed from the circuit",
+                element.pretty_string()
+            )));
+        }
+        if frequency < 0.0 {
+            return Err(Known(format!(
+                "Frequency cannot be negative {} Hz",
+                frequency
+            )));
+        }
+        let name: String = element.pretty_string();
+        let unsupported = || {
+            Known(format!(
+                "The power factor of {} needs an AC solve, only a source driving a series loop of resistors, inductors and capacitors is supported",
+                name
+            ))
+        };
+        match element.class {
+            Ground => return Err(Known("Ground has no power factor".to_string())),
+            Resistor => return Ok(1.0),
+            Inductor | Capacitor => return Ok(0.0),
+            VoltageSrc | CurrentSrc => {}
+            _ => return Err(unsupported()),
+        }
+        drop(element);
+
+        // A single loop through every branch is a series circuit
+        let container: Container = self.with_nodes()?;
+        let loops: Vec<Vec<usize>> = container.fundamental_loops();
+        if loops.len() != 1 || loops[0].len() != container.branch_count() {
+            return Err(unsupported());
+        }
+        let omega: f64 = 2.0 * std::f64::consts::PI * frequency;
+        let (mut resistance, mut reactance, mut blocked) = (0.0, 0.0, false);
+        for id in loops[0].iter().filter(|x| **x != element_id) {
+            let other = container.get_element_by_id(*id).borrow();
+            match other.class {
+                Resistor => resistance += other.value,
+                Inductor => reactance += omega * other.value,
+                Capacitor if omega == 0.0 => blocked = true,
+                Capacitor => reactance -= 1.0 / (omega * other.value),
+                _ => return Err(unsupported()),
+            }
+        }
+        if blocked {
+            return Ok(0.0);
+        }
+        let impedance: f64 = resistance.hypot(reactance);
+        if impedance == 0.0 {
+            return Err(Known(format!(
+                "{} sees no impedance at {} Hz",
+                name, frequency
+            )));
+        }
+        Ok(resistance / impedance)
+    }
+
+    /// `impedance_at` across the nodes of a named port
+    pub fn impedance_at_port(&self, name: &str) -> Result<f64, StatusError> {
+        let port: &Port = self.get_port(name)?;
+        self.impedance_at(port.positive, port.negative)
+    }
+
+    /// Norton equivalent of the circuit seen between two nodes
+    ///
+    /// A current source of `V_oc / R_th` in parallel with `R_th`, where `V_oc` is the open
+    /// circuit voltage `V(node_a) - V(node_b)` and `R_th` the DC driving point resistance,
+    /// see `impedance_at`. Nodes joined by voltage sources alone, `R_th` below 1 pΩ, are
+    /// left with an ideal source of `V_oc` instead. The terminals are the port
+    /// `"equivalent"` of the returned container, its nodes are already created.
+    pub fn equivalent_circuit_between(
+        &self,
+        node_a: usize,
+        node_b: usize,
+    ) -> Result<Container, StatusError> {
+        let mut solver: NodeMatrixSolver =
+            Solver::new(Rc::new(RefCell::new(self.dc_equivalent()?)));
+        solver.solve()?;
+        let voltage: f64 = solver.voltage_between(node_a, node_b)?;
+        let resistance: f64 = self.impedance_at(node_a, node_b)?;
+
+        // A lone resistor end would not form a node, so the terminal is shared by two elements
+        let mut equivalent = Container::new();
+        if resistance.abs() < 1e-12 {
+            equivalent.add_element_between_nodes(VoltageSrc, voltage, 1, 0);
+        } else {
+            equivalent.add_element_between_nodes(CurrentSrc, voltage / resistance, 1, 0);
+            equivalent.add_element_between_nodes(Resistor, resistance, 1, 0);
+        }
+        equivalent.create_nodes()?;
+        equivalent.add_port("equivalent".to_string(), 1, 0)?;
+        Ok(equivalent)
+    }
+
+    /// Check that the circuit is reciprocal, `Z[i][j] == Z[j][i]` for every pair of nodes
+    ///
+    /// Z is the DC node impedance matrix with every independent source zeroed. A circuit of
+    /// resistors is always reciprocal, so an asymmetric entry beyond a relative tolerance of
+    /// 1e-9 is an error. Dependent sources make a circuit non-reciprocal, each one is
+    /// reported as a warning and Z is not formed.
+    pub fn validate_reciprocity(&self) -> Vec<(Severity, StatusError)> {
+        let dependent: Vec<(Severity, StatusError)> = self
+            .elements
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| matches!(x.class, DependentVoltage | DependentCurrent))
+            .map(|x| {
+                (
+                    Severity::Warning,
+                    Known(format!(
+                        "Dependent source {} makes the circuit non-reciprocal",
+                        x.pretty_string()
+                    )),
+                )
+            })
+            .collect();
+        if !dependent.is_empty() {
+            return dependent;
+        }
+
+        let impedances = self.dc_equivalent().and_then(|x| {
+            let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(x)));
+            solver.impedance_matrix()
+        });
+        let (nodes, z) = match impedances {
+            Ok(x) => x,
+            Err(error) => return vec![(Severity::Error, error)],
+        };
+
+        let mut issues: Vec<(Severity, StatusError)> = Vec::new();
+        for i in 0..nodes.len() {
+            for j in i + 1..nodes.len() {
+                let (forward, backward) = (z[(i, j)], z[(j, i)]);
+                let scale: f64 = forward.abs().max(backward.abs()).max(f64::MIN_POSITIVE);
+                if (forward - backward).abs() > 1e-9 * scale {
+                    issues.push((
+                        Severity::Error,
+                        Known(format!(
+                            "Z[{}][{}] = {} Ω but Z[{}][{}] = {} Ω, the circuit is not reciprocal",
+                            nodes[i], nodes[j], forward, nodes[j], nodes[i], backward
+                        )),
+                    ));
+                }
+            }
+        }
+        issues
+    }
+
+    /// Check that voltage sources are not wired backwards
+    ///
+    /// Heuristic based on a quick DC pre-solve: a source is reported when its positive
+    /// terminal connects to more nodes below ground than above ground. The terminal node
+    /// itself and the nodes on the far side of the elements attached to it are counted.
+    pub fn validate_source_polarity(&self) -> Result<(), StatusError> {
+        self.validate()?;
+
+        let container: Rc<RefCell<Container>> = Rc::new(RefCell::new(self.dc_equivalent()?));
+        let mut solver: NodeMatrixSolver = Solver::new(container.clone());
+        solver.solve()?;
+        let voltages: Vec<(usize, f64)> = solver.node_voltages()?;
+
+        let voltage_at = |element: usize, side: &Vec<usize>| -> f64 {
+            container
+                .borrow()
+                .node_at(element, side)
+                .and_then(|id| voltages.iter().find(|(x, _)| *x == id))
+                .map(|(_, voltage)| *voltage)
+                .unwrap_or(0.0)
+        };
+
+        let mut errors: Vec<StatusError> = Vec::new();
+        for source in self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().class == VoltageSrc)
+        {
+            let source = source.borrow();
+            let mut terminal: Vec<usize> = source.positive.clone();
+            terminal.push(source.id);
+
+            let mut connected: Vec<f64> = vec![voltage_at(source.id, &source.positive)];
+            for id in source.positive.iter() {
+                let element = self.get_element_by_id(*id).borrow();
+                if element.class == Ground {
+                    continue;
+                }
+                // The side of the element attached to the terminal contains the rest of it
+                let rest: Vec<usize> = terminal.iter().filter(|x| **x != *id).copied().collect();
+                let far_side: &Vec<usize> = if rest.iter().all(|x| element.positive.contains(x)) {
+                    &element.negative
+                } else {
+                    &element.positive
+                };
+                connected.push(voltage_at(*id, far_side));
+            }
+
+            let below: usize = connected.iter().filter(|x| **x < -1e-9).count();
+            let above: usize = connected.iter().filter(|x| **x > 1e-9).count();
+            if below > above {
+                errors.push(Known(format!(
+                    "Voltage source {} may be reversed, its positive terminal connects to {} nodes below ground and {} above. Swap its terminals if this is not intended",
+                    source.pretty_string(),
+                    below,
+                    above
+                )));
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors[0].clone()),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    /// Check the independent sources against the passive sign convention
+    ///
+    /// Passive elements take current in at their positive terminal, sources push it out of
+    /// theirs. After a DC pre-solve, a voltage source whose current enters its positive
+    /// terminal absorbs power and is reported as possibly reversed, as is a current source
+    /// that pushes its current up to a lower voltage. A source charged by a larger one is
+    /// reported as well, the check cannot tell it from a wiring mistake.
+    /// Passive elements follow the convention by definition, a negative current only means
+    /// the voltage across them is negative too.
+    pub fn check_passive_sign_convention(&self) -> Result<(), StatusError> {
+        self.validate()?;
+
+        let container: Rc<RefCell<Container>> = Rc::new(RefCell::new(self.dc_equivalent()?));
+        let mut solver: NodeMatrixSolver = Solver::new(container);
+        solver.solve()?;
+
+        let mut errors: Vec<StatusError> = Vec::new();
+        for source in self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().class == VoltageSrc)
+        {
+            let source = source.borrow();
+            let absorbed: f64 = solver.current_through_source(source.id)? * source.value;
+            if absorbed > 1e-9 {
+                errors.push(Known(format!(
+                    "Voltage source {} absorbs {} W, its current enters the positive terminal. Swap its terminals if it should supply the circuit",
+                    source.pretty_string(),
+                    absorbed
+                )));
+            }
+        }
+
+        let solution: Solution = Solution::from_solver(&solver)?;
+        for result in solution.elements.iter().filter(|x| x.class == CurrentSrc) {
+            if result.power > 1e-9 {
+                errors.push(Known(format!(
+                    "Current source {} absorbs {} W, it drives its current into the lower voltage. Swap its terminals if it should supply the circuit",
+                    self.get_element_by_id(result.id).borrow().pretty_string(),
+                    result.power
+                )));
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors[0].clone()),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    /// Check that every current source drives a closed circuit
+    ///
+    /// An ideal current source with nothing on one of its terminals forces its current into an
+    /// open circuit, which needs an infinite voltage. Works on the element terminals, nodes do
+    /// not need to exist. Detached sources are skipped.
+    pub fn validate_no_open_circuit_current_source(&self) -> Result<(), StatusError> {
+        let mut errors: Vec<StatusError> = Vec::new();
+        for source in self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().class == CurrentSrc && !x.borrow().is_detached())
+        {
+            let source = source.borrow();
+            for (side, name) in [
+                (&source.positive, "positive"),
+                (&source.negative, "negative"),
+            ] {
+                if side.iter().all(|x| *x == source.id) {
+                    errors.push(Known(format!(
+                        "Current source {} has an open {} terminal, nothing else is connected to it",
+                        source.pretty_string(),
+                        name
+                    )));
+                }
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors[0].clone()),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    /// Run every validator and collect all of their issues instead of the first one
+    ///
+    /// Covers `validate`, node creation, `validate_voltage_source_count`, nodes that only
+    /// connect current sources, nodes without a path to ground when ground is connected and
+    /// the warnings recorded while creating nodes. Nodes are created
+    /// on a copy. The source polarity heuristic needs a solve, it only runs when nothing
+    /// else is wrong. Multiple errors are flattened and every issue is listed once.
+    pub fn validate_all(&self) -> Vec<(Severity, StatusError)> {
+        if self.elements.is_empty() {
+            return vec![(Severity::Error, StatusError::EmptyCircuit)];
+        }
+
+        let mut errors: Vec<StatusError> = Vec::new();
+        let mut warnings: Vec<StatusError> = Vec::new();
+        let flatten = |result: Result<(), StatusError>| -> Vec<StatusError> {
+            match result {
+                Ok(()) => vec![],
+                Err(StatusError::Multiple(list)) => list,
+                Err(error) => vec![error],
+            }
+        };
+
+        errors.append(&mut flatten(self.validate().map(|_| ())));
+
+        // Node creation looks every connection up, it must not run into a missing element
+        let count: usize = self.elements.len();
+        let connected: bool = self.elements.iter().all(|x| {
+            let x = x.borrow();
+            x.positive
+                .iter()
+                .chain(x.negative.iter())
+                .all(|id| *id < count)
+        });
+        let mut noded: Container = self.clone();
+        let created: bool = match (connected, noded.nodes().is_empty()) {
+            (false, _) => false,
+            (true, false) => true,
+            (true, true) => match noded.create_nodes() {
+                Ok(_) => true,
+                Err(error) => {
+                    errors.append(&mut flatten(Err(error)));
+                    false
+                }
+            },
+        };
+        if created {
+            errors.append(&mut flatten(noded.validate_voltage_source_count()));
+            errors.append(&mut noded.current_source_node_errors());
+            // Without a connected ground every node would be reported as floating
+            if noded.grounded_nodes().len() > 1 {
+                errors.append(&mut noded.floating_node_errors());
+            }
+            warnings.extend(noded.warnings.iter().map(|x| Known(x.message.clone())));
+        } else {
+            warnings.extend(self.warnings.iter().map(|x| Known(x.message.clone())));
+        }
+
+        if errors.is_empty() {
+            warnings.append(&mut flatten(self.validate_source_polarity()));
+        }
+
+        let mut issues: Vec<(Severity, StatusError)> = Vec::new();
+        let issues_iter = errors
+            .into_iter()
+            .map(|x| (Severity::Error, x))
+            .chain(warnings.into_iter().map(|x| (Severity::Warning, x)));
+        for issue in issues_iter {
+            if !issues.contains(&issue) {
+                issues.push(issue);
+            }
+        }
+        issues
+    }
+
+    /// Nodes that no chain of elements connects to ground, their voltage is undefined
+    fn floating_node_errors(&self) -> Vec<StatusError> {
+        let grounded: Vec<usize> = self.grounded_nodes();
+        self.node_ids()
+            .into_iter()
+            .filter(|x| !grounded.contains(x))
+            .map(|x| Known(floating_node_message(x)))
+            .collect()
+    }
+
+    /// Nodes where every element is a current source, KCL can only hold by coincidence
+    fn current_source_node_errors(&self) -> Vec<StatusError> {
+        self.current_source_nodes()
+            .into_iter()
+            .map(|x| Known(current_source_node_message(x)))
+            .collect()
+    }
+
+    /// Ids of the nodes that only connect current sources
+    pub(crate) fn current_source_nodes(&self) -> Vec<usize> {
+        self.nodes()
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .filter(|node| {
+                let members: Vec<Rc<RefCell<Element>>> = node
+                    .borrow()
+                    .members
+                    .iter()
+                    .filter_map(|x| x.upgrade())
+                    .collect();
+                !members.is_empty() && members.iter().all(|x| x.borrow().class == CurrentSrc)
+            })
+            .map(|x| x.borrow().id)
+            .collect()
+    }
+
+    /// Ids of the nodes reached from ground through the elements, ground included.
+    pub(crate) fn grounded_nodes(&self) -> Vec<usize> {
+        let mut grounded: Vec<usize> = vec![0];
+        let mut queue: VecDeque<usize> = VecDeque::from([0]);
+        while let Some(node) = queue.pop_front() {
+            for element in self.elements.iter() {
+                let element = element.borrow();
+                if element.class == Ground || element.is_detached() {
+                    continue;
+                }
+                let (a, b) = match (
+                    self.node_at(element.id, &element.positive),
+                    self.node_at(element.id, &element.negative),
+                ) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => continue,
+                };
+                let other: usize = match (a == node, b == node) {
+                    (true, _) => b,
+                    (_, true) => a,
+                    _ => continue,
+                };
+                if !grounded.contains(&other) {
+                    grounded.push(other);
+                    queue.push_back(other);
+                }
+            }
+        }
+        grounded
+    }
+
+    /// Copy with its nodes created, or the container itself if it already has nodes
+    pub(crate) fn with_nodes(&self) -> Result<Container, StatusError> {
+        let mut container: Container = self.clone();
+        if container.nodes().is_empty() {
+            container.create_nodes()?;
+        }
+        Ok(container)
+    }
+
+    /// Whether every node can be reached from ground through the elements
+    ///
+    /// Nodes are created on a copy if there are none yet, a circuit whose nodes cannot be
+    /// created is not connected.
+    pub fn is_connected(&self) -> bool {
+        match self.with_nodes() {
+            Ok(container) => container.grounded_nodes().len() == container.nodes().len() + 1,
+            Err(_) => false,
+        }
+    }
+
+    /// Number of independent loops from Euler's formula `L = B - N + 1`
+    ///
+    /// B counts every element but ground and detached elements, N counts the nodes
+    /// including ground. The formula holds for a connected circuit, every additional
+    /// separate part adds one loop that is not counted, see `is_connected`.
+    pub fn number_of_loops(&self) -> Result<usize, StatusError> {
+        let container: Container = self.with_nodes()?;
+        Ok((container.branch_count() + 1).saturating_sub(container.nodes().len() + 1))
+    }
+
+    /// Elements that form a branch, every element but ground and detached elements.
+    pub fn branch_count(&self) -> usize {
+        self.elements
+            .iter()
+            .filter(|x| x.borrow().class != Ground && !x.borrow().is_detached())
+            .count()
+    }
+
+    /// Number of elements of each class, keyed by `Component::as_str`
+    pub fn count_elements_by_type(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for element in &self.elements {
+            *counts.entry(element.borrow().class.as_str()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// One line description of the resistors, sources, nodes and loops of the circuit
+    ///
+    /// e.g. "Circuit contains 2 resistors (R1=1Ω, R2=1Ω), 1 voltage source (SRC(V)3=1V),
+    /// 0 current sources. Total nodes: 3. Total loops: 1." Nodes include ground.
+    pub fn component_summary(&self) -> String {
+        let counts: BTreeMap<&'static str, usize> = self.count_elements_by_type();
+        let classes: [(Component, &str); 3] = [
+            (Resistor, "resistor"),
+            (VoltageSrc, "voltage source"),
+            (CurrentSrc, "current source"),
+        ];
+        let parts: Vec<String> = classes
+            .into_iter()
+            .map(|(class, noun)| {
+                let count: usize = counts.get(class.as_str()).copied().unwrap_or(0);
+                let plural: &str = if count == 1 { "" } else { "s" };
+                let values: Vec<String> = self
+                    .elements
+                    .iter()
+                    .map(|x| x.borrow())
+                    .filter(|x| x.class == class)
+                    .map(|x| format!("{}={}{}", x.basic_string(), x.value, class.unit().symbol()))
+                    .collect();
+                match values.is_empty() {
+                    true => format!("{} {}{}", count, noun, plural),
+                    false => format!("{} {}{} ({})", count, noun, plural, values.join(", ")),
+                }
+            })
+            .collect();
+
+        let totals: String = match (self.with_nodes(), self.number_of_loops()) {
+            (Ok(container), Ok(loops)) => format!(
+                "Total nodes: {}. Total loops: {}.",
+                container.nodes().len() + 1,
+                loops
+            ),
+            (Err(error), _) | (_, Err(error)) => {
+                format!("Nodes could not be created: {}.", String::from(error))
+            }
+        };
+        format!("Circuit contains {}. {}", parts.join(", "), totals)
+    }
+
+    /// Deep copy of the elements with new values
+    fn map_values<F: Fn(&Element) -> f64>(&self, value: F) -> Container {
+        let mut container = Container::new();
+        container.ground = self.ground;
+        for element in &self.elements {
+            let mut copy: Element = element.borrow().clone();
+            copy.value = value(&copy);
+            container.add_element_core(copy);
+        }
+        container
+    }
+
+    /// Get all the node pairs in the circuit.
+    ///
+    /// Returns a vector of tuples containing the node ids and the element
+    pub fn get_all_node_pairs(&self) -> Vec<(usize, usize, Rc<RefCell<Element>>)> {
+        let mut node_to_node_resistors: Vec<(usize, usize, Rc<RefCell<Element>>)> = Vec::new();
+
+        for element in self.elements.iter() {
+            if node_to_node_resistors
+                .iter()
+                .any(|x| x.2.borrow().id == element.borrow().id)
+                || element.borrow().class == Ground
+                || element.borrow().is_detached()
+            {
+                continue;
+            }
+
+            let tools = self.get_tools_for_element(element.borrow().id);
+            if element.borrow().connected_to_ground() {
+                node_to_node_resistors.push((
+                    tools[0].upgrade().unwrap().borrow().id,
+                    0,
+                    element.clone(),
+                ));
+            } else {
+                node_to_node_resistors.push((
+                    tools[0].upgrade().unwrap().borrow().id,
+                    tools[1].upgrade().unwrap().borrow().id,
+                    element.clone(),
+                ));
+            }
+        }
+
+        node_to_node_resistors
+    }
+
+    #[deprecated(note = "use get_elements_by_type(VoltageSrc)")]
+    pub fn get_voltage_sources(&self) -> Vec<Weak<RefCell<Element>>> {
+        self.get_elements_by_type(VoltageSrc)
+    }
+
+    /// Returns every element of the given class in id order
+    /// Note Weak RCs are returned
+    ///
+    /// ```
+    /// use circuit_solver_algorithms::component::Component;
+    /// use circuit_solver_algorithms::component::Component::*;
+    /// use circuit_solver_algorithms::util::create_mna_container_2;
+    ///
+    /// let container = create_mna_container_2();
+    /// let count = |class: Component| container.get_elements_by_type(class).len();
+    /// assert_eq!(count(Ground), 1);
+    /// assert_eq!(count(Resistor), 3);
+    /// assert_eq!(count(CurrentSrc), 1);
+    /// assert_eq!(count(VoltageSrc), 1);
+    /// for class in [DependentVoltage, DependentCurrent, Switch, Inductor, Capacitor] {
+    ///     assert_eq!(count(class), 0);
+    /// }
+    /// ```
+    pub fn get_elements_by_type(&self, class: Component) -> Vec<Weak<RefCell<Element>>> {
+        self.elements
+            .iter()
+            .filter(|x| x.borrow().class == class)
+            .map(|x| Rc::downgrade(x))
+            .collect()
+    }
+}
+
+pub(crate) fn dc_inductor_message(element: &Element) -> String {
+    format!(
+        "Inductor {} is a short circuit at DC, enable allow_dc_inductors to solve it as one",
+        element.pretty_string()
+    )
+}
+
+pub(crate) fn floating_node_message(node: usize) -> String {
+    format!(
+        "Node {} has no path to ground, its voltage is undefined",
+        node
+    )
+}
+
+pub(crate) fn current_source_node_message(node: usize) -> String {
+    format!(
+        "Node {} only connects current sources, the currents into it cannot balance",
+        node
+    )
+}
+
+impl Validation for Container {
+    /// Validate the Container and the circuit within are usable.
+    ///
+    /// This function will check that the Container is in a valid state to be solved.
+    /// It will make calls to validate functions in the elements themselves and let
+    /// them handle their own internal validation. This will take care of the high
+    /// level validation.
+    ///
+    /// * All Elements have a valid Component, Value, Positive, and Negative
+    /// * No duplicate Elements or Tools
+    /// * Contains at least one source and a single ground
+    /// * No floating Elements, Tools, etc.
+    /// * No shorted or open Elements
+    fn validate(&self) -> ValidationResult {
+        if self.elements.is_empty() {
+            return Err(StatusError::EmptyCircuit);
+        }
+
+        let mut errors: Vec<StatusError> = Vec::new();
+
+        // Check that all elements and tools are valid individually
+        errors.append(&mut get_all_internal_status_errors(&self.elements));
+        errors.append(&mut get_all_internal_status_errors(&self.tools));
+
+        // Check that there are no duplicates in elements or tools
+        errors.append(&mut check_duplicates(&self.elements));
+        errors.append(&mut check_duplicates(&self.tools));
+
+        // Check that there is at least one source and a single ground
+        if !self.elements.iter().any(|x| x.borrow().class.is_source()) {
+            errors.push(StatusError::NoSourcePresent);
+        }
+        if self
+            .elements
+            .iter()
+            .filter(|x| x.borrow().class == Ground)
+            .count()
+            != 1
+        {
+            errors.push(Known("Multiple Grounds".parse().unwrap()));
+        }
+
+        // An element only has two terminals so it can be a member of at most two nodes
+        for element in &self.elements {
+            let count: usize = self
+                .tools
+                .iter()
+                .filter(|x| x.borrow().class == ToolType::Node)
+                .filter(|x| x.borrow().contains(element.clone()))
+                .count();
+            if count > 2 {
+                errors.push(Known(format!(
+                    "Element {} appears in {} nodes but only has two terminals",
+                    element.pretty_string(),
+                    count
+                )));
+            }
+        }
+
+        errors.append(&mut self.port_errors());
+        errors.append(&mut self.control_errors());
+
+        match errors.len() {
+            0 => Ok(Status::Valid),
+            1 => Err(errors[0].clone()),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    fn id(&self) -> usize {
+        panic!("Container does not have an id")
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::component::Component::{Ground, Resistor};
+    use crate::assert_circuit_eq;
+    use crate::assert_known_error;
+    use crate::component::Component;
+    use crate::component::Component::{
+        Capacitor, CurrentSrc, DependentVoltage, Ground, Inductor, Resistor, VoltageSrc,
+    };
     use crate::container::Container;
-    use crate::elements::Element;
-    use crate::tools::ToolType::{Mesh, SuperNode};
+    use crate::elements::{Control, Element};
+    use crate::solvers::node_matrix_solver::{form_g_matrix, NodeMatrixSolver};
+    use crate::solvers::options::RowOrder;
+    use crate::solvers::solver::{NodeSolver, Solver};
+    use crate::tools::Tool;
+    use crate::tools::ToolType::{Mesh, SuperMesh, SuperNode};
     use crate::util::*;
     use crate::validation::Status::Valid;
-    use crate::validation::{StatusError, Validation};
+    use crate::validation::StatusError::Known;
+    use crate::validation::{Severity, StatusError, Validation, WarningKind};
+    use nalgebra::{DMatrix, DVector};
+    use operations::math::EquationMember;
     use regex_lite::Regex;
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     #[test]
     fn test_debug() {
@@ -419,6 +2771,16 @@ mod tests {
         assert!(container.validate().is_err());
     }
 
+    #[test]
+    fn test_empty_and_sourceless() {
+        assert_eq!(Container::new().validate(), Err(StatusError::EmptyCircuit));
+
+        let mut container = Container::new();
+        container.add_element_no_id(Element::new(Ground, 0., vec![1], vec![]));
+        container.add_element_no_id(Element::new(Resistor, 1.0, vec![0], vec![0]));
+        assert_eq!(container.validate(), Err(StatusError::NoSourcePresent));
+    }
+
     #[test]
     fn test_add_element() {
         let mut container = create_basic_container();
@@ -521,7 +2883,7 @@ mod tests {
         assert_eq!(basic.validate(), Ok(Valid));
         assert_eq!(basic.tools.len(), 3);
 
-        let mesh_members: Vec<usize> = vec![0, 1, 2];
+        let mesh_members: Vec<usize> = vec![1, 2, 3];
         let mesh = basic.get_tools(Mesh).get(0).unwrap().upgrade().unwrap();
         assert_eq!(mesh.borrow().members.len(), mesh_members.len());
         for member in mesh.borrow().members.iter() {
@@ -529,6 +2891,1018 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_duplicate_membership() {
+        let mut container = Container::new();
+        container.add_element_no_id(Element::new(Ground, 1.0, vec![3, 2], vec![]));
+        container.add_element_no_id(Element::new(Resistor, 1.0, vec![3], vec![2]));
+        container.add_element_no_id(Element::new(Resistor, 1.0, vec![1, 1], vec![0, 3]));
+        container.add_element_no_id(Element::new(VoltageSrc, 1.0, vec![2, 0], vec![1]));
+        container.create_nodes().unwrap();
+        assert_eq!(container.validate(), Ok(Valid));
+
+        let node = container.get_tool_by_id(1);
+        assert_eq!(node.borrow().member_ids(), vec![1, 2]);
+        assert_eq!(container.warnings().len(), 1);
+        assert_eq!(
+            container.warnings()[0].kind,
+            WarningKind::DuplicateMembership
+        );
+        assert_eq!(
+            container.warnings()[0].message,
+            "Element 1 appears more than once in Node 2, the duplicate was removed"
+        );
+
+        container.rebuild_nodes().unwrap();
+        assert_eq!(container.warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_element_in_too_many_nodes() {
+        let mut container = create_basic_container();
+        container.create_nodes().unwrap();
+        container.add_tool(Tool::create_node(vec![
+            Rc::downgrade(container.get_element_by_id(1)),
+            Rc::downgrade(container.get_element_by_id(3)),
+        ]));
+        assert_known_error!(
+            container.validate(),
+            "Element R1: 1 Ω appears in 3 nodes but only has two terminals"
+        );
+    }
+
+    #[test]
+    fn test_get_supernode_for() {
+        let mut container = create_basic_supernode_container();
+        container
+            .create_nodes()
+            .unwrap()
+            .create_super_nodes()
+            .unwrap();
+
+        for node_id in [1, 2] {
+            let super_node = container.get_supernode_for(node_id).unwrap();
+            assert_eq!(super_node.borrow().class, SuperNode);
+            assert_eq!(super_node.borrow().id, 4);
+        }
+        assert!(container.get_supernode_for(3).is_none());
+        assert!(container.get_supernode_for(4).is_none());
+        assert!(container.get_supernode_for(99).is_none());
+    }
+
+    #[test]
+    fn test_get_supermesh_for() {
+        let mut basic: Container = create_basic_container();
+        basic.create_nodes().unwrap();
+        basic.create_meshes();
+        basic.create_super_meshes();
+
+        let mesh_id: usize = basic.get_tools(Mesh)[0].upgrade().unwrap().borrow().id;
+        assert!(basic.get_supermesh_for(mesh_id).is_none());
+        assert!(basic.get_supermesh_for(99).is_none());
+    }
+
+    #[test]
+    fn test_create_super_meshes() {
+        let mesh = |c: &Container, members: [usize; 3]| -> Tool {
+            Tool::create_mesh(
+                members
+                    .iter()
+                    .map(|x| Rc::downgrade(c.get_element_by_id(*x)))
+                    .collect(),
+            )
+        };
+
+        // SRC(C)2 is shared by the first two meshes, SRC(C)4 only sits on the third
+        let mut c: Container = create_basic_supermesh_container();
+        for members in [[1, 2, 5], [2, 3, 6], [4, 6, 7]] {
+            let tool: Tool = mesh(&c, members);
+            c.add_tool(tool);
+        }
+        c.create_super_meshes();
+        let super_meshes = c.get_tools(SuperMesh);
+        assert_eq!(super_meshes.len(), 1);
+        assert_eq!(
+            super_meshes[0].upgrade().unwrap().borrow().member_ids(),
+            vec![1, 2, 5, 3, 6]
+        );
+        assert_eq!(c.get_supermesh_for(1).unwrap().borrow().id, 4);
+        assert_eq!(c.get_supermesh_for(2).unwrap().borrow().id, 4);
+        assert!(c.get_supermesh_for(3).is_none());
+
+        // Meshes chained through both sources become one supermesh
+        let mut c: Container = create_basic_supermesh_container();
+        for members in [[1, 2, 5], [2, 4, 6], [3, 4, 7]] {
+            let tool: Tool = mesh(&c, members);
+            c.add_tool(tool);
+        }
+        c.create_super_meshes();
+        let super_meshes = c.get_tools(SuperMesh);
+        assert_eq!(super_meshes.len(), 1);
+        assert_eq!(
+            super_meshes[0].upgrade().unwrap().borrow().member_ids(),
+            vec![1, 2, 5, 4, 6, 3, 7]
+        );
+        for id in 1..=3 {
+            assert_eq!(c.get_supermesh_for(id).unwrap().borrow().id, 4);
+        }
+    }
+
+    #[test]
+    fn test_scale_all_impedances() {
+        let mut original = create_mna_container();
+        assert_circuit_eq!(original.scale_all_impedances(1.), original);
+        let mut scaled = original.scale_all_impedances(1000.);
+        assert_eq!(scaled.get_element_by_id(1).borrow().value, 2000.);
+        assert_eq!(scaled.get_element_by_id(4).borrow().value, 32.);
+        assert_eq!(original.get_element_by_id(1).borrow().value, 2.);
+
+        original.create_nodes().unwrap();
+        scaled.create_nodes().unwrap();
+        let mut original: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(original)));
+        let mut scaled: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(scaled)));
+        original.solve().unwrap();
+        scaled.solve().unwrap();
+        assert_eq!(scaled.compare_with(&original, 1e-9), vec![]);
+    }
+
+    #[test]
+    fn test_scale_all_frequencies() {
+        let mut container = Container::new();
+        container.add_element_no_id(Element::new(Ground, 0., vec![1, 3], vec![]));
+        container.add_element_no_id(Element::new(VoltageSrc, 1., vec![2], vec![0]));
+        container.add_element_no_id(Element::new(Inductor, 2., vec![1], vec![3]));
+        container.add_element_no_id(Element::new(Capacitor, 4., vec![2], vec![0]));
+        let scaled = container.scale_all_frequencies(2.);
+        let values: Vec<f64> = scaled
+            .get_elements()
+            .iter()
+            .map(|x| x.borrow().value)
+            .collect();
+        assert_eq!(values, vec![0., 1., 1., 2.]);
+
+        // DC node voltages do not depend on the frequency
+        let mut original = create_mna_container();
+        let mut scaled = original.scale_all_frequencies(10.);
+        original.create_nodes().unwrap();
+        scaled.create_nodes().unwrap();
+        let mut original: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(original)));
+        let mut scaled: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(scaled)));
+        original.solve().unwrap();
+        scaled.solve().unwrap();
+        assert_eq!(scaled.compare_with(&original, 1e-9), vec![]);
+    }
+
+    #[test]
+    fn test_normalize_to_1_ohm_1_rad() {
+        // Second order low pass, R = 50 Ω, L = 10 mH, C = 1 µF so ω0 = 10 krad/s
+        let mut container = Container::new();
+        container.add_element_no_id(Element::new(Ground, 0., vec![1, 4], vec![]));
+        container.add_element_no_id(Element::new(VoltageSrc, 1., vec![2], vec![0]));
+        container.add_element_no_id(Element::new(Resistor, 50., vec![1], vec![3]));
+        container.add_element_no_id(Element::new(Inductor, 1e-2, vec![2], vec![4]));
+        container.add_element_no_id(Element::new(Capacitor, 1e-6, vec![3], vec![0]));
+
+        let (normalized, resistance, omega) = container.normalize_to_1_ohm_1_rad().unwrap();
+        assert!((resistance - 50.).abs() < 1e-6);
+        assert!((omega - 1e4).abs() < 1e-6);
+        assert_eq!(container.get_element_by_id(2).borrow().value, 50.);
+
+        let values: Vec<f64> = normalized
+            .get_elements()
+            .iter()
+            .map(|x| x.borrow().value)
+            .collect();
+        let expected: Vec<f64> = vec![0., 1., 1., 2., 0.5];
+        for (value, expected) in values.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+
+        assert!(create_mna_container().normalize_to_1_ohm_1_rad().is_err());
+    }
+
+    #[test]
+    fn test_validate_source_polarity() {
+        let mut container = Container::new();
+        container.add_element_no_id(Element::new(Ground, 0., vec![1, 3], vec![]));
+        container.add_element_no_id(Element::new(VoltageSrc, 10., vec![2], vec![0, 3]));
+        container.add_element_no_id(Element::new(Resistor, 2., vec![1], vec![3]));
+        container.add_element_no_id(Element::new(Resistor, 2., vec![2], vec![0, 1]));
+        assert_eq!(container.validate_source_polarity(), Ok(()));
+        assert_eq!(create_ladder_container().validate_source_polarity(), Ok(()));
+
+        let mut reversed = Container::new();
+        reversed.add_element_no_id(Element::new(Ground, 0., vec![1, 3], vec![]));
+        reversed.add_element_no_id(Element::new(VoltageSrc, 10., vec![0, 3], vec![2]));
+        reversed.add_element_no_id(Element::new(Resistor, 2., vec![1], vec![3]));
+        reversed.add_element_no_id(Element::new(Resistor, 2., vec![2], vec![0, 1]));
+        assert!(matches!(
+            reversed.validate_source_polarity(),
+            Err(Known(message)) if message.starts_with("Voltage source SRC(V)1: 10 V may be reversed")
+        ));
+    }
+
+    #[test]
+    fn test_check_passive_sign_convention() {
+        let mut divider = Container::new();
+        divider.add_element_no_id(Element::new(Ground, 0., vec![1, 3], vec![]));
+        divider.add_element_no_id(Element::new(VoltageSrc, 10., vec![2], vec![0, 3]));
+        divider.add_element_no_id(Element::new(Resistor, 2., vec![1], vec![3]));
+        divider.add_element_no_id(Element::new(Resistor, 2., vec![2], vec![0, 1]));
+        assert_eq!(divider.check_passive_sign_convention(), Ok(()));
+
+        // The 32 V source drives 20 W into the 20 V one
+        assert!(matches!(
+            create_mna_container().check_passive_sign_convention(),
+            Err(Known(message)) if message.starts_with("Voltage source SRC(V)5: 20 V absorbs 20")
+        ));
+
+        // I3 pushes 1 A into node 1 at 10 V and supplies 10 W
+        let current = |a: usize, b: usize| {
+            let mut c = Container::new();
+            c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+            c.add_element_between_nodes(Resistor, 10., 1, 0);
+            c.add_element_between_nodes(CurrentSrc, 1., a, b);
+            c
+        };
+        assert_eq!(current(1, 0).check_passive_sign_convention(), Ok(()));
+        // Reversed it draws 1 A out of node 1 and absorbs 10 W
+        assert!(matches!(
+            current(0, 1).check_passive_sign_convention(),
+            Err(Known(message)) if message.starts_with("Current source SRC(C)3: 1 A absorbs 10")
+        ));
+    }
+
+    #[test]
+    fn test_validate_no_open_circuit_current_source() {
+        let mut closed = Container::new();
+        closed.add_element_between_nodes(CurrentSrc, 1., 1, 0);
+        closed.add_element_between_nodes(Resistor, 2., 1, 0);
+        assert_eq!(closed.validate_no_open_circuit_current_source(), Ok(()));
+        assert_eq!(
+            create_mna_container().validate_no_open_circuit_current_source(),
+            Ok(())
+        );
+
+        // Nothing else sits on node 1
+        let mut open = Container::new();
+        open.add_element_between_nodes(CurrentSrc, 1., 1, 0);
+        open.add_element_between_nodes(Resistor, 2., 2, 0);
+        assert!(matches!(
+            open.validate_no_open_circuit_current_source(),
+            Err(Known(message)) if message == "Current source SRC(C)1: 1 A has an open positive terminal, nothing else is connected to it"
+        ));
+
+        let mut floating = Container::new();
+        floating.add_element_no_id(Element::new(CurrentSrc, 1., vec![], vec![]));
+        assert!(matches!(
+            floating.validate_no_open_circuit_current_source(),
+            Err(StatusError::Multiple(errors)) if errors.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_equivalent_circuit_between() {
+        let mut c: Container = bridge(1., 2., 3., 4., 5., 10.);
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+        solver.solve().unwrap();
+
+        // Across the galvanometer, across a leg and across the source
+        for (a, b) in [(2, 3), (3, 0), (1, 0)] {
+            let equivalent: Container = c.equivalent_circuit_between(a, b).unwrap();
+            assert_eq!(equivalent.validate(), Ok(Valid));
+            let port = equivalent.get_port("equivalent").unwrap().clone();
+            let resistance: f64 = equivalent.impedance_at_port("equivalent").unwrap();
+            assert!((resistance - c.impedance_at(a, b).unwrap()).abs() < 1e-9);
+
+            let mut reduced: NodeMatrixSolver =
+                Solver::new(Rc::new(RefCell::new(equivalent.clone())));
+            reduced.solve().unwrap();
+            let voltage: f64 = reduced
+                .voltage_between(port.positive, port.negative)
+                .unwrap();
+            assert!((voltage - solver.voltage_between(a, b).unwrap()).abs() < 1e-9);
+        }
+
+        // Ground, a current source and a resistor, the source across node 1 stays ideal
+        let classes = |a: usize, b: usize| -> Vec<Component> {
+            let equivalent: Container = c.equivalent_circuit_between(a, b).unwrap();
+            let classes = equivalent
+                .get_elements()
+                .iter()
+                .map(|x| x.borrow().class.clone());
+            classes.collect()
+        };
+        assert_eq!(classes(2, 3), vec![Ground, CurrentSrc, Resistor]);
+        assert_eq!(classes(1, 0), vec![Ground, VoltageSrc]);
+    }
+
+    #[test]
+    fn test_validate_reciprocity() {
+        let mut resistive: Container = bridge(1., 2., 3., 4., 5., 10.);
+        assert_eq!(resistive.validate_reciprocity(), vec![]);
+        assert_eq!(create_mna_container_2().validate_reciprocity(), vec![]);
+
+        resistive.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(resistive.clone())));
+        let (nodes, z) = solver.impedance_matrix().unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert!(z.iter().filter(|x| **x > 0.).count() >= 4);
+        assert!((&z - z.transpose()).norm() < 1e-12);
+
+        // A voltage controlled voltage source across the galvanometer
+        resistive.add_element_between_nodes(DependentVoltage, 2., 2, 3);
+        let issues = resistive.validate_reciprocity();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, Severity::Warning);
+        assert!(matches!(
+            &issues[0].1,
+            Known(message) if message.starts_with("Dependent source") && message.ends_with("makes the circuit non-reciprocal")
+        ));
+    }
+
+    #[test]
+    fn test_element_current_direction() {
+        let mut container: Container = create_mna_container();
+        container.create_nodes().unwrap();
+        assert_eq!(container.element_current_direction(1), (0, 3));
+        assert_eq!(container.element_current_direction(2), (1, 2));
+        assert_eq!(container.element_current_direction(4), (3, 2));
+        assert_eq!(container.element_current_direction(5), (1, 0));
+    }
+
+    #[test]
+    fn test_add_element_between_nodes() {
+        let mut c = Container::new();
+        assert_eq!(c.add_element_between_nodes(Resistor, 2., 0, 3), 1);
+        assert_eq!(c.add_element_between_nodes(Resistor, 4., 1, 2), 2);
+        assert_eq!(c.add_element_between_nodes(Resistor, 8., 2, 0), 3);
+        assert_eq!(c.add_element_between_nodes(VoltageSrc, 32., 3, 2), 4);
+        assert_eq!(c.add_element_between_nodes(VoltageSrc, 20., 1, 0), 5);
+        assert_eq!(c.validate(), Ok(Valid));
+
+        let expected = create_mna_container();
+        assert_eq!(c.get_elements().len(), expected.get_elements().len());
+        for (element, other) in c.get_elements().iter().zip(expected.get_elements()) {
+            let (element, other) = (element.borrow(), other.borrow());
+            assert_eq!(element.class, other.class);
+            assert_eq!(element.value, other.value);
+            assert_eq!(element.positive, other.positive, "{}", element.id);
+            assert_eq!(element.negative, other.negative, "{}", element.id);
+        }
+
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        for (node, voltage) in [(1, 20.), (2, 24.), (3, -8.)] {
+            assert!((solver.node_voltage(node).unwrap() - voltage).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_element_power_factor() {
+        // V5 drives R1 and L2 in series with R3 and C4 in parallel
+        let c = create_rlc_container();
+        for frequency in [0., 50., 1e3, 1e6] {
+            assert_eq!(c.element_power_factor(1, frequency), Ok(1.0));
+            assert_eq!(c.element_power_factor(3, frequency), Ok(1.0));
+            assert_eq!(c.element_power_factor(2, frequency), Ok(0.0));
+            assert_eq!(c.element_power_factor(4, frequency), Ok(0.0));
+            // R3 and C4 are in parallel, not a series loop
+            assert_known_error!(
+                c.element_power_factor(5, frequency),
+                "The power factor of V5: 10 V needs an AC solve, only a source driving a series loop of resistors, inductors and capacitors is supported"
+            );
+        }
+
+        // V1 drives R2 and C3 in series, R / sqrt(R^2 + (1 / wC)^2)
+        let mut series = Container::new();
+        series.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        series.add_element_between_nodes(Resistor, 100., 1, 2);
+        series.add_element_between_nodes(Capacitor, 1e-6, 2, 0);
+        for frequency in [50., 1591.5494309189535, 1e5] {
+            let reactance: f64 = 1. / (2. * std::f64::consts::PI * frequency * 1e-6);
+            let expected: f64 = 100. / (100_f64.powi(2) + reactance.powi(2)).sqrt();
+            let factor: f64 = series.element_power_factor(1, frequency).unwrap();
+            assert!((factor - expected).abs() < 1e-12);
+        }
+        // R equals 1 / wC at about 1.59 kHz
+        let factor: f64 = series.element_power_factor(1, 1591.5494309189535).unwrap();
+        assert!((factor - 0.5_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(series.element_power_factor(1, 0.), Ok(0.0));
+
+        assert_known_error!(c.element_power_factor(0, 50.), "Ground has no power factor");
+        assert_known_error!(c.element_power_factor(9, 50.), "Element 9 does not exist");
+        assert_known_error!(
+            c.element_power_factor(1, -50.),
+            "Frequency cannot be negative -50 Hz"
+        );
+        let mut c = c;
+        c.remove_element(4).unwrap();
+        assert_known_error!(
+            c.element_power_factor(4, 50.),
+            "C4: 0.000001 F was removed from the circuit"
+        );
+    }
+
+    #[test]
+    fn test_validate_voltage_source_count() {
+        let mut c = create_mna_container();
+        assert_eq!(c.validate_voltage_source_count(), Ok(()));
+        c.create_nodes().unwrap();
+        assert_eq!(c.validate_voltage_source_count(), Ok(()));
+
+        // Two sources in parallel across the only node
+        let mut c = Container::new();
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 5., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 5., 1, 0);
+        assert_known_error!(
+            c.validate_voltage_source_count(),
+            "Circuit has 2 voltage sources but only 1 nodes, the MNA matrix would be over-constrained"
+        );
+
+        // Enough nodes, but V5 and V6 both set the difference between nodes 1 and 2
+        let mut c = Container::new();
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(Resistor, 1., 2, 0);
+        c.add_element_between_nodes(Resistor, 1., 3, 0);
+        c.add_element_between_nodes(Resistor, 1., 2, 3);
+        c.add_element_between_nodes(VoltageSrc, 1., 1, 2);
+        c.add_element_between_nodes(VoltageSrc, 1., 1, 2);
+        assert_known_error!(
+            c.validate_voltage_source_count(),
+            "Voltage source SRC(V)6: 1 V closes a loop of voltage sources, the MNA matrix would be over-constrained"
+        );
+    }
+
+    #[test]
+    fn test_validate_all() {
+        assert!(create_ladder_container().validate_all().is_empty());
+        assert_eq!(
+            Container::new().validate_all(),
+            vec![(Severity::Error, StatusError::EmptyCircuit)]
+        );
+
+        // A negative resistor, a loop of voltage sources and a part without ground
+        let mut c = Container::new();
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 5., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 5., 1, 0);
+        c.add_element_between_nodes(Resistor, 2., 2, 3);
+        c.add_element_between_nodes(Resistor, -2., 3, 4);
+        c.add_element_between_nodes(Resistor, 2., 4, 2);
+        let error = |x: &str| (Severity::Error, Known(x.to_string()));
+        assert_eq!(
+            c.validate_all(),
+            vec![
+                error("Value cannot be zero or negative R5: -2 Ω"),
+                error("Voltage source SRC(V)3: 5 V closes a loop of voltage sources, the MNA matrix would be over-constrained"),
+                error("Node 2 has no path to ground, its voltage is undefined"),
+                error("Node 3 has no path to ground, its voltage is undefined"),
+                error("Node 4 has no path to ground, its voltage is undefined"),
+            ]
+        );
+        assert!(c.validate().is_err());
+
+        // Only the polarity heuristic complains, as a warning
+        let mut reversed = Container::new();
+        reversed.add_element_no_id(Element::new(Ground, 0., vec![1, 3], vec![]));
+        reversed.add_element_no_id(Element::new(VoltageSrc, 10., vec![0, 3], vec![2]));
+        reversed.add_element_no_id(Element::new(Resistor, 2., vec![1], vec![3]));
+        reversed.add_element_no_id(Element::new(Resistor, 2., vec![2], vec![0, 1]));
+        let issues = reversed.validate_all();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, Severity::Warning);
+    }
+
+    #[test]
+    fn test_component_summary() {
+        let fixtures: [(fn() -> Container, &str); 5] = [
+            (
+                create_basic_container,
+                "Circuit contains 2 resistors (R1=1Ω, R2=1Ω), 1 voltage source (SRC(V)3=1V), 0 current sources. Total nodes: 3. Total loops: 1.",
+            ),
+            (
+                create_basic_supernode_container,
+                "Circuit contains 3 resistors (R2=10Ω, R3=10Ω, R4=10Ω), 2 voltage sources (SRC(V)1=10V, SRC(V)5=10V), 0 current sources. Total nodes: 4. Total loops: 2.",
+            ),
+            (
+                create_basic_supermesh_container,
+                "Circuit contains 4 resistors (R3=2Ω, R5=2Ω, R6=4Ω, R7=1Ω), 1 voltage source (SRC(V)1=3V), 2 current sources (SRC(C)2=1.5A, SRC(C)4=2A). Total nodes: 5. Total loops: 3.",
+            ),
+            (
+                create_mna_container,
+                "Circuit contains 3 resistors (R1=2Ω, R2=4Ω, R3=8Ω), 2 voltage sources (SRC(V)4=32V, SRC(V)5=20V), 0 current sources. Total nodes: 4. Total loops: 2.",
+            ),
+            (
+                create_mna_container_2,
+                "Circuit contains 3 resistors (R1=2Ω, R2=4Ω, R3=8Ω), 1 voltage source (SRC(V)5=32V), 1 current source (SRC(C)4=10A). Total nodes: 3. Total loops: 3.",
+            ),
+        ];
+        for (fixture, expected) in fixtures {
+            assert_eq!(fixture().component_summary(), expected);
+        }
+
+        let counts = create_mna_container().count_elements_by_type();
+        assert_eq!(counts["ground"], 1);
+        assert_eq!(counts["resistor"], 3);
+        assert_eq!(counts["voltage_src"], 2);
+        assert!(!counts.contains_key("current_src"));
+    }
+
+    #[test]
+    fn test_number_of_loops() {
+        let c = create_mna_container();
+        assert_eq!(c.branch_count(), 5);
+        assert_eq!(c.number_of_loops(), Ok(2));
+        assert!(c.is_connected());
+        assert_eq!(create_ladder_container().number_of_loops(), Ok(1));
+
+        // A triangle of resistors without a path to ground, the formula misses its loop
+        let mut c = Container::new();
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 5., 1, 0);
+        c.add_element_between_nodes(Resistor, 2., 2, 3);
+        c.add_element_between_nodes(Resistor, 2., 3, 4);
+        c.add_element_between_nodes(Resistor, 2., 4, 2);
+        assert!(!c.is_connected());
+        assert_eq!(c.number_of_loops(), Ok(1));
+        c.create_nodes().unwrap();
+        assert!(!c.is_connected());
+    }
+
+    #[test]
+    fn test_identify_pi_networks() {
+        // R2 feeds the π of R3, R4 and R5, node 1 only has the source to ground
+        let pi = |capacitor: bool| {
+            let mut c = Container::new();
+            c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+            c.add_element_between_nodes(Resistor, 1., 1, 2);
+            c.add_element_between_nodes(Resistor, 2., 2, 0);
+            c.add_element_between_nodes(Resistor, 3., 2, 3);
+            c.add_element_between_nodes(Resistor, 4., 3, 0);
+            if capacitor {
+                c.add_element_between_nodes(Capacitor, 1e-6, 0, 1);
+            }
+            c.create_nodes().unwrap();
+            c
+        };
+        assert_eq!(pi(false).identify_pi_networks(), vec![(3, 4, 5)]);
+        // A shunt capacitor at node 1 makes R2 the series element of a second π
+        assert_eq!(pi(true).identify_pi_networks(), vec![(6, 2, 3), (3, 4, 5)]);
+
+        // R2 has the source on one side, not a shunt element
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        assert!(c.identify_pi_networks().is_empty());
+    }
+
+    #[test]
+    fn test_pi_to_t_transform() {
+        // R3, R4 and R5 form a π between R2 and a 1 A load
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        c.add_element_between_nodes(Resistor, 1., 1, 2);
+        c.add_element_between_nodes(Resistor, 2., 2, 0);
+        c.add_element_between_nodes(Resistor, 3., 2, 3);
+        c.add_element_between_nodes(Resistor, 4., 3, 0);
+        c.add_element_between_nodes(CurrentSrc, 1., 3, 0);
+        c.create_nodes().unwrap();
+        // R2, R3 and R4 overlap the π as a T
+        assert_eq!(c.identify_t_networks(), vec![(2, 3, 4)]);
+
+        // Voltages at the terminals of the π, the nodes of R3 and R5 away from ground
+        let terminals = |c: &Container| -> Vec<f64> {
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+            solver.solve().unwrap();
+            [3, 5]
+                .into_iter()
+                .map(|id| {
+                    let element = c.get_element_by_id(id).borrow();
+                    let node: usize = c.node_at(id, &element.positive).unwrap();
+                    solver.node_voltage(node).unwrap()
+                })
+                .collect()
+        };
+        let before: Vec<f64> = terminals(&c);
+
+        assert_known_error!(
+            c.apply_pi_to_t_transform((5, 4, 3)),
+            "Elements 5, 4 and 3 do not form a π network"
+        );
+        c.apply_pi_to_t_transform((3, 4, 5)).unwrap();
+        assert!(c.identify_pi_networks().is_empty());
+        assert_eq!(c.identify_t_networks(), vec![(3, 4, 5)]);
+        // Σ = 9 Ω, the arms are 2 * 3 / 9, 2 * 4 / 9 and 3 * 4 / 9
+        let value = |id: usize| c.get_element_by_id(id).borrow().value;
+        assert!((value(3) - 2. / 3.).abs() < 1e-12);
+        assert!((value(4) - 8. / 9.).abs() < 1e-12);
+        assert!((value(5) - 4. / 3.).abs() < 1e-12);
+
+        let after: Vec<f64> = terminals(&c);
+        for (x, y) in before.iter().zip(after) {
+            assert!((x - y).abs() < 1e-9);
+        }
+        // The middle node sits on the series element, which now goes to ground
+        assert_eq!(
+            c.get_element_between(0, c.element_current_direction(4).0),
+            vec![4]
+        );
+
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        c.add_element_between_nodes(Capacitor, 1e-6, 1, 0);
+        c.add_element_between_nodes(Resistor, 1., 1, 2);
+        c.add_element_between_nodes(Resistor, 1., 2, 0);
+        c.create_nodes().unwrap();
+        assert!(matches!(
+            c.apply_pi_to_t_transform((2, 3, 4)),
+            Err(Known(message)) if message.starts_with("Only resistive π networks can be transformed")
+        ));
+    }
+
+    #[test]
+    fn test_norton_to_thevenin() {
+        // 2 A with a 5 Ω shunt driving R3 and R4 in series
+        let mut c = Container::new();
+        c.add_element_between_nodes(CurrentSrc, 2., 1, 0);
+        c.add_element_between_nodes(Resistor, 5., 1, 0);
+        c.add_element_between_nodes(Resistor, 5., 1, 2);
+        c.add_element_between_nodes(Resistor, 10., 2, 0);
+        c.create_nodes().unwrap();
+
+        // Voltages at the positive terminals of two elements
+        let terminals = |c: &Container, ids: [usize; 2]| -> Vec<f64> {
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+            solver.solve().unwrap();
+            ids.into_iter()
+                .map(|id| {
+                    let element = c.get_element_by_id(id).borrow();
+                    let node: usize = c.node_at(id, &element.positive).unwrap();
+                    solver.node_voltage(node).unwrap()
+                })
+                .collect()
+        };
+        let before: Vec<f64> = terminals(&c, [3, 4]);
+        assert!((before[0] - 7.5).abs() < 1e-9);
+        assert!((before[1] - 5.0).abs() < 1e-9);
+
+        assert_known_error!(
+            c.apply_norton_to_thevenin(2, 1),
+            "Element 2 is not a current source in the circuit"
+        );
+        assert_known_error!(
+            c.apply_norton_to_thevenin(1, 3),
+            "Elements 1 and 3 are not in parallel"
+        );
+        c.apply_norton_to_thevenin(1, 2).unwrap();
+        assert!(c.get_element_by_id(1).borrow().is_detached());
+        assert!(c.get_element_by_id(2).borrow().is_detached());
+        // 2 A * 5 Ω in series with 5 Ω
+        assert_eq!(c.get_element_by_id(5).borrow().class, VoltageSrc);
+        assert!((c.get_element_by_id(5).borrow().value - 10.0).abs() < 1e-12);
+        assert_eq!(c.get_element_by_id(6).borrow().class, Resistor);
+        assert!((c.get_element_by_id(6).borrow().value - 5.0).abs() < 1e-12);
+        // The series resistor takes the ground side, the source leads on to R3
+        let middle: usize = c.element_current_direction(5).0;
+        assert_eq!(c.get_element_between(0, middle), vec![6]);
+        assert_eq!(
+            c.get_element_between(middle, c.element_current_direction(3).0),
+            vec![5]
+        );
+
+        let after: Vec<f64> = terminals(&c, [3, 4]);
+        for (x, y) in before.iter().zip(after) {
+            assert!((x - y).abs() < 1e-9);
+        }
+        assert_known_error!(
+            c.apply_norton_to_thevenin(1, 2),
+            "Element 1 is not a current source in the circuit"
+        );
+
+        // Reversed, the source draws from node 1 and the resistor stays on ground
+        let mut c = Container::new();
+        c.add_element_between_nodes(CurrentSrc, 2., 0, 1);
+        c.add_element_between_nodes(Resistor, 5., 1, 0);
+        c.add_element_between_nodes(Resistor, 5., 1, 2);
+        c.add_element_between_nodes(Resistor, 10., 2, 0);
+        c.create_nodes().unwrap();
+        let before: Vec<f64> = terminals(&c, [3, 4]);
+        assert!((before[0] + 7.5).abs() < 1e-9);
+        c.apply_norton_to_thevenin(1, 2).unwrap();
+        let middle: usize = c.element_current_direction(6).0;
+        assert_eq!(c.get_element_between(0, middle), vec![6]);
+        let after: Vec<f64> = terminals(&c, [3, 4]);
+        for (x, y) in before.iter().zip(after) {
+            assert!((x - y).abs() < 1e-9);
+        }
+
+        // A floating 1.5 A source with a 3 Ω shunt across R1 and R2
+        let mut c = Container::new();
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(Resistor, 2., 2, 0);
+        c.add_element_between_nodes(CurrentSrc, 1.5, 1, 2);
+        c.add_element_between_nodes(Resistor, 3., 1, 2);
+        c.create_nodes().unwrap();
+        let before: Vec<f64> = terminals(&c, [1, 2]);
+        assert!((before[0] - 0.75).abs() < 1e-9);
+        assert!((before[1] + 1.5).abs() < 1e-9);
+
+        c.apply_norton_to_thevenin(3, 4).unwrap();
+        assert!((c.get_element_by_id(5).borrow().value - 4.5).abs() < 1e-12);
+        let after: Vec<f64> = terminals(&c, [1, 2]);
+        for (x, y) in before.iter().zip(after) {
+            assert!((x - y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_thevenin_to_norton() {
+        // 10 V behind 5 Ω driving R3 and R4 in series
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 0, 1);
+        c.add_element_between_nodes(Resistor, 5., 1, 2);
+        c.add_element_between_nodes(Resistor, 5., 2, 3);
+        c.add_element_between_nodes(Resistor, 10., 3, 0);
+        c.create_nodes().unwrap();
+
+        // Voltages at the external nodes, the positive terminals of R3 and R4
+        let terminals = |c: &Container| -> Vec<f64> {
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+            solver.solve().unwrap();
+            [3, 4]
+                .into_iter()
+                .map(|id| {
+                    let element = c.get_element_by_id(id).borrow();
+                    let node: usize = c.node_at(id, &element.positive).unwrap();
+                    solver.node_voltage(node).unwrap()
+                })
+                .collect()
+        };
+        let before: Vec<f64> = terminals(&c);
+        assert!((before[0] - 7.5).abs() < 1e-9);
+        assert!((before[1] - 5.0).abs() < 1e-9);
+
+        assert_known_error!(
+            c.apply_thevenin_to_norton(2, 1),
+            "Element 2 is not a voltage source in the circuit"
+        );
+        assert_known_error!(
+            c.apply_thevenin_to_norton(1, 4),
+            "Elements 1 and 4 are not in series"
+        );
+        c.apply_thevenin_to_norton(1, 2).unwrap();
+        assert!(c.get_element_by_id(1).borrow().is_detached());
+        assert!(c.get_element_by_id(2).borrow().is_detached());
+        // 10 V / 5 Ω across 5 Ω, the middle node is gone
+        assert_eq!(c.nodes().len(), 2);
+        assert_eq!(c.get_element_by_id(5).borrow().class, CurrentSrc);
+        assert!((c.get_element_by_id(5).borrow().value - 2.0).abs() < 1e-12);
+        assert_eq!(c.get_element_by_id(6).borrow().class, Resistor);
+        assert!((c.get_element_by_id(6).borrow().value - 5.0).abs() < 1e-12);
+        let node: usize = c.element_current_direction(3).0;
+        assert_eq!(c.get_element_between(0, node), vec![5, 6]);
+
+        let after: Vec<f64> = terminals(&c);
+        for (x, y) in before.iter().zip(after) {
+            assert!((x - y).abs() < 1e-9);
+        }
+
+        // Back to a floating Thevenin source and once more to Norton
+        c.apply_norton_to_thevenin(5, 6).unwrap();
+        c.apply_thevenin_to_norton(7, 8).unwrap();
+        assert_eq!(c.get_element_by_id(9).borrow().class, CurrentSrc);
+        assert!((c.get_element_by_id(9).borrow().value - 2.0).abs() < 1e-12);
+        let after: Vec<f64> = terminals(&c);
+        for (x, y) in before.iter().zip(after) {
+            assert!((x - y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_get_element_between() {
+        let mut c = Container::new();
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(Resistor, 2., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 5., 0, 1);
+        c.add_element_between_nodes(Resistor, 3., 1, 2);
+        c.add_element_between_nodes(Resistor, 4., 1, 2);
+        c.add_element_between_nodes(Resistor, 5., 2, 1);
+        c.add_element_between_nodes(Resistor, 6., 2, 0);
+        c.create_nodes().unwrap();
+
+        assert_eq!(c.get_element_between(1, 2), vec![4, 5, 6]);
+        assert_eq!(c.get_element_between(2, 1), vec![4, 5, 6]);
+        assert_eq!(c.get_element_between(1, 0), vec![1, 2, 3]);
+        assert_eq!(c.get_element_between(0, 2), vec![7]);
+        assert!(c.get_element_between(1, 1).is_empty());
+        assert!(c.get_element_between(2, 3).is_empty());
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        for id in 1..6 {
+            let (a, b) = c.element_current_direction(id);
+            assert_eq!(c.get_element_between(a, b), vec![id]);
+        }
+    }
+
+    #[test]
+    fn test_copy_elements_from() {
+        let mut c = create_mna_container();
+        let basic = create_basic_container();
+        let ids: Vec<usize> = c.copy_elements_from(&basic, 6).unwrap();
+        assert_eq!(ids, vec![6, 7, 8, 9]);
+        assert_eq!(c.get_elements().len(), 10);
+
+        for (copied, original) in c.get_elements()[6..].iter().zip(basic.get_elements()) {
+            let (copied, original) = (copied.borrow(), original.borrow());
+            assert_eq!(copied.id, original.id + 6);
+            assert_eq!(copied.class, original.class);
+            assert_eq!(copied.value, original.value);
+            let shifted = |x: &Vec<usize>| x.iter().map(|x| x + 6).collect::<Vec<usize>>();
+            assert_eq!(copied.positive, shifted(&original.positive));
+            assert_eq!(copied.negative, shifted(&original.negative));
+        }
+        // The original elements are untouched
+        assert_eq!(c.get_element_by_id(1).borrow().positive, vec![0]);
+
+        assert!(c.copy_elements_from(&basic, 9).is_err());
+        assert!(c.copy_elements_from(&basic, 11).is_err());
+        assert_eq!(c.get_elements().len(), 10);
+    }
+
+    #[test]
+    fn test_get_admittance() {
+        for mut container in [create_mna_container(), create_mna_container_2()] {
+            container.create_nodes().unwrap();
+            let mut ids: Vec<usize> = container
+                .nodes()
+                .iter()
+                .map(|x| x.upgrade().unwrap().borrow().id)
+                .collect();
+            ids.sort();
+            let n: usize = ids.len();
+            let container = Rc::new(RefCell::new(container));
+            let g = form_g_matrix(container.clone(), n, RowOrder::Ascending);
+
+            for (i, node_i) in ids.iter().enumerate() {
+                for (j, node_j) in ids.iter().enumerate() {
+                    let mutual = container.borrow().get_mutual_admittance(*node_i, *node_j);
+                    if i == j {
+                        assert_eq!(mutual, 0.0);
+                        let diagonal = container.borrow().get_self_admittance(*node_i);
+                        assert!((diagonal - g[(i, i)].value()).abs() < 1e-12);
+                    } else {
+                        assert!((mutual - g[(i, j)].value()).abs() < 1e-12);
+                    }
+                }
+            }
+        }
+
+        let mut container: Container = create_mna_container();
+        container.create_nodes().unwrap();
+        assert_eq!(container.get_mutual_admittance(1, 2), -0.25);
+        assert_eq!(container.get_mutual_admittance(1, 3), 0.0);
+        assert_eq!(container.get_self_admittance(2), 0.375);
+    }
+
+    #[test]
+    fn test_validate_port_definitions() {
+        let mut container: Container = create_mna_container();
+        container.add_port("input".to_string(), 2, 0).unwrap();
+        assert_eq!(container.validate_port_definitions(), Ok(()));
+        assert_eq!(container.validate(), Ok(Valid));
+
+        container.add_port("shorted".to_string(), 1, 1).unwrap();
+        assert_known_error!(
+            container.validate_port_definitions(),
+            "Port shorted connects node 1 to itself"
+        );
+        assert_known_error!(
+            container.validate(),
+            "Port shorted connects node 1 to itself"
+        );
+
+        let mut container: Container = create_mna_container();
+        container.add_port("floating".to_string(), 7, 0).unwrap();
+        assert_known_error!(
+            container.validate(),
+            "Port floating uses node 7 which does not exist"
+        );
+        container.create_nodes().unwrap();
+        assert_known_error!(
+            container.validate_port_definitions(),
+            "Port floating uses node 7 which does not exist"
+        );
+    }
+
+    #[test]
+    fn test_validate_controls() {
+        let mut container: Container = load_problem("dependent_source_amplifier")
+            .unwrap()
+            .create_container();
+        assert_eq!(container.validate(), Ok(Valid));
+
+        container.get_element_by_id(4).borrow_mut().control = Some(Control::Element(9));
+        assert_known_error!(
+            container.validate(),
+            "VCVS4: 20 V/V is controlled by element 9 which is not a branch"
+        );
+        container.get_element_by_id(4).borrow_mut().control = Some(Control::Nodes(2, 7));
+        assert_known_error!(
+            container.validate(),
+            "VCVS4: 20 V/V is controlled by node 7 which does not exist"
+        );
+
+        // R3 runs from node 2 to ground
+        container.get_element_by_id(4).borrow_mut().control = Some(Control::Element(3));
+        container.create_nodes().unwrap();
+        assert_eq!(container.control_nodes(4), Ok((2, 0)));
+    }
+
+    #[test]
+    fn test_ports() {
+        let mut container: Container = create_mna_container();
+        container.add_port("input".to_string(), 2, 0).unwrap();
+        container.add_port("output".to_string(), 1, 3).unwrap();
+        assert!(container.add_port("input".to_string(), 1, 0).is_err());
+        assert_eq!(container.ports().len(), 2);
+
+        // With the sources shorted R1, R2 and R3 are in parallel from node 2 to ground
+        let impedance: f64 = container.impedance_at(2, 0).unwrap();
+        assert!((impedance - 8. / 7.).abs() < 1e-9);
+        assert_eq!(container.impedance_at_port("input").unwrap(), impedance);
+        assert_eq!(
+            container.impedance_at_port("output").unwrap(),
+            container.impedance_at(1, 3).unwrap()
+        );
+        assert!(container.impedance_at_port("missing").is_err());
+        assert!(container.impedance_at(2, 9).is_err());
+    }
+
+    #[test]
+    fn test_compute_reduced_incidence_matrix() {
+        let mut container: Container = create_mna_container();
+        container.create_nodes().unwrap();
+        let incidence: DMatrix<i8> = container.compute_reduced_incidence_matrix();
+        assert_eq!(incidence.shape(), (5, 3));
+        // R1 runs from ground to node 3, SRC(V)4 from node 3 to node 2
+        assert_eq!(
+            incidence.row(0).iter().copied().collect::<Vec<i8>>(),
+            vec![0, 0, -1]
+        );
+        assert_eq!(
+            incidence.row(3).iter().copied().collect::<Vec<i8>>(),
+            vec![0, -1, 1]
+        );
+
+        // Sources carry no admittance, leaving the resistors only
+        let admittance: DMatrix<f64> = DMatrix::from_diagonal(&DVector::from_iterator(
+            5,
+            container.get_elements().iter().skip(1).map(|x| {
+                let x = x.borrow();
+                match x.class {
+                    Resistor => 1.0 / x.value,
+                    _ => 0.0,
+                }
+            }),
+        ));
+        let reduced: DMatrix<f64> = incidence.map(|x| x as f64);
+        let g: DMatrix<f64> = reduced.transpose() * admittance * reduced;
+
+        let n: usize = container.nodes().len();
+        let expected = form_g_matrix(Rc::new(RefCell::new(container)), n, RowOrder::Ascending);
+        for i in 0..n {
+            for j in 0..n {
+                assert!((g[(i, j)] - expected[(i, j)].value()).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let container: Container = create_mna_container();
+        let dot: String = container.to_dot();
+        assert!(dot.starts_with("graph {"));
+        for element in container.get_elements().iter().skip(1) {
+            assert!(dot.contains(&element.borrow().pretty_string()));
+        }
+        assert!(dot.contains("[label=\"SRC(V)4: 32 V\", style=bold]"));
+        assert!(container.nodes().is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let mut container: Container = create_basic_supermesh_container();
+        container.create_nodes().unwrap();
+        assert_eq!(
+            container.to_string(),
+            include_str!("../tests/data/supermesh_summary.txt")
+        );
+    }
+
     #[test]
     fn test_get_calculation_nodes() {
         let mut basic: Container = create_basic_container();
@@ -555,3 +3929,81 @@ impl Debug for Container {
             .finish()
     }
 }
+
+/// Text summary of the circuit for debugging
+///
+/// Lists the elements as a table followed by the nodes, the meshes if they were
+/// created and any validation errors or warnings. The output is deterministic.
+impl Display for Container {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Elements:")?;
+        writeln!(
+            f,
+            "{:>4}  {:<18}{:>10}  {:<15} {:<15} {}",
+            "id", "class", "value", "positive", "negative", "name"
+        )?;
+        for element in &self.elements {
+            let element = element.borrow();
+            writeln!(
+                f,
+                "{:>4}  {:<18}{:>10}  {:<15} {:<15} {}",
+                element.id,
+                element.class.as_str(),
+                element.value,
+                format!("{:?}", element.positive),
+                format!("{:?}", element.negative),
+                element.basic_string()
+            )?;
+        }
+
+        let write_tools = |f: &mut Formatter<'_>, types: [ToolType; 2]| -> std::fmt::Result {
+            let tools: Vec<&Rc<RefCell<Tool>>> = self
+                .tools
+                .iter()
+                .filter(|x| types.contains(&x.borrow().class))
+                .collect();
+            if tools.is_empty() {
+                return writeln!(f, "  none");
+            }
+            for tool in tools {
+                let tool = tool.borrow();
+                writeln!(
+                    f,
+                    "{:>4}  {:<10}{}",
+                    tool.id,
+                    tool.class.to_string(),
+                    tool.basic_string()
+                )?;
+            }
+            Ok(())
+        };
+
+        writeln!(f, "Nodes:")?;
+        write_tools(f, [ToolType::Node, ToolType::SuperNode])?;
+        if self
+            .tools
+            .iter()
+            .any(|x| matches!(x.borrow().class, ToolType::Mesh | ToolType::SuperMesh))
+        {
+            writeln!(f, "Meshes:")?;
+            write_tools(f, [ToolType::Mesh, ToolType::SuperMesh])?;
+        }
+
+        writeln!(f, "Issues:")?;
+        let errors: Vec<StatusError> = match self.validate() {
+            Ok(_) => vec![],
+            Err(StatusError::Multiple(errors)) => errors,
+            Err(error) => vec![error],
+        };
+        if errors.is_empty() && self.warnings.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for error in errors {
+            writeln!(f, "  error: {}", error)?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "  warning: {:?}: {}", warning.kind, warning.message)?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
-use crate::component::Component;
-use crate::component::Component::Ground;
+use crate::component::Component::{CurrentSrc, Ground, Resistor, VoltageSrc};
+use crate::component::{Component, Unit};
 use crate::container::Container;
 use crate::util::PrettyPrint;
 use crate::validation::Status::Valid;
@@ -13,6 +13,16 @@ use std::cell::RefCell;
 use std::fmt::Display;
 use std::rc::{Rc, Weak};
 
+/// Voltage a dependent source reads, its value is the gain applied to it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Control {
+    /// Across an element, from its positive to its negative terminal.
+    Element(usize),
+    /// Between two nodes by id, the first minus the second. Ground is node 0.
+    Nodes(usize, usize),
+}
+
 /// Representation of a Schematic Element
 #[derive(Debug, Deserialize, Clone)]
 pub struct Element {
@@ -27,6 +37,20 @@ pub struct Element {
     pub(crate) class: Component,     //
     pub(crate) positive: Vec<usize>, // Link to other elements
     pub(crate) negative: Vec<usize>, //
+    #[serde(default)]
+    pub(crate) tags: Vec<String>, // Groups used for aggregate reporting
+    /// Largest current a voltage source delivers before it turns into a current source.
+    #[serde(default)]
+    pub(crate) current_limit: Option<f64>,
+    /// Compliance of a current source, the largest voltage it drives across its terminals.
+    #[serde(default)]
+    pub(crate) voltage_limit: Option<f64>,
+    /// The source was swapped for its limiting model, see `NodeMatrixSolver`.
+    #[serde(default)]
+    pub(crate) limiting: bool,
+    /// Control voltage of a dependent source, see `Element::with_control`.
+    #[serde(default)]
+    pub(crate) control: Option<Control>,
 }
 
 impl Element {
@@ -64,9 +88,59 @@ impl Element {
             class,
             positive,
             negative,
+            tags: vec![],
+            current_limit: None,
+            voltage_limit: None,
+            limiting: false,
+            control: None,
         }
     }
 
+    /// Voltage source that turns into a current source of `limit` amperes when its load
+    /// draws more, like a bench supply in constant current mode.
+    pub fn with_current_limit(mut self, limit: f64) -> Element {
+        self.current_limit = Some(limit);
+        self
+    }
+
+    /// Current source that turns into a voltage source of `limit` volts when its load
+    /// needs more, the compliance voltage of a real current source.
+    pub fn with_voltage_limit(mut self, limit: f64) -> Element {
+        self.voltage_limit = Some(limit);
+        self
+    }
+
+    /// Dependent source reading its control voltage across an element or a node pair.
+    pub fn with_control(mut self, control: Control) -> Element {
+        self.control = Some(control);
+        self
+    }
+
+    /// Source with a current or voltage limit, only solved by `NodeMatrixSolver`.
+    pub(crate) fn has_limit(&self) -> bool {
+        self.current_limit.is_some() || self.voltage_limit.is_some()
+    }
+
+    /// Whether the source runs at its limit instead of its nominal value
+    pub fn is_limiting(&self) -> bool {
+        self.limiting
+    }
+
+    /// Zero resistance branch, modelled according to `SolveOptions::zero_ohm_strategy`.
+    pub(crate) fn is_wire(&self) -> bool {
+        self.class == Resistor && self.value == 0.0
+    }
+
+    /// Resistor with a negative value, only solved with `allow_negative_resistance`.
+    pub(crate) fn is_negative_resistance(&self) -> bool {
+        self.class == Resistor && self.value < 0.0
+    }
+
+    /// Element that was taken out of the circuit, e.g. a merged wire.
+    pub(crate) fn is_detached(&self) -> bool {
+        self.positive.is_empty() && self.negative.is_empty()
+    }
+
     pub(crate) fn connected_to_ground(&self) -> bool {
         self.positive.contains(&0) || self.negative.contains(&0)
     }
@@ -83,6 +157,11 @@ impl Element {
         self.current = current;
     }
 
+    /// Groups the element belongs to, see `Solution::by_tag`
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
     pub(crate) fn set_voltage_drop(&mut self, voltage_drop: f64) {
         self.voltage_drop = voltage_drop;
     }
@@ -101,13 +180,10 @@ impl Element {
 
 impl PrettyPrint for Element {
     fn pretty_string(&self) -> String {
-        format!(
-            "{}{}: {} {}",
-            self.name,
-            self.id,
-            self.value,
-            self.class.unit_string()
-        )
+        match self.class.unit() {
+            Unit::None => format!("{}{}: {}", self.name, self.id, self.value),
+            unit => format!("{}{}: {} {}", self.name, self.id, self.value, unit),
+        }
     }
 
     fn basic_string(&self) -> String {
@@ -184,7 +260,8 @@ impl Validation for Element {
             _ => {
                 // TODO: Check if the element is valid for other components
                 // Resistor, Capacitor, Inductor, VoltageSource, CurrentSource
-                if self.value <= 0.0 {
+                // A resistor of exactly 0 Ω is a wire or an ideal ammeter
+                if self.value <= 0.0 && !self.is_wire() {
                     return Err(Known(format!(
                         "Value cannot be zero or negative {}",
                         self.pretty_string()
@@ -203,6 +280,59 @@ impl Validation for Element {
                 }
             }
         }
+        for (limit, class, name) in [
+            (self.current_limit, VoltageSrc, "Current"),
+            (self.voltage_limit, CurrentSrc, "Voltage"),
+        ] {
+            match limit {
+                Some(_) if self.class != class => {
+                    return Err(Known(format!(
+                        "{} limit is only available on a {:?} {}",
+                        name,
+                        class,
+                        self.pretty_string()
+                    )));
+                }
+                Some(limit) if limit <= 0.0 => {
+                    return Err(Known(format!(
+                        "{} limit has to be positive {}",
+                        name,
+                        self.pretty_string()
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        match (self.control, self.class.is_dependent()) {
+            (None, true) => {
+                return Err(Known(format!(
+                    "Dependent source has no control {}",
+                    self.pretty_string()
+                )));
+            }
+            (Some(_), false) => {
+                return Err(Known(format!(
+                    "Control is only available on a dependent source {}",
+                    self.pretty_string()
+                )));
+            }
+            (Some(Control::Element(id)), true) if id == self.id => {
+                return Err(Known(format!(
+                    "Dependent source cannot control itself {}",
+                    self.pretty_string()
+                )));
+            }
+            (Some(Control::Nodes(a, b)), true) if a == b => {
+                return Err(Known(format!(
+                    "Dependent source reads node {} against itself {}",
+                    a,
+                    self.pretty_string()
+                )));
+            }
+            _ => {}
+        }
+
         if self.positive.len() == 0 && self.negative.len() == 0 {
             return Err(Known("Element has no connections".to_string()));
         }
@@ -238,7 +368,7 @@ impl Serialize for Element {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Element", 9)?;
+        let mut state = serializer.serialize_struct("Element", 15)?;
         state.serialize_field("name", &self.name)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("value", &self.value)?;
@@ -247,6 +377,11 @@ impl Serialize for Element {
         state.serialize_field("class", &self.class)?;
         state.serialize_field("positive", &self.positive)?;
         state.serialize_field("negative", &self.negative)?;
+        state.serialize_field("tags", &self.tags)?;
+        state.serialize_field("current_limit", &self.current_limit)?;
+        state.serialize_field("voltage_limit", &self.voltage_limit)?;
+        state.serialize_field("limiting", &self.limiting)?;
+        state.serialize_field("control", &self.control)?;
         state.serialize_field("pretty_string", &self.pretty_string())?;
         state.serialize_field("latex_string", &self.latex_string())?;
         state.end()
@@ -257,7 +392,7 @@ impl Serialize for Element {
 mod tests {
     use crate::assert_known_error;
     use crate::component::Component;
-    use crate::elements::Element;
+    use crate::elements::{Control, Element};
     use crate::validation::StatusError::Known;
     use crate::validation::Validation;
 
@@ -288,6 +423,10 @@ mod tests {
         assert!(a.validate().is_ok());
         a.value = -0.5;
         assert_known_error!(a.validate(), "Value cannot be zero or negative R1: -0.5 Ω");
+        a.value = 0.0;
+        assert!(a.validate().is_ok() && a.is_wire());
+        a.class = Component::VoltageSrc;
+        assert!(a.validate().is_err());
 
         // TODO This was a result of removing the short validation. Should this be a validation?
         // let mut b = Element::new(Component::Resistor, 1.0, vec![1], vec![1]);
@@ -310,6 +449,11 @@ mod tests {
             class: Component::Ground,
             positive: vec![1],
             negative: vec![2],
+            tags: vec![],
+            current_limit: None,
+            voltage_limit: None,
+            limiting: false,
+            control: None,
         };
         assert_known_error!(d.validate(), "Ground element cannot have dual polarity");
 
@@ -320,4 +464,85 @@ mod tests {
         let f = Element::new(Component::Resistor, 1.0, vec![], vec![]);
         assert_known_error!(f.validate(), "Element has no connections");
     }
+
+    #[test]
+    fn test_tags_round_trip() {
+        let mut element = Element::new(Component::Resistor, 1.0, vec![2], vec![3]);
+        assert!(element.tags().is_empty());
+        element.tags = vec!["load".to_string(), "feedback network".to_string()];
+
+        let json: String = serde_json::to_string(&element).unwrap();
+        let parsed: Element = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.tags(), element.tags());
+
+        let untagged: Element = serde_json::from_str(
+            r#"{"id": 1, "value": 1.0, "class": "resistor", "positive": [2], "negative": [3]}"#,
+        )
+        .unwrap();
+        assert!(untagged.tags().is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut supply =
+            Element::new(Component::VoltageSrc, 10.0, vec![2], vec![3]).with_current_limit(1.0);
+        supply.id = 1;
+        assert!(supply.validate().is_ok() && supply.has_limit() && !supply.is_limiting());
+        supply.current_limit = Some(0.0);
+        assert_known_error!(
+            supply.validate(),
+            "Current limit has to be positive SRC(V)1: 10 V"
+        );
+
+        let mut resistor =
+            Element::new(Component::Resistor, 1.0, vec![2], vec![3]).with_voltage_limit(5.0);
+        resistor.id = 1;
+        assert_known_error!(
+            resistor.validate(),
+            "Voltage limit is only available on a CurrentSrc R1: 1 Ω"
+        );
+
+        let json: String = serde_json::to_string(&supply).unwrap();
+        let parsed: Element = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.current_limit, Some(0.0));
+        assert_eq!(parsed.voltage_limit, None);
+    }
+
+    #[test]
+    fn test_control() {
+        let mut amplifier = Element::new(Component::DependentVoltage, 20.0, vec![2], vec![0]);
+        amplifier.id = 1;
+        assert_known_error!(
+            amplifier.validate(),
+            "Dependent source has no control VCVS1: 20 V/V"
+        );
+        amplifier = amplifier.with_control(Control::Element(1));
+        assert_known_error!(
+            amplifier.validate(),
+            "Dependent source cannot control itself VCVS1: 20 V/V"
+        );
+        amplifier.control = Some(Control::Nodes(2, 2));
+        assert_known_error!(
+            amplifier.validate(),
+            "Dependent source reads node 2 against itself VCVS1: 20 V/V"
+        );
+        amplifier.control = Some(Control::Element(3));
+        assert!(amplifier.validate().is_ok());
+
+        let mut resistor = Element::new(Component::Resistor, 1.0, vec![2], vec![3])
+            .with_control(Control::Element(4));
+        resistor.id = 1;
+        assert_known_error!(
+            resistor.validate(),
+            "Control is only available on a dependent source R1: 1 Ω"
+        );
+
+        let json: String = serde_json::to_string(&amplifier).unwrap();
+        assert!(json.contains(r#""control":{"element":3}"#));
+        let parsed: Element = serde_json::from_str(
+            r#"{"id": 1, "value": 0.5, "class": "dependent_current", "positive": [2], "negative": [0], "control": {"nodes": [1, 0]}}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.control, Some(Control::Nodes(1, 0)));
+    }
 }
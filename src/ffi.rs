@@ -0,0 +1,117 @@
+//! C ABI for embedding the solver outside Rust and javascript
+//!
+//! Every function wraps the native JSON path and catches panics before they reach the
+//! caller. Strings handed out by this module must be released with `css_free`. The
+//! header is checked in at `include/circuit_solver.h`, regenerate it with
+//! `cbindgen --config cbindgen.toml --output include/circuit_solver.h` after a change.
+
+use crate::container::Container;
+use crate::interfaces::{solve_nodal, ContainerSetup};
+use crate::solvers::progress::NoProgress;
+use crate::solvers::solver::serialize_steps;
+use crate::validation::StatusError;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// The circuit was solved, `out` holds the steps.
+pub const CSS_OK: i32 = 0;
+/// The input was null, not UTF-8 or not a circuit.
+pub const CSS_INVALID_INPUT: i32 = 1;
+pub const CSS_UNKNOWN: i32 = 2;
+pub const CSS_KNOWN: i32 = 3;
+pub const CSS_MULTIPLE: i32 = 4;
+pub const CSS_SYMBOLIC_LIMIT: i32 = 5;
+pub const CSS_EMPTY_CIRCUIT: i32 = 6;
+pub const CSS_NO_SOURCE: i32 = 7;
+pub const CSS_STALE_SOLVER: i32 = 8;
+pub const CSS_UNSUPPORTED: i32 = 9;
+pub const CSS_STRICT_MODE: i32 = 10;
+/// The solver panicked, `out` holds the panic message if there was one.
+pub const CSS_PANIC: i32 = -1;
+
+static VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// Status code of an error kind
+pub fn status_code(error: &StatusError) -> i32 {
+    match error {
+        StatusError::Unknown => CSS_UNKNOWN,
+        StatusError::Known(_) => CSS_KNOWN,
+        StatusError::Multiple(_) => CSS_MULTIPLE,
+        StatusError::SymbolicLimit(_) => CSS_SYMBOLIC_LIMIT,
+        StatusError::EmptyCircuit => CSS_EMPTY_CIRCUIT,
+        StatusError::NoSourcePresent => CSS_NO_SOURCE,
+        StatusError::StaleSolver => CSS_STALE_SOLVER,
+        StatusError::Unsupported { .. } => CSS_UNSUPPORTED,
+        StatusError::StrictModeViolation(_) => CSS_STRICT_MODE,
+    }
+}
+
+/// Solve a circuit given as the JSON of a `ContainerSetup` with the node matrix solver
+///
+/// On success `*out` is set to the JSON list of steps, on failure to the same
+/// `{"errors": [...]}` object the wasm `solve` returns. Returns one of the `CSS_` codes.
+///
+/// # Safety
+///
+/// `input` must be null or a nul terminated string and `out` must be null or valid for
+/// writes. The string written to `*out` has to be released with `css_free`.
+#[no_mangle]
+pub unsafe extern "C" fn css_solve_json(input: *const c_char, out: *mut *mut c_char) -> i32 {
+    let (code, message) = match catch_unwind(AssertUnwindSafe(|| solve_json(input))) {
+        Ok(result) => result,
+        Err(panic) => {
+            let message: String = match panic.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+            };
+            (CSS_PANIC, message)
+        }
+    };
+
+    if !out.is_null() {
+        // Interior nul bytes cannot be passed on, the message is dropped instead
+        *out = CString::new(message).map_or(ptr::null_mut(), CString::into_raw);
+    }
+    code
+}
+
+unsafe fn solve_json(input: *const c_char) -> (i32, String) {
+    if input.is_null() {
+        return (CSS_INVALID_INPUT, "Input is null".to_string());
+    }
+    let input: &str = match CStr::from_ptr(input).to_str() {
+        Ok(input) => input,
+        Err(_) => return (CSS_INVALID_INPUT, "Input is not valid UTF-8".to_string()),
+    };
+    let setup: ContainerSetup = match serde_json::from_str(input) {
+        Ok(setup) => setup,
+        Err(error) => return (CSS_INVALID_INPUT, error.to_string()),
+    };
+
+    match solve_nodal(Container::from(setup), true, &NoProgress) {
+        Ok(steps) => match serialize_steps(steps) {
+            Ok(json) => (CSS_OK, json),
+            Err(error) => (CSS_UNKNOWN, error),
+        },
+        Err(error) => (status_code(&error), String::from(error)),
+    }
+}
+
+/// Release a string returned by this library, null is ignored.
+///
+/// # Safety
+///
+/// `ptr` must be null or a string returned by this library that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn css_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+/// Version of the library, a static string that must not be freed.
+#[no_mangle]
+pub extern "C" fn css_version() -> *const c_char {
+    VERSION.as_ptr() as *const c_char
+}
@@ -1,24 +1,150 @@
-use crate::container::Container;
+use crate::component::Component;
+use crate::container::{Container, Port};
 use crate::elements::Element;
+use crate::solvers::mesh_matrix_solver::MeshMatrixSolver;
+use crate::solvers::mesh_step_solver::MeshStepSolver;
 use crate::solvers::node_matrix_solver::NodeMatrixSolver;
 use crate::solvers::node_step_solver::NodeStepSolver;
-use crate::solvers::solver::{serialize_steps, Solver, Step};
+use crate::solvers::progress::{NoProgress, Phase, ProgressSink};
+use crate::solvers::solver::{serialize_steps, Solver, SolverType, Step, StepStub};
+use crate::solvers::support::{supports, SolverDescriptor, Support};
+use crate::suggestion::Diagnostic;
+use crate::topology::{EditSession, Topology, TopologyDelta};
+use crate::transformation::Transformation;
 use crate::util::{
     create_basic_container, create_basic_supermesh_container, create_basic_supernode_container,
-    create_mna_container, create_mna_container_2,
+    create_mna_container, create_mna_container_2, generate_container, load_problem,
+    problem_names, Problem, GENERATORS,
 };
-use crate::validation::{StatusError, Validation};
+use crate::validation::{StatusError, Validation, Warning};
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::from_value;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
 use crate::validation::StatusError::Known;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ContainerSetup {
     pub elements: Vec<Element>,
+    #[serde(default)]
+    pub ports: Vec<Port>,
+}
+
+thread_local! {
+    /// Solvers kept alive between calls so steps can be rendered lazily
+    static SESSIONS: RefCell<HashMap<u32, Session>> = RefCell::new(HashMap::new());
+    /// Circuits being edited, see `open_edit_session`
+    static EDIT_SESSIONS: RefCell<HashMap<u32, EditSession>> = RefCell::new(HashMap::new());
+    static NEXT_SESSION: RefCell<u32> = RefCell::new(1);
+}
+
+/// A solved circuit kept alive by `solve_lazy`
+struct Session {
+    solver: NodeStepSolver,
+    /// Node matrix solver over the same container, created by the first `session_explain`
+    explainer: Option<NodeMatrixSolver>,
+}
+
+/// Response of `solve_lazy`, the steps are stubs until rendered with `render_step`
+#[derive(Serialize)]
+pub struct LazySolution {
+    pub session_id: u32,
+    pub steps: Vec<StepStub>,
+}
+
+/// Response of `session_add_element`
+#[derive(Serialize)]
+pub struct AddedElement {
+    pub id: usize,
+    pub delta: TopologyDelta,
+}
+
+/// A single step rendered on demand
+#[derive(Serialize)]
+pub struct RenderedStep {
+    pub id: usize,
+    pub elapsed_ms: f64,
+    pub step: Step,
+}
+
+/// Node assigned to each terminal of every element, returned by `get_connectivity`
+#[derive(Serialize)]
+pub struct Connectivity {
+    /// Element id to `[positive terminal node, negative terminal node]`, ground elements map to `[0]`.
+    pub terminals: BTreeMap<usize, Vec<usize>>,
+    /// Node ids that are connected to ground.
+    pub ground: Vec<usize>,
+}
+
+/// Response of `circuit_info`
+#[derive(Serialize)]
+pub struct CircuitInfo {
+    /// Nodes including ground.
+    pub nodes: usize,
+    pub branches: usize,
+    /// Independent loops, `branches - nodes + 1`.
+    pub loops: usize,
+    pub connected: bool,
+}
+
+/// Analysis that succeeded before a solve failed
+#[derive(Serialize, Default)]
+pub struct PartialAnalysis {
+    pub topology: Option<Topology>,
+    /// Number of elements of each class.
+    pub stats: Option<BTreeMap<&'static str, usize>>,
+}
+
+/// Response of `solve_report`, failing circuits still carry their partial analysis
+#[derive(Serialize, Default)]
+pub struct SolveReport {
+    pub ok: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<Warning>,
+    /// Automatic rewrites of the circuit, what was solved differs from the input by these
+    pub transformations: Vec<Transformation>,
+    pub partial: PartialAnalysis,
+    pub result: Option<Vec<Step>>,
+}
+
+/// Everything the frontend needs to know about what this build supports
+#[derive(Serialize)]
+pub struct Capabilities {
+    pub components: Vec<ComponentCapability>,
+    pub solvers: Vec<SolverDescriptor>,
+}
+
+#[derive(Serialize)]
+pub struct ComponentCapability {
+    pub class: &'static str,
+    pub legacy_class: &'static str,
+    pub code: u8,
+    /// How each solver handles the component
+    pub support: Vec<(SolverType, Support)>,
+}
+
+/// List the supported component classes with their stable names and codes, and the solvers.
+#[wasm_bindgen]
+pub fn get_capabilities() -> String {
+    let capabilities = Capabilities {
+        components: Component::ALL
+            .iter()
+            .map(|x| ComponentCapability {
+                class: x.as_str(),
+                legacy_class: x.legacy_str(),
+                code: x.code(),
+                support: SolverType::ALL
+                    .iter()
+                    .map(|solver| (*solver, supports(*solver, x)))
+                    .collect(),
+            })
+            .collect(),
+        solvers: SolverType::ALL.iter().map(|x| x.descriptor()).collect(),
+    };
+    serde_json::to_string(&capabilities).unwrap()
 }
 
 /// This can be used as a test to see if the container is being loaded in properly.
@@ -47,6 +173,115 @@ pub fn get_tools(container_js: JsValue) -> Result<String, StatusError> {
     Ok(serde_json::to_string(&nodes).unwrap())
 }
 
+/// Graphviz DOT description of the circuit, render with `dot -Tsvg`
+#[wasm_bindgen]
+pub fn to_dot(container_js: JsValue) -> Result<String, StatusError> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let mut c: Container = Container::from(setup);
+    c.validate()?;
+    c.create_nodes()?;
+    Ok(c.to_dot())
+}
+
+/// Numeric G matrix with the contribution of every element to each entry
+///
+/// Rows follow the default row order of the node matrix solver. Each cell lists
+/// `[element id, signed conductance]` pairs that sum to the entry.
+#[wasm_bindgen]
+pub fn g_matrix_contributions(container_js: JsValue) -> Result<String, StatusError> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let mut c: Container = Container::from(setup);
+    c.validate()?;
+    c.create_nodes()?;
+    let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+    let contributions = solver.g_contributions();
+    let rows: Vec<Vec<Vec<(usize, f64)>>> = contributions
+        .row_iter()
+        .map(|row| row.iter().cloned().collect())
+        .collect();
+    Ok(serde_json::to_string(&rows).unwrap())
+}
+
+/// Steps explaining the role of one element in the solution of the container
+///
+/// Solves with the node matrix solver, see `NodeMatrixSolver::explain`. Use
+/// `session_explain` to explain several elements of the same circuit.
+#[wasm_bindgen]
+pub fn explain(container_js: JsValue, element_id: usize) -> Result<String, String> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let mut c: Container = Container::from(setup);
+    c.validate()?;
+    c.create_nodes()?;
+    let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+    serialize_steps(solver.explain(element_id)?)
+}
+
+/// Node, branch and loop counts of the container and whether it is connected
+#[wasm_bindgen]
+pub fn circuit_info(container_js: JsValue) -> Result<String, StatusError> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let mut c: Container = Container::from(setup);
+    c.validate()?;
+    c.create_nodes()?;
+    let info = CircuitInfo {
+        nodes: c.nodes().len() + 1,
+        branches: c.branch_count(),
+        loops: c.number_of_loops()?,
+        connected: c.is_connected(),
+    };
+    Ok(serde_json::to_string(&info).unwrap())
+}
+
+/// Every validation issue of the container with a suggested fix where one is known.
+#[wasm_bindgen]
+pub fn diagnose(container_js: JsValue) -> Result<String, StatusError> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let c: Container = Container::from(setup);
+    let diagnostics: Vec<Diagnostic> = c.diagnose();
+    Ok(serde_json::to_string(&diagnostics).unwrap())
+}
+
+/// Names of the ports defined on the container, in the order they were added.
+#[wasm_bindgen]
+pub fn port_names(container_js: JsValue) -> Result<String, StatusError> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let c: Container = Container::from(setup);
+    let names: Vec<&String> = c.ports().iter().map(|x| &x.name).collect();
+    Ok(serde_json::to_string(&names).unwrap())
+}
+
+/// Node assigned to every element terminal after node creation
+///
+/// Finer grained than `get_tools`, used by the editor to check its junction model.
+#[wasm_bindgen]
+pub fn get_connectivity(container_js: JsValue) -> Result<String, StatusError> {
+    let setup: ContainerSetup = from_value(container_js).unwrap();
+    let mut c: Container = Container::from(setup);
+    c.validate()?;
+    c.create_nodes()?;
+
+    let mut connectivity = Connectivity {
+        terminals: BTreeMap::new(),
+        ground: vec![0],
+    };
+    for element in c.get_elements() {
+        let element = element.borrow();
+        let terminals: Vec<usize> = match element.class {
+            Component::Ground => vec![0],
+            _ => [&element.positive, &element.negative]
+                .iter()
+                .map(|side| {
+                    c.node_at(element.id, side).ok_or_else(|| {
+                        Known(format!("Element {} is not connected to a node", element.id))
+                    })
+                })
+                .collect::<Result<Vec<usize>, StatusError>>()?,
+        };
+        connectivity.terminals.insert(element.id, terminals);
+    }
+    Ok(serde_json::to_string(&connectivity).unwrap())
+}
+
 #[wasm_bindgen]
 pub fn validate(container_js: JsValue) -> Result<String, StatusError> {
     let setup: ContainerSetup = from_value(container_js).unwrap();
@@ -57,6 +292,184 @@ pub fn validate(container_js: JsValue) -> Result<String, StatusError> {
 
 #[wasm_bindgen]
 pub fn solve(matrix: bool, nodal: bool, container_js: JsValue) -> Result<String, String> {
+    solve_reporting(matrix, nodal, container_js, &NoProgress)
+}
+
+/// Same as `solve` but calls `callback(phase, fraction)` as the solve progresses
+#[wasm_bindgen]
+pub fn solve_with_progress(
+    matrix: bool,
+    nodal: bool,
+    container_js: JsValue,
+    callback: js_sys::Function,
+) -> Result<String, String> {
+    solve_reporting(matrix, nodal, container_js, &JsProgress(callback))
+}
+
+/// Routes progress reports to a javascript callback
+struct JsProgress(js_sys::Function);
+
+impl ProgressSink for JsProgress {
+    fn report(&self, phase: Phase, fraction: f32) {
+        let phase: JsValue = serde_wasm_bindgen::to_value(&phase).unwrap_or(JsValue::NULL);
+        let _ = self
+            .0
+            .call2(&JsValue::NULL, &phase, &JsValue::from_f64(fraction as f64));
+    }
+}
+
+/// Validate, build the nodes and solve a container with one of the nodal solvers
+pub fn solve_nodal(
+    mut c: Container,
+    matrix: bool,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<Step>, StatusError> {
+    progress.report(Phase::Validation, 0.0);
+    c.validate()?;
+    progress.report(Phase::Validation, 1.0);
+
+    solve_validated(Rc::new(RefCell::new(c)), matrix, progress)
+}
+
+/// Build the nodes of a validated container and solve it
+fn solve_validated(
+    container: Rc<RefCell<Container>>,
+    matrix: bool,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<Step>, StatusError> {
+    progress.report(Phase::NodeCreation, 0.0);
+    container.borrow_mut().create_nodes()?;
+    container.borrow_mut().create_super_nodes()?;
+    progress.report(Phase::NodeCreation, 1.0);
+
+    if matrix {
+        let mut solver: NodeMatrixSolver = Solver::new(container);
+        solver.solve_with_progress(progress)
+    } else {
+        let mut solver: NodeStepSolver = Solver::new(container);
+        solver.solve_with_progress(progress)
+    }
+}
+
+/// Validate, build the nodes and solve a container with one of the mesh solvers
+pub fn solve_mesh(
+    mut c: Container,
+    matrix: bool,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<Step>, StatusError> {
+    progress.report(Phase::Validation, 0.0);
+    c.validate()?;
+    progress.report(Phase::Validation, 1.0);
+
+    progress.report(Phase::NodeCreation, 0.0);
+    c.create_nodes()?;
+    progress.report(Phase::NodeCreation, 1.0);
+
+    if matrix {
+        let mut solver: MeshMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve_with_progress(progress)
+    } else {
+        let mut solver: MeshStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve_with_progress(progress)
+    }
+}
+
+/// Same as `solve_nodal` but the topology and element counts are kept when solving fails
+///
+/// Topology is analyzed before validation, so a circuit that fails validation still
+/// reports its nodes.
+pub fn report_nodal(c: Container, matrix: bool, progress: &dyn ProgressSink) -> SolveReport {
+    let mut report: SolveReport = SolveReport::default();
+    report.partial.stats = Some(c.count_elements_by_type());
+
+    let topology: Result<Topology, StatusError> = c.analyze_topology();
+    progress.report(Phase::Validation, 0.0);
+    if let Err(error) = c.validate() {
+        report.errors.extend(error_messages(error));
+    }
+    progress.report(Phase::Validation, 1.0);
+    match topology {
+        Ok(topology) => report.partial.topology = Some(topology),
+        Err(error) => report.errors.extend(error_messages(error)),
+    }
+    if !report.errors.is_empty() {
+        return report;
+    }
+
+    let container: Rc<RefCell<Container>> = Rc::new(RefCell::new(c));
+    let solved: Result<Vec<Step>, StatusError> =
+        solve_validated(container.clone(), matrix, progress);
+    report.warnings = container.borrow().warnings().clone();
+    report.transformations = container.borrow().transformations().clone();
+    match solved {
+        Ok(steps) => {
+            report.ok = true;
+            report.result = Some(steps);
+        }
+        Err(error) => report.errors.extend(error_messages(error)),
+    }
+    report
+}
+
+/// One message per error, nested errors are flattened
+fn error_messages(error: StatusError) -> Vec<String> {
+    match error {
+        StatusError::Multiple(errors) => errors.into_iter().flat_map(error_messages).collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+fn solve_reporting(
+    matrix: bool,
+    nodal: bool,
+    container_js: JsValue,
+    progress: &dyn ProgressSink,
+) -> Result<String, String> {
+    let setup: ContainerSetup = if let Ok(setup) = from_value(container_js) {
+        setup
+    } else {
+        return Err(String::from(Known("Failed to parse and deserialize input case".to_string())));
+    };
+
+    let c: Container = Container::from(setup);
+
+    return match nodal {
+        true => serialize_steps(solve_nodal(c, matrix, progress)?),
+        false => serialize_steps(solve_mesh(c, matrix, progress)?),
+    };
+}
+
+/// Same as `solve` but always returns a `SolveReport`
+///
+/// `solve` keeps returning only the errors for callers that expect that shape.
+#[wasm_bindgen]
+pub fn solve_report(matrix: bool, nodal: bool, container_js: JsValue) -> String {
+    let report: SolveReport = match from_value::<ContainerSetup>(container_js) {
+        Err(_) => SolveReport {
+            errors: vec!["Failed to parse and deserialize input case".to_string()],
+            ..SolveReport::default()
+        },
+        Ok(setup) if nodal => report_nodal(Container::from(setup), matrix, &NoProgress),
+        Ok(setup) => match solve_mesh(Container::from(setup), matrix, &NoProgress) {
+            Ok(steps) => SolveReport {
+                ok: true,
+                result: Some(steps),
+                ..SolveReport::default()
+            },
+            Err(error) => SolveReport {
+                errors: error_messages(error),
+                ..SolveReport::default()
+            },
+        },
+    };
+    serde_json::to_string(&report).unwrap()
+}
+
+/// Solve with the step solver but defer rendering of the steps
+///
+/// The solver is kept in a session until `close_session` is called.
+#[wasm_bindgen]
+pub fn solve_lazy(container_js: JsValue) -> Result<String, String> {
     let setup: ContainerSetup = if let Ok(setup) = from_value(container_js) {
         setup
     } else {
@@ -65,31 +478,156 @@ pub fn solve(matrix: bool, nodal: bool, container_js: JsValue) -> Result<String,
 
     let mut c: Container = Container::from(setup);
     c.validate()?;
+    c.create_nodes()?;
+    c.create_super_nodes()?;
+    let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+    let steps: Vec<StepStub> = solver.solve_lazy()?;
 
-    return match nodal {
-        true => {
-            c.create_nodes()?;
-            c.create_super_nodes()?;
-            let steps: Vec<Step>;
-            if matrix {
-                return Err(String::from(Known("Matrix solver not implemented for nodal".to_string())));
-                let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
-                steps = solver.solve()?;
-            } else {
-                let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
-                steps = solver.solve()?;
-            }
-            serialize_steps(steps)
-        }
-        false => {
-            return Err(format!(
-                "{} Solver not implemented for meshes",
-                if matrix { "Matrix" } else { "Step" }
-            ));
-            c.create_meshes();
-            c.create_super_meshes();
-        }
+    let session_id: u32 = next_session_id();
+    let session = Session {
+        solver,
+        explainer: None,
     };
+    SESSIONS.with(|sessions| sessions.borrow_mut().insert(session_id, session));
+
+    Ok(serde_json::to_string(&LazySolution { session_id, steps }).unwrap())
+}
+
+/// Render one step of a session created by `solve_lazy`
+#[wasm_bindgen]
+pub fn render_step(session_id: u32, step_id: usize) -> Result<String, String> {
+    SESSIONS.with(|sessions| {
+        let sessions = sessions.borrow();
+        let solver: &NodeStepSolver = match sessions.get(&session_id) {
+            Some(session) => &session.solver,
+            None => {
+                return Err(String::from(Known(format!(
+                    "Session {} does not exist",
+                    session_id
+                ))))
+            }
+        };
+
+        let start: f64 = now_ms();
+        let step: Step = solver.render_step(step_id)?;
+        let rendered = RenderedStep {
+            id: step_id,
+            elapsed_ms: now_ms() - start,
+            step,
+        };
+        Ok(serde_json::to_string(&rendered).unwrap())
+    })
+}
+
+/// Same as `explain` for the circuit of a session created by `solve_lazy`
+///
+/// The circuit was validated when the session was created and is solved by the first
+/// call only, later calls reuse that solution.
+#[wasm_bindgen]
+pub fn session_explain(session_id: u32, element_id: usize) -> Result<String, String> {
+    SESSIONS.with(|sessions| {
+        let mut sessions = sessions.borrow_mut();
+        let session: &mut Session = match sessions.get_mut(&session_id) {
+            Some(session) => session,
+            None => {
+                return Err(String::from(Known(format!(
+                    "Session {} does not exist",
+                    session_id
+                ))))
+            }
+        };
+
+        let container = session.solver.container();
+        let explainer: &mut NodeMatrixSolver = session
+            .explainer
+            .get_or_insert_with(|| Solver::new(container));
+        serialize_steps(explainer.explain(element_id)?)
+    })
+}
+
+/// Drop a session created by `solve_lazy` or `open_edit_session`, returns false if it did not exist
+#[wasm_bindgen]
+pub fn close_session(session_id: u32) -> bool {
+    let solver: bool = SESSIONS.with(|sessions| sessions.borrow_mut().remove(&session_id).is_some());
+    let edit: bool =
+        EDIT_SESSIONS.with(|sessions| sessions.borrow_mut().remove(&session_id).is_some());
+    solver || edit
+}
+
+fn next_session_id() -> u32 {
+    NEXT_SESSION.with(|next| {
+        let id: u32 = *next.borrow();
+        *next.borrow_mut() += 1;
+        id
+    })
+}
+
+/// Start editing an empty circuit, the nodes are updated with every edit
+///
+/// The session is kept until `close_session` is called.
+#[wasm_bindgen]
+pub fn open_edit_session() -> u32 {
+    let session_id: u32 = next_session_id();
+    EDIT_SESSIONS.with(|sessions| {
+        sessions
+            .borrow_mut()
+            .insert(session_id, EditSession::new(Container::new()))
+    });
+    session_id
+}
+
+/// Run an edit on a session created by `open_edit_session`
+fn with_edit_session<T>(
+    session_id: u32,
+    edit: impl FnOnce(&mut EditSession) -> Result<T, StatusError>,
+) -> Result<T, String> {
+    EDIT_SESSIONS.with(|sessions| match sessions.borrow_mut().get_mut(&session_id) {
+        Some(session) => edit(session).map_err(String::from),
+        None => Err(String::from(Known(format!(
+            "Session {} does not exist",
+            session_id
+        )))),
+    })
+}
+
+/// Add an element between two node labels, label 0 is ground
+///
+/// Returns the id of the element and the nodes that changed, see `TopologyDelta`.
+#[wasm_bindgen]
+pub fn session_add_element(
+    session_id: u32,
+    class_js: JsValue,
+    value: f64,
+    node_a: usize,
+    node_b: usize,
+) -> Result<String, String> {
+    let class: Component = from_value(class_js)
+        .map_err(|_| String::from(Known("Failed to parse the element class".to_string())))?;
+    let (id, delta) = with_edit_session(session_id, |session| {
+        Ok(session.add_element_between_nodes(class, value, node_a, node_b))
+    })?;
+    Ok(serde_json::to_string(&AddedElement { id, delta }).unwrap())
+}
+
+/// Remove an element, returns the nodes that changed, see `TopologyDelta`
+#[wasm_bindgen]
+pub fn session_remove_element(session_id: u32, element_id: usize) -> Result<String, String> {
+    let delta: TopologyDelta =
+        with_edit_session(session_id, |session| session.remove_element(element_id))?;
+    Ok(serde_json::to_string(&delta).unwrap())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
 }
 
 #[wasm_bindgen]
@@ -116,6 +654,34 @@ pub fn solve_test_container(container_id: i32) -> Result<String, String> {
     serialize_steps(solver.solve()?)
 }
 
+/// Solve a problem of the textbook library by name with the node matrix solver.
+#[wasm_bindgen]
+pub fn solve_test_problem(name: &str) -> Result<String, String> {
+    let problem: Problem = load_problem(name)?;
+    serialize_steps(solve_nodal(problem.create_container(), true, &NoProgress)?)
+}
+
+/// Names accepted by `solve_test_problem` as a JSON list.
+#[wasm_bindgen]
+pub fn test_problem_names() -> String {
+    serde_json::to_string(&problem_names()).unwrap()
+}
+
+/// Solve a container built by one of the generators in `util` with the node matrix solver
+///
+/// `params` are the generator parameters in the order listed by `test_generator_names`.
+#[wasm_bindgen]
+pub fn solve_generated_container(name: &str, params: Vec<f64>) -> Result<String, String> {
+    let c: Container = generate_container(name, &params)?;
+    serialize_steps(solve_nodal(c, true, &NoProgress)?)
+}
+
+/// Generator names with their parameter names as a JSON list.
+#[wasm_bindgen]
+pub fn test_generator_names() -> String {
+    serde_json::to_string(&GENERATORS).unwrap()
+}
+
 impl From<Vec<Element>> for Container {
     fn from(wasm: Vec<Element>) -> Container {
         let mut container = Container::new();
@@ -132,6 +698,10 @@ impl From<ContainerSetup> for Container {
         for element in setup.elements {
             container.add_element_no_id(element);
         }
+        for port in setup.ports {
+            // A repeated name keeps the first port
+            let _ = container.add_port(port.name, port.positive, port.negative);
+        }
         container
     }
 }
@@ -1,8 +1,13 @@
 pub mod component;
 pub mod container;
 pub mod elements;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod interfaces;
 pub mod solvers;
+pub mod suggestion;
 pub mod tools;
+pub mod topology;
+pub mod transformation;
 pub mod util;
 pub mod validation;
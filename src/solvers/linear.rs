@@ -0,0 +1,406 @@
+use crate::validation::StatusError;
+use crate::validation::StatusError::Known;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+/// LU factors of a square matrix, kept so the same system can be solved again
+///
+/// Plain vectors are stored so the factors serialize without extra nalgebra features.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LuFactors {
+    /// Number of rows and columns of the factored matrix.
+    pub size: usize,
+    /// L below the diagonal, its unit diagonal implied, and U on and above it, row major.
+    pub packed: Vec<f64>,
+    /// `rows[i]` is the row of the original matrix that ended up in row `i`.
+    pub rows: Vec<usize>,
+    /// Elimination steps in the order they were taken.
+    #[serde(default)]
+    pub pivots: Vec<Pivot>,
+}
+
+/// One column of the elimination
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pivot {
+    /// Column eliminated, also the row the pivot ends up in.
+    pub column: usize,
+    /// Row the pivot was taken from, equal to `column` when no swap was needed.
+    pub row: usize,
+    /// `(row, factor)` pairs, `factor` times the pivot row is subtracted from `row`.
+    pub factors: Vec<(usize, f64)>,
+}
+
+impl Pivot {
+    pub fn swapped(&self) -> bool {
+        self.row != self.column
+    }
+}
+
+impl LuFactors {
+    /// Doolittle elimination choosing the largest pivot of each column.
+    pub fn factorize(a: &DMatrix<f64>) -> Result<LuFactors, StatusError> {
+        LuFactors::factorize_with(a, true)
+    }
+
+    /// Doolittle elimination, rows are only swapped for a strictly larger pivot.
+    ///
+    /// Without pivoting the diagonal is used as is, as in a hand elimination that
+    /// follows the natural order. A zero pivot is then an error instead of a swap.
+    pub fn factorize_with(a: &DMatrix<f64>, pivoting: bool) -> Result<LuFactors, StatusError> {
+        if !a.is_square() {
+            return Err(Known(format!(
+                "Cannot factorize a {}x{} matrix",
+                a.nrows(),
+                a.ncols()
+            )));
+        }
+        LuFactors::eliminate(a, pivoting).map_err(|k| {
+            Known(match pivoting {
+                true => "Matrix is not invertible!".to_string(),
+                false => format!(
+                    "Zero pivot in row {} without pivoting, solve with pivoting enabled",
+                    k + 1
+                ),
+            })
+        })
+    }
+
+    /// First column of a square matrix without a usable pivot, None if it can be factored
+    ///
+    /// The elimination is the one of `factorize` with pivoting. The unknown of that column
+    /// is not determined by the other equations.
+    pub(crate) fn singular_column(a: &DMatrix<f64>) -> Option<usize> {
+        if !a.is_square() {
+            return None;
+        }
+        LuFactors::eliminate(a, true).err()
+    }
+
+    /// Elimination of a square matrix, fails with the column that has no usable pivot
+    fn eliminate(a: &DMatrix<f64>, pivoting: bool) -> Result<LuFactors, usize> {
+        let n: usize = a.nrows();
+        let mut lu: DMatrix<f64> = a.clone();
+        let mut rows: Vec<usize> = (0..n).collect();
+        // Pivots this small relative to the largest entry are treated as zero
+        let tolerance: f64 = f64::EPSILON * n as f64 * a.amax();
+
+        let mut pivots: Vec<Pivot> = Vec::new();
+        for k in 0..n {
+            let mut pivot: usize = k;
+            if pivoting {
+                for i in k + 1..n {
+                    if lu[(i, k)].abs() > lu[(pivot, k)].abs() {
+                        pivot = i;
+                    }
+                }
+            }
+            if lu[(pivot, k)].abs() <= tolerance {
+                return Err(k);
+            }
+            if pivot != k {
+                lu.swap_rows(pivot, k);
+                rows.swap(pivot, k);
+            }
+
+            let mut factors: Vec<(usize, f64)> = Vec::new();
+            for i in k + 1..n {
+                let factor: f64 = lu[(i, k)] / lu[(k, k)];
+                lu[(i, k)] = factor;
+                for j in k + 1..n {
+                    let shift: f64 = factor * lu[(k, j)];
+                    lu[(i, j)] -= shift;
+                }
+                factors.push((i, factor));
+            }
+            pivots.push(Pivot {
+                column: k,
+                row: pivot,
+                factors,
+            });
+        }
+
+        Ok(LuFactors {
+            size: n,
+            packed: lu.transpose().iter().copied().collect(),
+            rows,
+            pivots,
+        })
+    }
+
+    /// Inverse of the factored matrix, one substitution per column.
+    pub fn inverse(&self) -> DMatrix<f64> {
+        let columns: Vec<DVector<f64>> = (0..self.size)
+            .map(|k| {
+                self.solve(&DVector::from_fn(
+                    self.size,
+                    |i, _| if i == k { 1.0 } else { 0.0 },
+                ))
+            })
+            .collect();
+        DMatrix::from_columns(&columns)
+    }
+
+    /// Permuted `b` with the row operations of the elimination applied, the right hand
+    /// side of the upper triangular system.
+    pub fn forward_substitution(&self, b: &DVector<f64>) -> DVector<f64> {
+        let n: usize = self.size;
+        let mut y: DVector<f64> = DVector::from_iterator(n, self.rows.iter().map(|i| b[*i]));
+        for i in 0..n {
+            let shift: f64 = (0..i).map(|j| self.packed[i * n + j] * y[j]).sum();
+            y[i] -= shift;
+        }
+        y
+    }
+
+    /// Forward and back substitution of `b` through the factors.
+    pub fn solve(&self, b: &DVector<f64>) -> DVector<f64> {
+        let n: usize = self.size;
+        let at = |i: usize, j: usize| self.packed[i * n + j];
+        let mut x: DVector<f64> = self.forward_substitution(b);
+        for i in (0..n).rev() {
+            let shift: f64 = (i + 1..n).map(|j| at(i, j) * x[j]).sum();
+            x[i] = (x[i] - shift) / at(i, i);
+        }
+        x
+    }
+}
+
+/// Kahan compensated sum
+///
+/// The result only depends on the order of `values`, callers that need identical
+/// results on every target sort the values first.
+pub fn kahan_sum<I: IntoIterator<Item = f64>>(values: I) -> f64 {
+    let mut sum: f64 = 0.0;
+    let mut compensation: f64 = 0.0;
+    for value in values {
+        let y: f64 = value - compensation;
+        let t: f64 = sum + y;
+        compensation = (t - sum) - y;
+        sum = t;
+    }
+    sum
+}
+
+/// Largest denominator `Ratio::from_f64` reads a value as
+const MAX_DENOMINATOR: i128 = 1_000_000;
+
+/// Fraction used to solve small systems without rounding
+///
+/// Always reduced with a positive denominator, every operation returns `None` on overflow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+impl Ratio {
+    pub(crate) const ZERO: Ratio = Ratio { num: 0, den: 1 };
+
+    pub(crate) fn new(num: i128, den: i128) -> Option<Ratio> {
+        if den == 0 {
+            return None;
+        }
+        let divisor: i128 = i128::try_from(gcd(num.unsigned_abs(), den.unsigned_abs())).ok()?;
+        let (num, den) = (num / divisor, den / divisor);
+        if den < 0 {
+            Ratio::new(num.checked_neg()?, den.checked_neg()?)
+        } else {
+            Some(Ratio { num, den })
+        }
+    }
+
+    /// Shortest fraction within 1e-12 of `x`, e.g. 0.1 reads as 1/10
+    ///
+    /// Found with the continued fraction of `x`, fails if the denominator would exceed
+    /// `MAX_DENOMINATOR`.
+    pub(crate) fn from_f64(x: f64) -> Option<Ratio> {
+        if !x.is_finite() {
+            return None;
+        }
+        let tolerance: f64 = 1e-12 * x.abs().max(1.0);
+        let (mut h0, mut h1, mut k0, mut k1) = (0i128, 1i128, 1i128, 0i128);
+        let mut rest: f64 = x;
+        while rest.is_finite() && rest.abs() < 1e15 {
+            let a: i128 = rest.floor() as i128;
+            let h: i128 = a.checked_mul(h1)?.checked_add(h0)?;
+            let k: i128 = a.checked_mul(k1)?.checked_add(k0)?;
+            if k > MAX_DENOMINATOR {
+                return None;
+            }
+            if (h as f64 / k as f64 - x).abs() <= tolerance {
+                return Ratio::new(h, k);
+            }
+            (h0, h1, k0, k1) = (h1, h, k1, k);
+            rest = 1.0 / (rest - rest.floor());
+        }
+        None
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub(crate) fn add(self, other: Ratio) -> Option<Ratio> {
+        Ratio::new(
+            self.num
+                .checked_mul(other.den)?
+                .checked_add(other.num.checked_mul(self.den)?)?,
+            self.den.checked_mul(other.den)?,
+        )
+    }
+
+    pub(crate) fn sub(self, other: Ratio) -> Option<Ratio> {
+        self.add(Ratio::new(other.num.checked_neg()?, other.den)?)
+    }
+
+    pub(crate) fn mul(self, other: Ratio) -> Option<Ratio> {
+        Ratio::new(
+            self.num.checked_mul(other.num)?,
+            self.den.checked_mul(other.den)?,
+        )
+    }
+
+    pub(crate) fn div(self, other: Ratio) -> Option<Ratio> {
+        Ratio::new(
+            self.num.checked_mul(other.den)?,
+            self.den.checked_mul(other.num)?,
+        )
+    }
+
+    pub(crate) fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Solve `a * x = b` in fractions, `None` if `a` is singular or a fraction overflows
+pub(crate) fn solve_exact(mut a: Vec<Vec<Ratio>>, mut b: Vec<Ratio>) -> Option<Vec<Ratio>> {
+    let n: usize = b.len();
+    for column in 0..n {
+        // Any nonzero pivot is exact, no need to look for the largest
+        let pivot: usize = (column..n).find(|x| !a[*x][column].is_zero())?;
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        for row in column + 1..n {
+            if a[row][column].is_zero() {
+                continue;
+            }
+            let factor: Ratio = a[row][column].div(a[column][column])?;
+            for k in column..n {
+                a[row][k] = a[row][k].sub(factor.mul(a[column][k])?)?;
+            }
+            b[row] = b[row].sub(factor.mul(b[column])?)?;
+        }
+    }
+
+    let mut x: Vec<Ratio> = vec![Ratio::ZERO; n];
+    for i in (0..n).rev() {
+        let mut rest: Ratio = b[i];
+        for k in i + 1..n {
+            rest = rest.sub(a[i][k].mul(x[k])?)?;
+        }
+        x[i] = rest.div(a[i][i])?;
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lu_solve() {
+        let a = DMatrix::from_row_slice(3, 3, &[4., 1., 0., 1., 3., 1., 0., 1., 2.]);
+        let b = DVector::from_row_slice(&[1., 2., 3.]);
+        let x = LuFactors::factorize(&a).unwrap().solve(&b);
+        assert!((&a * &x - &b).norm() < 1e-12);
+
+        let singular = DMatrix::from_row_slice(2, 2, &[1., 2., 2., 4.]);
+        assert!(LuFactors::factorize(&singular).is_err());
+        assert_eq!(LuFactors::singular_column(&singular), Some(1));
+    }
+
+    #[test]
+    fn test_lu_factors() {
+        // The first column forces a row swap
+        let a = DMatrix::from_row_slice(3, 3, &[0., 2., 1., 1., 1., 1., 4., 0., 2.]);
+        let factors = LuFactors::factorize(&a).unwrap();
+        assert_eq!(factors.rows[0], 2);
+
+        let json: String = serde_json::to_string(&factors).unwrap();
+        let factors: LuFactors = serde_json::from_str(&json).unwrap();
+        for b in [[1., 2., 3.], [0., -1., 5.]] {
+            let b = DVector::from_row_slice(&b);
+            assert!((&a * factors.solve(&b) - &b).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_kahan_sum() {
+        assert_eq!(kahan_sum(vec![]), 0.0);
+        // The naive sum loses every small term against the large one
+        let values: Vec<f64> = std::iter::once(1.0)
+            .chain(std::iter::repeat(1e-16).take(10_000))
+            .collect();
+        let naive: f64 = values.iter().sum();
+        assert_eq!(naive, 1.0);
+        assert!((kahan_sum(values) - (1.0 + 1e-12)).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let a = DMatrix::from_row_slice(3, 3, &[0., 2., 1., 1., 1., 1., 4., 0., 2.]);
+        let inverse = LuFactors::factorize(&a).unwrap().inverse();
+        assert!((&a * inverse - DMatrix::<f64>::identity(3, 3)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_pivots() {
+        // A zero leading entry can only be eliminated with a row swap
+        let a = DMatrix::from_row_slice(3, 3, &[0., 2., 1., 1., 1., 1., 4., 0., 2.]);
+        let factors = LuFactors::factorize(&a).unwrap();
+        assert!(factors.pivots[0].swapped());
+        assert_eq!(factors.pivots[0].row, 2);
+        assert_eq!(factors.pivots[0].factors, vec![(1, 0.25), (2, 0.)]);
+        assert!(LuFactors::factorize_with(&a, false).is_err());
+
+        // Diagonally dominant, the natural order is kept either way
+        let a = DMatrix::from_row_slice(3, 3, &[4., 1., 0., 1., 3., 1., 0., 1., 2.]);
+        let b = DVector::from_row_slice(&[1., 2., 3.]);
+        let pivoted = LuFactors::factorize(&a).unwrap();
+        let natural = LuFactors::factorize_with(&a, false).unwrap();
+        assert!(pivoted.pivots.iter().all(|x| !x.swapped()));
+        assert_eq!(pivoted.pivots, natural.pivots);
+        assert_eq!(natural.pivots[0].factors, vec![(1, 0.25), (2, 0.)]);
+        assert!((pivoted.solve(&b) - natural.solve(&b)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(Ratio::from_f64(0.1), Ratio::new(1, 10));
+        assert_eq!(Ratio::from_f64(-2.5), Ratio::new(-5, 2));
+        assert_eq!(Ratio::from_f64(1.0 / 3.0), Ratio::new(1, 3));
+        assert_eq!(Ratio::from_f64(0.0), Some(Ratio::ZERO));
+        assert_eq!(Ratio::from_f64(0.500000001), None);
+
+        // 2x + y = 1, x + 3y = 0 has the solution x = 3/5, y = -1/5
+        let r = |x: i128| Ratio::new(x, 1).unwrap();
+        let a = vec![vec![r(2), r(1)], vec![r(1), r(3)]];
+        let x = solve_exact(a, vec![r(1), r(0)]).unwrap();
+        assert_eq!(
+            x,
+            vec![Ratio::new(3, 5).unwrap(), Ratio::new(-1, 5).unwrap()]
+        );
+        assert_eq!(
+            solve_exact(vec![vec![r(1), r(2)], vec![r(2), r(4)]], vec![r(1), r(2)]),
+            None
+        );
+    }
+}
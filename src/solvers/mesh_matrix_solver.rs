@@ -1,69 +1,944 @@
+use crate::component::Component::{Capacitor, CurrentSrc, Ground, Resistor, VoltageSrc};
+use crate::component::Unit;
 use crate::container::Container;
-use crate::solvers::solver::{Solver, Step};
+use crate::elements::Element;
+use crate::solvers::linear::LuFactors;
+use crate::solvers::options::SolveOptions;
+use crate::solvers::progress::{NoProgress, Phase, ProgressSink};
+use crate::solvers::solution::SolvedValues;
+use crate::solvers::solver::{
+    check_auto_transform, check_generation, check_negative_resistance, check_source_limits,
+    check_strict, negative_resistance_sub_step, NodeSolver, Solver, SolverType, Step, StepForms,
+    SubStep,
+};
+use crate::solvers::support::check_support;
+use crate::tools::ToolType::{Mesh, SuperMesh};
+use crate::util::PrettyPrint;
 use crate::validation::StatusError;
+use crate::validation::StatusError::Known;
+use nalgebra::{DMatrix, DVector};
+use operations::math::{EquationMember, EquationRepr};
+use operations::prelude::{Negate, Operation, Sum, Text, Value, Variable};
 use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
 use std::rc::Rc;
 
-// TODO MeshMatrixSolver
-#[allow(dead_code)]
+/// Element of a loop with the direction of the loop current through it, 1 from the
+/// positive to the negative terminal and -1 against it.
+type Branch = (usize, f64);
+
+/// Mesh analysis, the dual of the node matrix solver
+///
+/// One current is assigned to each Mesh tool of the container and flows in the direction
+/// of the mesh's first element. The meshes are created with the nodes when the container
+/// has none, see `Container::create_meshes`, they are the independent loops of the circuit
+/// and not necessarily the windows of a planar drawing. KVL around the meshes gives the R
+/// block of the A matrix and the voltage sources the z vector. The voltage across a current
+/// source cannot be written in KVL, it is an extra unknown and the current of the source an
+/// extra row. Meshes through the same current source form a SuperMesh tool and are solved
+/// together that way. Capacitors are open in DC and are handled as 0 A current sources.
 pub struct MeshMatrixSolver {
     container: Rc<RefCell<Container>>,
+    options: SolveOptions,
+    loops: Vec<Vec<Branch>>, // Loops in the order of their currents in x
+    a_matrix: DMatrix<Operation>,
+    x_matrix: DVector<Operation>,
+    z_matrix: DVector<Operation>,
+    result: DVector<f64>, // Loop currents followed by the voltages across the current sources
+    solved: bool,
+    steps: Vec<Step>,              // Cached output of the first solve
+    generation: u64,               // Container generation the solver was created for
+    pub(crate) solver: SolverType, // Capability table the circuit is checked against
 }
 
 impl Solver for MeshMatrixSolver {
-    fn new(container: Rc<RefCell<Container>>) -> Self {
-        MeshMatrixSolver { container }
+    fn new_with_options(container: Rc<RefCell<Container>>, options: SolveOptions) -> Self {
+        // An invalid strategy leaves the wires alone and is reported by `solve`
+        let _ = container.borrow_mut().apply_auto_transforms(&options);
+        let _ = container.borrow_mut().create_nodes();
+        if container.borrow().get_tools(Mesh).is_empty() {
+            container.borrow_mut().create_meshes().create_super_meshes();
+        }
+        let generation: u64 = container.borrow().generation();
+        MeshMatrixSolver {
+            container,
+            options,
+            loops: vec![],
+            a_matrix: DMatrix::zeros(0, 0),
+            x_matrix: DVector::zeros(0),
+            z_matrix: DVector::zeros(0),
+            result: DVector::zeros(0),
+            solved: false,
+            steps: vec![],
+            generation,
+            solver: SolverType::MeshMatrix,
+        }
     }
 
     fn solve(&mut self) -> Result<Vec<Step>, StatusError> {
-        todo!()
+        self.solve_with_progress(&NoProgress)
+    }
+
+    /// The loops need every terminal on a node, the matrices are formed by the first solve.
+    fn solve_with_progress(
+        &mut self,
+        progress: &dyn ProgressSink,
+    ) -> Result<Vec<Step>, StatusError> {
+        check_generation(&self.container, self.generation)?;
+        if !self.steps.is_empty() {
+            return Ok(self.steps.clone());
+        }
+        check_support(self.solver, &self.container)?;
+        check_source_limits(&self.container)?;
+        check_auto_transform(&self.container, &self.options)?;
+        check_negative_resistance(&self.container, &self.options)?;
+        self.options.zero_ohm_strategy.validate()?;
+        check_strict(&self.container, &self.options)?;
+        self.check_causality()?;
+
+        progress.report(Phase::MatrixFormation, 0.0);
+        let branches: Vec<Element> = current_branches(&self.container.borrow());
+        self.loops = form_loops(&self.container.borrow())?;
+        self.a_matrix = form_a_matrix(&self.container.borrow(), &self.loops, &branches);
+        self.x_matrix = form_x_vector(self.loops.len(), &branches);
+        self.z_matrix = form_z_vector(&self.container.borrow(), &self.loops, &branches);
+        progress.report(Phase::MatrixFormation, 1.0);
+
+        // Without loops or current sources no current flows and there is nothing to invert
+        if self.a_matrix.nrows() == 0 {
+            self.result = DVector::zeros(0);
+            self.solved = true;
+            let mut step: Step = Step::new("Empty circuit");
+            step.description = Some("The circuit has no loops, no current flows".to_string());
+            self.steps = vec![step, self.solved_values_step(&branches)?];
+            return Ok(self.steps.clone());
+        }
+
+        progress.report(Phase::Factorization, 0.0);
+        let numeric_a: DMatrix<f64> = self.a_matrix.map(|x| x.value());
+        let numeric_z: DVector<f64> = self.z_matrix.map(|x| x.value());
+        let factors: LuFactors =
+            LuFactors::factorize_with(&numeric_a, !self.options.no_pivoting)
+                .map_err(|x| self.singular_error(&numeric_a, &branches).unwrap_or(x))?;
+        self.result = factors.solve(&numeric_z);
+        self.solved = true;
+        progress.report(Phase::Factorization, 1.0);
+        progress.report(Phase::Rendering, 0.0);
+
+        let symbolic_z: DVector<Operation> =
+            form_z_vector_symbolic(&self.container.borrow(), &self.loops, &branches);
+        let display = self.options.forms;
+        let mut sub_steps: Vec<SubStep> = vec![self.loop_step()];
+        if !branches.is_empty() {
+            sub_steps.push(self.supermesh_step(&numeric_a, &branches));
+        }
+        sub_steps.extend([
+            SubStep::new_with_forms(
+                "A Matrix",
+                StepForms {
+                    symbolic: Some(self.a_matrix.latex_string()),
+                    numeric: Some(numeric_a.latex_string()),
+                },
+                display,
+            ),
+            SubStep::new_with_forms(
+                "Z Matrix",
+                StepForms {
+                    symbolic: Some(symbolic_z.latex_string()),
+                    numeric: Some(self.z_matrix.latex_string()),
+                },
+                display,
+            ),
+            SubStep {
+                result: None,
+                description: Some("X Matrix".to_string()),
+                operations: vec![Variable(Rc::new(self.x_matrix.clone()))],
+                forms: None,
+            },
+            SubStep {
+                description: Some("Inverse A Matrix".to_string()),
+                result: None,
+                operations: vec![Variable(Rc::new(factors.inverse()))],
+                forms: None,
+            },
+        ]);
+        if let Some(sub_step) = negative_resistance_sub_step(&self.container) {
+            sub_steps.push(sub_step);
+        }
+
+        let mut step: Step = Step::new_with_steps("Mesh Matrix Solver", sub_steps);
+        step.description = Some("Form matrices".to_string());
+        step.result = Some(Text(format!(
+            "${} = {}$",
+            self.x_matrix.equation_repr(),
+            self.result
+                .map(|x| (x * 100.).round() / 100.)
+                .equation_repr()
+        )));
+        // The loops cover every branch that carries current
+        for branch in self.loops.iter().flatten() {
+            step.reference_element(branch.0);
+        }
+        for branch in branches.iter() {
+            step.reference_element(branch.id);
+        }
+
+        self.steps = vec![step, self.solved_values_step(&branches)?];
+        progress.report(Phase::Rendering, 1.0);
+        Ok(self.steps.clone())
+    }
+}
+
+impl NodeSolver for MeshMatrixSolver {
+    fn container(&self) -> Rc<RefCell<Container>> {
+        self.container.clone()
+    }
+
+    /// Node voltages follow from the branch voltages, walked out from ground.
+    fn node_voltages(&self) -> Result<Vec<(usize, f64)>, StatusError> {
+        if !self.solved {
+            return Err(Known("Circuit has not been solved".to_string()));
+        }
+
+        let container = self.container.borrow();
+        let mut drops: Vec<(usize, usize, f64)> = Vec::new();
+        for element in container.get_elements().iter().map(|x| x.borrow()) {
+            if element.class == Ground || element.is_detached() {
+                continue;
+            }
+            let (from, to) = container.element_current_direction(element.id);
+            drops.push((from, to, self.branch_voltage(element.id)?));
+        }
+
+        let mut voltages: BTreeMap<usize, f64> = BTreeMap::from([(0, 0.0)]);
+        let mut queue: VecDeque<usize> = VecDeque::from([0]);
+        while let Some(node) = queue.pop_front() {
+            let voltage: f64 = voltages[&node];
+            for (from, to, drop) in drops.iter() {
+                let (other, other_voltage) = match node {
+                    x if x == *from => (*to, voltage - drop),
+                    x if x == *to => (*from, voltage + drop),
+                    _ => continue,
+                };
+                if !voltages.contains_key(&other) {
+                    voltages.insert(other, other_voltage);
+                    queue.push_back(other);
+                }
+            }
+        }
+
+        let mut nodes: Vec<usize> = container
+            .nodes()
+            .iter()
+            .map(|x| x.upgrade().unwrap().borrow().id)
+            .collect();
+        nodes.sort();
+        let mut raw: Vec<(usize, f64)> = vec![(0, 0.0)];
+        for id in nodes {
+            match voltages.get(&id) {
+                Some(voltage) => raw.push((id, *voltage)),
+                None => return Err(Known(format!("Node {} has no path to ground", id))),
+            }
+        }
+
+        self.options.reference.apply(raw)
+    }
+
+    /// Voltage source currents are the sums of the loop currents through them.
+    fn voltage_source_current(&self, source_id: usize) -> Result<f64, StatusError> {
+        let class = self
+            .container
+            .borrow()
+            .get_elements()
+            .get(source_id)
+            .map(|x| x.borrow().class.clone());
+        if class != Some(VoltageSrc) {
+            return Err(Known(format!(
+                "Element {} is not a voltage source",
+                source_id
+            )));
+        }
+
+        let sign: f64 = source_sign(&self.container.borrow(), source_id);
+        Ok(-sign * self.branch_current(source_id)?)
+    }
+}
+
+impl MeshMatrixSolver {
+    /// Loops in the order of their currents, as element ids with the direction of the loop
+    /// current through each element. Empty until solved.
+    pub fn loops(&self) -> Vec<Vec<(usize, f64)>> {
+        self.loops.clone()
+    }
+
+    /// Current of every loop, in the order of `loops`
+    pub fn loop_currents(&self) -> Result<Vec<f64>, StatusError> {
+        if !self.solved {
+            return Err(Known("Circuit has not been solved".to_string()));
+        }
+        Ok(self.result.iter().take(self.loops.len()).copied().collect())
+    }
+
+    /// Current through an element from its positive to its negative terminal
+    ///
+    /// The sum of the currents of the loops through it, zero for an element in no loop.
+    pub fn branch_current(&self, element_id: usize) -> Result<f64, StatusError> {
+        let currents: Vec<f64> = self.loop_currents()?;
+        if element_id >= self.container.borrow().get_elements().len() {
+            return Err(Known(format!("Element {} does not exist", element_id)));
+        }
+
+        Ok(self
+            .loops
+            .iter()
+            .zip(currents)
+            .filter_map(|(branches, current)| {
+                let (_, direction) = branches.iter().find(|(id, _)| *id == element_id)?;
+                Some(direction * current)
+            })
+            .sum())
+    }
+
+    /// Loop currents followed by the voltages across the current sources, empty until solved
+    pub(crate) fn result(&self) -> &DVector<f64> {
+        &self.result
+    }
+
+    /// Numeric A matrix and z vector, empty until solved
+    pub(crate) fn numeric_system(&self) -> (DMatrix<f64>, DVector<f64>) {
+        (
+            self.a_matrix.map(|x| x.value()),
+            self.z_matrix.map(|x| x.value()),
+        )
+    }
+
+    /// Voltage of an element's positive terminal against its negative terminal
+    fn branch_voltage(&self, element_id: usize) -> Result<f64, StatusError> {
+        let container = self.container.borrow();
+        let element: Element = match container.get_elements().get(element_id) {
+            Some(x) => x.borrow().clone(),
+            None => return Err(Known(format!("Element {} does not exist", element_id))),
+        };
+
+        match element.class {
+            VoltageSrc => Ok(-source_sign(&container, element_id) * element.value),
+            CurrentSrc | Capacitor => {
+                let index: usize = current_branches(&container)
+                    .iter()
+                    .position(|x| x.id == element_id)
+                    .unwrap_or_default();
+                Ok(-source_sign(&container, element_id) * self.result[self.loops.len() + index])
+            }
+            _ => Ok(element.value * self.branch_current(element_id)?),
+        }
+    }
+
+    /// Elements of every loop in the order they are walked
+    pub(crate) fn loop_step(&self) -> SubStep {
+        SubStep {
+            description: Some(
+                "Loops, each current flows in the direction of the first element".to_string(),
+            ),
+            result: None,
+            operations: self
+                .loops
+                .iter()
+                .enumerate()
+                .map(|(i, branches)| {
+                    let names: Vec<String> = branches
+                        .iter()
+                        .map(|(id, _)| {
+                            self.container
+                                .borrow()
+                                .get_element_by_id(*id)
+                                .basic_string()
+                        })
+                        .collect();
+                    Text(format!("I_{{{}}}: {}", i + 1, names.join(", ")))
+                })
+                .collect(),
+            forms: None,
+        }
+    }
+
+    /// The loop currents each current source ties together, read off its row of A, and the
+    /// meshes of every SuperMesh tool
+    pub(crate) fn supermesh_step(&self, numeric_a: &DMatrix<f64>, branches: &[Element]) -> SubStep {
+        let l: usize = self.loops.len();
+        let container = self.container.borrow();
+        let meshes: Vec<usize> = container
+            .get_tools(Mesh)
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .map(|x| x.borrow().id)
+            .collect();
+        let super_meshes: Vec<Operation> = container
+            .get_tools(SuperMesh)
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .map(|super_mesh| {
+                let id: usize = super_mesh.borrow().id;
+                let currents: Vec<String> = meshes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, mesh)| {
+                        container
+                            .get_supermesh_for(**mesh)
+                            .is_some_and(|x| x.borrow().id == id)
+                    })
+                    .map(|(i, _)| format!("I_{{{}}}", i + 1))
+                    .collect();
+                Text(format!("Supermesh: {}", currents.join(", ")))
+            })
+            .collect();
+        SubStep {
+            description: Some(
+                "Current sources, the loops through a source form a supermesh and the voltage across it is an unknown"
+                    .to_string(),
+            ),
+            result: None,
+            operations: branches
+                .iter()
+                .enumerate()
+                .map(|(k, element)| {
+                    let mut constraint: String = String::new();
+                    for i in 0..l {
+                        let sign: &str = match numeric_a[(l + k, i)] {
+                            x if x > 0.0 => "+",
+                            x if x < 0.0 => "-",
+                            _ => continue,
+                        };
+                        constraint = match (constraint.is_empty(), sign) {
+                            (true, "+") => format!("I_{{{}}}", i + 1),
+                            (true, _) => format!("-I_{{{}}}", i + 1),
+                            (false, _) => format!("{} {} I_{{{}}}", constraint, sign, i + 1),
+                        };
+                    }
+                    let value: f64 = match element.class {
+                        CurrentSrc => element.value,
+                        _ => 0.0,
+                    };
+                    Text(format!(
+                        "{}: {} = {} {}",
+                        element.basic_string(),
+                        constraint,
+                        value,
+                        Unit::Ampere
+                    ))
+                })
+                .chain(super_meshes)
+                .collect(),
+            forms: None,
+        }
+    }
+
+    /// Error naming the unknown a singular A matrix leaves undetermined
+    ///
+    /// None if the matrix can be factored after all.
+    fn singular_error(
+        &self,
+        numeric_a: &DMatrix<f64>,
+        branches: &[Element],
+    ) -> Option<StatusError> {
+        let column: usize = LuFactors::singular_column(numeric_a)?;
+        let l: usize = self.loops.len();
+        let unknown: String = match column < l {
+            true => format!("the current of loop {}", column + 1),
+            false => format!("the voltage across {}", branches[column - l].basic_string()),
+        };
+        Some(Known(format!(
+            "Matrix is not invertible, {} is not determined by the circuit. Check for current sources in series and current sources without a loop through them",
+            unknown
+        )))
+    }
+
+    /// Final step with the loop currents and the values the node solvers report
+    fn solved_values_step(&self, branches: &[Element]) -> Result<Step, StatusError> {
+        let values: SolvedValues = SolvedValues::from_solver(self)?;
+        let round = |x: f64| (x * 100.).round() / 100.;
+        let symbolic_z: DVector<Operation> =
+            form_z_vector_symbolic(&self.container.borrow(), &self.loops, branches);
+        let names: Vec<String> = values
+            .source_currents
+            .keys()
+            .map(|id| {
+                self.container
+                    .borrow()
+                    .get_element_by_id(*id)
+                    .basic_string()
+            })
+            .collect();
+
+        let mut step: Step = Step::new_with_steps(
+            "Solved Values",
+            vec![
+                SubStep::new_with_forms(
+                    "Equation",
+                    StepForms {
+                        symbolic: Some(format!(
+                            "{} = {}^{{-1}} * {}",
+                            self.x_matrix.equation_repr(),
+                            self.a_matrix.equation_repr(),
+                            symbolic_z.equation_repr()
+                        )),
+                        numeric: Some(format!(
+                            "{} = {}",
+                            self.x_matrix.equation_repr(),
+                            self.result.map(round).equation_repr()
+                        )),
+                    },
+                    self.options.forms,
+                ),
+                SubStep {
+                    description: Some("Loop currents".to_string()),
+                    result: None,
+                    operations: self
+                        .loop_currents()?
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, current)| {
+                            Text(format!(
+                                "I_{{{}}} = {} {}",
+                                i + 1,
+                                round(current),
+                                Unit::Ampere
+                            ))
+                        })
+                        .collect(),
+                    forms: None,
+                },
+                SubStep {
+                    description: Some("Node voltages".to_string()),
+                    result: None,
+                    operations: values
+                        .node_voltages
+                        .iter()
+                        .map(|(id, voltage)| {
+                            Text(format!("V_{{{}}} = {} {}", id, round(*voltage), Unit::Volt))
+                        })
+                        .collect(),
+                    forms: None,
+                },
+                SubStep {
+                    description: Some(
+                        "Source currents, from the positive to the negative terminal".to_string(),
+                    ),
+                    result: None,
+                    operations: values
+                        .source_currents
+                        .values()
+                        .zip(names)
+                        .map(|(current, name)| {
+                            Text(format!(
+                                "I_{{{}}} = {} {}",
+                                name,
+                                round(*current),
+                                Unit::Ampere
+                            ))
+                        })
+                        .collect(),
+                    forms: None,
+                },
+            ],
+        );
+        for id in values.node_voltages.keys() {
+            step.reference_node(*id);
+        }
+        for id in values.source_currents.keys() {
+            step.reference_element(*id);
+        }
+        Ok(step)
+    }
+}
+
+/// Which terminal a source drives, 1 for the node at its negative terminal, -1 for the positive one
+///
+/// Follows the node solvers. A voltage source to ground drives the other node whatever its
+/// orientation and a floating one holds its negative terminal above the positive one. A
+/// current source always pushes its current out of the positive terminal.
+pub(crate) fn source_sign(container: &Container, id: usize) -> f64 {
+    if container.get_element_by_id(id).borrow().class != VoltageSrc {
+        return -1.0;
+    }
+    match container.element_current_direction(id) {
+        (_, 0) => -1.0,
+        _ => 1.0,
+    }
+}
+
+/// Elements with a fixed current, the current sources and the capacitors, in id order
+pub(crate) fn current_branches(container: &Container) -> Vec<Element> {
+    container
+        .get_elements()
+        .iter()
+        .filter(|x| matches!(x.borrow().class, CurrentSrc | Capacitor) && !x.borrow().is_detached())
+        .map(|x| x.borrow().clone())
+        .collect()
+}
+
+/// Meshes with the direction of the mesh current through every element
+///
+/// The mesh current takes the direction of the first element, the rest follow the walk.
+pub(crate) fn form_loops(container: &Container) -> Result<Vec<Vec<Branch>>, StatusError> {
+    for element in container.get_elements().iter().map(|x| x.borrow()) {
+        if element.class == Ground || element.is_detached() {
+            continue;
+        }
+        if container.node_at(element.id, &element.positive).is_none()
+            || container.node_at(element.id, &element.negative).is_none()
+        {
+            return Err(Known(format!(
+                "{} is not connected on both terminals, it cannot be part of a loop",
+                element.pretty_string()
+            )));
+        }
     }
+
+    Ok(container
+        .get_tools(Mesh)
+        .iter()
+        .filter_map(|x| x.upgrade())
+        .map(|mesh| {
+            let ids: Vec<usize> = mesh.borrow().member_ids();
+            let mut node: usize = container.element_current_direction(ids[0]).0;
+            ids.into_iter()
+                .map(|id| {
+                    let (from, to) = container.element_current_direction(id);
+                    if node == from {
+                        node = to;
+                        (id, 1.0)
+                    } else {
+                        node = from;
+                        (id, -1.0)
+                    }
+                })
+                .collect::<Vec<Branch>>()
+        })
+        .collect())
+}
+
+fn form_a_matrix(
+    container: &Container,
+    loops: &[Vec<Branch>],
+    branches: &[Element],
+) -> DMatrix<Operation> {
+    let (l, k) = (loops.len(), branches.len());
+    let mut a_matrix: DMatrix<Operation> = DMatrix::zeros(l + k, l + k);
+
+    let r: DMatrix<Operation> = form_r_matrix(container, loops);
+    let b: DMatrix<Operation> = form_b_matrix(container, loops, branches);
+    let c: DMatrix<Operation> = form_c_matrix(container, loops, branches);
+
+    a_matrix.view_mut((0, 0), (l, l)).copy_from(&r);
+    a_matrix.view_mut((0, l), (l, k)).copy_from(&b);
+    a_matrix.view_mut((l, 0), (k, l)).copy_from(&c);
+
+    a_matrix
+}
+
+/// Resistors of each loop on the diagonal, the resistors two loops share off the diagonal
+///
+/// A shared resistor is negated when the two loop currents run through it in opposite
+/// directions.
+fn form_r_matrix(container: &Container, loops: &[Vec<Branch>]) -> DMatrix<Operation> {
+    DMatrix::from_fn(loops.len(), loops.len(), |row, column| {
+        let set: Vec<Operation> = loops[row]
+            .iter()
+            .filter_map(|(id, direction)| {
+                let element: Element = container.get_element_by_id(*id).borrow().clone();
+                if element.class != Resistor {
+                    return None;
+                }
+                let (_, other) = loops[column].iter().find(|(x, _)| x == id)?;
+                let variable: Operation = Variable(Rc::new(element));
+                Some(match direction * other > 0.0 {
+                    true => variable,
+                    false => Negate(Some(Box::new(variable))),
+                })
+            })
+            .collect();
+        Sum(set)
+    })
+}
+
+/// Voltage across each current source in the KVL of the loops through it
+fn form_b_matrix(
+    container: &Container,
+    loops: &[Vec<Branch>],
+    branches: &[Element],
+) -> DMatrix<Operation> {
+    DMatrix::from_fn(loops.len(), branches.len(), |row, column| {
+        let id: usize = branches[column].id;
+        match loops[row].iter().find(|(x, _)| *x == id) {
+            Some((_, direction)) => Value(-direction * source_sign(container, id)),
+            None => Value(0.0),
+        }
+    })
+}
+
+/// Loop currents through each current source, `-B` transposed
+fn form_c_matrix(
+    container: &Container,
+    loops: &[Vec<Branch>],
+    branches: &[Element],
+) -> DMatrix<Operation> {
+    DMatrix::from_fn(branches.len(), loops.len(), |row, column| {
+        let id: usize = branches[row].id;
+        match loops[column].iter().find(|(x, _)| *x == id) {
+            Some((_, direction)) => Value(direction * source_sign(container, id)),
+            None => Value(0.0),
+        }
+    })
+}
+
+/// Voltage sources around each loop, then the current of each current source
+fn form_z_vector(
+    container: &Container,
+    loops: &[Vec<Branch>],
+    branches: &[Element],
+) -> DVector<Operation> {
+    let mut z_vec: Vec<Operation> = Vec::new();
+
+    // V Matrix
+    // Rise of the sources along the loop current.
+    for branches in loops {
+        let set: Vec<Operation> = branches
+            .iter()
+            .map(|(id, direction)| (container.get_element_by_id(*id).borrow().clone(), direction))
+            .filter(|(element, _)| element.class == VoltageSrc)
+            .map(|(element, direction)| {
+                Value(direction * source_sign(container, element.id) * element.value)
+            })
+            .collect();
+        match set.is_empty() {
+            true => z_vec.push(Value(0.0)),
+            false => z_vec.push(Sum(set)),
+        }
+    }
+
+    // I Matrix
+    // The value of the current source, capacitors carry none.
+    for element in branches {
+        match element.class {
+            CurrentSrc => z_vec.push(Value(element.value)),
+            _ => z_vec.push(Value(0.0)),
+        }
+    }
+
+    DVector::from(z_vec)
+}
+
+/// Same rows as `form_z_vector` with the sources named instead of their values.
+fn form_z_vector_symbolic(
+    container: &Container,
+    loops: &[Vec<Branch>],
+    branches: &[Element],
+) -> DVector<Operation> {
+    let mut z_vec: Vec<Operation> = Vec::new();
+
+    for branches in loops {
+        let set: Vec<Operation> = branches
+            .iter()
+            .map(|(id, direction)| (container.get_element_by_id(*id).borrow().clone(), direction))
+            .filter(|(element, _)| element.class == VoltageSrc)
+            .map(|(element, direction)| {
+                let rise: f64 = direction * source_sign(container, element.id);
+                let variable: Operation = Variable(Rc::new(element));
+                match rise > 0.0 {
+                    true => variable,
+                    false => Negate(Some(Box::new(variable))),
+                }
+            })
+            .collect();
+        match set.is_empty() {
+            true => z_vec.push(Value(0.0)),
+            false => z_vec.push(Sum(set)),
+        }
+    }
+
+    for element in branches {
+        match element.class {
+            CurrentSrc => z_vec.push(Variable(Rc::new(element.clone()))),
+            _ => z_vec.push(Value(0.0)),
+        }
+    }
+
+    DVector::from(z_vec)
+}
+
+/// Loop currents followed by the voltage across each current source.
+fn form_x_vector(loops: usize, branches: &[Element]) -> DVector<Operation> {
+    let mut x_vec: Vec<Operation> = Vec::new();
+
+    for i in 0..loops {
+        x_vec.push(Variable(Rc::new(EquationRepr::new(
+            format!("I_{{{}}}", i + 1),
+            0.0,
+        ))));
+    }
+    for element in branches {
+        x_vec.push(Variable(Rc::new(EquationRepr::new(
+            format!("V_{{{}}}", element.basic_string()),
+            0.0,
+        ))));
+    }
+
+    DVector::from(x_vec)
 }
 
-// TODO: Mesh Tests
-// #[cfg(test)]
-// mod test {
-//     use crate::container::Container;
-//     use crate::elements::Element;
-//     use crate::tools::Tool;
-//     use crate::tools::ToolType::{Mesh, SuperMesh};
-//     use crate::util::create_basic_supermesh_container;
-//     use std::rc::{Rc, Weak};
-//
-//     #[test]
-//     fn test_get_meshes() {
-//         let mut container: Container = create_basic_supermesh_container();
-//         container.create_nodes();
-//         container.create_super_nodes();
-//         container.create_meshes();
-//         container.create_super_meshes();
-//         let meshes: Vec<Weak<Tool>> = container.get_tools_by_type(Mesh);
-//         assert_eq!(meshes.len(), 3);
-//
-//         let mesh_members: Vec<Vec<usize>> = meshes
-//             .iter()
-//             .map(|x| x.upgrade().unwrap().members())
-//             .collect();
-//         let expected_members: Vec<Vec<usize>> = vec![vec![1, 2, 5], vec![2, 3, 6], vec![3, 4, 7]];
-//
-//         println!(
-//             "{:?}",
-//             meshes
-//                 .iter()
-//                 .map(|x| x
-//                     .upgrade()
-//                     .unwrap()
-//                     .members
-//                     .iter()
-//                     .map(|y| y.upgrade().unwrap())
-//                     .collect::<Vec<Rc<Element>>>())
-//                 .collect::<Vec<Vec<Rc<Element>>>>()
-//         );
-//
-//         for i in 0..meshes.len() {
-//             println!("{:?}", mesh_members[i]);
-//             // assert_eq!(mesh_members[i], expected_members[i]);
-//         }
-//         todo!()
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use crate::assert_known_error;
+    use crate::component::Component::{Capacitor, CurrentSrc, Resistor, VoltageSrc};
+    use crate::container::Container;
+    use crate::solvers::mesh_matrix_solver::MeshMatrixSolver;
+    use crate::solvers::node_matrix_solver::NodeMatrixSolver;
+    use crate::solvers::solver::{NodeSolver, Solver, Step};
+    use crate::tools::ToolType::SuperMesh;
+    use crate::util::{create_mna_container, load_problem};
+    use crate::validation::StatusError::Known;
+    use operations::prelude::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn solved(container: Container) -> MeshMatrixSolver {
+        let mut solver: MeshMatrixSolver = Solver::new(Rc::new(RefCell::new(container)));
+        solver.solve().unwrap();
+        solver
+    }
+
+    #[test]
+    fn test_a_matrix() {
+        let solver: MeshMatrixSolver = solved(create_mna_container());
+        assert_eq!(
+            solver.loops(),
+            vec![
+                vec![(2, 1.0), (3, 1.0), (5, -1.0)],
+                vec![(4, 1.0), (3, 1.0), (1, 1.0)]
+            ]
+        );
+
+        let expected = vec![vec!["R2 + R3", "R3"], vec!["R3", "R3 + R1"]];
+        for i in 0..2 {
+            for j in 0..2 {
+                assert_eq!(expected[i][j], solver.a_matrix[(i, j)].equation_repr());
+            }
+        }
+        assert_eq!(solver.a_matrix[(0, 1)].value(), 8.0);
+        assert_eq!(solver.z_matrix[0].value(), 20.0);
+        assert_eq!(solver.z_matrix[1].value(), 32.0);
+        assert_eq!(solver.x_matrix[1].equation_repr(), "I_{2}");
+        assert_eq!(solver.loop_currents().unwrap(), vec![-1.0, 4.0]);
+    }
+
+    #[test]
+    fn test_supermesh() {
+        let problem = load_problem("supermesh").unwrap();
+        let solver: MeshMatrixSolver = solved(problem.create_container());
+
+        // The current source adds its voltage as an unknown and its current as a row
+        let expected = vec![
+            vec!["R2", "", "1"],
+            vec!["", "R4 + R5", "1"],
+            vec!["-1", "-1", "0"],
+        ];
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(expected[i][j], solver.a_matrix[(i, j)].equation_repr());
+            }
+        }
+        assert_eq!(solver.x_matrix[2].equation_repr(), "V_{SRC(C)3}");
+        assert!((solver.branch_current(4).unwrap() + 1.2).abs() < 1e-9);
+        assert!((solver.branch_current(3).unwrap() + 1.0).abs() < 1e-9);
+        for (id, voltage) in problem.node_voltages.iter() {
+            assert!((solver.node_voltage(*id).unwrap() - voltage).abs() < 1e-9);
+        }
+
+        let steps = serde_json::to_value(solver.steps.clone()).unwrap();
+        assert_eq!(
+            steps[0]["sub_steps"][1]["operations"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        let operations: String = steps[0]["sub_steps"][1]["operations"].to_string();
+        assert!(operations.contains("SRC(C)3: -I_{1} - I_{2} = 1 A"));
+        assert!(operations.contains("Supermesh: I_{1}, I_{2}"));
+        assert_eq!(solver.container().borrow().get_tools(SuperMesh).len(), 1);
+    }
+
+    #[test]
+    fn test_against_node_matrix_solver() {
+        for container in [
+            create_mna_container(),
+            load_problem("superposition").unwrap().create_container(),
+            load_problem("supernode_current_source")
+                .unwrap()
+                .create_container(),
+            load_problem("wheatstone_bridge_unbalanced")
+                .unwrap()
+                .create_container(),
+        ] {
+            let mesh: MeshMatrixSolver = solved(container.clone());
+            let mut node: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(container)));
+            node.solve().unwrap();
+
+            assert!(mesh.compare_with(&node, 1e-9).is_empty());
+            for element in mesh.container().borrow().get_elements() {
+                let id: usize = element.borrow().id;
+                if element.borrow().class == VoltageSrc {
+                    let current: f64 = mesh.current_through_source(id).unwrap();
+                    assert!((current - node.current_through_source(id).unwrap()).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_steps() {
+        let mut solver: MeshMatrixSolver =
+            Solver::new(Rc::new(RefCell::new(create_mna_container())));
+        assert_known_error!(solver.node_voltages(), "Circuit has not been solved");
+
+        let steps: Vec<Step> = solver.solve().unwrap();
+        let titles: Vec<String> = steps.iter().filter_map(|x| x.title()).collect();
+        assert_eq!(titles, vec!["Mesh Matrix Solver", "Solved Values"]);
+        assert_eq!(steps[0].referenced_elements, vec![1, 2, 3, 4, 5]);
+        let steps = serde_json::to_value(steps).unwrap();
+        assert!(steps[0]["sub_steps"][0]["operations"]
+            .to_string()
+            .contains("I_{1}: R2, R3, SRC(V)5"));
+        assert_eq!(steps[1]["sub_steps"][1]["description"], "Loop currents");
+        let currents: String = steps[1]["sub_steps"][1]["operations"].to_string();
+        assert!(currents.contains("I_{1} = -1 A") && currents.contains("I_{2} = 4 A"));
+    }
+
+    #[test]
+    fn test_capacitor_is_open() {
+        let mut container = Container::new();
+        container.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        container.add_element_between_nodes(Resistor, 2., 1, 2);
+        container.add_element_between_nodes(Resistor, 2., 2, 0);
+        container.add_element_between_nodes(Capacitor, 1., 2, 0);
+        let solver: MeshMatrixSolver = solved(container);
+
+        assert!((solver.node_voltage(2).unwrap() - 5.0).abs() < 1e-9);
+        assert!(solver.branch_current(4).unwrap().abs() < 1e-9);
+        assert!((solver.element_voltage(4).unwrap() - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_current_sources_in_series() {
+        let mut container = Container::new();
+        container.add_element_between_nodes(CurrentSrc, 1., 1, 0);
+        container.add_element_between_nodes(CurrentSrc, 2., 2, 1);
+        container.add_element_between_nodes(Resistor, 1., 2, 0);
+        let mut solver: MeshMatrixSolver = Solver::new(Rc::new(RefCell::new(container)));
+
+        assert!(matches!(
+            solver.solve(),
+            Err(Known(message)) if message.starts_with("Matrix is not invertible")
+        ));
+    }
+}
@@ -1,21 +1,475 @@
+use crate::component::Component::{Capacitor, CurrentSrc, Ground, Resistor, VoltageSrc};
+use crate::component::Unit;
 use crate::container::Container;
-use crate::solvers::solver::{Solver, Step};
+use crate::elements::Element;
+use crate::solvers::mesh_matrix_solver::{
+    current_branches, form_loops, source_sign, MeshMatrixSolver,
+};
+use crate::solvers::options::SolveOptions;
+use crate::solvers::progress::{NoProgress, ProgressSink};
+use crate::solvers::solver::{
+    check_generation, NodeSolver, Solver, SolverType, Step, StepForms, SubStep,
+};
+use crate::tools::ToolType::Mesh;
+use crate::util::PrettyPrint;
 use crate::validation::StatusError;
+use nalgebra::{DMatrix, DVector};
+use operations::prelude::Text;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-// TODO MeshStepSolver
-#[allow(dead_code)]
+/// Mesh analysis worked by hand
+///
+/// Lists the Mesh tools of the container, writes KVL around every mesh with the resistor
+/// drops in terms of the mesh currents, collects the equations into one system and reads
+/// the branch currents off the solved mesh currents. The numbers are those of
+/// `MeshMatrixSolver`, which creates the meshes and solves the same system.
 pub struct MeshStepSolver {
     container: Rc<RefCell<Container>>,
+    options: SolveOptions,
+    matrix: MeshMatrixSolver, // Solves the system the steps walk through
+    steps: Vec<Step>,         // Cached output of the first solve
+    generation: u64,          // Container generation the solver was created for
 }
 
 impl Solver for MeshStepSolver {
-    fn new(container: Rc<RefCell<Container>>) -> Self {
-        MeshStepSolver { container }
+    fn new_with_options(container: Rc<RefCell<Container>>, options: SolveOptions) -> Self {
+        let mut matrix: MeshMatrixSolver =
+            Solver::new_with_options(container.clone(), options.clone());
+        matrix.solver = SolverType::MeshStep;
+        let generation: u64 = container.borrow().generation();
+        MeshStepSolver {
+            container,
+            options,
+            matrix,
+            steps: vec![],
+            generation,
+        }
     }
 
     fn solve(&mut self) -> Result<Vec<Step>, StatusError> {
-        todo!()
+        self.solve_with_progress(&NoProgress)
+    }
+
+    fn solve_with_progress(
+        &mut self,
+        progress: &dyn ProgressSink,
+    ) -> Result<Vec<Step>, StatusError> {
+        check_generation(&self.container, self.generation)?;
+        if !self.steps.is_empty() {
+            return Ok(self.steps.clone());
+        }
+        let solved: Vec<Step> = self.matrix.solve_with_progress(progress)?;
+
+        let branches: Vec<Element> = current_branches(&self.container.borrow());
+        if self.meshes().is_empty() && branches.is_empty() {
+            self.steps = solved;
+            return Ok(self.steps.clone());
+        }
+
+        self.steps = vec![
+            self.meshes_step(&branches),
+            self.kvl_step(&branches),
+            self.system_step(&branches),
+            self.mesh_currents_step(&branches),
+            self.branch_currents_step(),
+        ];
+        Ok(self.steps.clone())
+    }
+}
+
+impl NodeSolver for MeshStepSolver {
+    fn container(&self) -> Rc<RefCell<Container>> {
+        self.container.clone()
+    }
+
+    fn node_voltages(&self) -> Result<Vec<(usize, f64)>, StatusError> {
+        self.matrix.node_voltages()
+    }
+
+    fn voltage_source_current(&self, source_id: usize) -> Result<f64, StatusError> {
+        self.matrix.voltage_source_current(source_id)
+    }
+}
+
+impl MeshStepSolver {
+    /// Mesh tools of the container with the direction of the mesh current through every
+    /// element, in the order of the mesh currents
+    fn meshes(&self) -> Vec<Vec<(usize, f64)>> {
+        form_loops(&self.container.borrow()).unwrap_or_default()
+    }
+
+    /// The meshes found and the current sources that tie them into supermeshes
+    fn meshes_step(&self, branches: &[Element]) -> Step {
+        let container = self.container.borrow();
+        let meshes: Vec<Vec<(usize, f64)>> = self.meshes();
+        let found: SubStep = SubStep {
+            description: Some(
+                "Meshes found in the circuit, each current flows in the direction of the first element"
+                    .to_string(),
+            ),
+            result: None,
+            operations: meshes
+                .iter()
+                .enumerate()
+                .map(|(i, mesh)| {
+                    let names: Vec<String> = mesh
+                        .iter()
+                        .map(|(id, _)| container.get_element_by_id(*id).basic_string())
+                        .collect();
+                    Text(format!(
+                        "Mesh {}: I_{{{}}} through {}",
+                        i + 1,
+                        i + 1,
+                        names.join(", ")
+                    ))
+                })
+                .collect(),
+            forms: None,
+        };
+        let mut sub_steps: Vec<SubStep> = vec![found];
+        if !branches.is_empty() {
+            sub_steps.push(
+                self.matrix
+                    .supermesh_step(&self.matrix.numeric_system().0, branches),
+            );
+        }
+
+        let mut step: Step = Step::new_with_steps("Meshes", sub_steps);
+        step.description = Some(format!(
+            "{} meshes of the circuit with one current each",
+            container.get_tools(Mesh).len()
+        ));
+        for (id, _) in meshes.iter().flatten() {
+            step.reference_element(*id);
+        }
+        step
+    }
+
+    /// KVL around every mesh, the drops on the left and the source rises on the right
+    fn kvl_step(&self, branches: &[Element]) -> Step {
+        let meshes: Vec<Vec<(usize, f64)>> = self.meshes();
+        let sub_steps: Vec<SubStep> = (0..meshes.len())
+            .map(|i| {
+                SubStep::new_with_forms(
+                    &format!("Mesh {}", i + 1),
+                    StepForms {
+                        symbolic: Some(self.kvl(i, false)),
+                        numeric: Some(self.kvl(i, true)),
+                    },
+                    self.options.forms,
+                )
+            })
+            .collect();
+
+        let mut step: Step = Step::new_with_steps("KVL Equations", sub_steps);
+        step.description = Some(match branches.is_empty() {
+            true => "The voltage drops around each loop add up to the voltage sources in it"
+                .to_string(),
+            false => "The voltage drops around each loop add up to the voltage sources in it, the voltage across a current source is unknown".to_string(),
+        });
+        for (id, _) in meshes.iter().flatten() {
+            step.reference_element(*id);
+        }
+        step
+    }
+
+    /// KVL around mesh `index`, with the element values instead of their names if `numeric`
+    fn kvl(&self, index: usize, numeric: bool) -> String {
+        let container = self.container.borrow();
+        let loops: Vec<Vec<(usize, f64)>> = self.meshes();
+        let mut drops: Vec<(f64, String)> = Vec::new();
+        let mut rises: Vec<(f64, String)> = Vec::new();
+
+        for (id, direction) in loops[index].iter() {
+            let element: Element = container.get_element_by_id(*id).borrow().clone();
+            let name: String = match numeric {
+                true => format!("{}", element.value),
+                false => element.basic_string(),
+            };
+            match element.class {
+                Resistor => drops.push((
+                    1.0,
+                    format!("{} * {}", name, loop_current_sum(&loops, index, *id)),
+                )),
+                VoltageSrc => rises.push((direction * source_sign(&container, *id), name)),
+                CurrentSrc | Capacitor => drops.push((
+                    -direction * source_sign(&container, *id),
+                    format!("V_{{{}}}", element.basic_string()),
+                )),
+                _ => {}
+            }
+        }
+        format!("{} = {}", signed_sum(&drops), signed_sum(&rises))
+    }
+
+    /// Every KVL equation and current source constraint as one row of the system
+    fn system_step(&self, branches: &[Element]) -> Step {
+        let (a, z): (DMatrix<f64>, DVector<f64>) = self.matrix.numeric_system();
+        let unknowns: Vec<String> = unknowns(self.meshes().len(), branches);
+        let operations = (0..a.nrows())
+            .map(|row| {
+                let terms: Vec<(f64, String)> = (0..a.ncols())
+                    .filter(|column| a[(row, *column)] != 0.0)
+                    .map(|column| {
+                        let coefficient: f64 = a[(row, column)].abs();
+                        match coefficient == 1.0 {
+                            true => (a[(row, column)], unknowns[column].clone()),
+                            false => (
+                                a[(row, column)],
+                                format!("{} {}", coefficient, unknowns[column]),
+                            ),
+                        }
+                    })
+                    .collect();
+                Text(format!("{} = {}", signed_sum(&terms), z[row]))
+            })
+            .collect();
+
+        let mut step: Step = Step::new_with_steps(
+            "System of Equations",
+            vec![SubStep {
+                description: Some(format!(
+                    "{} equations for {} unknowns",
+                    a.nrows(),
+                    a.ncols()
+                )),
+                result: None,
+                operations,
+                forms: None,
+            }],
+        );
+        step.description = Some("Collect the terms of each unknown".to_string());
+        step
+    }
+
+    /// Solved loop currents and the voltages across the current sources
+    fn mesh_currents_step(&self, branches: &[Element]) -> Step {
+        let l: usize = self.meshes().len();
+        let unknowns: Vec<String> = unknowns(l, branches);
+        let operations = self
+            .matrix
+            .result()
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let unit: Unit = match i < l {
+                    true => Unit::Ampere,
+                    false => Unit::Volt,
+                };
+                Text(format!("{} = {} {}", unknowns[i], round(*value), unit))
+            })
+            .collect();
+
+        Step::new_with_steps(
+            "Mesh Currents",
+            vec![SubStep {
+                description: Some("Solve the system of equations".to_string()),
+                result: None,
+                operations,
+                forms: None,
+            }],
+        )
+    }
+
+    /// Current through every element as the sum of the loop currents through it
+    fn branch_currents_step(&self) -> Step {
+        let container = self.container.borrow();
+        let loops: Vec<Vec<(usize, f64)>> = self.meshes();
+        let mut step: Step = Step::new("Branch Currents");
+        let mut operations = Vec::new();
+
+        for element in container.get_elements().iter().map(|x| x.borrow()) {
+            if element.class == Ground || element.is_detached() {
+                continue;
+            }
+            let terms: Vec<(f64, String)> = loops
+                .iter()
+                .enumerate()
+                .filter_map(|(i, branches)| {
+                    let (_, direction) = branches.iter().find(|(id, _)| *id == element.id)?;
+                    Some((*direction, format!("I_{{{}}}", i + 1)))
+                })
+                .collect();
+            // The loops were solved already, every element has a current
+            let current: f64 = self.matrix.branch_current(element.id).unwrap_or_default();
+            operations.push(Text(format!(
+                "I_{{{}}} = {} = {} {}",
+                element.basic_string(),
+                signed_sum(&terms),
+                round(current),
+                Unit::Ampere
+            )));
+            step.reference_element(element.id);
+        }
+
+        step.sub_steps = vec![SubStep {
+            description: Some("Currents from the positive to the negative terminal".to_string()),
+            result: None,
+            operations,
+            forms: None,
+        }];
+        step
+    }
+}
+
+/// Current through element `id` along loop `index` in terms of the loop currents
+///
+/// The current of the loop itself comes first, the loops sharing the element follow with
+/// a minus sign where their current runs the other way, e.g. `(I_{1} - I_{2})`.
+fn loop_current_sum(loops: &[Vec<(usize, f64)>], index: usize, id: usize) -> String {
+    let direction = |i: usize| -> Option<f64> {
+        loops[i]
+            .iter()
+            .find(|(x, _)| *x == id)
+            .map(|(_, direction)| *direction)
+    };
+    let own: f64 = direction(index).unwrap_or(1.0);
+    let mut terms: Vec<(f64, String)> = vec![(1.0, format!("I_{{{}}}", index + 1))];
+    for i in (0..loops.len()).filter(|x| *x != index) {
+        if let Some(other) = direction(i) {
+            terms.push((own * other, format!("I_{{{}}}", i + 1)));
+        }
+    }
+
+    match terms.len() {
+        1 => terms[0].1.clone(),
+        _ => format!("({})", signed_sum(&terms)),
+    }
+}
+
+/// Terms joined by their signs, 0 without any
+fn signed_sum(terms: &[(f64, String)]) -> String {
+    let mut sum: String = String::new();
+    for (sign, term) in terms {
+        sum = match (sum.is_empty(), *sign < 0.0) {
+            (true, false) => term.clone(),
+            (true, true) => format!("-{}", term),
+            (false, false) => format!("{} + {}", sum, term),
+            (false, true) => format!("{} - {}", sum, term),
+        };
+    }
+    match sum.is_empty() {
+        true => "0".to_string(),
+        false => sum,
+    }
+}
+
+/// Names of the unknowns in the order of the mesh matrix solver
+fn unknowns(loops: usize, branches: &[Element]) -> Vec<String> {
+    (0..loops)
+        .map(|i| format!("I_{{{}}}", i + 1))
+        .chain(
+            branches
+                .iter()
+                .map(|x| format!("V_{{{}}}", x.basic_string())),
+        )
+        .collect()
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.).round() / 100.
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::Component::{Capacitor, Resistor, VoltageSrc};
+    use crate::container::Container;
+    use crate::solvers::mesh_matrix_solver::MeshMatrixSolver;
+    use crate::solvers::mesh_step_solver::MeshStepSolver;
+    use crate::solvers::solver::{NodeSolver, Solver, Step};
+    use crate::tools::ToolType::Mesh;
+    use crate::util::{create_mna_container, load_problem};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn solve(container: Container) -> (MeshStepSolver, serde_json::Value) {
+        let mut solver: MeshStepSolver = Solver::new(Rc::new(RefCell::new(container)));
+        let steps: Vec<Step> = solver.solve().unwrap();
+        (solver, serde_json::to_value(steps).unwrap())
+    }
+
+    #[test]
+    fn test_steps() {
+        let (solver, steps) = solve(create_mna_container());
+        let titles: Vec<&str> = steps
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Meshes",
+                "KVL Equations",
+                "System of Equations",
+                "Mesh Currents",
+                "Branch Currents"
+            ]
+        );
+
+        let meshes: String = steps[0]["sub_steps"][0]["operations"].to_string();
+        assert!(meshes.contains("Mesh 1: I_{1} through R2, R3, SRC(V)5"));
+        assert!(meshes.contains("Mesh 2: I_{2} through SRC(V)4, R3, R1"));
+        assert_eq!(solver.container().borrow().get_tools(Mesh).len(), 2);
+
+        // R3 carries both mesh currents, in the same direction
+        let kvl: String = steps[1]["sub_steps"].to_string();
+        assert!(kvl.contains("R2 * I_{1} + R3 * (I_{1} + I_{2}) = SRC(V)5"));
+        assert!(kvl.contains("4 * I_{1} + 8 * (I_{1} + I_{2}) = 20"));
+        assert!(kvl.contains("R3 * (I_{2} + I_{1}) + R1 * I_{2} = SRC(V)4"));
+
+        let system: String = steps[2]["sub_steps"].to_string();
+        assert!(system.contains("12 I_{1} + 8 I_{2} = 20"));
+        assert!(system.contains("8 I_{1} + 10 I_{2} = 32"));
+
+        let currents: String = steps[3]["sub_steps"].to_string();
+        assert!(currents.contains("I_{1} = -1 A") && currents.contains("I_{2} = 4 A"));
+
+        let branches: String = steps[4]["sub_steps"].to_string();
+        assert!(branches.contains("I_{R3} = I_{1} + I_{2} = 3 A"));
+        assert!(branches.contains("I_{SRC(V)5} = -I_{1} = 1 A"));
+
+        assert!((solver.node_voltage(1).unwrap() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_supermesh() {
+        let problem = load_problem("supermesh").unwrap();
+        let (solver, steps) = solve(problem.create_container());
+
+        // The current source voltage is an unknown of both loops and its current a row
+        for i in 0..2 {
+            let kvl: &str = steps[1]["sub_steps"][i]["forms"]["symbolic"]
+                .as_str()
+                .unwrap();
+            assert!(kvl.contains("V_{SRC(C)3}"));
+        }
+        let system = steps[2]["sub_steps"][0]["operations"].as_array().unwrap();
+        assert_eq!(system.len(), 3);
+        assert!(system[2].to_string().contains("-I_{1} - I_{2} = 1"));
+        assert!(steps[3]["sub_steps"]
+            .to_string()
+            .contains("V_{SRC(C)3} = 9.6 V"));
+
+        for (id, voltage) in problem.node_voltages.iter() {
+            assert!((solver.node_voltage(*id).unwrap() - voltage).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_against_mesh_matrix_solver() {
+        let mut container = Container::new();
+        container.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        container.add_element_between_nodes(Resistor, 2., 1, 2);
+        container.add_element_between_nodes(Resistor, 2., 2, 0);
+        container.add_element_between_nodes(Capacitor, 1., 2, 0);
+
+        let (step, _) = solve(container.clone());
+        let mut matrix: MeshMatrixSolver = Solver::new(Rc::new(RefCell::new(container)));
+        matrix.solve().unwrap();
+        assert!(step.compare_with(&matrix, 1e-9).is_empty());
+        assert!((step.node_voltage(2).unwrap() - 5.0).abs() < 1e-9);
     }
 }
@@ -1,5 +1,11 @@
+pub mod linear;
 pub mod mesh_matrix_solver;
 pub mod mesh_step_solver;
 pub mod node_matrix_solver;
 pub mod node_step_solver;
+pub mod options;
+pub mod progress;
+pub mod simplifier;
+pub mod solution;
 pub mod solver;
+pub mod support;
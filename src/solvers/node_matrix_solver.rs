@@ -1,472 +1,3014 @@
-use crate::component::Component::{CurrentSrc, Resistor, VoltageSrc};
+use crate::component::Component::{
+    Capacitor, CurrentSrc, DependentCurrent, DependentVoltage, Ground, Inductor, Resistor,
+    VoltageSrc,
+};
+use crate::component::{Component, Unit};
 use crate::container::Container;
 use crate::elements::Element;
-use crate::solvers::solver::{Solver, Step, SubStep};
+use crate::solvers::linear::{kahan_sum, LuFactors, Pivot};
+use crate::solvers::options::{RowOrder, SolveOptions};
+use crate::solvers::progress::{NoProgress, Phase, ProgressSink};
+use crate::solvers::solution::{Solution, SolvedValues};
+use crate::solvers::solver::{
+    check_auto_transform, check_controls, check_dc_inductors, check_generation,
+    check_negative_resistance, check_strict, limit_description, negative_resistance_sub_step,
+    NodeSolver, Solver, SolverState, SolverType, Step, StepForms, SubStep,
+};
+use crate::solvers::support::check_support;
+use crate::tools::Tool;
 use crate::util::PrettyPrint;
 use crate::validation::StatusError::Known;
-use crate::validation::{StatusError, Validation};
+use crate::validation::{StatusError, Validation, Warning, WarningKind};
 use nalgebra::{DMatrix, DVector};
 use operations::math::{EquationMember, EquationRepr};
 use operations::prelude::{Divide, Negate, Operation, Sum, Text, Value, Variable};
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+/// Relative overshoot a source limit tolerates before the source is swapped.
+const LIMIT_TOLERANCE: f64 = 1e-9;
+
+/// Angular frequency the matrices are formed at, the solver is DC only.
+const DC: f64 = 0.0;
 
 pub struct NodeMatrixSolver {
+    container: Rc<RefCell<Container>>,
+    caller: Rc<RefCell<Container>>, // Container passed in, limits are solved on a copy of it
+    options: SolveOptions,
     a_matrix: DMatrix<Operation>,
     x_matrix: DVector<Operation>,
     z_matrix: DVector<Operation>,
+    result: DVector<f64>,               // Numeric x vector, empty until solved
+    factors: Option<LuFactors>,         // Factors of the numeric A matrix, reused once known
+    source_values: HashMap<usize, f64>, // Source values set by `update_source_values`
+    steps: Vec<Step>,                   // Cached output of the first solve
+    generation: u64,                    // Container generation the matrices were formed from
 }
 
 impl Solver for NodeMatrixSolver {
-    fn new(container: Rc<RefCell<Container>>) -> NodeMatrixSolver {
+    fn new_with_options(
+        container: Rc<RefCell<Container>>,
+        options: SolveOptions,
+    ) -> NodeMatrixSolver {
+        // An invalid strategy leaves the wires alone and is reported by `solve`
+        let _ = container.borrow_mut().apply_auto_transforms(&options);
         container.borrow_mut().create_nodes();
         let n = container.borrow().nodes().len();
-        let m = container // Source Count
-            .borrow()
-            .get_elements()
-            .iter()
-            .fold(0, |acc: usize, x: &Rc<RefCell<Element>>| {
-                match x.borrow().class {
-                    VoltageSrc => acc + 1,
-                    _ => acc,
-                }
-            });
+        let m = branch_elements(&container).len(); // Source, VCVS and inductor count
 
         // https://lpsa.swarthmore.edu/Systems/Electrical/mna/MNA3.html#B_matrix
         NodeMatrixSolver {
-            a_matrix: form_a_matrix(container.clone(), n, m),
-            x_matrix: form_x_vector(container.clone()),
-            z_matrix: form_z_vector(container.clone()),
+            container: container.clone(),
+            caller: container.clone(),
+            a_matrix: form_a_matrix(container.clone(), n, m, options.row_order),
+            x_matrix: form_x_vector(container.clone(), options.row_order),
+            z_matrix: form_z_vector(container.clone(), options.row_order, &HashMap::new()),
+            result: DVector::zeros(0),
+            factors: None,
+            source_values: HashMap::new(),
+            steps: vec![],
+            generation: container.borrow().generation(),
+            options,
         }
     }
 
     /// Returns a string that represents the matrix equation to solve the circuit.
     fn solve(&mut self) -> Result<Vec<Step>, StatusError> {
-        let mut steps: Vec<Step> = Vec::new();
-
-        let inverse_result: Option<DMatrix<f64>> = DMatrix::from_iterator(
-            self.a_matrix.nrows(),
-            self.a_matrix.ncols(),
-            self.a_matrix.iter().map(|x| x.value()),
-        )
-        .try_inverse();
+        self.solve_with_progress(&NoProgress)
+    }
 
-        let inverse: DMatrix<f64>;
-        match inverse_result {
-            Some(a) => {
-                inverse = a;
-            }
-            None => {
-                return Err(Known(format!(
-                    "Matrix is not invertible!\nThis might have something to do with sizing.\n{}\n",
-                    self.a_matrix.latex_string()
-                )));
-            }
+    /// The matrices are formed when the solver is created, only the inversion and
+    /// the rendering of the steps are reported.
+    fn solve_with_progress(
+        &mut self,
+        progress: &dyn ProgressSink,
+    ) -> Result<Vec<Step>, StatusError> {
+        check_generation(&self.caller, self.generation)?;
+        if !self.steps.is_empty() {
+            return Ok(self.steps.clone());
         }
+        check_support(SolverType::NodeMatrix, &self.caller)?;
+        check_controls(&self.container)?;
+        check_auto_transform(&self.container, &self.options)?;
+        check_negative_resistance(&self.container, &self.options)?;
+        check_dc_inductors(&self.container, &self.options)?;
+        self.options.zero_ohm_strategy.validate()?;
+        // Limited sources add warnings, strict mode has to see them
+        self.apply_source_limits()?;
+        check_strict(&self.caller, &self.options)?;
+        self.check_causality()?;
+        let mut steps: Vec<Step> = Vec::new();
 
-        let z_vector: DVector<f64> = self
-            .z_matrix
-            .iter()
-            .map(|x| x.value())
-            .collect::<Vec<f64>>()
-            .into();
+        // Without nodes or sources the matrices are empty and there is nothing to invert
+        if self.a_matrix.nrows() == 0 {
+            let mut step: Step = Step::new("Empty circuit");
+            step.description =
+                Some("The circuit has no nodes, there is nothing to solve".to_string());
+            steps.push(step);
+            return Ok(steps);
+        }
 
-        let mut result = inverse.clone() * z_vector;
+        progress.report(Phase::Factorization, 0.0);
+        let (numeric_a, z_vector) = self.numeric_system();
+        // Factored once, the unknowns come from the factors and the inverse is only shown
+        let factors: LuFactors = match self.factors.take() {
+            Some(factors) => factors,
+            None => LuFactors::factorize_with(&numeric_a, !self.options.no_pivoting)
+                .map_err(|x| self.singular_error(&numeric_a).unwrap_or(x))?,
+        };
+        let inverse: DMatrix<f64> = factors.inverse();
+        self.result = factors.solve(&z_vector);
+        self.factors = Some(factors);
+        progress.report(Phase::Factorization, 1.0);
+        progress.report(Phase::Rendering, 0.0);
+        let mut result = self.result.clone();
 
         result
             .iter_mut()
             .for_each(|x| *x = (*x * 100.).round() / 100.);
 
+        let symbolic_z: DVector<Operation> =
+            form_z_vector_symbolic(self.container.clone(), self.options.row_order);
+        let display = self.options.forms;
+
         steps.push(Step {
             title: Some("Node Matrix Solver".to_string()),
             description: Some("Form matrices".to_string()),
             sub_steps: vec![
-                SubStep {
-                    description: Some("A Matrix".to_string()),
-                    result: None,
-                    operations: vec![Variable(Rc::new(self.a_matrix.clone()))],
-                },
-                SubStep {
-                    description: Some("Z Matrix".to_string()),
-                    result: None,
-                    operations: vec![Variable(Rc::new(self.z_matrix.clone()))],
-                },
+                SubStep::new_with_forms(
+                    "A Matrix",
+                    StepForms {
+                        symbolic: Some(self.a_matrix.latex_string()),
+                        numeric: Some(numeric_a.latex_string()),
+                    },
+                    display,
+                ),
+                SubStep::new_with_forms(
+                    "Z Matrix",
+                    StepForms {
+                        symbolic: Some(symbolic_z.latex_string()),
+                        numeric: Some(self.z_matrix.latex_string()),
+                    },
+                    display,
+                ),
                 SubStep {
                     result: None,
                     description: Some("X Matrix".to_string()),
                     operations: vec![Variable(Rc::new(self.x_matrix.clone()))],
+                    forms: None,
                 },
                 SubStep {
                     description: Some("Inverse A Matrix".to_string()),
                     result: None,
                     operations: vec![Variable(Rc::new(inverse.clone()))],
+                    forms: None,
                 },
-                SubStep {
-                    description: Some("Final Equation".to_string()),
-                    result: None,
-                    operations: vec![Text(format!(
-                        "{} = {}^{{-1}} * {}",
-                        self.x_matrix.equation_repr(),
-                        self.a_matrix.equation_repr(),
-                        self.z_matrix.equation_repr()
-                    ))],
-                },
+                SubStep::new_with_forms(
+                    "Final Equation",
+                    StepForms {
+                        symbolic: Some(format!(
+                            "{} = {}^{{-1}} * {}",
+                            self.x_matrix.equation_repr(),
+                            self.a_matrix.equation_repr(),
+                            symbolic_z.equation_repr()
+                        )),
+                        numeric: Some(format!(
+                            "{} = {}^{{-1}} * {}",
+                            self.x_matrix.equation_repr(),
+                            numeric_a.equation_repr(),
+                            self.z_matrix.equation_repr()
+                        )),
+                    },
+                    display,
+                ),
+                self.reference_step()?,
             ],
             result: Some(Text(format!(
                 "${} = {}$",
                 self.x_matrix.equation_repr(),
                 result.equation_repr()
             ))),
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
         });
+        // The matrices involve every branch and every node
+        let step: &mut Step = steps.last_mut().unwrap();
+        for element in self.container.borrow().get_elements() {
+            if element.borrow().class != Ground && !element.borrow().is_detached() {
+                step.reference_element(element.borrow().id);
+            }
+        }
+        for (_, node) in node_rows(&self.container, self.options.row_order) {
+            step.reference_node(node.borrow().id);
+        }
+        if let Some(sub_step) = self.pivot_step() {
+            steps[0].sub_steps.push(sub_step);
+        }
+        if let Some(sub_step) = self.tag_step()? {
+            steps[0].sub_steps.push(sub_step);
+        }
+        if let Some(sub_step) = negative_resistance_sub_step(&self.container) {
+            steps[0].sub_steps.push(sub_step);
+        }
+        if let Some(sub_step) = self.limit_step() {
+            steps[0].sub_steps.push(sub_step);
+        }
+        steps.push(self.solved_values_step()?);
+        progress.report(Phase::Rendering, 1.0);
 
+        self.steps = steps.clone();
         Ok(steps)
     }
 }
 
-fn form_a_matrix(container: Rc<RefCell<Container>>, n: usize, m: usize) -> DMatrix<Operation> {
-    let mut a_matrix: DMatrix<Operation> = DMatrix::<Operation>::zeros(n + m, n + m);
-
-    let g: DMatrix<Operation> = form_g_matrix(container.clone(), n);
-    let b: DMatrix<Operation> = form_b_matrix(container.clone(), n, m);
-    let c: DMatrix<Operation> = form_c_matrix(container.clone(), n, m);
-    let d: DMatrix<Operation> = form_d_matrix(container.clone(), m);
-
-    a_matrix.view_mut((0, 0), (n, n)).copy_from(&g);
-    a_matrix.view_mut((0, n), (n, m)).copy_from(&b);
-    a_matrix.view_mut((n, 0), (m, n)).copy_from(&c);
-    a_matrix.view_mut((n, n), (m, m)).copy_from(&d);
-
-    a_matrix
-}
-
-fn form_g_matrix(container: Rc<RefCell<Container>>, n: usize) -> DMatrix<Operation> {
-    let mut matrix: DMatrix<Operation> = DMatrix::zeros(n, n);
-    let mut nodes = container.borrow().nodes().clone();
-    let _elements = container.borrow().get_elements().clone();
-
-    nodes.sort_by(|a, b| a.upgrade().unwrap().id().cmp(&b.upgrade().unwrap().id()));
+impl NodeSolver for NodeMatrixSolver {
+    fn container(&self) -> Rc<RefCell<Container>> {
+        self.container.clone()
+    }
 
-    // assert_eq!(nodes.len(), n);
+    fn node_voltages(&self) -> Result<Vec<(usize, f64)>, StatusError> {
+        if self.result.len() == 0 {
+            return Err(Known("Circuit has not been solved".to_string()));
+        }
 
-    // Form the diagonal
-    for (i, tool) in nodes.iter().enumerate() {
-        let equation_members: Vec<EquationRepr> = tool
-            .upgrade()
-            .unwrap()
+        let mut nodes: Vec<usize> = self
+            .container
             .borrow()
-            .members
+            .nodes()
             .iter()
-            .filter(|x| x.upgrade().unwrap().borrow().class == Resistor)
-            .map(|x| EquationRepr::from(Rc::new(x.upgrade().unwrap().borrow().clone())))
-            .collect();
-        let set: Vec<Operation> = equation_members
-            .into_iter()
-            .map(|x| {
-                Divide(
-                    Some(Box::new(Value(1.0))),
-                    Some(Box::new(Variable(Rc::new(x)))),
-                )
-            })
+            .map(|x| x.upgrade().unwrap().borrow().id)
             .collect();
+        nodes.sort();
+
+        // Rows follow the configured order, see node_rows
+        let n: usize = nodes.len();
+        let mut raw: Vec<(usize, f64)> = vec![(0, 0.0)];
+        for (i, id) in nodes.into_iter().enumerate() {
+            raw.push((id, self.result[self.options.row_order.row(i, n)]));
+        }
 
-        matrix[(n - i - 1, n - i - 1)] = Sum(set);
+        self.options.reference.apply(raw)
     }
 
-    // Form the off-diagonal
-    // Find all resistors between two nodes
-    for (i, tool) in nodes.iter().enumerate() {
-        for (j, tool2) in nodes.iter().enumerate() {
-            if i == j {
-                continue;
-            }
-            let mut set: Vec<Operation> = Vec::new();
-            for element in &tool.upgrade().unwrap().borrow().members {
-                let element = element.upgrade().unwrap();
-                if element.borrow().class != Resistor {
-                    continue;
-                }
-                for element2 in tool2.upgrade().unwrap().borrow().members.clone() {
-                    let element2 = element2.upgrade().unwrap();
-                    if element2.borrow().class != Resistor {
-                        continue;
-                    }
-                    if element.borrow().id == element2.borrow().id {
-                        set.push(Negate(Some(Box::new(Divide(
-                            Some(Box::new(Value(1.0))),
-                            Some(Box::from(Variable(Rc::new(element.borrow().clone())))),
-                        )))));
-                    }
-                }
-            }
-            matrix[(n - i - 1, n - j - 1)] = Sum(set);
+    /// Voltage source currents are the J sub-vector of the solution, inductor currents follow them.
+    fn voltage_source_current(&self, source_id: usize) -> Result<f64, StatusError> {
+        if self.result.len() == 0 {
+            return Err(Known("Circuit has not been solved".to_string()));
         }
-    }
-    matrix
-}
 
-pub fn form_b_matrix(container: Rc<RefCell<Container>>, n: usize, m: usize) -> DMatrix<Operation> {
-    let mut matrix: DMatrix<Operation> = DMatrix::zeros(n, m);
+        let n: usize = self.container.borrow().nodes().len();
+        let index: Option<usize> = branch_elements(&self.container)
+            .iter()
+            .position(|x| x.upgrade().unwrap().borrow().id == source_id);
 
-    for (i, tool) in container.borrow().nodes().iter().enumerate() {
-        for (j, element) in container.borrow().get_voltage_sources().iter().enumerate() {
-            if tool
-                .upgrade()
-                .unwrap()
-                .borrow()
-                .contains(element.upgrade().unwrap())
-            {
-                if element.upgrade().unwrap().borrow().positive.contains(
-                    &tool.upgrade().unwrap().borrow().members[0]
-                        .upgrade()
-                        .unwrap()
-                        .id(),
-                ) {
-                    matrix[(n - i - 1, j)] = Value(-1.0);
-                } else {
-                    matrix[(n - i - 1, j)] = Value(1.0);
-                }
-            }
+        match index {
+            Some(j) => Ok(self.result[n + j]),
+            None => Err(Known(format!(
+                "Element {} is not a voltage source",
+                source_id
+            ))),
         }
     }
 
-    matrix
-}
+    fn pivots(&self) -> Vec<Pivot> {
+        match &self.factors {
+            Some(factors) => factors.pivots.clone(),
+            None => vec![],
+        }
+    }
 
-pub(crate) fn form_c_matrix(
-    container: Rc<RefCell<Container>>,
-    n: usize,
-    m: usize,
-) -> DMatrix<Operation> {
-    let matrix: DMatrix<Operation> = form_b_matrix(container.clone(), n, m);
-    matrix.transpose()
-}
+    fn factored_system(&self) -> Option<(LuFactors, DVector<f64>)> {
+        let factors: LuFactors = self.factors.clone()?;
+        Some((factors, self.numeric_system().1))
+    }
 
-fn form_d_matrix(_container: Rc<RefCell<Container>>, m: usize) -> DMatrix<Operation> {
-    DMatrix::zeros(m, m)
-}
+    fn save_state(&self) -> Result<SolverState, StatusError> {
+        Ok(SolverState {
+            elements: self
+                .container
+                .borrow()
+                .get_elements()
+                .iter()
+                .map(|x| x.borrow().clone())
+                .collect(),
+            options: self.options.clone(),
+            factorization: self.factors.clone(),
+            solution: self.result.iter().copied().collect(),
+            source_values: self.source_values.clone(),
+        })
+    }
 
-fn form_z_vector(container: Rc<RefCell<Container>>) -> DVector<Operation> {
-    let mut z_vec: Vec<Operation> = Vec::new();
+    /// The restored solver owns a new container built from the saved elements.
+    fn restore_state(&mut self, state: SolverState) -> Result<(), StatusError> {
+        let mut container: Container = Container::new();
+        for element in state.elements {
+            container.add_element_no_id(element);
+        }
+        let mut restored: NodeMatrixSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(container)), state.options);
 
-    // I Matrix
-    // The balance of current flowing in the node.
-    container.borrow().nodes().iter().for_each(|tool| {
-        let mut set: Vec<Operation> = Vec::new();
-        for element in &tool.upgrade().unwrap().borrow().members {
-            let element = element.upgrade().unwrap();
-            if element.borrow().class != CurrentSrc {
-                continue;
+        let size: usize = restored.a_matrix.nrows();
+        if let Some(factors) = &state.factorization {
+            if factors.size != size {
+                return Err(Known(format!(
+                    "Saved factorization is {}x{} but the circuit needs {}x{}",
+                    factors.size, factors.size, size, size
+                )));
             }
-            set.push(Value(element.borrow().value));
         }
-        if set.len() == 0 {
-            z_vec.push(Value(0.0));
-        } else {
-            z_vec.push(Sum(set));
+        if !state.solution.is_empty() && state.solution.len() != size {
+            return Err(Known(format!(
+                "Saved solution has {} values but the circuit has {} unknowns",
+                state.solution.len(),
+                size
+            )));
         }
-    });
-
-    // E Matrix
-    // The value of the voltage source.
-    container
-        .borrow()
-        .get_voltage_sources()
-        .iter()
-        .for_each(|source| {
-            z_vec.push(Value(source.upgrade().unwrap().borrow().value));
-        });
 
-    DVector::from(z_vec)
-}
+        restored.factors = state.factorization;
+        restored.result = DVector::from_vec(state.solution);
+        // The solution was solved against the updated source values, z has to match it
+        if !state.source_values.is_empty() {
+            restored.source_values = state.source_values;
+            restored.z_matrix = form_z_vector(
+                restored.container.clone(),
+                restored.options.row_order,
+                &restored.source_values,
+            );
+        }
+        *self = restored;
+        Ok(())
+    }
 
-fn form_x_vector(container: Rc<RefCell<Container>>) -> DVector<Operation> {
-    let mut x_vec: Vec<Operation> = Vec::new();
+    /// Only z is formed again, the factors of the first solve are reused.
+    ///
+    /// The steps of the first solve are dropped, `solve` renders them with the new values.
+    fn update_source_values(&mut self, new_values: HashMap<usize, f64>) -> Result<(), StatusError> {
+        check_generation(&self.caller, self.generation)?;
+        let factors: LuFactors = self
+            .factors
+            .clone()
+            .ok_or_else(|| Known("Circuit has not been solved".to_string()))?;
+        for id in new_values.keys() {
+            let class: Option<Component> = self
+                .container
+                .borrow()
+                .get_elements()
+                .get(*id)
+                .map(|x| x.borrow().class.clone());
+            if !matches!(class, Some(VoltageSrc | CurrentSrc)) {
+                return Err(Known(format!(
+                    "Element {} is not an independent source",
+                    id
+                )));
+            }
+        }
 
-    // V Matrix
-    for tool in container.borrow().nodes() {
-        x_vec.push(Variable(Rc::new(EquationRepr::new(
-            format!("{}", tool.upgrade().unwrap().borrow().pretty_string()),
-            0.0,
-        ))));
+        self.source_values.extend(new_values);
+        self.z_matrix = form_z_vector(
+            self.container.clone(),
+            self.options.row_order,
+            &self.source_values,
+        );
+        self.result = factors.solve(&self.numeric_z());
+        self.steps.clear();
+        Ok(())
     }
+}
 
-    // J Matrix
-    for source in container.borrow().get_voltage_sources() {
-        x_vec.push(Variable(Rc::new(EquationRepr::new(
-            format!("{}", source.upgrade().unwrap().pretty_string()),
-            0.0,
-        ))));
+impl NodeMatrixSolver {
+    /// Describe which reference the reported node voltages are measured against.
+    fn reference_step(&self) -> Result<SubStep, StatusError> {
+        Ok(SubStep {
+            description: Some(format!(
+                "Node voltages are reported relative to {}",
+                self.options.reference
+            )),
+            result: None,
+            operations: self
+                .node_voltages()?
+                .into_iter()
+                .map(|(id, voltage)| {
+                    Text(format!(
+                        "V_{{{}}} = {} {}",
+                        id,
+                        (voltage * 100.).round() / 100.,
+                        Unit::Volt
+                    ))
+                })
+                .collect(),
+            forms: None,
+        })
     }
 
-    DVector::from(x_vec)
-}
+    /// Error naming the unknown a singular A matrix leaves undetermined
+    ///
+    /// None if the matrix can be factored after all.
+    fn singular_error(&self, numeric_a: &DMatrix<f64>) -> Option<StatusError> {
+        let column: usize = LuFactors::singular_column(numeric_a)?;
+        let n: usize = self.container.borrow().nodes().len();
+        let unknown: String = if column < n {
+            let (_, tool) = node_rows(&self.container, self.options.row_order)
+                .into_iter()
+                .find(|(row, _)| *row == column)?;
+            let id: usize = tool.borrow().id;
+            format!("the voltage of Node {}", id)
+        } else {
+            let source = branch_elements(&self.container)
+                .get(column - n)?
+                .upgrade()?;
+            let name: String = source.basic_string();
+            format!("the current through {}", name)
+        };
+        Some(Known(format!(
+            "Matrix is not invertible, {} is not determined by the circuit. Check for nodes without a path to ground and loops of voltage sources",
+            unknown
+        )))
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::solvers::node_matrix_solver::{
-        form_b_matrix, form_c_matrix, form_d_matrix, form_g_matrix, NodeMatrixSolver,
-    };
-    use crate::solvers::solver::Solver;
-    use crate::util::{create_mna_container, create_mna_container_2};
-    use operations::prelude::*;
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    /// Final step with the solved equation and the value of every unknown
+    fn solved_values_step(&self) -> Result<Step, StatusError> {
+        let values: SolvedValues = SolvedValues::from_solver(self)?;
+        let round = |x: f64| (x * 100.).round() / 100.;
+        let names: Vec<String> = values
+            .source_currents
+            .keys()
+            .map(|id| {
+                self.container
+                    .borrow()
+                    .get_element_by_id(*id)
+                    .basic_string()
+            })
+            .collect();
+        let symbolic_z: DVector<Operation> =
+            form_z_vector_symbolic(self.container.clone(), self.options.row_order);
 
-    #[test]
-    fn test_node_solver() {
-        let mut c = create_mna_container();
-        c.create_nodes().unwrap();
-        let _solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let mut step: Step = Step::new_with_steps(
+            "Solved Values",
+            vec![
+                SubStep::new_with_forms(
+                    "Equation",
+                    StepForms {
+                        symbolic: Some(format!(
+                            "{} = {}^{{-1}} * {}",
+                            self.x_matrix.equation_repr(),
+                            self.a_matrix.equation_repr(),
+                            symbolic_z.equation_repr()
+                        )),
+                        numeric: Some(format!(
+                            "{} = {}",
+                            self.x_matrix.equation_repr(),
+                            self.result.map(round).equation_repr()
+                        )),
+                    },
+                    self.options.forms,
+                ),
+                SubStep {
+                    description: Some("Node voltages".to_string()),
+                    result: None,
+                    operations: values
+                        .node_voltages
+                        .iter()
+                        .map(|(id, voltage)| {
+                            Text(format!("V_{{{}}} = {} {}", id, round(*voltage), Unit::Volt))
+                        })
+                        .collect(),
+                    forms: None,
+                },
+                SubStep {
+                    description: Some(
+                        "Source currents, from the positive to the negative terminal".to_string(),
+                    ),
+                    result: None,
+                    operations: values
+                        .source_currents
+                        .values()
+                        .zip(names)
+                        .map(|(current, name)| {
+                            Text(format!(
+                                "I_{{{}}} = {} {}",
+                                name,
+                                round(*current),
+                                Unit::Ampere
+                            ))
+                        })
+                        .collect(),
+                    forms: None,
+                },
+            ],
+        );
+        for id in values.node_voltages.keys() {
+            step.reference_node(*id);
+        }
+        for id in values.source_currents.keys() {
+            step.reference_element(*id);
+        }
+        Ok(step)
+    }
 
-        let mut c = create_mna_container_2();
-        c.create_nodes().unwrap();
-        c.create_super_nodes();
-        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
-        let steps = solver.solve();
-        assert!(steps.is_ok());
+    /// Signed conductance contributions of each element to every G entry, rows as in A.
+    pub fn g_contributions(&self) -> DMatrix<Vec<(usize, f64)>> {
+        let n: usize = self.container.borrow().nodes().len();
+        g_contributions(self.container.clone(), n, self.options.row_order)
     }
 
-    #[test]
-    fn test_a_matrix() {
-        let expected = vec![
-            vec!["1/R1", "", "", "-1", "0"],
-            vec!["", "1/R2 + 1/R3", "-1/R2", "1", "0"],
-            vec!["", "-1/R2", "1/R2", "0", "1"],
-            vec!["-1", "1", "0", "0", "0"],
-            vec!["0", "0", "1", "0", "0"],
-        ];
+    /// Steps narrating the role of one element in the solution
+    ///
+    /// Covers the voltage, current and power of the element, the solver steps and G
+    /// matrix entries it appears in and, for independent sources, the node voltages it
+    /// sets on its own. The steps and factors of an earlier solve are reused, so only the
+    /// first call solves the circuit. Superposition voltages are referenced to ground.
+    pub fn explain(&mut self, element_id: usize) -> Result<Vec<Step>, StatusError> {
+        let solved: Vec<Step> = self.solve()?;
+        let solution: Solution = Solution::from_solver(self)?;
+        let result = solution
+            .elements
+            .iter()
+            .find(|x| x.id == element_id)
+            .ok_or_else(|| {
+                Known(format!(
+                    "Element {} is not part of the solution",
+                    element_id
+                ))
+            })?;
+        let element: Element = self
+            .container
+            .borrow()
+            .get_element_by_id(element_id)
+            .borrow()
+            .clone();
+        let name: String = element.basic_string();
+        let round = |x: f64| (x * 100.).round() / 100.;
+        let (a, b) = result.nodes;
 
-        let mut c = create_mna_container();
-        c.create_nodes().unwrap();
-        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+        // Zeros the solution proved exact say so, rounding cannot explain them away
+        let exact: String = result
+            .exact_reason
+            .as_ref()
+            .map_or(String::new(), |x| format!(" (exactly, {})", x));
 
-        assert_eq!(2., c.get_element_by_id(1).clone().borrow().value);
-        assert_eq!(1. / 2., solver.a_matrix[(0, 0)].value());
-        assert_eq!(
-            solver.a_matrix[(0, 0)].value(),
-            Divide(
-                Some(Box::new(Value(1.0))),
-                Some(Box::new(Variable(Rc::new(
-                    c.get_element_by_id(1).borrow().clone()
-                ))))
-            )
-            .value()
+        // Current is reported in the direction it actually flows
+        let (from, to) = match result.current < 0.0 {
+            true => (b, a),
+            false => (a, b),
+        };
+        let mut quantities: Step = Step::new_with_steps(
+            &format!("Element {}", name),
+            vec![
+                SubStep {
+                    description: Some("Voltage".to_string()),
+                    result: None,
+                    operations: vec![Text(format!(
+                        "V_{{{}}} = V_{{{}}} - V_{{{}}} = {} {}{}",
+                        name,
+                        a,
+                        b,
+                        round(result.voltage),
+                        Unit::Volt,
+                        match element.class {
+                            Resistor => exact.as_str(),
+                            _ => "",
+                        }
+                    ))],
+                    forms: None,
+                },
+                SubStep {
+                    description: Some("Current".to_string()),
+                    result: None,
+                    operations: vec![Text(match result.exact {
+                        true => format!("I_{{{}}} = 0 {}{}", name, Unit::Ampere, exact),
+                        false => format!(
+                            "I_{{{}}} = {} {} flowing from Node {} to Node {}",
+                            name,
+                            round(result.current.abs()),
+                            Unit::Ampere,
+                            from,
+                            to
+                        ),
+                    })],
+                    forms: None,
+                },
+                SubStep {
+                    description: Some("Power".to_string()),
+                    result: None,
+                    operations: vec![Text(format!(
+                        "P_{{{}}} = {} {} {}{}",
+                        name,
+                        round(result.power.abs()),
+                        Unit::Watt,
+                        match result.power < 0.0 {
+                            true => "delivered",
+                            false => "absorbed",
+                        },
+                        exact
+                    ))],
+                    forms: None,
+                },
+            ],
         );
-        for i in 0..5 {
-            for j in 0..5 {
-                assert_eq!(expected[i][j], solver.a_matrix[(i, j)].equation_repr());
-            }
+        quantities.description = Some(element.pretty_string());
+        quantities.reference_element(element_id);
+        quantities.reference_node(a);
+        quantities.reference_node(b);
+        let mut steps: Vec<Step> = vec![quantities];
+
+        let mut equations: Vec<SubStep> = vec![SubStep {
+            description: Some("Solver steps using the element".to_string()),
+            result: None,
+            operations: solved
+                .iter()
+                .filter(|x| x.referenced_elements.contains(&element_id))
+                .filter_map(|x| x.title.clone())
+                .map(Text)
+                .collect(),
+            forms: None,
+        }];
+        let contributions = self.g_contributions();
+        let entries: Vec<Operation> = contributions
+            .iter()
+            .enumerate()
+            .flat_map(|(index, cell)| {
+                // DMatrix iterates column major
+                let (row, column) = (index % contributions.nrows(), index / contributions.nrows());
+                cell.iter()
+                    .filter(|(id, _)| *id == element_id)
+                    .map(move |(_, conductance)| (row, column, *conductance))
+            })
+            .map(|(row, column, conductance)| {
+                Text(format!(
+                    "G[{}][{}] += {} {}",
+                    row + 1,
+                    column + 1,
+                    conductance,
+                    Unit::Siemens
+                ))
+            })
+            .collect();
+        if !entries.is_empty() {
+            equations.push(SubStep {
+                description: Some("G matrix entries".to_string()),
+                result: None,
+                operations: entries,
+                forms: None,
+            });
+        }
+        let n: usize = self.container.borrow().nodes().len();
+        let source_row: Option<usize> = branch_elements(&self.container)
+            .iter()
+            .position(|x| x.upgrade().unwrap().borrow().id == element_id)
+            .map(|j| n + j);
+        if let Some(row) = source_row {
+            equations.push(SubStep {
+                description: Some("Source equation".to_string()),
+                result: None,
+                operations: vec![Text(format!(
+                    "Row {} of A fixes the voltage of {}, its current is unknown {}",
+                    row + 1,
+                    name,
+                    row + 1
+                ))],
+                forms: None,
+            });
         }
+        steps.push(Step::new_with_steps("Equations", equations));
 
-        let expected = vec![
-            vec!["1/R2 + 1/R3", "-1/R2", "-1"],
-            vec!["-1/R2", "1/R1 + 1/R2", "1"],
-            vec!["-1", "1", "0"],
-        ];
+        if element.class.is_source() {
+            steps.push(self.superposition_step(&element, source_row)?);
+        }
+        for step in steps.iter_mut() {
+            step.reference_element(element_id);
+        }
+        Ok(steps)
+    }
 
-        let mut c = create_mna_container_2();
-        c.create_nodes().unwrap();
-        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+    /// Node voltages caused by one independent source with every other source zeroed
+    fn superposition_step(
+        &self,
+        source: &Element,
+        source_row: Option<usize>,
+    ) -> Result<Step, StatusError> {
+        let factors: &LuFactors = self
+            .factors
+            .as_ref()
+            .ok_or_else(|| Known("Circuit has not been solved".to_string()))?;
+        let rows: Vec<(usize, Rc<RefCell<Tool>>)> =
+            node_rows(&self.container, self.options.row_order);
 
-        for i in 0..3 {
-            for j in 0..3 {
-                assert_eq!(expected[i][j], solver.a_matrix[(i, j)].equation_repr());
+        // Only the entries of this source are kept in z
+        let mut z: DVector<f64> = DVector::zeros(self.a_matrix.nrows());
+        match source_row {
+            Some(row) => z[row] = source.value,
+            None => {
+                for (row, tool) in rows.iter() {
+                    if tool.borrow().member_ids().contains(&source.id) {
+                        z[*row] = source.value;
+                    }
+                }
             }
         }
-        assert_eq!(expected.len(), solver.a_matrix.nrows());
+        let x: DVector<f64> = factors.solve(&z);
+
+        let mut nodes: Vec<(usize, f64)> = rows
+            .iter()
+            .map(|(row, tool)| (tool.borrow().id, x[*row]))
+            .collect();
+        nodes.sort_by_key(|(id, _)| *id);
+        let mut step: Step = Step::new_with_steps(
+            "Superposition",
+            vec![SubStep {
+                description: Some(format!(
+                    "Node voltages with only {} active, the other sources zeroed",
+                    source.basic_string()
+                )),
+                result: None,
+                operations: nodes
+                    .iter()
+                    .map(|(id, voltage)| {
+                        Text(format!(
+                            "V_{{{}}} = {} {}",
+                            id,
+                            (voltage * 100.).round() / 100.,
+                            Unit::Volt
+                        ))
+                    })
+                    .collect(),
+                forms: None,
+            }],
+        );
+        for (id, _) in nodes {
+            step.reference_node(id);
+        }
+        Ok(step)
     }
 
-    #[test]
-    fn test_g_matrix() {
+    /// Driving point impedance between two nodes with every independent source zeroed
+    ///
+    /// A 1 A test current is injected into `a` and drawn from `b`. Voltage sources stay
+    /// in the system as shorts, current sources only appear in z and are left open.
+    pub(crate) fn impedance_between(&self, a: usize, b: usize) -> Result<f64, StatusError> {
+        if a == b {
+            return Ok(0.0);
+        }
+        let mut nodes: Vec<usize> = self
+            .container
+            .borrow()
+            .nodes()
+            .iter()
+            .map(|x| x.upgrade().unwrap().borrow().id)
+            .collect();
+        nodes.sort();
+        let n: usize = nodes.len();
+        let row = |id: usize| -> Result<Option<usize>, StatusError> {
+            if id == 0 {
+                return Ok(None);
+            }
+            match nodes.iter().position(|x| *x == id) {
+                Some(i) => Ok(Some(self.options.row_order.row(i, n))),
+                None => Err(Known(format!("Node {} does not exist", id))),
+            }
+        };
+        let (row_a, row_b) = (row(a)?, row(b)?);
+
+        let mut injection: DVector<f64> = DVector::zeros(self.a_matrix.nrows());
+        for (target, current) in [(row_a, 1.0), (row_b, -1.0)] {
+            if let Some(target) = target {
+                injection[target] = current;
+            }
+        }
+        let (numeric_a, _) = self.numeric_system();
+        let x: DVector<f64> = LuFactors::factorize(&numeric_a)?.solve(&injection);
+        let voltage = |row: Option<usize>| row.map_or(0.0, |row| x[row]);
+        Ok(voltage(row_a) - voltage(row_b))
+    }
+
+    /// Node impedance matrix with every independent source zeroed, nodes in id order
+    ///
+    /// Column j holds the node voltages for 1 A injected into node j and drawn from
+    /// ground, so `Z[i][j]` is the transfer impedance from node j to node i. Sources are
+    /// treated as in `impedance_between`.
+    pub(crate) fn impedance_matrix(&self) -> Result<(Vec<usize>, DMatrix<f64>), StatusError> {
+        let mut nodes: Vec<usize> = self
+            .container
+            .borrow()
+            .nodes()
+            .iter()
+            .map(|x| x.upgrade().unwrap().borrow().id)
+            .collect();
+        nodes.sort();
+        let n: usize = nodes.len();
+        let rows: Vec<usize> = (0..n).map(|i| self.options.row_order.row(i, n)).collect();
+
+        let (numeric_a, _) = self.numeric_system();
+        let factors: LuFactors = LuFactors::factorize(&numeric_a)?;
+        let mut z: DMatrix<f64> = DMatrix::zeros(n, n);
+        for (j, column) in rows.iter().enumerate() {
+            let mut injection: DVector<f64> = DVector::zeros(numeric_a.nrows());
+            injection[*column] = 1.0;
+            let x: DVector<f64> = factors.solve(&injection);
+            for (i, row) in rows.iter().enumerate() {
+                z[(i, j)] = x[*row];
+            }
+        }
+        Ok((nodes, z))
+    }
+
+    /// Swap sources past their limit for their limiting model until no limit is exceeded
+    ///
+    /// A voltage source driving more than its `current_limit` becomes a current source of
+    /// the limit with the nominal voltage as compliance. A current source needing more than
+    /// its `voltage_limit` becomes a voltage source of the limit with the nominal current as
+    /// current limit. Swapping twice restores a source, so a source whose load recovers
+    /// goes back to its nominal value. Limited sources need a terminal on ground.
+    ///
+    /// The sources are swapped on a copy of the circuit which the solver keeps, so the
+    /// elements of the caller are left as they were built. The warnings still go to the
+    /// caller's container.
+    fn apply_source_limits(&mut self) -> Result<(), StatusError> {
+        let has_limit: bool = self
+            .container
+            .borrow()
+            .get_elements()
+            .iter()
+            .any(|x| x.borrow().has_limit());
+        if has_limit && Rc::ptr_eq(&self.container, &self.caller) {
+            let copy: Container = self.container.borrow().deep_copy()?;
+            self.reform(Rc::new(RefCell::new(copy)));
+        }
+
+        let limited: Vec<Rc<RefCell<Element>>> = self
+            .container
+            .borrow()
+            .get_elements()
+            .iter()
+            .filter(|x| x.borrow().has_limit())
+            .cloned()
+            .collect();
+        if limited.is_empty() {
+            return Ok(());
+        }
+
+        // Row of the node at the terminal that is not ground
+        let mut rows: Vec<usize> = Vec::new();
+        for element in limited.iter() {
+            let element = element.borrow();
+            let container = self.container.borrow();
+            let node: usize = match (
+                container.node_at(element.id, &element.positive),
+                container.node_at(element.id, &element.negative),
+            ) {
+                (Some(0), Some(node)) | (Some(node), Some(0)) if node != 0 => node,
+                _ => {
+                    return Err(Known(format!(
+                        "{} needs a terminal on ground to be limited",
+                        element.pretty_string()
+                    )))
+                }
+            };
+            rows.extend(
+                node_rows(&self.container, self.options.row_order)
+                    .iter()
+                    .filter(|(_, tool)| tool.borrow().id == node)
+                    .map(|(row, _)| *row),
+            );
+        }
+
+        let mut seen: Vec<Vec<bool>> = Vec::new();
+        let mut swapped: bool = false;
+        loop {
+            let state: Vec<bool> = limited.iter().map(|x| x.borrow().limiting).collect();
+            if seen.contains(&state) {
+                return Err(Known(
+                    "Source limits do not settle, the sources keep switching between their nominal and limiting model".to_string(),
+                ));
+            }
+            seen.push(state);
+
+            let (a, z) = self.numeric_system();
+            let x: DVector<f64> =
+                LuFactors::factorize_with(&a, !self.options.no_pivoting)?.solve(&z);
+            let n: usize = self.container.borrow().nodes().len();
+            let sources: Vec<usize> = branch_elements(&self.container)
+                .iter()
+                .map(|x| x.upgrade().unwrap().borrow().id)
+                .collect();
+
+            // Current a voltage source pushes into its node, or the voltage a current source drives it to
+            let mut violated: Vec<(usize, f64)> = Vec::new();
+            for (i, element) in limited.iter().enumerate() {
+                let element = element.borrow();
+                let (measured, limit) = match element.class {
+                    VoltageSrc => {
+                        let j: usize = n + sources.iter().position(|x| *x == element.id).unwrap();
+                        (-a[(rows[i], j)] * x[j], element.current_limit)
+                    }
+                    _ => (x[rows[i]], element.voltage_limit),
+                };
+                if let Some(limit) = limit {
+                    if measured.abs() > limit * (1.0 + LIMIT_TOLERANCE) {
+                        violated.push((i, measured));
+                    }
+                }
+            }
+            if violated.is_empty() {
+                break;
+            }
+
+            let nodes: Vec<(usize, Rc<RefCell<Tool>>)> =
+                node_rows(&self.container, self.options.row_order);
+            for (i, measured) in violated {
+                // The C row of a voltage source fixes its node voltage times its B entry,
+                // which depends on the first member of the node
+                let first: usize = nodes[rows[i]].1.borrow().members[0]
+                    .upgrade()
+                    .unwrap()
+                    .borrow()
+                    .id;
+                // A current source pushes into the node at its positive terminal
+                let orientation: f64 = {
+                    let element = limited[i].borrow();
+                    let node: usize = nodes[rows[i]].1.borrow().id;
+                    match self
+                        .container
+                        .borrow()
+                        .node_at(element.id, &element.positive)
+                    {
+                        Some(x) if x == node => 1.0,
+                        _ => -1.0,
+                    }
+                };
+                let mut element = limited[i].borrow_mut();
+                let nominal: f64 = element.value.abs();
+                let previous: Component = element.class.clone();
+                match previous {
+                    VoltageSrc => {
+                        element.value =
+                            orientation * element.current_limit.unwrap() * measured.signum();
+                        element.class = CurrentSrc;
+                        element.voltage_limit = Some(nominal);
+                        element.current_limit = None;
+                    }
+                    _ => {
+                        let b: f64 = match element.positive.contains(&first) {
+                            true => -1.0,
+                            false => 1.0,
+                        };
+                        element.value = b * element.voltage_limit.unwrap() * measured.signum();
+                        element.class = VoltageSrc;
+                        element.current_limit = Some(nominal);
+                        element.voltage_limit = None;
+                    }
+                }
+                if element.name == previous.basic_string() {
+                    element.name = element.class.basic_string();
+                }
+                element.limiting = !element.limiting;
+            }
+            swapped = true;
+            self.reform(self.container.clone());
+        }
+
+        if swapped {
+            for element in limited.iter().filter(|x| x.borrow().limiting) {
+                let warning: Warning = Warning::new(
+                    WarningKind::SourceLimited,
+                    limit_description(&element.borrow()),
+                );
+                if !self.caller.borrow().warnings().contains(&warning) {
+                    self.caller.borrow_mut().add_warning(warning);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Form the matrices again from `container`, keeping the caller and its generation
+    fn reform(&mut self, container: Rc<RefCell<Container>>) {
+        let caller: Rc<RefCell<Container>> = self.caller.clone();
+        let generation: u64 = self.generation;
+        *self = Solver::new_with_options(container, self.options.clone());
+        self.caller = caller;
+        self.generation = generation;
+    }
+
+    /// Sources running at their limit, `None` if every source holds its nominal value.
+    fn limit_step(&self) -> Option<SubStep> {
+        let operations: Vec<Operation> = self
+            .container
+            .borrow()
+            .get_elements()
+            .iter()
+            .filter(|x| x.borrow().is_limiting())
+            .map(|x| Text(limit_description(&x.borrow())))
+            .collect();
+        if operations.is_empty() {
+            return None;
+        }
+
+        Some(SubStep {
+            description: Some("Limited sources".to_string()),
+            result: None,
+            operations,
+            forms: None,
+        })
+    }
+
+    /// Numeric A matrix and z vector
+    ///
+    /// With `deterministic_fp` the conductance and current source sums are recomputed in
+    /// element id order with compensation instead of evaluating the operations.
+    fn numeric_system(&self) -> (DMatrix<f64>, DVector<f64>) {
+        let mut a: DMatrix<f64> = self.a_matrix.map(|x| x.value());
+        if !self.options.deterministic_fp {
+            return (a, self.numeric_z());
+        }
+
+        let n: usize = self.container.borrow().nodes().len();
+        let contributions = g_contributions(self.container.clone(), n, self.options.row_order);
+        for row in 0..n {
+            for column in 0..n {
+                let mut cell: Vec<(usize, f64)> = contributions[(row, column)].clone();
+                cell.sort_by_key(|(id, _)| *id);
+                a[(row, column)] = kahan_sum(cell.into_iter().map(|(_, x)| x));
+            }
+        }
+        (a, self.numeric_z())
+    }
+
+    /// Numeric z vector, see `numeric_system`
+    fn numeric_z(&self) -> DVector<f64> {
+        let mut z: DVector<f64> = self.z_matrix.map(|x| x.value());
+        if !self.options.deterministic_fp {
+            return z;
+        }
+
+        for (row, tool) in node_rows(&self.container, self.options.row_order) {
+            let mut sources: Vec<(usize, f64)> = tool
+                .borrow()
+                .members
+                .iter()
+                .map(|x| x.upgrade().unwrap())
+                .filter(|x| x.borrow().class == CurrentSrc)
+                .map(|x| {
+                    let (id, value) = (x.borrow().id, x.borrow().value);
+                    let sign: f64 = current_source_sign(&tool.borrow(), &x.borrow());
+                    (id, sign * *self.source_values.get(&id).unwrap_or(&value))
+                })
+                .collect();
+            sources.sort_by_key(|(id, _)| *id);
+            z[row] = kahan_sum(sources.into_iter().map(|(_, x)| x));
+        }
+        z
+    }
+
+    /// Row swaps taken by the elimination, only present when a swap was needed.
+    ///
+    /// Rows and columns are numbered from 1 as in a hand elimination.
+    fn pivot_step(&self) -> Option<SubStep> {
+        let operations: Vec<Operation> = self
+            .pivots()
+            .into_iter()
+            .filter(|x| x.swapped())
+            .map(|x| {
+                Text(format!(
+                    "Swapped R{} ↔ R{} because |A[{}][{}]| > |A[{}][{}]|",
+                    x.column + 1,
+                    x.row + 1,
+                    x.row + 1,
+                    x.column + 1,
+                    x.column + 1,
+                    x.column + 1
+                ))
+            })
+            .collect();
+        if operations.is_empty() {
+            return None;
+        }
+
+        Some(SubStep {
+            description: Some("Elimination order".to_string()),
+            result: None,
+            operations,
+            forms: None,
+        })
+    }
+
+    /// Results grouped by element tag, only present when any element is tagged.
+    fn tag_step(&self) -> Result<Option<SubStep>, StatusError> {
+        let groups = Solution::from_solver(self)?.by_tag();
+        if groups.is_empty() {
+            return Ok(None);
+        }
+
+        let operations: Vec<Operation> = groups
+            .into_iter()
+            .map(|(tag, summary)| {
+                let container = self.container.borrow();
+                let names: Vec<String> = summary
+                    .elements
+                    .iter()
+                    .map(|id| container.get_element_by_id(*id).basic_string())
+                    .collect();
+                let mut line: String = format!(
+                    "{}: {}, P = {} {}",
+                    tag,
+                    names.join(", "),
+                    (summary.power * 100.).round() / 100.,
+                    Unit::Watt
+                );
+                if let Some(resistance) = summary.equivalent_resistance {
+                    line += &format!(
+                        ", R_{{eq}} = {} {}",
+                        (resistance * 100.).round() / 100.,
+                        Unit::Ohm
+                    );
+                }
+                Text(line)
+            })
+            .collect();
+
+        Ok(Some(SubStep {
+            description: Some("Results by tag".to_string()),
+            result: None,
+            operations,
+            forms: None,
+        }))
+    }
+}
+
+fn form_a_matrix(
+    container: Rc<RefCell<Container>>,
+    n: usize,
+    m: usize,
+    order: RowOrder,
+) -> DMatrix<Operation> {
+    let mut a_matrix: DMatrix<Operation> = DMatrix::<Operation>::zeros(n + m, n + m);
+
+    let g: DMatrix<Operation> = form_g_matrix(container.clone(), n, order);
+    let b: DMatrix<Operation> = form_b_matrix(container.clone(), n, m, order);
+    let c: DMatrix<Operation> = form_c_matrix(container.clone(), n, m, order);
+    let d: DMatrix<Operation> = form_d_matrix(container.clone(), m);
+
+    a_matrix.view_mut((0, 0), (n, n)).copy_from(&g);
+    a_matrix.view_mut((0, n), (n, m)).copy_from(&b);
+    a_matrix.view_mut((n, 0), (m, n)).copy_from(&c);
+    a_matrix.view_mut((n, n), (m, m)).copy_from(&d);
+
+    a_matrix
+}
+
+/// Elements with their current as an unknown, the voltage sources, the VCVS and the inductors
+///
+/// A VCVS is stamped like a voltage source whose value is moved into its C row, see
+/// `form_c_matrix`. An inductor is a short at DC. It is stamped like a 0 V source, one B
+/// column, C row and z entry each, and its current is solved for alongside the source currents.
+fn branch_elements(container: &Rc<RefCell<Container>>) -> Vec<Weak<RefCell<Element>>> {
+    let container = container.borrow();
+    let mut elements: Vec<Weak<RefCell<Element>>> = container.get_elements_by_type(VoltageSrc);
+    elements.append(&mut container.get_elements_by_type(DependentVoltage));
+    elements.append(&mut container.get_elements_by_type(Inductor));
+    elements
+}
+
+/// Nodes paired with the row of their voltage unknown, sorted by row.
+fn node_rows(
+    container: &Rc<RefCell<Container>>,
+    order: RowOrder,
+) -> Vec<(usize, Rc<RefCell<Tool>>)> {
+    let mut nodes: Vec<Rc<RefCell<Tool>>> = container
+        .borrow()
+        .nodes()
+        .iter()
+        .map(|x| x.upgrade().unwrap())
+        .collect();
+    nodes.sort_by_key(|x| x.borrow().id);
+
+    let n: usize = nodes.len();
+    let mut rows: Vec<(usize, Rc<RefCell<Tool>>)> = nodes
+        .into_iter()
+        .enumerate()
+        .map(|(i, tool)| (order.row(i, n), tool))
+        .collect();
+    rows.sort_by_key(|(row, _)| *row);
+    rows
+}
+
+pub(crate) fn form_g_matrix(
+    container: Rc<RefCell<Container>>,
+    n: usize,
+    order: RowOrder,
+) -> DMatrix<Operation> {
+    let mut matrix: DMatrix<Operation> =
+        g_members(&container, n, order).map_with_location(|row, column, elements| {
+            let set: Vec<Operation> = elements
+                .into_iter()
+                .map(|element| match row == column {
+                    true => Divide(
+                        Some(Box::new(Value(1.0))),
+                        Some(Box::new(Variable(Rc::new(EquationRepr::from(Rc::new(
+                            element,
+                        )))))),
+                    ),
+                    false => Negate(Some(Box::new(Divide(
+                        Some(Box::new(Value(1.0))),
+                        Some(Box::from(Variable(Rc::new(element)))),
+                    )))),
+                })
+                .collect();
+            Sum(set)
+        });
+
+    for (row, column, sign, source) in vccs_stamps(&container, order) {
+        let gain: Operation = Variable(Rc::new(source));
+        if let Sum(set) = &mut matrix[(row, column)] {
+            set.push(match sign > 0.0 {
+                true => gain,
+                false => Negate(Some(Box::new(gain))),
+            });
+        }
+    }
+
+    // A capacitor admits nothing at DC, there are no stamps and G stays as it was
+    for (row, column, admittance, _) in capacitor_stamps(&container, order, DC) {
+        if let Sum(set) = &mut matrix[(row, column)] {
+            set.push(Value(admittance));
+        }
+    }
+    matrix
+}
+
+/// Signed conductance every resistor, VCCS and capacitor adds to each G entry as
+/// `(element id, conductance)`
+///
+/// The entries of `form_g_matrix` are the sums of these contributions. A capacitor only
+/// contributes away from DC, so it is never listed.
+pub(crate) fn g_contributions(
+    container: Rc<RefCell<Container>>,
+    n: usize,
+    order: RowOrder,
+) -> DMatrix<Vec<(usize, f64)>> {
+    let mut contributions: DMatrix<Vec<(usize, f64)>> = g_members(&container, n, order)
+        .map_with_location(|row, column, elements| {
+            let sign: f64 = if row == column { 1.0 } else { -1.0 };
+            elements.iter().map(|x| (x.id, sign / x.value)).collect()
+        });
+
+    for (row, column, sign, source) in vccs_stamps(&container, order) {
+        contributions[(row, column)].push((source.id, sign * source.value));
+    }
+    for (row, column, admittance, capacitor) in capacitor_stamps(&container, order, DC) {
+        contributions[(row, column)].push((capacitor.id, admittance));
+    }
+    contributions
+}
+
+/// G entries of the capacitors at the angular frequency `omega` as `(row, column, admittance, capacitor)`
+///
+/// A capacitor admits jωC between its nodes, stamped like the conductance of a resistor:
+/// added on the diagonal of both nodes and subtracted between them, ground has no row.
+/// Only the magnitude ωC is returned. G is real, so the solver stamps at ω = 0 where the
+/// capacitor is an open circuit and there is nothing to stamp.
+fn capacitor_stamps(
+    container: &Rc<RefCell<Container>>,
+    order: RowOrder,
+    omega: f64,
+) -> Vec<(usize, usize, f64, Element)> {
+    if omega == 0.0 {
+        return Vec::new();
+    }
+    let capacitors: Vec<Rc<RefCell<Element>>> = container
+        .borrow()
+        .get_elements_by_type(Capacitor)
+        .iter()
+        .map(|x| x.upgrade().unwrap())
+        .collect();
+    let nodes: Vec<(usize, Rc<RefCell<Tool>>)> = node_rows(container, order);
+
+    let mut stamps: Vec<(usize, usize, f64, Element)> = Vec::new();
+    for capacitor in capacitors {
+        let admittance: f64 = omega * capacitor.borrow().value;
+        let rows: Vec<usize> = nodes
+            .iter()
+            .filter(|(_, tool)| tool.borrow().contains(capacitor.clone()))
+            .map(|(row, _)| *row)
+            .collect();
+        for row in rows.iter() {
+            for column in rows.iter() {
+                let sign: f64 = if row == column { 1.0 } else { -1.0 };
+                stamps.push((*row, *column, sign * admittance, capacitor.borrow().clone()));
+            }
+        }
+    }
+    stamps
+}
+
+/// G entries of the voltage controlled current sources as `(row, column, sign, source)`
+///
+/// A VCCS pushes its gain times the control voltage into a node like a current source,
+/// see `current_source_sign`. Moved to the left hand side of the KCL row of that node the
+/// gain is subtracted at the positive control node and added at the negative one.
+fn vccs_stamps(
+    container: &Rc<RefCell<Container>>,
+    order: RowOrder,
+) -> Vec<(usize, usize, f64, Element)> {
+    let sources: Vec<Rc<RefCell<Element>>> = container
+        .borrow()
+        .get_elements_by_type(DependentCurrent)
+        .iter()
+        .map(|x| x.upgrade().unwrap())
+        .collect();
+    let nodes: Vec<(usize, Rc<RefCell<Tool>>)> = node_rows(container, order);
+
+    let mut stamps: Vec<(usize, usize, f64, Element)> = Vec::new();
+    for source in sources {
+        let columns: Vec<(usize, f64)> = control_rows(container, &source.borrow(), order);
+        for (row, tool) in nodes.iter() {
+            if !tool.borrow().contains(source.clone()) {
+                continue;
+            }
+            let direction: f64 = current_source_sign(&tool.borrow(), &source.borrow());
+            for (column, sign) in columns.iter() {
+                stamps.push((*row, *column, -direction * sign, source.borrow().clone()));
+            }
+        }
+    }
+    stamps
+}
+
+/// Rows of the nodes a dependent source reads its control voltage across
+///
+/// 1 for the positive node and -1 for the negative one, ground has no row. A control that
+/// can not be resolved has no rows, `check_controls` rejects it before the solve.
+fn control_rows(
+    container: &Rc<RefCell<Container>>,
+    source: &Element,
+    order: RowOrder,
+) -> Vec<(usize, f64)> {
+    let (positive, negative) = container
+        .borrow()
+        .control_nodes(source.id)
+        .unwrap_or((0, 0));
+    node_rows(container, order)
+        .into_iter()
+        .filter_map(|(row, tool)| match tool.borrow().id {
+            id if id == positive => Some((row, 1.0)),
+            id if id == negative => Some((row, -1.0)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Resistors behind each G entry
+///
+/// The diagonal lists every resistor of the node, the off-diagonal every resistor the
+/// two nodes share.
+fn g_members(
+    container: &Rc<RefCell<Container>>,
+    n: usize,
+    order: RowOrder,
+) -> DMatrix<Vec<Element>> {
+    let mut members: DMatrix<Vec<Element>> = DMatrix::from_element(n, n, Vec::new());
+    let nodes = node_rows(container, order);
+
+    for (row, tool) in nodes.iter() {
+        for (column, tool2) in nodes.iter() {
+            for element in &tool.borrow().members {
+                let element = element.upgrade().unwrap();
+                if element.borrow().class != Resistor {
+                    continue;
+                }
+                if row == column {
+                    members[(*row, *column)].push(element.borrow().clone());
+                    continue;
+                }
+                for element2 in tool2.borrow().members.clone() {
+                    let element2 = element2.upgrade().unwrap();
+                    if element2.borrow().class != Resistor {
+                        continue;
+                    }
+                    if element.borrow().id == element2.borrow().id {
+                        members[(*row, *column)].push(element.borrow().clone());
+                    }
+                }
+            }
+        }
+    }
+    members
+}
+
+pub fn form_b_matrix(
+    container: Rc<RefCell<Container>>,
+    n: usize,
+    m: usize,
+    order: RowOrder,
+) -> DMatrix<Operation> {
+    let mut matrix: DMatrix<Operation> = DMatrix::zeros(n, m);
+
+    for (row, tool) in node_rows(&container, order) {
+        for (j, element) in branch_elements(&container).iter().enumerate() {
+            let element = element.upgrade().unwrap();
+            if tool.borrow().contains(element.clone()) {
+                matrix[(row, j)] = Value(voltage_source_sign(&tool.borrow(), &element.borrow()));
+            }
+        }
+    }
+
+    matrix
+}
+
+/// Entry of a voltage source in the B matrix for a node it is connected to
+///
+/// -1 if the source lists the first member of the node on its positive side, else 1.
+pub(crate) fn voltage_source_sign(tool: &Tool, source: &Element) -> f64 {
+    match source
+        .positive
+        .contains(&tool.members[0].upgrade().unwrap().id())
+    {
+        true => -1.0,
+        false => 1.0,
+    }
+}
+
+/// Transpose of B, the row of a VCVS also subtracts its gain times the control voltage
+///
+/// The row of a voltage source equates its terminal voltage to its value in z. A VCVS
+/// equates it to the gain times the control voltage instead, moved to the left hand side
+/// so its z entry is 0.
+pub(crate) fn form_c_matrix(
+    container: Rc<RefCell<Container>>,
+    n: usize,
+    m: usize,
+    order: RowOrder,
+) -> DMatrix<Operation> {
+    let mut matrix: DMatrix<Operation> = form_b_matrix(container.clone(), n, m, order).transpose();
+
+    for (j, source) in branch_elements(&container).iter().enumerate() {
+        let source = source.upgrade().unwrap();
+        if source.borrow().class != DependentVoltage {
+            continue;
+        }
+        for (column, sign) in control_rows(&container, &source.borrow(), order) {
+            let gain: Operation = Variable(Rc::new(source.borrow().clone()));
+            let term: Operation = match sign > 0.0 {
+                true => Negate(Some(Box::new(gain))),
+                false => gain,
+            };
+            // The source can read a node it is connected to
+            matrix[(j, column)] = match &matrix[(j, column)] {
+                Value(x) if *x == 0.0 => term,
+                entry => Sum(vec![entry.clone(), term]),
+            };
+        }
+    }
+    matrix
+}
+
+fn form_d_matrix(_container: Rc<RefCell<Container>>, m: usize) -> DMatrix<Operation> {
+    DMatrix::zeros(m, m)
+}
+
+/// Direction of a current source in the KCL row of a node, 1 if it pushes into the node
+///
+/// A source pushes its current out of the positive terminal, which is on the node holding
+/// every element connected to that terminal, and draws it from the negative one. A source
+/// to ground drawing from its node pulls that node below ground. Elements in parallel with
+/// the source show up on both sides, so a single member can not tell the sides apart.
+fn current_source_sign(tool: &Tool, source: &Element) -> f64 {
+    let members: Vec<usize> = tool.member_ids();
+    match source.positive.iter().all(|x| members.contains(x)) {
+        true => 1.0,
+        false => -1.0,
+    }
+}
+
+/// `values` replaces the value of the sources it holds, keyed by element id.
+fn form_z_vector(
+    container: Rc<RefCell<Container>>,
+    order: RowOrder,
+    values: &HashMap<usize, f64>,
+) -> DVector<Operation> {
+    let value = |element: &Element| -> f64 { *values.get(&element.id).unwrap_or(&element.value) };
+    let mut z_vec: Vec<Operation> = Vec::new();
+
+    // I Matrix
+    // The balance of current flowing in the node.
+    node_rows(&container, order).iter().for_each(|(_, tool)| {
+        let mut set: Vec<Operation> = Vec::new();
+        for element in &tool.borrow().members {
+            let element = element.upgrade().unwrap();
+            if element.borrow().class != CurrentSrc {
+                continue;
+            }
+            let sign: f64 = current_source_sign(&tool.borrow(), &element.borrow());
+            set.push(Value(sign * value(&element.borrow())));
+        }
+        if set.len() == 0 {
+            z_vec.push(Value(0.0));
+        } else {
+            z_vec.push(Sum(set));
+        }
+    });
+
+    // E Matrix
+    // The value of the voltage source, an inductor holds its nodes at the same voltage and
+    // a VCVS has its value in its C row.
+    branch_elements(&container).iter().for_each(|source| {
+        let source = source.upgrade().unwrap();
+        match source.borrow().class {
+            Inductor | DependentVoltage => z_vec.push(Value(0.0)),
+            _ => z_vec.push(Value(value(&source.borrow()))),
+        }
+    });
+
+    DVector::from(z_vec)
+}
+
+/// Same rows as `form_z_vector` with the sources named instead of their values.
+fn form_z_vector_symbolic(
+    container: Rc<RefCell<Container>>,
+    order: RowOrder,
+) -> DVector<Operation> {
+    let mut z_vec: Vec<Operation> = Vec::new();
+
+    node_rows(&container, order).iter().for_each(|(_, tool)| {
+        let set: Vec<Operation> = tool
+            .borrow()
+            .members
+            .iter()
+            .map(|x| x.upgrade().unwrap())
+            .filter(|x| x.borrow().class == CurrentSrc)
+            .map(|x| {
+                let variable: Operation = Variable(Rc::new(x.borrow().clone()));
+                match current_source_sign(&tool.borrow(), &x.borrow()) > 0.0 {
+                    true => variable,
+                    false => Negate(Some(Box::new(variable))),
+                }
+            })
+            .collect();
+        if set.len() == 0 {
+            z_vec.push(Value(0.0));
+        } else {
+            z_vec.push(Sum(set));
+        }
+    });
+
+    branch_elements(&container).iter().for_each(|source| {
+        let source = source.upgrade().unwrap();
+        match source.borrow().class {
+            Inductor | DependentVoltage => z_vec.push(Value(0.0)),
+            _ => z_vec.push(Variable(Rc::new(source.borrow().clone()))),
+        }
+    });
+
+    DVector::from(z_vec)
+}
+
+/// Unknowns in row order, so the node to row mapping can be read off the x vector.
+fn form_x_vector(container: Rc<RefCell<Container>>, order: RowOrder) -> DVector<Operation> {
+    let mut x_vec: Vec<Operation> = Vec::new();
+
+    // V Matrix
+    for (_, tool) in node_rows(&container, order) {
+        x_vec.push(Variable(Rc::new(EquationRepr::new(
+            format!("{}", tool.borrow().pretty_string()),
+            0.0,
+        ))));
+    }
+
+    // J Matrix
+    for source in branch_elements(&container) {
+        x_vec.push(Variable(Rc::new(EquationRepr::new(
+            format!("{}", source.upgrade().unwrap().pretty_string()),
+            0.0,
+        ))));
+    }
+
+    DVector::from(x_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_known_error;
+    use crate::component::Component::{
+        Capacitor, CurrentSrc, DependentCurrent, Ground, Inductor, Resistor, VoltageSrc,
+    };
+    use crate::container::Container;
+    use crate::elements::{Control, Element};
+    use crate::solvers::linear::LuFactors;
+    use crate::solvers::node_matrix_solver::{
+        capacitor_stamps, form_b_matrix, form_c_matrix, form_d_matrix, form_g_matrix,
+        form_z_vector, form_z_vector_symbolic, g_contributions, NodeMatrixSolver,
+    };
+    use crate::solvers::node_step_solver::NodeStepSolver;
+    use crate::solvers::options::{
+        FormDisplay, Reference, RowOrder, SolveOptions, ZeroOhmStrategy,
+    };
+    use crate::solvers::solution::{Solution, SolvedValues};
+    use crate::solvers::solver::{NodeSolver, Solver, SolverState, SolverType, Step};
+    use crate::solvers::support::check_support;
+    use crate::transformation::TransformationKind;
+    use crate::util::{
+        bridge, create_basic_container, create_basic_supermesh_container,
+        create_basic_supernode_container, create_ladder_container, create_mna_container,
+        create_mna_container_2, create_rlc_container, load_problem,
+    };
+    use crate::validation::Status::Valid;
+    use crate::validation::StatusError::Known;
+    use crate::validation::{StatusError, Validation, WarningKind};
+    use operations::prelude::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_node_solver() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let _solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        let mut c = create_mna_container_2();
+        c.create_nodes().unwrap();
+        c.create_super_nodes();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let steps = solver.solve();
+        assert!(steps.is_ok());
+    }
+
+    #[test]
+    fn test_a_matrix() {
+        let expected = vec![
+            vec!["1/R1", "", "", "-1", "0"],
+            vec!["", "1/R2 + 1/R3", "-1/R2", "1", "0"],
+            vec!["", "-1/R2", "1/R2", "0", "1"],
+            vec!["-1", "1", "0", "0", "0"],
+            vec!["0", "0", "1", "0", "0"],
+        ];
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+
+        assert_eq!(2., c.get_element_by_id(1).clone().borrow().value);
+        assert_eq!(1. / 2., solver.a_matrix[(0, 0)].value());
+        assert_eq!(
+            solver.a_matrix[(0, 0)].value(),
+            Divide(
+                Some(Box::new(Value(1.0))),
+                Some(Box::new(Variable(Rc::new(
+                    c.get_element_by_id(1).borrow().clone()
+                ))))
+            )
+            .value()
+        );
+        for i in 0..5 {
+            for j in 0..5 {
+                assert_eq!(expected[i][j], solver.a_matrix[(i, j)].equation_repr());
+            }
+        }
+
+        let expected = vec![
+            vec!["1/R2 + 1/R3", "-1/R2", "-1"],
+            vec!["-1/R2", "1/R1 + 1/R2", "1"],
+            vec!["-1", "1", "0"],
+        ];
+
+        let mut c = create_mna_container_2();
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(expected[i][j], solver.a_matrix[(i, j)].equation_repr());
+            }
+        }
+        assert_eq!(expected.len(), solver.a_matrix.nrows());
+    }
+
+    #[test]
+    fn test_g_matrix() {
         let expected = vec![
             vec!["1/R1", "", ""],
             vec!["", "1/R2 + 1/R3", "-1/R2"],
             vec!["", "-1/R2", "1/R2"],
         ];
 
-        let mut c = create_mna_container();
-        c.create_nodes().unwrap();
-        let n = c.nodes().len();
-        let matrix = form_g_matrix(Rc::new(RefCell::new(c)), n);
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let n = c.nodes().len();
+        let matrix = form_g_matrix(Rc::new(RefCell::new(c)), n, RowOrder::Descending);
+
+        for i in 0..n {
+            for j in 0..n {
+                assert_eq!(expected[i][j], matrix[(i, j)].equation_repr());
+            }
+        }
+    }
+
+    #[test]
+    fn test_g_contributions() {
+        let mut c = create_basic_container();
+        c.create_nodes().unwrap();
+        let n = c.nodes().len();
+        let container = Rc::new(RefCell::new(c));
+        let matrix = form_g_matrix(container.clone(), n, RowOrder::Descending);
+        let contributions = g_contributions(container, n, RowOrder::Descending);
+
+        for i in 0..n {
+            for j in 0..n {
+                let sum: f64 = contributions[(i, j)].iter().map(|(_, g)| g).sum();
+                assert!((sum - matrix[(i, j)].value()).abs() < 1e-12);
+                assert_eq!(
+                    contributions[(i, j)].len(),
+                    matrix[(i, j)].equation_repr().matches('R').count()
+                );
+            }
+        }
+        assert!(contributions
+            .iter()
+            .flatten()
+            .all(|(id, _)| [1, 2].contains(id)));
+    }
+
+    #[test]
+    fn test_capacitor_stamps() {
+        // C6 across R4, between two nodes away from ground
+        let circuit = |capacitor: bool| {
+            let mut c = Container::new();
+            c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+            c.add_element_between_nodes(Resistor, 1., 1, 2);
+            c.add_element_between_nodes(Resistor, 2., 2, 0);
+            c.add_element_between_nodes(Resistor, 3., 2, 3);
+            c.add_element_between_nodes(Resistor, 4., 3, 0);
+            if capacitor {
+                c.add_element_between_nodes(Capacitor, 1e-6, 2, 3);
+            }
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+        let (with, without) = (circuit(true), circuit(false));
+        let n = with.borrow().nodes().len();
+        assert_eq!(n, without.borrow().nodes().len());
+
+        // ωC on both diagonals and subtracted between the two nodes
+        let stamps = capacitor_stamps(&with, RowOrder::Descending, 1e3);
+        assert_eq!(stamps.len(), 4);
+        for (row, column, admittance, capacitor) in stamps.iter() {
+            let expected: f64 = if row == column { 1e-3 } else { -1e-3 };
+            assert_eq!(capacitor.id, 6);
+            assert!((admittance - expected).abs() < 1e-15);
+        }
+        assert!(capacitor_stamps(&with, RowOrder::Descending, 0.0).is_empty());
+
+        // At DC G is unchanged and the capacitor is not listed as a contribution
+        let g = form_g_matrix(with.clone(), n, RowOrder::Descending);
+        let g_without = form_g_matrix(without.clone(), n, RowOrder::Descending);
+        for (entry, expected) in g.iter().zip(g_without.iter()) {
+            assert_eq!(entry.equation_repr(), expected.equation_repr());
+        }
+        let contributions = g_contributions(with.clone(), n, RowOrder::Descending);
+        assert!(contributions.iter().flatten().all(|(id, _)| *id != 6));
+
+        // So are the DC results
+        let mut solver: NodeMatrixSolver = Solver::new(with);
+        let mut solver_without: NodeMatrixSolver = Solver::new(without);
+        solver.solve().unwrap();
+        solver_without.solve().unwrap();
+        assert_eq!(
+            solver.node_voltages().unwrap(),
+            solver_without.node_voltages().unwrap()
+        );
+        assert_eq!(
+            solver.current_through_source(1).unwrap(),
+            solver_without.current_through_source(1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_b_matrix() {
+        let expected = vec![vec!["-1", "0"], vec!["1", "0"], vec!["0", "1"]];
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let n = c.nodes().len();
+        let m = c.get_elements_by_type(VoltageSrc).len();
+        let matrix = form_b_matrix(Rc::new(RefCell::new(c)), n, m, RowOrder::Descending);
+
+        for i in 0..n {
+            for j in 0..m {
+                assert_eq!(expected[i][j], matrix[(i, j)].equation_repr());
+            }
+        }
+    }
+
+    #[test]
+    fn test_c_matrix() {
+        let expected = vec![vec!["-1", "1", "0"], vec!["0", "0", "1"]];
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let n = c.nodes().len();
+        let m = c.get_elements_by_type(VoltageSrc).len();
+
+        let c_matrix = form_c_matrix(Rc::new(RefCell::new(c)), n, m, RowOrder::Descending);
+
+        for i in 0..m {
+            for j in 0..n {
+                assert_eq!(expected[i][j], c_matrix[(i, j)].equation_repr());
+            }
+        }
+    }
+
+    #[test]
+    fn test_d_matrix() {
+        let expected = vec![vec!["0", "0"], vec!["0", "0"]];
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let m = c.get_elements_by_type(VoltageSrc).len();
+
+        let d_matrix = form_d_matrix(Rc::new(RefCell::new(c)), m);
+
+        for i in 0..m {
+            for j in 0..m {
+                assert_eq!(expected[i][j], d_matrix[(i, j)].equation_repr());
+            }
+        }
+    }
+
+    #[test]
+    fn test_x_matrix() {
+        let expected = "\\begin{bmatrix}Node: 3\\\\Node: 2\\\\Node: 1\\\\SRC(V)4: 32 V\\\\SRC(V)5: 20 V\\\\\\end{bmatrix}";
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        assert_eq!(solver.x_matrix.equation_repr(), expected);
+    }
+
+    #[test]
+    fn test_row_order() {
+        let solve = |row_order: RowOrder| {
+            let mut c = create_mna_container();
+            c.create_nodes().unwrap();
+            let options = SolveOptions {
+                row_order,
+                ..Default::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+            solver.solve().unwrap();
+            solver
+        };
+        let ascending = solve(RowOrder::Ascending);
+        let descending = solve(RowOrder::Descending);
+
+        // Node rows are mirrored, source rows keep their place
+        let n: usize = 3;
+        let permute = |i: usize| if i < n { n - i - 1 } else { i };
+        for i in 0..5 {
+            assert_eq!(
+                ascending.x_matrix[i].equation_repr(),
+                descending.x_matrix[permute(i)].equation_repr()
+            );
+            assert_eq!(
+                ascending.z_matrix[i].equation_repr(),
+                descending.z_matrix[permute(i)].equation_repr()
+            );
+            for j in 0..5 {
+                assert_eq!(
+                    ascending.a_matrix[(i, j)].equation_repr(),
+                    descending.a_matrix[(permute(i), permute(j))].equation_repr()
+                );
+            }
+        }
+        assert_eq!(
+            ascending.x_matrix.equation_repr(),
+            "\\begin{bmatrix}Node: 1\\\\Node: 2\\\\Node: 3\\\\SRC(V)4: 32 V\\\\SRC(V)5: 20 V\\\\\\end{bmatrix}"
+        );
+        let voltages = ascending
+            .node_voltages()
+            .unwrap()
+            .into_iter()
+            .zip(descending.node_voltages().unwrap());
+        for ((id_a, voltage_a), (id_d, voltage_d)) in voltages {
+            assert_eq!(id_a, id_d);
+            assert!((voltage_a - voltage_d).abs() < 1e-9);
+        }
+        let current = ascending.current_through_source(4).unwrap()
+            - descending.current_through_source(4).unwrap();
+        assert!(current.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_z_matrix() {
+        let expected = "\\begin{bmatrix}0\\\\0\\\\0\\\\32\\\\20\\\\\\end{bmatrix}";
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        assert_eq!(solver.z_matrix.equation_repr(), expected);
+    }
+
+    #[test]
+    fn test_rlc_matrices() {
+        let g = vec![
+            vec!["1/R3", "", ""],
+            vec!["", "1/R1", "-1/R1"],
+            vec!["", "-1/R1", "1/R1"],
+        ];
+        // The inductor column follows the voltage source columns
+        let b = vec![vec!["0", "1"], vec!["0", "-1"], vec!["1", "0"]];
+
+        let mut c = create_rlc_container();
+        c.create_nodes().unwrap();
+        let n = c.nodes().len();
+        let m = c.get_elements_by_type(VoltageSrc).len() + c.get_elements_by_type(Inductor).len();
+        let container = Rc::new(RefCell::new(c));
+        let g_matrix = form_g_matrix(container.clone(), n, RowOrder::Descending);
+        let b_matrix = form_b_matrix(container.clone(), n, m, RowOrder::Descending);
+        let c_matrix = form_c_matrix(container.clone(), n, m, RowOrder::Descending);
+        let d_matrix = form_d_matrix(container.clone(), m);
+
+        assert_eq!((n, m), (3, 2));
+        for i in 0..n {
+            for j in 0..n {
+                assert_eq!(g[i][j], g_matrix[(i, j)].equation_repr());
+            }
+            for j in 0..m {
+                assert_eq!(b[i][j], b_matrix[(i, j)].equation_repr());
+                assert_eq!(b[i][j], c_matrix[(j, i)].equation_repr());
+            }
+        }
+        assert!(d_matrix.iter().all(|x| x.equation_repr() == "0"));
+
+        let solver: NodeMatrixSolver = Solver::new(container);
+        assert_eq!(
+            solver.x_matrix.equation_repr(),
+            "\\begin{bmatrix}Node: 3\\\\Node: 2\\\\Node: 1\\\\SRC(V)5: 10 V\\\\L2: 1 H\\\\\\end{bmatrix}"
+        );
+        assert_eq!(
+            solver.z_matrix.equation_repr(),
+            "\\begin{bmatrix}0\\\\0\\\\0\\\\10\\\\0\\\\\\end{bmatrix}"
+        );
+    }
+
+    #[test]
+    fn test_current_source_orientation() {
+        // SRC(C)3 between the nodes of R1 and R2, once each way
+        let create = |a: usize, b: usize| {
+            let mut c = Container::new();
+            c.add_element_between_nodes(Resistor, 1., 1, 0);
+            c.add_element_between_nodes(Resistor, 2., 2, 0);
+            c.add_element_between_nodes(CurrentSrc, 1.5, a, b);
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+
+        for ((a, b), expected) in [((1, 2), [1.5, -1.5]), ((2, 1), [-1.5, 1.5])] {
+            let c = create(a, b);
+            let z = form_z_vector(c.clone(), RowOrder::Ascending, &HashMap::new());
+            assert_eq!(z.len(), 2);
+            for row in 0..2 {
+                assert_eq!(z[row].value(), expected[row]);
+            }
+            // The row the source leaves is negated
+            let z = form_z_vector_symbolic(c.clone(), RowOrder::Ascending);
+            for row in 0..2 {
+                let negated: bool = matches!(&z[row], Sum(x) if matches!(x[0], Negate(_)));
+                assert_eq!(negated, expected[row] < 0.0);
+            }
+
+            // The node the source pushes into rises, the one it draws from falls below ground
+            let mut solver: NodeMatrixSolver = Solver::new(c);
+            solver.solve().unwrap();
+            assert!((solver.node_voltage(1).unwrap() - expected[0]).abs() < 1e-9);
+            assert!((solver.node_voltage(2).unwrap() - 2. * expected[1]).abs() < 1e-9);
+        }
 
-        for i in 0..n {
-            for j in 0..n {
-                assert_eq!(expected[i][j], matrix[(i, j)].equation_repr());
+        // SRC(C)1 to ground drives node 1 from its positive terminal or draws from it
+        for ((a, b), expected) in [((1, 0), 4.), ((0, 1), -4.)] {
+            let mut c = Container::new();
+            c.add_element_between_nodes(CurrentSrc, 2., a, b);
+            c.add_element_between_nodes(Resistor, 2., 1, 0);
+            c.create_nodes().unwrap();
+            let c = Rc::new(RefCell::new(c));
+            let z = form_z_vector(c.clone(), RowOrder::Ascending, &HashMap::new());
+            assert_eq!(z[0].value(), expected / 2.);
+
+            let mut solver: NodeMatrixSolver = Solver::new(c.clone());
+            solver.solve().unwrap();
+            assert!((solver.node_voltage(1).unwrap() - expected).abs() < 1e-9);
+            let mut step: NodeStepSolver = Solver::new(c);
+            step.solve().unwrap();
+            assert!((step.node_voltage(1).unwrap() - expected).abs() < 1e-9);
+
+            // The source delivers the 8 W the resistor absorbs either way
+            let solution: Solution = Solution::from_solver(&solver).unwrap();
+            assert!((solution.elements[0].power + 8.).abs() < 1e-9);
+            assert!((solution.elements[1].power - 8.).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_dependent_sources() {
+        // Lowest node first, the layout of the LPSA examples
+        let options = SolveOptions {
+            row_order: RowOrder::Ascending,
+            ..Default::default()
+        };
+        let assert_a = |solver: &NodeMatrixSolver, expected: Vec<Vec<&str>>| {
+            assert_eq!(solver.a_matrix.nrows(), expected.len());
+            for (i, row) in expected.iter().enumerate() {
+                for (j, entry) in row.iter().enumerate() {
+                    assert_eq!(
+                        *entry,
+                        solver.a_matrix[(i, j)].equation_repr(),
+                        "({}, {})",
+                        i,
+                        j
+                    );
+                }
             }
+        };
+
+        // VCVS4 holds node 3 at 20 times the voltage across R3. Its current is the last
+        // unknown and its C row carries the gain at the control node.
+        let mut c: Container = load_problem("dependent_source_amplifier")
+            .unwrap()
+            .create_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(c)), options.clone());
+        assert_a(
+            &solver,
+            vec![
+                vec!["1/R2", "-1/R2", "", "", "1", "0"],
+                vec!["-1/R2", "1/R2 + 1/R3", "", "", "0", "0"],
+                vec!["", "", "1/R5", "-1/R5", "0", "1"],
+                vec!["", "", "-1/R5", "1/R5 + 1/R6", "0", "0"],
+                vec!["1", "0", "0", "0", "0", "0"],
+                vec!["0", "-VCVS4", "1", "0", "0", "0"],
+            ],
+        );
+        assert_eq!(
+            solver.x_matrix.equation_repr(),
+            "\\begin{bmatrix}Node: 1\\\\Node: 2\\\\Node: 3\\\\Node: 4\\\\SRC(V)1: 1 V\\\\VCVS4: 20 V/V\\\\\\end{bmatrix}"
+        );
+        assert_eq!(
+            solver.z_matrix.equation_repr(),
+            "\\begin{bmatrix}0\\\\0\\\\0\\\\0\\\\1\\\\0\\\\\\end{bmatrix}"
+        );
+        solver.solve().unwrap();
+        assert!((solver.node_voltage(3).unwrap() - 200. / 11.).abs() < 1e-9);
+        assert!((solver.node_voltage(4).unwrap() - 160. / 11.).abs() < 1e-9);
+
+        // VCCS3 pushes 0.5 A/V times the voltage of node 1 into node 2
+        let create = |control: Control| {
+            let mut c = Container::new();
+            c.add_element_no_id(Element::new(Ground, 0., vec![1, 2, 3, 4], vec![]));
+            c.add_element_no_id(Element::new(VoltageSrc, 2., vec![0], vec![2]));
+            c.add_element_no_id(Element::new(Resistor, 100., vec![1], vec![0]));
+            c.add_element_no_id(
+                Element::new(DependentCurrent, 0.5, vec![4], vec![0]).with_control(control),
+            );
+            c.add_element_no_id(Element::new(Resistor, 10., vec![3], vec![0]));
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+        let c = create(Control::Nodes(1, 0));
+        assert_eq!(c.borrow().validate(), Ok(Valid));
+        let mut solver: NodeMatrixSolver = Solver::new_with_options(c.clone(), options.clone());
+        assert_a(
+            &solver,
+            vec![
+                vec!["1/R2", "", "1"],
+                vec!["-VCCS3", "1/R4", "0"],
+                vec!["1", "0", "0"],
+            ],
+        );
+        assert_eq!(solver.g_contributions()[(1, 0)], vec![(3, -0.5)]);
+        solver.solve().unwrap();
+        assert!((solver.node_voltage(2).unwrap() - 10.).abs() < 1e-9);
+
+        // The step solvers refuse dependent sources
+        assert_eq!(
+            check_support(SolverType::NodeStep, &c),
+            Err(StatusError::Unsupported {
+                solver: SolverType::NodeStep,
+                components: vec![DependentCurrent],
+                alternatives: vec![SolverType::NodeMatrix],
+            })
+        );
+
+        let mut solver: NodeMatrixSolver = Solver::new(create(Control::Element(9)));
+        assert_known_error!(
+            solver.solve(),
+            "VCCS3: 0.5 A/V is controlled by element 9 which is not a branch"
+        );
+    }
+
+    #[test]
+    fn test_dc_inductors() {
+        let create = || {
+            let mut c = create_rlc_container();
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+        let rejected: &str =
+            "Inductor L2: 1 H is a short circuit at DC, enable allow_dc_inductors to solve it as one";
+        let options = SolveOptions {
+            allow_dc_inductors: true,
+            ..SolveOptions::default()
+        };
+
+        assert_eq!(create_rlc_container().validate(), Ok(Valid));
+        assert_known_error!(
+            create_rlc_container().validate_with_options(&SolveOptions::default()),
+            rejected
+        );
+        assert_eq!(
+            create_rlc_container().validate_with_options(&options),
+            Ok(Valid)
+        );
+        let mut solver: NodeMatrixSolver = Solver::new(create());
+        assert_known_error!(solver.solve(), rejected);
+        let mut solver: NodeStepSolver = Solver::new_with_options(create(), options.clone());
+        assert!(solver.solve().is_err());
+
+        // L2 shorts nodes 2 and 3, 2 A flow through R1 and R3 and none into C4
+        let mut solver: NodeMatrixSolver = Solver::new_with_options(create(), options);
+        solver.solve().unwrap();
+        let voltages: Vec<(usize, f64)> = solver.node_voltages().unwrap();
+        assert_eq!(voltages.len(), 4);
+        for ((id, voltage), expected) in voltages.iter().zip([(0, 0.), (1, 10.), (2, 6.), (3, 6.)])
+        {
+            assert_eq!(*id, expected.0);
+            assert!((voltage - expected.1).abs() < 1e-9);
         }
+        assert!((solver.voltage_source_current(2).unwrap().abs() - 2.).abs() < 1e-9);
+        assert!((solver.current_through_source(5).unwrap().abs() - 2.).abs() < 1e-9);
     }
 
     #[test]
-    fn test_b_matrix() {
-        let expected = vec![vec!["-1", "0"], vec!["1", "0"], vec!["0", "1"]];
+    fn test_result_units() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let steps = serde_json::to_value(solver.solve().unwrap()).unwrap();
+        let operations =
+            steps[0]["sub_steps"].as_array().unwrap().last().unwrap()["operations"].to_string();
+        assert!(operations.contains("V_{1} = 20 V"));
+    }
 
+    #[test]
+    fn test_explain() {
         let mut c = create_mna_container();
         c.create_nodes().unwrap();
-        let n = c.nodes().len();
-        let m = c.get_voltage_sources().len();
-        let matrix = form_b_matrix(Rc::new(RefCell::new(c)), n, m);
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
 
-        for i in 0..n {
-            for j in 0..m {
-                assert_eq!(expected[i][j], matrix[(i, j)].equation_repr());
-            }
+        let steps = solver.explain(1).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps.iter().all(|x| x.referenced_elements.contains(&1)));
+        let steps = serde_json::to_value(steps).unwrap().to_string();
+        assert!(steps.contains("V_{R1} = V_{0} - V_{3} = 8 V"));
+        assert!(steps.contains("I_{R1} = 4 A flowing from Node 0 to Node 3"));
+        assert!(steps.contains("P_{R1} = 32 W absorbed"));
+        assert!(steps.contains("G[3][3] += 0.5 S"));
+
+        let steps = solver.explain(5).unwrap();
+        assert_eq!(steps.len(), 3);
+        let steps = serde_json::to_value(steps).unwrap().to_string();
+        assert!(steps.contains("I_{SRC(V)5} = 1 A flowing from Node 1 to Node 0"));
+        assert!(steps.contains("P_{SRC(V)5} = 20 W absorbed"));
+        assert!(steps.contains("Row 5 of A"));
+        assert!(steps.contains("V_{1} = 20 V"));
+        assert!(steps.contains("V_{2} = 5.71 V"));
+        assert!(steps.contains("V_{3} = 5.71 V"));
+
+        assert_known_error!(solver.explain(9), "Element 9 is not part of the solution");
+
+        // The galvanometer of a balanced bridge carries no current at all
+        let mut c = bridge(1., 2., 3., 6., 5., 10.);
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let steps = serde_json::to_value(solver.explain(6).unwrap())
+            .unwrap()
+            .to_string();
+        assert!(steps.contains("I_{R6} = 0 A (exactly, by symmetry)"));
+        assert!(steps.contains("P_{R6} = 0 W absorbed (exactly, by symmetry)"));
+    }
+
+    #[test]
+    fn test_tag_step() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+        let steps = solver.solve().unwrap();
+        assert!(steps[0]
+            .sub_steps
+            .iter()
+            .all(|x| x.description() != Some("Results by tag".to_string())));
+
+        c.get_element_by_id(2).borrow_mut().tags = vec!["load".to_string()];
+        c.get_element_by_id(3).borrow_mut().tags = vec!["load".to_string()];
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let steps = serde_json::to_value(solver.solve().unwrap()).unwrap();
+        let sub_step = steps[0]["sub_steps"].as_array().unwrap().last().unwrap();
+        assert_eq!(sub_step["description"], "Results by tag");
+        assert!(sub_step["operations"]
+            .to_string()
+            .contains("load: R2, R3, P = 76 W"));
+    }
+
+    #[test]
+    fn test_pivots() {
+        let solve = |no_pivoting: bool| {
+            let mut c = create_mna_container();
+            c.create_nodes().unwrap();
+            let options = SolveOptions {
+                no_pivoting,
+                ..Default::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+            let steps = solver.solve().unwrap();
+            (solver, steps)
+        };
+        let description = |steps: &Vec<Step>| {
+            steps[0]
+                .sub_steps
+                .iter()
+                .find(|x| x.description() == Some("Elimination order".to_string()))
+                .map(|x| serde_json::to_string(&x.get_steps()).unwrap())
+        };
+
+        // The first column has 1/R1 on the diagonal and -1 in the C block below it
+        let (pivoted, steps) = solve(false);
+        let solution = Solution::from_solver(&pivoted).unwrap();
+        assert_eq!(solution.pivots.len(), 5);
+        assert_eq!(solution.pivots[0].row, 3);
+        assert!(description(&steps)
+            .unwrap()
+            .contains("Swapped R1 ↔ R4 because |A[4][1]| > |A[1][1]|"));
+
+        let (natural, steps) = solve(true);
+        assert!(natural.pivots().iter().all(|x| !x.swapped()));
+        assert_eq!(description(&steps), None);
+        for id in 1..4 {
+            let difference = pivoted.node_voltage(id).unwrap() - natural.node_voltage(id).unwrap();
+            assert!(difference.abs() < 1e-9);
         }
     }
 
     #[test]
-    fn test_c_matrix() {
-        let expected = vec![vec!["-1", "1", "0"], vec!["0", "0", "1"]];
+    fn test_update_source_values() {
+        let rebuild = |value: f64| -> NodeMatrixSolver {
+            let mut c = create_mna_container();
+            c.get_element_by_id(5).borrow_mut().value = value;
+            c.create_nodes().unwrap();
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+            solver.solve().unwrap();
+            solver
+        };
 
         let mut c = create_mna_container();
         c.create_nodes().unwrap();
-        let n = c.nodes().len();
-        let m = c.get_voltage_sources().len();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert_known_error!(
+            solver.update_source_values(HashMap::from([(5, 10.)])),
+            "Circuit has not been solved"
+        );
+        solver.solve().unwrap();
+        let factors: Option<LuFactors> = solver.factors.clone();
+        assert_known_error!(
+            solver.update_source_values(HashMap::from([(1, 10.)])),
+            "Element 1 is not an independent source"
+        );
 
-        let c_matrix = form_c_matrix(Rc::new(RefCell::new(c)), n, m);
+        // Sweep the 20 V source, the factors of the first solve are kept
+        let values: Vec<f64> = (0..50).map(|x| x as f64).collect();
+        let mut updated: Vec<Vec<(usize, f64)>> = Vec::new();
+        for value in values.iter() {
+            solver
+                .update_source_values(HashMap::from([(5, *value)]))
+                .unwrap();
+            updated.push(solver.node_voltages().unwrap());
+        }
+        let rebuilt: Vec<Vec<(usize, f64)>> = values
+            .iter()
+            .map(|x| rebuild(*x).node_voltages().unwrap())
+            .collect();
 
-        for i in 0..m {
-            for j in 0..n {
-                assert_eq!(expected[i][j], c_matrix[(i, j)].equation_repr());
+        for (a, b) in updated.into_iter().zip(rebuilt) {
+            for ((id_a, voltage_a), (id_b, voltage_b)) in a.into_iter().zip(b) {
+                assert_eq!(id_a, id_b);
+                assert!((voltage_a - voltage_b).abs() < 1e-9);
             }
         }
+        assert_eq!(solver.factors, factors);
+        let container = solver.container();
+        assert_eq!(container.borrow().get_element_by_id(5).borrow().value, 20.);
+
+        // The steps are rendered again with the new value
+        solver.solve().unwrap();
+        assert!(
+            (solver.current_through_source(5).unwrap()
+                - rebuild(49.).current_through_source(5).unwrap())
+            .abs()
+                < 1e-9
+        );
     }
 
     #[test]
-    fn test_d_matrix() {
-        let expected = vec![vec!["0", "0"], vec!["0", "0"]];
+    fn test_solved_values() {
+        for (mut c, nodes, sources) in [
+            (
+                create_mna_container(),
+                vec![(1, 20.), (2, 24.), (3, -8.)],
+                vec![(4, -4.), (5, 1.)],
+            ),
+            (
+                create_mna_container_2(),
+                vec![(1, 22.4), (2, -9.6)],
+                vec![(4, 10.), (5, -9.2)],
+            ),
+        ] {
+            c.create_nodes().unwrap();
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+            let steps: Vec<Step> = solver.solve().unwrap();
+            let values: SolvedValues = SolvedValues::from_solver(&solver).unwrap();
+            for (id, voltage) in nodes {
+                assert!((values.node_voltages[&id] - voltage).abs() < 1e-9);
+            }
+            for (id, current) in sources {
+                assert!((values.source_currents[&id] - current).abs() < 1e-9);
+            }
+
+            let last = serde_json::to_value(steps.last().unwrap()).unwrap();
+            assert_eq!(last["title"], "Solved Values");
+            assert_eq!(last["sub_steps"][0]["description"], "Equation");
+            assert!(!last["sub_steps"][0]["forms"]["symbolic"].is_null());
+            assert!(!last["sub_steps"][0]["forms"]["numeric"].is_null());
+        }
+
+        // A triangle of resistors floating next to the divider
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        c.add_element_between_nodes(Resistor, 5., 1, 0);
+        c.add_element_between_nodes(Resistor, 1., 2, 3);
+        c.add_element_between_nodes(Resistor, 1., 3, 4);
+        c.add_element_between_nodes(Resistor, 1., 4, 2);
+        c.create_nodes().unwrap();
+        for deterministic_fp in [false, true] {
+            let options = SolveOptions {
+                deterministic_fp,
+                ..SolveOptions::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c.clone())), options);
+            assert!(matches!(
+                solver.solve(),
+                Err(Known(message)) if message.starts_with("Matrix is not invertible, the voltage of Node ")
+                    && message.contains("is not determined by the circuit")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_save_restore_state() {
+        let solve = |value: f64| -> NodeMatrixSolver {
+            let mut c = create_mna_container();
+            c.get_element_by_id(5).borrow_mut().value = value;
+            c.create_nodes().unwrap();
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+            solver.solve().unwrap();
+            solver
+        };
+        let close = |a: Vec<(usize, f64)>, b: Vec<(usize, f64)>| {
+            assert_eq!(a.len(), b.len());
+            for ((id_a, voltage_a), (id_b, voltage_b)) in a.into_iter().zip(b) {
+                assert_eq!(id_a, id_b);
+                assert!((voltage_a - voltage_b).abs() < 1e-9);
+            }
+        };
+
+        // Sweep the 20 V source and checkpoint halfway through
+        let mut voltages: Vec<Vec<(usize, f64)>> = Vec::new();
+        let mut checkpoint: String = String::new();
+        for (i, value) in [10., 20., 30.].into_iter().enumerate() {
+            let solver: NodeMatrixSolver = solve(value);
+            voltages.push(solver.node_voltages().unwrap());
+            if i == 1 {
+                checkpoint = serde_json::to_string(&solver.save_state().unwrap()).unwrap();
+            }
+        }
+
+        let state: SolverState = serde_json::from_str(&checkpoint).unwrap();
+        let mut restored: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(Container::new())));
+        restored.restore_state(state.clone()).unwrap();
+        assert!(restored.factors.is_some());
+        assert_eq!(
+            restored
+                .container()
+                .borrow()
+                .get_element_by_id(5)
+                .borrow()
+                .value,
+            20.
+        );
+        close(restored.node_voltages().unwrap(), voltages[1].clone());
+        restored.solve().unwrap();
+        close(restored.node_voltages().unwrap(), voltages[1].clone());
+
+        // Updated source values are saved with the solution they were solved against
+        let mut updated: NodeMatrixSolver = solve(20.);
+        updated
+            .update_source_values(HashMap::from([(5, 30.)]))
+            .unwrap();
+        let checkpoint: String = serde_json::to_string(&updated.save_state().unwrap()).unwrap();
+        let mut restored: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(Container::new())));
+        restored
+            .restore_state(serde_json::from_str(&checkpoint).unwrap())
+            .unwrap();
+        assert_eq!(restored.source_values, HashMap::from([(5, 30.)]));
+        assert_eq!(
+            restored.z_matrix.equation_repr(),
+            updated.z_matrix.equation_repr()
+        );
+        close(restored.node_voltages().unwrap(), voltages[2].clone());
+        restored.solve().unwrap();
+        close(restored.node_voltages().unwrap(), voltages[2].clone());
+        assert!(
+            (restored.current_through_source(5).unwrap()
+                - solve(30.).current_through_source(5).unwrap())
+            .abs()
+                < 1e-9
+        );
 
+        let mut unsolved: NodeMatrixSolver =
+            Solver::new(Rc::new(RefCell::new(create_basic_container())));
+        let mut mismatched = state;
+        mismatched.elements = unsolved.save_state().unwrap().elements;
+        assert!(unsolved.restore_state(mismatched).is_err());
+    }
+
+    #[test]
+    fn test_solve_twice() {
         let mut c = create_mna_container();
         c.create_nodes().unwrap();
-        let m = c.get_voltage_sources().len();
+        let container = Rc::new(RefCell::new(c));
+        let mut solver: NodeMatrixSolver = Solver::new(container.clone());
+        let first = serde_json::to_string(&solver.solve().unwrap()).unwrap();
+        let second = serde_json::to_string(&solver.solve().unwrap()).unwrap();
+        assert_eq!(first, second);
 
-        let d_matrix = form_d_matrix(Rc::new(RefCell::new(c)), m);
+        container
+            .borrow_mut()
+            .add_element_no_id(Element::new(Resistor, 1., vec![1], vec![0]));
+        assert_eq!(solver.solve().err(), Some(StatusError::StaleSolver));
+    }
 
-        for i in 0..m {
-            for j in 0..m {
-                assert_eq!(expected[i][j], d_matrix[(i, j)].equation_repr());
+    #[test]
+    fn test_empty_container() {
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(Container::new())));
+        let steps = solver.solve().unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].title(), Some("Empty circuit".to_string()));
+        assert!(solver.node_voltages().is_err());
+    }
+
+    #[test]
+    fn test_step_forms() {
+        for (display, count) in [
+            (FormDisplay::Both, 2),
+            (FormDisplay::Symbolic, 1),
+            (FormDisplay::Numeric, 1),
+        ] {
+            let mut c = create_basic_container();
+            c.create_nodes().unwrap();
+            let options = SolveOptions {
+                forms: display,
+                ..Default::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+            let steps = serde_json::to_value(solver.solve().unwrap()).unwrap();
+            let sub_steps = steps[0]["sub_steps"].as_array().unwrap();
+
+            for index in [0, 1, 4] {
+                let forms = &sub_steps[index]["forms"];
+                let symbolic = forms["symbolic"].as_str().unwrap();
+                let numeric = forms["numeric"].as_str().unwrap();
+                assert_ne!(symbolic, numeric);
+                assert_eq!(
+                    sub_steps[index]["operations"].as_array().unwrap().len(),
+                    count
+                );
             }
+            assert!(sub_steps[0]["forms"]["symbolic"]
+                .as_str()
+                .unwrap()
+                .contains('R'));
+            assert!(!sub_steps[0]["forms"]["numeric"]
+                .as_str()
+                .unwrap()
+                .contains('R'));
+            assert!(sub_steps[2].get("forms").is_none());
         }
     }
 
     #[test]
-    fn test_x_matrix() {
-        let expected = "\\begin{bmatrix}Node: 1\\\\Node: 2\\\\Node: 3\\\\SRC(V)4: 32 V\\\\SRC(V)5: 20 V\\\\\\end{bmatrix}";
+    fn test_reference() {
+        let references = vec![
+            Reference::Auto,
+            Reference::Node(2),
+            Reference::MidpointOf(1, 3),
+        ];
+        let pairs = vec![(1, 3), (2, 3), (1, 0), (2, 0)];
+        let expected = vec![28.0, 32.0, 20.0, 24.0];
+
+        for reference in references {
+            let mut c = create_mna_container();
+            c.create_nodes().unwrap();
+            let options = SolveOptions {
+                reference: reference.clone(),
+                ..Default::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+            solver.solve().unwrap();
+
+            for (i, (a, b)) in pairs.iter().enumerate() {
+                let difference = solver.voltage_between(*a, *b).unwrap();
+                assert!((difference - expected[i]).abs() < 1e-9);
+            }
+
+            match reference {
+                Reference::Auto => assert!(solver.node_voltage(0).unwrap().abs() < 1e-9),
+                Reference::Node(id) => assert!(solver.node_voltage(id).unwrap().abs() < 1e-9),
+                Reference::MidpointOf(a, b) => {
+                    let sum = solver.node_voltage(a).unwrap() + solver.node_voltage(b).unwrap();
+                    assert!(sum.abs() < 1e-9);
+                }
+            }
+        }
+    }
 
+    #[test]
+    fn test_current_through_source() {
         let mut c = create_mna_container();
         c.create_nodes().unwrap();
-        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert!(solver.current_through_source(4).is_err());
+        solver.solve().unwrap();
 
-        assert_eq!(solver.x_matrix.equation_repr(), expected);
+        // Power absorbed by each source, R1, R2 and R3 dissipate 32 W, 4 W and 72 W
+        let absorbed_4 = solver.current_through_source(4).unwrap() * 32.;
+        let absorbed_5 = solver.current_through_source(5).unwrap() * 20.;
+        assert!((absorbed_4 + 128.).abs() < 1e-9);
+        assert!((absorbed_5 - 20.).abs() < 1e-9);
+        assert!((absorbed_4 + absorbed_5 + 108.).abs() < 1e-9);
+
+        assert!(solver.current_through_source(1).is_err());
+        assert!(solver.current_through_source(10).is_err());
+
+        let mut c = create_mna_container_2();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        assert_eq!(solver.current_through_source(4).unwrap(), 10.);
     }
 
     #[test]
-    fn test_z_matrix() {
-        let expected = "\\begin{bmatrix}0\\\\0\\\\0\\\\32\\\\20\\\\\\end{bmatrix}";
+    fn test_element_voltage() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert!(solver.element_voltage(1).is_err());
+        solver.solve().unwrap();
+
+        // R1 is in series with V4 and R2 with V5
+        let r1 = -solver.current_through_source(4).unwrap() * 2.;
+        let r2 = -solver.current_through_source(5).unwrap() * 4.;
+        assert!((solver.element_voltage(1).unwrap() - r1).abs() < 1e-9);
+        assert!((solver.element_voltage(2).unwrap() - r2).abs() < 1e-9);
+        assert!((solver.element_voltage(3).unwrap() - 24.).abs() < 1e-9);
+
+        assert!((solver.element_voltage(5).unwrap() - 20.).abs() < 1e-9);
+        // The terminal lists of V4 are the reverse of its polarity
+        assert!((solver.element_voltage(4).unwrap() + 32.).abs() < 1e-9);
+
+        assert!(solver.element_voltage(0).is_err());
+        assert!(solver.element_voltage(10).is_err());
+
+        let mut c = create_mna_container_2();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        let node = solver.node_voltage(1).unwrap();
+        assert!((solver.element_voltage(4).unwrap() - node).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_power_balance() {
+        let fixtures: Vec<fn() -> Container> = vec![
+            create_basic_container,
+            create_basic_supernode_container,
+            create_basic_supermesh_container,
+            create_mna_container,
+            create_mna_container_2,
+            create_ladder_container,
+        ];
+        for fixture in fixtures {
+            let mut c = fixture();
+            c.create_nodes().unwrap();
+            c.create_super_nodes().unwrap();
+            let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+            assert!(solver.power_balance_error().is_err());
+            solver.solve().unwrap();
+
+            assert!(solver.power_delivered_by_sources().unwrap() > 0.);
+            assert!(solver.power_balance_error().unwrap() < 1e-9);
+        }
 
+        // R1, R2 and R3 dissipate 32 W, 4 W and 72 W
         let mut c = create_mna_container();
         c.create_nodes().unwrap();
-        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        assert!((solver.power_absorbed_by_resistors().unwrap() - 108.).abs() < 1e-9);
+    }
 
-        assert_eq!(solver.z_matrix.equation_repr(), expected);
+    #[test]
+    fn test_back_substitution_trace() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert!(solver.back_substitution_trace().is_empty());
+        solver.solve().unwrap();
+
+        let steps: Vec<Step> = solver.back_substitution_trace();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0].title(), Some("From row 5".to_string()));
+        assert_eq!(
+            steps[0].description(),
+            Some("V_5 = z_5 / A[5][5]".to_string())
+        );
+        assert_eq!(
+            steps[3].description(),
+            Some("V_2 = (z_2 - A[2][3]*V_3 - A[2][4]*V_4 - A[2][5]*V_5) / A[2][2]".to_string())
+        );
+        // Same arithmetic as the solve, so the values match exactly
+        for (step, row) in steps.iter().zip((0..5).rev()) {
+            assert_eq!(step.result.as_ref().unwrap().value(), solver.result[row]);
+        }
+    }
+
+    #[test]
+    fn test_single_resistor() {
+        let mut c = Container::new();
+        c.add_element_no_id(Element::new(Ground, 0., vec![1, 2], vec![]));
+        c.add_element_no_id(Element::new(VoltageSrc, 5., vec![2], vec![0]));
+        c.add_element_no_id(Element::new(Resistor, 10., vec![1], vec![0]));
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        assert!((solver.node_voltage(1).unwrap() - 5.0).abs() < 1e-9);
+        assert!((solver.current_through_source(1).unwrap() + 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_g_matrix_duplicate_membership() {
+        // Basic container where R2 lists R1 twice on its positive side
+        let mut c = Container::new();
+        c.add_element_no_id(Element::new(Ground, 1.0, vec![3, 2], vec![]));
+        c.add_element_no_id(Element::new(Resistor, 1.0, vec![3], vec![2]));
+        c.add_element_no_id(Element::new(Resistor, 1.0, vec![1, 1], vec![0, 3]));
+        c.add_element_no_id(Element::new(VoltageSrc, 1.0, vec![2, 0], vec![1]));
+        c.create_nodes().unwrap();
+        assert_eq!(c.warnings().len(), 1);
+
+        let n = c.nodes().len();
+        let matrix = form_g_matrix(Rc::new(RefCell::new(c)), n, RowOrder::Descending);
+        assert_eq!(matrix[(0, 0)].equation_repr(), "1/R1 + 1/R2");
+        assert_eq!(matrix[(0, 0)].value(), 2.0);
+    }
+
+    #[test]
+    fn test_zero_ohm_strategy() {
+        // V1 drives R2 and R4 in series, W3 is a wire between them
+        let solve = |strategy: ZeroOhmStrategy| {
+            let mut c = Container::new();
+            c.add_element_no_id(Element::new(Ground, 0., vec![1, 4], vec![]));
+            c.add_element_no_id(Element::new(VoltageSrc, 10., vec![2], vec![0]));
+            c.add_element_no_id(Element::new(Resistor, 2., vec![1], vec![3]));
+            c.add_element_no_id(Element::new(Resistor, 0., vec![2], vec![4]));
+            c.add_element_no_id(Element::new(Resistor, 2., vec![3], vec![0]));
+            c.create_nodes().unwrap();
+            let options = SolveOptions {
+                zero_ohm_strategy: strategy,
+                ..SolveOptions::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+            solver.solve().unwrap();
+            solver
+        };
+
+        let strategies = [
+            ZeroOhmStrategy::Merge,
+            ZeroOhmStrategy::SenseBranch,
+            ZeroOhmStrategy::Epsilon(1e-6),
+        ];
+        for strategy in strategies {
+            let solver = solve(strategy);
+            let container = solver.container();
+            assert_eq!(container.borrow().zero_ohm_strategy(), Some(strategy));
+            let (a, b) = container.borrow().element_current_direction(4);
+            assert!((solver.voltage_between(a, b).unwrap() - 5.0).abs() < 1e-5);
+
+            let warned: bool = container
+                .borrow()
+                .warnings()
+                .iter()
+                .any(|x| x.kind == WarningKind::IllConditioned);
+            assert_eq!(warned, matches!(strategy, ZeroOhmStrategy::Epsilon(_)));
+        }
+
+        // Merging removes a node, only the sense branch solves for the wire current
+        assert_eq!(
+            solve(ZeroOhmStrategy::Merge)
+                .container()
+                .borrow()
+                .nodes()
+                .len(),
+            2
+        );
+        assert!(solve(ZeroOhmStrategy::Merge)
+            .current_through_source(3)
+            .is_err());
+        let current: f64 = solve(ZeroOhmStrategy::SenseBranch)
+            .current_through_source(3)
+            .unwrap();
+        assert!((current - 2.5).abs() < 1e-9);
+
+        let invalid = SolveOptions {
+            zero_ohm_strategy: ZeroOhmStrategy::Epsilon(0.0),
+            ..SolveOptions::default()
+        };
+        let mut solver: NodeMatrixSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(create_mna_container())), invalid);
+        assert!(solver.solve().is_err());
+    }
+
+    #[test]
+    fn test_source_limits() {
+        // 10 V supply limited to 1 A, a 2 Ω load would draw 5 A
+        let supply = |load: f64| {
+            let mut c = Container::new();
+            let source: usize = c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+            c.add_element_between_nodes(Resistor, load, 1, 0);
+            c.get_element_by_id(source).borrow_mut().current_limit = Some(1.0);
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+        let c = supply(2.0);
+        let mut solver: NodeMatrixSolver = Solver::new(c.clone());
+        let steps = serde_json::to_value(solver.solve().unwrap()).unwrap();
+        assert!(close(solver.node_voltage(1).unwrap(), 2.0));
+        assert!(close(solver.current_through_source(1).unwrap(), 1.0));
+        assert!(solver
+            .container()
+            .borrow()
+            .get_element_by_id(1)
+            .borrow()
+            .is_limiting());
+        // The limiting model lives on the solver's copy, the caller's source is untouched
+        let source: Element = c.borrow().get_element_by_id(1).borrow().clone();
+        assert_eq!((source.class, source.value), (VoltageSrc, 10.));
+        assert!(!source.is_limiting());
+        let sub_step = steps[0]["sub_steps"].as_array().unwrap().last().unwrap();
+        assert_eq!(sub_step["description"], "Limited sources");
+        let description: &str = "SRC(C)1: 1 A is a voltage source in current limit, it delivers 1 A instead of holding 10 V";
+        assert!(sub_step["operations"].to_string().contains(description));
+        let warnings = c.borrow().warnings().clone();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::SourceLimited);
+        assert_eq!(warnings[0].message, description);
+
+        // Solving again swaps on a new copy without warning twice
+        let mut solver: NodeMatrixSolver = Solver::new(c.clone());
+        solver.solve().unwrap();
+        assert!(close(solver.node_voltage(1).unwrap(), 2.0));
+        assert_eq!(c.borrow().warnings().len(), 1);
+
+        // A 20 Ω load only draws 0.5 A
+        let c = supply(20.0);
+        let mut solver: NodeMatrixSolver = Solver::new(c.clone());
+        solver.solve().unwrap();
+        assert!(close(solver.node_voltage(1).unwrap(), 10.0));
+        assert!(!c.borrow().get_element_by_id(1).borrow().is_limiting());
+        assert!(c.borrow().warnings().is_empty());
+
+        // 1 A source with 5 V compliance would need 10 V across 10 Ω
+        let mut c = Container::new();
+        let source: usize = c.add_element_between_nodes(CurrentSrc, 1., 1, 0);
+        c.add_element_between_nodes(Resistor, 10., 1, 0);
+        c.get_element_by_id(source).borrow_mut().voltage_limit = Some(5.0);
+        c.create_nodes().unwrap();
+        let c = Rc::new(RefCell::new(c));
+        let mut solver: NodeMatrixSolver = Solver::new(c.clone());
+        solver.solve().unwrap();
+        assert!(close(solver.node_voltage(1).unwrap(), 5.0));
+        assert_eq!(
+            solver
+                .container()
+                .borrow()
+                .get_element_by_id(1)
+                .borrow()
+                .class,
+            VoltageSrc
+        );
+        let source: Element = c.borrow().get_element_by_id(1).borrow().clone();
+        assert_eq!((source.class, source.value), (CurrentSrc, 1.));
+        assert_eq!(
+            c.borrow().warnings()[0].message,
+            "SRC(V)1: 5 V is a current source at its compliance limit, it holds 5 V instead of driving 1 A"
+        );
+
+        let mut solver: NodeStepSolver = Solver::new(supply(2.0));
+        assert_known_error!(
+            solver.solve(),
+            "SRC(V)1: 10 V has a limit, switch to the node matrix solver to solve source limits"
+        );
+    }
+
+    #[test]
+    fn test_negative_resistance() {
+        // R3 in parallel with R4 looks like -8 Ω, which pushes node 2 above the source
+        let create = || {
+            let mut c = Container::new();
+            c.add_element_between_nodes(VoltageSrc, 10., 0, 1);
+            c.add_element_between_nodes(Resistor, 2., 1, 2);
+            c.add_element_between_nodes(Resistor, -4., 2, 0);
+            c.add_element_between_nodes(Resistor, 8., 2, 0);
+            c
+        };
+        let rejected: &str =
+            "Value cannot be zero or negative R3: -4 Ω, enable allow_negative_resistance to model active devices";
+        let options = SolveOptions {
+            allow_negative_resistance: true,
+            ..SolveOptions::default()
+        };
+
+        assert!(create().validate().is_err());
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(create())));
+        assert_known_error!(solver.solve(), rejected);
+        let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(create())));
+        assert_known_error!(solver.solve(), rejected);
+
+        assert_eq!(create().validate_with_options(&options), Ok(Valid));
+        let mut solver: NodeMatrixSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(create())), options.clone());
+        let steps: Vec<Step> = solver.solve().unwrap();
+        assert!((solver.node_voltage(2).unwrap() - 40. / 3.).abs() < 1e-9);
+
+        // The source absorbs power, R3 delivers all of it
+        assert!((solver.power_delivered_by_sources().unwrap() + 50. / 3.).abs() < 1e-9);
+        assert!((solver.power_generated_by_negative_resistors().unwrap() - 400. / 9.).abs() < 1e-9);
+        assert!((solver.power_absorbed_by_resistors().unwrap() - 250. / 9.).abs() < 1e-9);
+        assert!(solver.power_balance_error().unwrap() < 1e-9);
+        let total: f64 = Solution::from_solver(&solver)
+            .unwrap()
+            .elements
+            .iter()
+            .map(|x| x.power)
+            .sum();
+        assert!(total.abs() < 1e-9);
+
+        let note = steps[0]
+            .sub_steps
+            .iter()
+            .find(|x| x.description == Some("Negative resistances".to_string()))
+            .unwrap();
+        assert_eq!(note.operations.len(), 1);
+
+        let mut solver: NodeStepSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(create())), options);
+        assert!(solver.solve().is_ok());
+    }
+
+    #[test]
+    fn test_auto_transform() {
+        // test_zero_ohm_strategy with R5 looping on the node of R2 and W3, and W3 listed
+        // twice by R4
+        let create = || {
+            let mut c = Container::new();
+            c.add_element_no_id(Element::new(Ground, 0., vec![1, 4], vec![]));
+            c.add_element_no_id(Element::new(VoltageSrc, 10., vec![2], vec![0]));
+            c.add_element_no_id(Element::new(Resistor, 2., vec![1], vec![3, 5]));
+            c.add_element_no_id(Element::new(Resistor, 0., vec![2, 5], vec![4]));
+            c.add_element_no_id(Element::new(Resistor, 2., vec![3, 3], vec![0]));
+            c.add_element_no_id(Element::new(Resistor, 7., vec![2, 3], vec![2, 3]));
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+
+        let mut solver: NodeMatrixSolver = Solver::new(create());
+        solver.solve().unwrap();
+        let container = solver.container();
+        let kinds: Vec<TransformationKind> = container
+            .borrow()
+            .transformations()
+            .iter()
+            .map(|x| x.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TransformationKind::DuplicateMembershipRemoved,
+                TransformationKind::SelfLoopPruned,
+                TransformationKind::ZeroOhmSenseBranch,
+            ]
+        );
+        let affected: Vec<Vec<usize>> = container
+            .borrow()
+            .transformations()
+            .iter()
+            .map(|x| x.affected.clone())
+            .collect();
+        assert_eq!(affected, vec![vec![3], vec![5], vec![3]]);
+        let (a, b) = container.borrow().element_current_direction(4);
+        assert!((solver.voltage_between(a, b).unwrap() - 5.0).abs() < 1e-9);
+
+        // Disabled, the circuit is left as drawn and every required rewrite is reported
+        let options = SolveOptions {
+            allow_auto_transform: false,
+            ..SolveOptions::default()
+        };
+        let mut solver: NodeMatrixSolver = Solver::new_with_options(create(), options.clone());
+        assert_known_error!(
+            solver.solve(),
+            "Automatic transformations are disabled but the circuit needs them: \
+             Element 3 appears more than once in Node 3, the duplicate was removed, \
+             R5: 7 Ω connects a node to itself, R3: 0 Ω is a zero resistance branch"
+        );
+        let container = solver.container();
+        assert_eq!(container.borrow().transformations().len(), 1);
+        assert_eq!(
+            container.borrow().get_elements()[3].borrow().class,
+            Resistor
+        );
+        assert!(!container.borrow().get_elements()[5].borrow().is_detached());
+
+        // A circuit that needs no rewrite solves either way
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+        assert!(solver.solve().is_ok());
+        assert!(solver.container().borrow().transformations().is_empty());
+    }
+
+    #[test]
+    fn test_deterministic_fp() {
+        // Expected values are the exact serialized outputs, not rounded ones
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/data/deterministic_fp.json")).unwrap();
+        let options = SolveOptions {
+            deterministic_fp: true,
+            ..SolveOptions::default()
+        };
+        for vector in vectors.as_array().unwrap() {
+            let mut c = match vector["container"].as_str().unwrap() {
+                "mna" => create_mna_container(),
+                "mna_2" => create_mna_container_2(),
+                "ladder" => create_ladder_container(),
+                other => panic!("Unknown container {}", other),
+            };
+            c.create_nodes().unwrap();
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options.clone());
+            solver.solve().unwrap();
+
+            let voltages: Vec<(usize, f64)> = solver.node_voltages().unwrap();
+            assert_eq!(
+                serde_json::to_string(&voltages).unwrap(),
+                vector["node_voltages"].as_str().unwrap()
+            );
+            let elements: Vec<(usize, f64, f64)> = Solution::from_solver(&solver)
+                .unwrap()
+                .elements
+                .iter()
+                .map(|x| (x.id, x.current, x.power))
+                .collect();
+            assert_eq!(
+                serde_json::to_string(&elements).unwrap(),
+                vector["elements"].as_str().unwrap()
+            );
+        }
     }
 }
@@ -1,10 +1,19 @@
-use crate::component::Component::{Resistor, VoltageSrc};
+use crate::component::Component::{CurrentSrc, Resistor, VoltageSrc};
 use crate::container::Container;
 use crate::elements::Element;
-use crate::solvers::solver::{Solver, Step, SubStep};
+use crate::solvers::linear::LuFactors;
+use crate::solvers::options::SolveOptions;
+use crate::solvers::progress::{NoProgress, Phase, ProgressSink};
+use crate::solvers::solver::{
+    check_auto_transform, check_generation, check_negative_resistance, check_source_limits,
+    check_strict, negative_resistance_sub_step, NodeSolver, Solver, SolverType, Step, StepStub,
+    SubStep,
+};
+use crate::solvers::support::check_support;
 use crate::tools::Tool;
 use crate::tools::ToolType::{Node, SuperNode};
-use crate::validation::StatusError::Known;
+use crate::util::PrettyPrint;
+use crate::validation::StatusError::{Known, SymbolicLimit};
 use crate::validation::{StatusError, Validation};
 use nalgebra::{DMatrix, DVector};
 use operations::mappings::expand;
@@ -21,7 +30,9 @@ use std::rc::{Rc, Weak};
 
 pub struct NodeStepSolver {
     pub(crate) container: Rc<RefCell<Container>>,
+    options: SolveOptions,
     sources: Vec<SourceConnection>,          // Voltage sources
+    injections: DVector<f64>, // Current into each node, only used without voltage sources
     current_values: Vec<(usize, Operation)>, // (Element ID, Equation for current form nodes)
     node_pairs: Vec<(usize, usize, Rc<RefCell<Element>>)>, // Each element is attached to a pair of nodes.
     node_coefficients: Vec<Operation>, // Coefficients of the node summation for the matrix
@@ -31,8 +42,25 @@ pub struct NodeStepSolver {
     matrix_evaluation: Operation, // Simple operation holding the matrix multiplication display.
     kcl_operations: Vec<Operation>,
     inverse: DMatrix<f64>,
+    partial_steps: Vec<Step>, // Steps generated before a symbolic solve was aborted
+    kcl_rows: usize,          // Number of KCL rows at the top of the connection matrix
+    steps: Vec<Step>,         // Cached output of the first solve
+    generation: u64,          // Container generation the node pairs were taken from
 }
 
+/// Largest node system `solve_with_substitution_trace` solves with Cramer's rule
+const CRAMER_LIMIT: usize = 3;
+
+/// Key and label of every step in the order they are rendered
+const STEPS: [(&str, &str); 6] = [
+    ("kcl_equations", "KCL Equations"),
+    ("connection_matrix", "Connection Matrix"),
+    ("reduced_system", "Reduced System"),
+    ("node_voltages", "Solve For Node Voltages"),
+    ("currents", "Currents"),
+    ("current_results", "Current Results"),
+];
+
 #[derive(Debug)]
 struct SourceConnection {
     matrix: DVector<f64>,
@@ -43,11 +71,16 @@ impl Solver for NodeStepSolver {
     /// Creates a new NodeStepSolver
     ///
     /// This is where all the steps are created and handled
-    fn new(container: Rc<RefCell<Container>>) -> Self {
+    fn new_with_options(container: Rc<RefCell<Container>>, options: SolveOptions) -> Self {
+        // An invalid strategy leaves the wires alone and is reported by `solve`
+        let _ = container.borrow_mut().apply_auto_transforms(&options);
         let node_pairs = container.borrow().get_all_node_pairs();
+        let generation: u64 = container.borrow().generation();
         let out: NodeStepSolver = NodeStepSolver {
             container,
+            options,
             sources: vec![],
+            injections: DVector::zeros(0),
             current_values: vec![],
             node_pairs,
             node_coefficients: vec![],
@@ -57,6 +90,10 @@ impl Solver for NodeStepSolver {
             matrix_evaluation: Text("".to_string()),
             kcl_operations: vec![],
             inverse: DMatrix::zeros(0, 0),
+            partial_steps: vec![],
+            kcl_rows: 1,
+            steps: vec![],
+            generation,
         };
 
         out
@@ -66,24 +103,225 @@ impl Solver for NodeStepSolver {
     ///
     /// This Handles the formatting of the data into what the frontend requires.
     fn solve(&mut self) -> Result<Vec<Step>, StatusError> {
-        // SETUP and CALCULATIONS
-        self.setup_connections()?;
-        self.setup_node_equations()?;
-        self.setup_node_coefficients()?;
-        self.solve_node_voltages()?;
+        self.solve_with_progress(&NoProgress)
+    }
+
+    fn solve_with_progress(
+        &mut self,
+        progress: &dyn ProgressSink,
+    ) -> Result<Vec<Step>, StatusError> {
+        check_generation(&self.container, self.generation)?;
+        if !self.steps.is_empty() {
+            return Ok(self.steps.clone());
+        }
+        let stubs: Vec<StepStub> = self.calculate(progress)?;
 
-        // FORMATTING and OUTPUT
+        progress.report(Phase::Rendering, 0.0);
         let mut steps: Vec<Step> = Vec::new();
-        steps.push(self.display_base_kcl_equations()?);
-        steps.push(self.display_connection_matrix()?);
-        steps.push(self.display_solved_matrix()?);
-        steps.push(self.display_currents()?);
-        steps.push(self.current_steps()?);
+        for stub in stubs.iter() {
+            steps.push(self.render_step(stub.id)?);
+            progress.report(Phase::Rendering, steps.len() as f32 / stubs.len() as f32);
+        }
+        self.steps = steps.clone();
         Ok(steps)
     }
 }
 
+impl NodeSolver for NodeStepSolver {
+    fn container(&self) -> Rc<RefCell<Container>> {
+        self.container.clone()
+    }
+
+    fn node_voltages(&self) -> Result<Vec<(usize, f64)>, StatusError> {
+        if self.node_voltages.len() == 0 {
+            return Err(Known("Circuit has not been solved".to_string()));
+        }
+
+        // Node voltages are stored in the same order as the container nodes
+        let mut raw: Vec<(usize, f64)> = vec![(0, 0.0)];
+        for (i, node) in self.container.borrow().nodes().iter().enumerate() {
+            raw.push((node.upgrade().unwrap().borrow().id, self.node_voltages[i]));
+        }
+
+        self.options.reference.apply(raw)
+    }
+
+    fn factored_system(&self) -> Option<(LuFactors, DVector<f64>)> {
+        if self.connection_matrix.is_empty() {
+            return None;
+        }
+        let n: usize = self.connection_matrix.ncols();
+        let voltages: DVector<f64> = self.augmented_connection_matrix().column(n).into_owned();
+        Some((
+            LuFactors::factorize(&self.connection_matrix).ok()?,
+            voltages,
+        ))
+    }
+}
+
 impl NodeStepSolver {
+    /// Solve the circuit without rendering any steps
+    ///
+    /// Returns a stub for every step, `render_step` materializes them on demand.
+    /// Repeated calls return the stubs without solving again.
+    pub fn solve_lazy(&mut self) -> Result<Vec<StepStub>, StatusError> {
+        self.calculate(&NoProgress)
+    }
+
+    fn calculate(&mut self, progress: &dyn ProgressSink) -> Result<Vec<StepStub>, StatusError> {
+        check_generation(&self.container, self.generation)?;
+        if self.node_voltages.len() > 0 {
+            return Ok(Self::stubs());
+        }
+        check_support(SolverType::NodeStep, &self.container)?;
+        check_source_limits(&self.container)?;
+        check_strict(&self.container, &self.options)?;
+        check_auto_transform(&self.container, &self.options)?;
+        check_negative_resistance(&self.container, &self.options)?;
+        self.options.zero_ohm_strategy.validate()?;
+        self.check_causality()?;
+
+        // SETUP and CALCULATIONS
+        progress.report(Phase::MatrixFormation, 0.0);
+        self.setup_connections()?;
+        let symbolic: Result<(), StatusError> = self
+            .setup_node_equations()
+            .and_then(|_| self.setup_node_coefficients());
+        if let Err(error) = symbolic {
+            if let SymbolicLimit(_) = error {
+                let kcl_equations: Step = self.display_base_kcl_equations()?;
+                self.partial_steps = vec![kcl_equations, Self::truncated_step(&error)];
+            }
+            return Err(error);
+        }
+        progress.report(Phase::MatrixFormation, 1.0);
+        progress.report(Phase::Factorization, 0.0);
+        self.solve_node_voltages()?;
+        progress.report(Phase::Factorization, 1.0);
+
+        Ok(Self::stubs())
+    }
+
+    fn stubs() -> Vec<StepStub> {
+        STEPS
+            .iter()
+            .enumerate()
+            .map(|(id, (key, label))| StepStub {
+                key: key.to_string(),
+                label: label.to_string(),
+                id,
+            })
+            .collect()
+    }
+
+    /// Render a single step of a solved circuit
+    ///
+    /// The content is identical to the matching step returned by `solve()`.
+    pub fn render_step(&self, step_id: usize) -> Result<Step, StatusError> {
+        check_generation(&self.container, self.generation)?;
+        if self.node_voltages.len() == 0 {
+            return Err(Known("Circuit has not been solved".to_string()));
+        }
+
+        Ok(match step_id {
+            0 => self.display_base_kcl_equations()?,
+            1 => self.display_connection_matrix()?,
+            2 => self.display_reduced_system(),
+            3 => self.display_solved_matrix()?,
+            4 => self.display_currents()?,
+            5 => self.current_steps()?,
+            _ => return Err(Known(format!("Step {} does not exist", step_id))),
+        })
+    }
+
+    /// Simplifications the user could make to the circuit before solving it by hand
+    pub fn hints(&self) -> Vec<String> {
+        let mut hints: Vec<String> = Vec::new();
+
+        // Two resistors meeting at a node with nothing else attached carry the same current
+        for node in self.container.borrow().nodes() {
+            let node: Rc<RefCell<Tool>> = node.upgrade().unwrap();
+            let members: Vec<Rc<RefCell<Element>>> = node.borrow().clone().into_iter().collect();
+            if members.len() == 2 && members.iter().all(|x| x.borrow().class == Resistor) {
+                let a: String = members[0].borrow().basic_string();
+                let b: String = members[1].borrow().basic_string();
+                hints.push(format!(
+                    "{a} and {b} are in series through Node {}: consider replacing with R_eq = {a} + {b}",
+                    node.borrow().id
+                ));
+            }
+        }
+
+        // Two resistors across the same pair of nodes share a voltage
+        let resistors: Vec<&(usize, usize, Rc<RefCell<Element>>)> = self
+            .node_pairs
+            .iter()
+            .filter(|(_, _, element)| element.borrow().class == Resistor)
+            .collect();
+        for (i, (a1, a2, first)) in resistors.iter().enumerate() {
+            for (b1, b2, second) in resistors.iter().skip(i + 1) {
+                if (a1, a2) == (b1, b2) || (a1, a2) == (b2, b1) {
+                    let a: String = first.borrow().basic_string();
+                    let b: String = second.borrow().basic_string();
+                    hints.push(format!(
+                        "{a} and {b} are in parallel: consider replacing with R_eq = {a}*{b}/({a}+{b})"
+                    ));
+                }
+            }
+        }
+
+        hints
+    }
+
+    /// Supernode constraints ready for display, e.g. `V_1 - V_3 = 10 (due to SRC(V)5)`
+    ///
+    /// One equation per voltage source between two nodes, the same rows the solver adds
+    /// to the connection matrix. Sources to ground set a node voltage on their own and are
+    /// left out. Available before solving.
+    pub fn format_supernode_equations(&self) -> Vec<String> {
+        self.node_pairs
+            .iter()
+            .filter(|(a, b, element)| element.borrow().class == VoltageSrc && *a != 0 && *b != 0)
+            .map(|(a, b, element)| {
+                let element = element.borrow();
+                format!(
+                    "V_{} - V_{} = {} (due to {})",
+                    a,
+                    b,
+                    element.value,
+                    element.basic_string()
+                )
+            })
+            .collect()
+    }
+
+    /// Steps that were generated before the solve was aborted
+    ///
+    /// Only populated when a symbolic expression exceeds `SolveOptions::max_symbolic_terms`,
+    /// the last step marks where the output was truncated.
+    pub fn partial_steps(&self) -> &Vec<Step> {
+        &self.partial_steps
+    }
+
+    fn truncated_step(error: &StatusError) -> Step {
+        Step {
+            title: Some("Truncated".to_string()),
+            description: Some(format!("{}", error)),
+            result: None,
+            sub_steps: vec![],
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        }
+    }
+
+    /// Abort once an expression grows past the configured number of terms
+    fn check_terms(&self, operation: &Operation) -> Result<(), StatusError> {
+        if term_count(operation) > self.options.max_symbolic_terms {
+            return Err(SymbolicLimit(self.options.max_symbolic_terms));
+        }
+        Ok(())
+    }
+
     /// Node Pairs
     fn setup_connections(&mut self) -> Result<(), String> {
         let vec_size: usize = match self
@@ -123,30 +361,12 @@ impl NodeStepSolver {
     }
 
     fn solve_node_voltages(&mut self) -> Result<(), StatusError> {
-        let mut source_voltages: DVector<f64> = DVector::zeros(self.sources.len() + 1);
-
-        self.sources.iter().enumerate().for_each(|(i, x)| {
-            source_voltages.get_mut(i + 1).map(|y| *y = x.voltage);
-        });
-
         // TODO Form matrix from coefficients
-        let n: usize = self.node_coefficients.len();
-        let m: usize = 1 + self.sources.len();
-        self.connection_matrix = DMatrix::zeros(n, m);
-
-        self.node_coefficients
-            .iter()
-            .enumerate()
-            .for_each(|(i, x)| {
-                self.connection_matrix
-                    .get_mut((0, i))
-                    .map(|y| *y = x.value());
-            });
-        self.sources.iter().enumerate().for_each(|(i, x)| {
-            x.matrix.iter().enumerate().for_each(|(j, y)| {
-                self.connection_matrix.get_mut((i + 1, j)).map(|z| *z = *y);
-            });
-        });
+        if self.sources.is_empty() {
+            self.setup_admittance_matrix();
+        } else {
+            self.setup_source_matrix();
+        }
 
         if self.connection_matrix.len() == 0 {
             return Err(Known("No nodes to solve".to_string()));
@@ -175,6 +395,7 @@ impl NodeStepSolver {
         } else {
             inverse = inverse_result.unwrap().unwrap();
         }
+        let source_voltages: DVector<f64> = self.source_vector();
 
         self.inverse = inverse.clone();
         let result_matrix = inverse * source_voltages.clone();
@@ -208,7 +429,92 @@ impl NodeStepSolver {
         Ok(())
     }
 
-    fn setup_node_equations(&mut self) -> Result<(), String> {
+    /// One KCL row followed by a row per voltage source
+    fn setup_source_matrix(&mut self) {
+        let n: usize = self.node_coefficients.len();
+        // When every node is fixed by a voltage source the KCL row is redundant
+        self.kcl_rows = if self.sources.len() == n { 0 } else { 1 };
+        let m: usize = self.kcl_rows + self.sources.len();
+        self.connection_matrix = DMatrix::zeros(n, m);
+
+        if self.kcl_rows == 1 {
+            self.node_coefficients
+                .iter()
+                .enumerate()
+                .for_each(|(i, x)| {
+                    self.connection_matrix
+                        .get_mut((0, i))
+                        .map(|y| *y = x.value());
+                });
+        }
+        self.sources.iter().enumerate().for_each(|(i, x)| {
+            x.matrix.iter().enumerate().for_each(|(j, y)| {
+                self.connection_matrix
+                    .get_mut((i + self.kcl_rows, j))
+                    .map(|z| *z = *y);
+            });
+        });
+    }
+
+    /// Reduced Y-bus of a circuit driven only by current sources
+    ///
+    /// Without voltage sources every node keeps its own KCL row, `Y_red * V = I_inj`, where
+    /// `Y_red` is the admittance matrix with the ground row and column removed and `I_inj`
+    /// the current the sources drive into each node, from the negative into the positive
+    /// terminal as in the matrix solver. Ground has no row and takes no injection.
+    fn setup_admittance_matrix(&mut self) {
+        let n: usize = self.container.borrow().nodes().len();
+        self.kcl_rows = n;
+        self.connection_matrix = DMatrix::zeros(n, n);
+        self.injections = DVector::zeros(n);
+
+        for (node1, node2, element) in self.node_pairs.iter() {
+            let element = element.borrow();
+            match element.class {
+                Resistor => {
+                    let conductance: f64 = 1.0 / element.value();
+                    for (a, b) in [(*node1, *node2), (*node2, *node1)] {
+                        if a == 0 {
+                            continue;
+                        }
+                        self.connection_matrix[(a - 1, a - 1)] += conductance;
+                        if b != 0 {
+                            self.connection_matrix[(a - 1, b - 1)] -= conductance;
+                        }
+                    }
+                }
+                CurrentSrc => {
+                    let (positive, negative) = self
+                        .container
+                        .borrow()
+                        .element_current_direction(element.id);
+                    for (node, sign) in [(positive, 1.0), (negative, -1.0)] {
+                        if node != 0 {
+                            self.injections[node - 1] += sign * element.value();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Right hand side of the connection matrix
+    ///
+    /// The source voltages below the KCL rows, or the injected node currents when the
+    /// circuit has no voltage sources.
+    fn source_vector(&self) -> DVector<f64> {
+        if self.sources.is_empty() {
+            return self.injections.clone();
+        }
+        let mut voltages: DVector<f64> = DVector::zeros(self.kcl_rows + self.sources.len());
+        for (i, source) in self.sources.iter().enumerate() {
+            voltages[i + self.kcl_rows] = source.voltage;
+        }
+        voltages
+    }
+
+    fn setup_node_equations(&mut self) -> Result<(), StatusError> {
         // Form the basic equation for each resistor
         assert_ne!(self.node_pairs.len(), 0);
         self.node_pairs
@@ -263,6 +569,7 @@ impl NodeStepSolver {
 
         self.kcl_operations
             .push(Sum(self.node_combination_steps.clone()));
+        self.check_terms(self.kcl_operations.last().unwrap())?;
 
         // Create nicely readable equation
         self.node_combination_steps = self
@@ -274,20 +581,20 @@ impl NodeStepSolver {
         Ok(())
     }
 
-    fn setup_node_coefficients(&mut self) -> Result<(), String> {
-        // Expand equation
+    fn setup_node_coefficients(&mut self) -> Result<(), StatusError> {
+        // Expand equation, simplifying each term straight away to keep the trees small
         assert_ne!(self.node_combination_steps.len(), 0);
-        let mut combination_steps = self
-            .node_combination_steps
-            .iter()
-            .map(|x| expand(x.clone()).unwrap_or_else(|_| x.clone()))
-            .collect::<Vec<Operation>>();
-        self.kcl_operations.push(Sum(combination_steps.clone()));
-        combination_steps = combination_steps
-            .iter()
-            .map(|x| x.simplify().unwrap_or_else(|| x.clone()))
-            .collect::<Vec<Operation>>();
+        let mut expanded_steps: Vec<Operation> = Vec::new();
+        let mut combination_steps: Vec<Operation> = Vec::new();
+        for step in &self.node_combination_steps {
+            let expanded: Operation = expand(step.clone()).unwrap_or_else(|_| step.clone());
+            self.check_terms(&expanded)?;
+            combination_steps.push(expanded.simplify().unwrap_or_else(|| expanded.clone()));
+            expanded_steps.push(expanded);
+        }
+        self.kcl_operations.push(Sum(expanded_steps));
         let mut sum: Operation = Sum(combination_steps.clone());
+        self.check_terms(&sum)?;
         self.kcl_operations.push(sum.clone());
         sum = sum.simplify().unwrap_or_else(|| sum.clone());
 
@@ -329,6 +636,7 @@ impl NodeStepSolver {
                     ),
                     result: Some(Text(format!("v_{{{},{}}}", node1, node2))),
                     operations: vec![],
+                    forms: None,
                 });
             });
         let node_labels: Vec<String> = self
@@ -342,6 +650,7 @@ impl NodeStepSolver {
             description: Some("Voltage at each node".to_string()),
             result: Some(Text(node_labels.join(", "))),
             operations: vec![],
+            forms: None,
         });
         sub_steps
     }
@@ -354,6 +663,7 @@ impl NodeStepSolver {
         let base_nodes: Vec<Weak<RefCell<Tool>>> = self.container.borrow().get_tools_by_type(Node);
 
         let mut kcl_equations: Vec<Operation> = Vec::new();
+        let mut referenced_elements: Vec<usize> = Vec::new();
         let mut node_count = 0;
         let mut supernode_count = 0;
         for node in nodes.iter() {
@@ -362,6 +672,7 @@ impl NodeStepSolver {
             let cleaned_i: Vec<Operation> = members
                 .iter()
                 .filter(|x| x.borrow().class != VoltageSrc)
+                .inspect(|x| referenced_elements.push(x.borrow().id))
                 .map(|x| {
                     let mut new: Element = (**x).borrow().clone();
                     new.set_name("i".to_string());
@@ -390,6 +701,7 @@ impl NodeStepSolver {
                 .iter()
                 .map(|x| Variable(Rc::new(x.upgrade().unwrap().borrow().clone())))
                 .collect(),
+            forms: None,
         });
 
         steps.push(SubStep {
@@ -399,12 +711,14 @@ impl NodeStepSolver {
                 .iter()
                 .map(|x| Variable(Rc::new(x.upgrade().unwrap().borrow().clone())))
                 .collect(),
+            forms: None,
         });
 
         steps.push(SubStep {
             description: Some("Current entering and exiting each node.".to_string()),
             result: None,
             operations: kcl_equations,
+            forms: None,
         });
 
         let mut i_values: Vec<Operation> = Vec::new();
@@ -446,17 +760,31 @@ impl NodeStepSolver {
             description: Some("Use potential difference between nodes ($ N_{j, k} $) and Ohm's law to solve for current. Where $j, k$ are the two nodes that the element is connected to. We can treat GND as 0.".to_string()),
             result: None,
             operations: i_values,
+            forms: None,
         });
+        if let Some(sub_step) = negative_resistance_sub_step(&self.container) {
+            steps.push(sub_step);
+        }
 
-        Ok(Step {
+        let mut step: Step = Step {
             title: Some("KCL Equations".to_string()),
             description: Some("Outline the basis of the circuit using KCL equations".to_string()),
             result: None,
             sub_steps: steps,
-        })
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        };
+        for element in referenced_elements {
+            step.reference_element(element);
+        }
+        for node in base_nodes.iter().filter_map(|x| x.upgrade()) {
+            step.reference_node(node.borrow().id);
+        }
+        Ok(step)
     }
 
     fn voltage_src_equations(&self) -> Result<Step, String> {
+        let mut references: Vec<(usize, usize, usize)> = Vec::new();
         let mut eq_steps: Vec<SubStep> = Vec::new();
         // Step 2.1.2 Find all voltage sources going between nodes including ground
 
@@ -464,6 +792,7 @@ impl NodeStepSolver {
             .iter()
             .filter(|(_, _, element)| element.borrow().class == VoltageSrc)
             .for_each(|(node1, node2, element)| {
+                references.push((element.borrow().id, *node1, *node2));
                 let mut tool2: Operation = Value(0.0);
                 let mut tool1: Operation = Value(0.0);
                 let mut id_1 = *node1;
@@ -498,22 +827,29 @@ impl NodeStepSolver {
                         Some(Box::new(Variable(Rc::new(element.borrow().clone())))),
                         Some(Box::new(Sum(vec![tool1, tool2]))),
                     )],
+                    forms: None,
                 })
             });
 
-        Ok(Step::new_with_steps(
-            "Find voltage across each voltage source",
-            eq_steps,
-        ))
+        let mut step: Step =
+            Step::new_with_steps("Find voltage across each voltage source", eq_steps);
+        for (element, node1, node2) in references {
+            step.reference_element(element);
+            step.reference_node(node1);
+            step.reference_node(node2);
+        }
+        Ok(step)
     }
 
     fn current_steps(&self) -> Result<Step, String> {
+        let mut references: Vec<(usize, usize, usize)> = Vec::new();
         let mut current_equations: Vec<Operation> = Vec::new();
         let mut element_vector: Vec<Operation> = Vec::new();
         self.node_pairs
             .iter()
             .filter(|(_, _, element)| element.borrow().class == Resistor)
             .for_each(|(node1, node2, element)| {
+                references.push((element.borrow().id, *node1, *node2));
                 let mut i = element.borrow().clone();
                 i.set_name("i".to_string());
                 element_vector.push(Variable(Rc::new(i)));
@@ -537,7 +873,7 @@ impl NodeStepSolver {
                 );
             });
 
-        Ok(Step {
+        let mut step: Step = Step {
             title: Some("Current Results".to_string()),
             description: None,
             result: Some(Equal(
@@ -549,7 +885,223 @@ impl NodeStepSolver {
                 ))))),
             )),
             sub_steps: vec![],
-        })
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        };
+        for (element, node1, node2) in references {
+            step.reference_element(element);
+            step.reference_node(node1);
+            step.reference_node(node2);
+        }
+        Ok(step)
+    }
+
+    /// Gaussian elimination of the connection matrix augmented with the source voltages
+    ///
+    /// One step per row operation, ending in reduced row echelon form with the node
+    /// voltages in the last column. Empty until the solver has been solved.
+    pub fn step_by_step_gaussian_elimination(&self) -> Vec<Step> {
+        if self.connection_matrix.is_empty() {
+            return vec![];
+        }
+        gaussian_elimination(self.augmented_connection_matrix())
+            .into_iter()
+            .map(|(title, description, matrix)| Step {
+                title: Some(title),
+                description: Some(description),
+                result: Some(Display(Rc::new(matrix))),
+                sub_steps: vec![],
+                referenced_elements: vec![],
+                referenced_nodes: vec![],
+            })
+            .collect()
+    }
+
+    /// Solve the circuit and show how the node system is solved by hand
+    ///
+    /// Systems of up to 3 nodes are solved with Cramer's rule, one step for the
+    /// determinant of the connection matrix and one per node voltage. Larger systems
+    /// fall back to `step_by_step_gaussian_elimination`. The trace follows the solve steps.
+    pub fn solve_with_substitution_trace(&mut self) -> Result<Vec<Step>, String> {
+        let mut steps: Vec<Step> = self.solve()?;
+        let n: usize = self.connection_matrix.ncols();
+        if n > CRAMER_LIMIT {
+            steps.append(&mut self.step_by_step_gaussian_elimination());
+            return Ok(steps);
+        }
+
+        let voltages: DVector<f64> = self.augmented_connection_matrix().column(n).into_owned();
+        let (determinant, mut sub_steps) = cofactor_expansion(&self.connection_matrix, "A");
+        // The determinant scales with the conductances, the factors judge the pivots relative
+        if LuFactors::singular_column(&self.connection_matrix).is_some() {
+            return Err("Connection matrix is singular, Cramer's rule does not apply".to_string());
+        }
+        sub_steps.insert(
+            0,
+            SubStep {
+                description: Some("Connection matrix".to_string()),
+                result: None,
+                operations: vec![Display(Rc::new(self.connection_matrix.clone()))],
+                forms: None,
+            },
+        );
+        steps.push(Step {
+            title: Some("Determinant".to_string()),
+            description: Some(
+                "Expand the determinant of the connection matrix A along its first row".to_string(),
+            ),
+            result: Some(Value(determinant)),
+            sub_steps,
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        });
+
+        for i in 0..n {
+            let mut replaced: DMatrix<f64> = self.connection_matrix.clone();
+            replaced.set_column(i, &voltages);
+            let name: String = format!("A_{}", i + 1);
+            let (numerator, mut sub_steps) = cofactor_expansion(&replaced, &name);
+            let voltage: f64 = numerator / determinant;
+            sub_steps.insert(
+                0,
+                SubStep {
+                    description: Some(format!(
+                        "Replace column {} of A with the source voltages",
+                        i + 1
+                    )),
+                    result: None,
+                    operations: vec![Display(Rc::new(replaced))],
+                    forms: None,
+                },
+            );
+            sub_steps.push(SubStep {
+                description: Some(format!(
+                    "V_{} = det({}) / det(A) = {} / {} = {}",
+                    i + 1,
+                    name,
+                    numerator,
+                    determinant,
+                    voltage
+                )),
+                result: Some(Value(voltage)),
+                operations: vec![],
+                forms: None,
+            });
+            steps.push(Step {
+                title: Some(format!("Solve for V_{}", i + 1)),
+                description: Some(format!(
+                    "Cramer's rule, V_{} = det({}) / det(A)",
+                    i + 1,
+                    name
+                )),
+                result: Some(Value(voltage)),
+                sub_steps,
+                referenced_elements: vec![],
+                referenced_nodes: vec![],
+            });
+        }
+        Ok(steps)
+    }
+
+    /// Connection matrix with the source voltages appended as the last column
+    fn augmented_connection_matrix(&self) -> DMatrix<f64> {
+        let columns: usize = self.connection_matrix.ncols();
+        let mut augmented: DMatrix<f64> =
+            self.connection_matrix.clone().insert_column(columns, 0.0);
+        augmented.set_column(columns, &self.source_vector());
+        augmented
+    }
+
+    /// Connection matrix and source voltages with the supernode constraints substituted
+    ///
+    /// A voltage source between two nodes fixes the difference of their voltages, so
+    /// one unknown per supernode is replaced in the other rows and its row is dropped.
+    /// Returns the reduced matrix, the matching source voltages and the substitutions
+    /// as `(eliminated column, remaining column, voltage offset)`.
+    fn reduced_system(&self) -> (DMatrix<f64>, DVector<f64>, Vec<(usize, usize, f64)>) {
+        let mut matrix: DMatrix<f64> = self.connection_matrix.clone();
+        let mut voltages: DVector<f64> = self.source_vector();
+
+        let mut rows: Vec<usize> = (0..matrix.nrows()).collect();
+        let mut columns: Vec<usize> = (0..matrix.ncols()).collect();
+        let mut substitutions: Vec<(usize, usize, f64)> = Vec::new();
+        for (i, source) in self.sources.iter().enumerate() {
+            if source.matrix.iter().filter(|x| **x != 0.0).count() != 2 {
+                continue; // Connected to ground, not a supernode
+            }
+            let row: usize = i + self.kcl_rows;
+            let connected: Vec<usize> = columns
+                .iter()
+                .copied()
+                .filter(|j| matrix[(row, *j)] != 0.0)
+                .collect();
+            if connected.len() != 2 {
+                continue;
+            }
+
+            // s_a * v_a + s_b * v_b = V  =>  v_a = (V - s_b * v_b) / s_a
+            let (a, b) = (connected[0], connected[1]);
+            let (s_a, s_b) = (matrix[(row, a)], matrix[(row, b)]);
+            for r in rows.iter().copied().filter(|r| *r != row) {
+                let coefficient: f64 = matrix[(r, a)];
+                if coefficient == 0.0 {
+                    continue;
+                }
+                matrix[(r, b)] -= coefficient * s_b / s_a;
+                let shift: f64 = coefficient * voltages[row] / s_a;
+                voltages[r] -= shift;
+                matrix[(r, a)] = 0.0;
+            }
+            substitutions.push((a, b, voltages[row] / s_a));
+            rows.retain(|r| *r != row);
+            columns.retain(|j| *j != a);
+        }
+
+        (
+            matrix
+                .select_rows(rows.iter())
+                .select_columns(columns.iter()),
+            voltages.select_rows(rows.iter()),
+            substitutions,
+        )
+    }
+
+    fn display_reduced_system(&self) -> Step {
+        let (matrix, voltages, substitutions) = self.reduced_system();
+
+        let mut sub_steps: Vec<SubStep> = substitutions
+            .iter()
+            .map(|(a, b, offset)| SubStep {
+                description: Some(format!(
+                    "Substitute $N_{{{}}} = N_{{{}}} {} {}$ from the supernode into the other equations",
+                    a + 1,
+                    b + 1,
+                    if *offset < 0.0 { "-" } else { "+" },
+                    offset.abs()
+                )),
+                result: None,
+                operations: vec![],
+                forms: None,
+            })
+            .collect();
+        sub_steps.push(SubStep {
+            description: Some("Reduced matrix and source voltages".to_string()),
+            result: None,
+            operations: vec![Display(Rc::new(matrix.clone())), Display(Rc::new(voltages))],
+            forms: None,
+        });
+
+        Step {
+            title: Some("Reduced System".to_string()),
+            description: Some(
+                "Each supernode fixes the difference between two node voltages, which removes one unknown"
+                    .to_string(),
+            ),
+            result: Some(Display(Rc::new(matrix))),
+            sub_steps,
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        }
     }
 
     fn display_connection_matrix(&self) -> Result<Step, String> {
@@ -576,11 +1128,12 @@ impl NodeStepSolver {
                         ))))),
                     )),
                     operations: vec![],
+                    forms: None,
                 },
                 SubStep {
                     description: Some("Element connections between nodes.".to_string()),
                     result: Some(Display(Rc::new(
-                        self.connection_matrix.clone().remove_rows(0, 1),
+                        self.connection_matrix.clone().remove_rows(0, self.kcl_rows),
                     ))),
                     operations: self
                         .node_pairs
@@ -600,13 +1153,17 @@ impl NodeStepSolver {
                             None
                         })
                         .collect::<Vec<Operation>>(),
+                    forms: None,
                 },
                 SubStep {
                     description: Some("TODO explain this super step".to_string()),
                     result: None,
                     operations: vec![],
+                    forms: None,
                 },
             ],
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
         })
     }
 
@@ -639,6 +1196,7 @@ impl NodeStepSolver {
                         ),
                         Display(Rc::new(self.inverse.clone())),
                     ],
+                    forms: None,
                 },
                 SubStep {
                     description: Some(
@@ -646,8 +1204,11 @@ impl NodeStepSolver {
                     ),
                     result: Some(Display(Rc::new(self.node_voltages.clone()))),
                     operations: vec![Display(Rc::new(self.matrix_evaluation.clone()))],
+                    forms: None,
                 },
             ],
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
         })
     }
 
@@ -668,9 +1229,10 @@ impl NodeStepSolver {
             description: Some("Use potential difference between nodes ($ N_j $) and Ohm's law to solve for current.".to_string()),
             result: None,
             operations: i_values,
+            forms: None,
         });
 
-        Ok(Step {
+        let mut step: Step = Step {
             title: Some("Currents".to_string()),
             description: Some(
                 "Evaluate the currents using the KCL equations and node voltages shown previously."
@@ -678,17 +1240,184 @@ impl NodeStepSolver {
             ),
             result: None,
             sub_steps: steps,
-        })
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        };
+        self.current_values
+            .iter()
+            .for_each(|(id, _)| step.reference_element(*id));
+        Ok(step)
+    }
+}
+
+/// Number of nodes in an operation tree
+fn term_count(operation: &Operation) -> usize {
+    match operation {
+        Sum(list) | Multiply(list) => 1 + list.iter().map(term_count).sum::<usize>(),
+        Divide(a, b) | Power(a, b) | Equal(a, b) => {
+            1 + a.as_deref().map_or(0, term_count) + b.as_deref().map_or(0, term_count)
+        }
+        Negate(a) => 1 + a.as_deref().map_or(0, term_count),
+        _ => 1,
     }
 }
 
+/// Determinant by cofactor expansion along the first row
+///
+/// Returns the determinant and the working, the minors first. A 2x2 determinant is
+/// written out as `a*d - b*c`.
+fn cofactor_expansion(matrix: &DMatrix<f64>, name: &str) -> (f64, Vec<SubStep>) {
+    let working = |description: String, value: f64| SubStep {
+        description: Some(description),
+        result: Some(Value(value)),
+        operations: vec![],
+        forms: None,
+    };
+    let m = |i: usize, j: usize| matrix[(i, j)];
+
+    match matrix.nrows() {
+        0 => (1.0, vec![]),
+        1 => (
+            m(0, 0),
+            vec![working(format!("det({}) = {}", name, m(0, 0)), m(0, 0))],
+        ),
+        2 => {
+            let value: f64 = m(0, 0) * m(1, 1) - m(0, 1) * m(1, 0);
+            let description: String = format!(
+                "det({}) = {}*{} - {}*{} = {}",
+                name,
+                m(0, 0),
+                m(1, 1),
+                m(0, 1),
+                m(1, 0),
+                value
+            );
+            (value, vec![working(description, value)])
+        }
+        n => {
+            let mut sub_steps: Vec<SubStep> = Vec::new();
+            let mut symbolic: Vec<String> = Vec::new();
+            let mut numeric: Vec<String> = Vec::new();
+            let mut value: f64 = 0.0;
+            for j in 0..n {
+                let minor_name: String = format!("{}_M_1{}", name, j + 1);
+                let minor: DMatrix<f64> = matrix.clone().remove_row(0).remove_column(j);
+                let (minor_value, mut minor_steps) = cofactor_expansion(&minor, &minor_name);
+                sub_steps.append(&mut minor_steps);
+
+                let sign: &str = match (j % 2 == 1, j == 0) {
+                    (true, _) => " - ",
+                    (false, true) => "",
+                    (false, false) => " + ",
+                };
+                symbolic.push(format!(
+                    "{}{}[1][{}]*det({})",
+                    sign,
+                    name,
+                    j + 1,
+                    minor_name
+                ));
+                numeric.push(format!("{}{}*{}", sign, m(0, j), minor_value));
+                value += if j % 2 == 1 { -1.0 } else { 1.0 } * m(0, j) * minor_value;
+            }
+            let description: String = format!(
+                "det({}) = {} = {} = {}",
+                name,
+                symbolic.concat(),
+                numeric.concat(),
+                value
+            );
+            sub_steps.push(working(description, value));
+            (value, sub_steps)
+        }
+    }
+}
+
+/// Row operations taking an augmented matrix to reduced row echelon form
+///
+/// Returns `(title, description, matrix after the operation)` for every operation. Rows
+/// are numbered from 1 as in a hand elimination and only swapped for a zero pivot.
+fn gaussian_elimination(mut matrix: DMatrix<f64>) -> Vec<(String, String, DMatrix<f64>)> {
+    let mut steps: Vec<(String, String, DMatrix<f64>)> = Vec::new();
+    let (rows, columns) = matrix.shape();
+    let mut pivot_row: usize = 0;
+    // Entries this small are left over from rounding and not used as pivots
+    let tolerance: f64 = f64::EPSILON * columns as f64 * matrix.amax();
+    // The last column holds the right hand side
+    for column in 0..columns.saturating_sub(1) {
+        if pivot_row == rows {
+            break;
+        }
+        let found: usize = match (pivot_row..rows).find(|r| matrix[(*r, column)].abs() > tolerance)
+        {
+            Some(row) => row,
+            None => continue,
+        };
+        if found != pivot_row {
+            matrix.swap_rows(found, pivot_row);
+            steps.push((
+                format!("Swap R_{} and R_{}", pivot_row + 1, found + 1),
+                format!("R_{} has no pivot in column {}", pivot_row + 1, column + 1),
+                matrix.clone(),
+            ));
+        }
+
+        let pivot: f64 = matrix[(pivot_row, column)];
+        if pivot != 1.0 {
+            for j in 0..columns {
+                matrix[(pivot_row, j)] /= pivot;
+            }
+            steps.push((
+                format!("Scale R_{} by 1 / {}", pivot_row + 1, pivot),
+                format!("The pivot of R_{} becomes 1", pivot_row + 1),
+                matrix.clone(),
+            ));
+        }
+
+        for row in (0..rows).filter(|r| *r != pivot_row) {
+            let coefficient: f64 = matrix[(row, column)];
+            if coefficient == 0.0 {
+                continue;
+            }
+            for j in 0..columns {
+                let shift: f64 = coefficient * matrix[(pivot_row, j)];
+                matrix[(row, j)] -= shift;
+            }
+            steps.push((
+                format!("Eliminate R_{} using pivot R_{}", row + 1, pivot_row + 1),
+                format!(
+                    "R_{} = R_{} {} {} R_{}",
+                    row + 1,
+                    row + 1,
+                    if coefficient < 0.0 { "+" } else { "-" },
+                    coefficient.abs(),
+                    pivot_row + 1
+                ),
+                matrix.clone(),
+            ));
+        }
+        pivot_row += 1;
+    }
+    steps
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::component::Component::{CurrentSrc, Ground, Resistor, VoltageSrc};
     use crate::container::Container;
-    use crate::solvers::node_step_solver::NodeStepSolver;
-    use crate::solvers::solver::Solver;
-    use crate::util::create_mna_container;
-    use nalgebra::DVector;
+    use crate::elements::Element;
+    use crate::solvers::node_matrix_solver::NodeMatrixSolver;
+    use crate::solvers::node_step_solver::{gaussian_elimination, NodeStepSolver, STEPS};
+    use crate::solvers::options::SolveOptions;
+    use crate::solvers::solver::{NodeSolver, Solver, Step};
+    use crate::tools::ToolType::SuperNode;
+    use crate::util::{
+        create_basic_container, create_basic_supernode_container, create_ladder_container,
+        create_mna_container,
+    };
+    use crate::validation::StatusError;
+    use crate::validation::StatusError::SymbolicLimit;
+    use nalgebra::{DMatrix, DVector};
     use operations::math::EquationMember;
     use std::cell::RefCell;
     use std::rc::Rc;
@@ -730,12 +1459,348 @@ mod tests {
         );
     }
 
-    fn setup_mna_solver() -> NodeStepSolver {
-        let mut c: Container = create_mna_container();
+    #[test]
+    fn test_symbolic_limit() {
+        let mut c: Container = create_ladder_container();
         c.create_nodes().unwrap();
-        c.create_super_nodes();
+        assert_eq!(c.nodes().len(), 5);
+        let options = SolveOptions {
+            max_symbolic_terms: 10,
+            ..Default::default()
+        };
+        let mut solver: NodeStepSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+
+        assert_eq!(solver.solve().err(), Some(SymbolicLimit(10)));
+        let partial = solver.partial_steps();
+        assert_eq!(partial.len(), 2);
+        assert_eq!(partial[0].title, Some("KCL Equations".to_string()));
+        assert_eq!(partial[1].title, Some("Truncated".to_string()));
+        assert_eq!(
+            partial[1].description,
+            Some("Symbolic expression exceeded 10 terms, switch to numeric mode".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lazy_steps() {
+        let eager: Vec<Step> = new_mna_solver().solve().unwrap();
+        let mut solver: NodeStepSolver = new_mna_solver();
+        assert!(solver.render_step(0).is_err());
+
+        let stubs = solver.solve_lazy().unwrap();
+        assert_eq!(stubs.len(), eager.len());
+        for (stub, step) in stubs.iter().zip(eager.iter()) {
+            assert_eq!(Some(stub.label.clone()), step.title);
+            assert_eq!(
+                serde_json::to_string(&solver.render_step(stub.id).unwrap()).unwrap(),
+                serde_json::to_string(step).unwrap()
+            );
+        }
+        assert!(solver.render_step(stubs.len()).is_err());
+    }
+
+    #[test]
+    fn test_hints() {
+        assert_eq!(new_mna_solver().hints(), Vec::<String>::new());
+
+        let mut c: Container = Container::new();
+        c.add_element_no_id(Element::new(Ground, 0., vec![1, 3], vec![]));
+        c.add_element_no_id(Element::new(VoltageSrc, 10., vec![2], vec![0]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![1], vec![3]));
+        c.add_element_no_id(Element::new(Resistor, 2., vec![2], vec![0]));
+        c.create_nodes().unwrap();
+        let solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert_eq!(
+            solver.hints(),
+            vec!["R2 and R3 are in series through Node 2: consider replacing with R_eq = R2 + R3"]
+        );
+    }
+
+    #[test]
+    fn test_single_resistor() {
+        let mut c: Container = Container::new();
+        c.add_element_no_id(Element::new(Ground, 0., vec![1, 2], vec![]));
+        c.add_element_no_id(Element::new(VoltageSrc, 5., vec![2], vec![0]));
+        c.add_element_no_id(Element::new(Resistor, 10., vec![1], vec![0]));
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        assert_eq!(solver.node_voltages, DVector::from_vec(vec![5.0]));
+    }
+
+    #[test]
+    fn test_current_sources_only() {
+        // 1 A into node 1 and 2 A into node 3, joined through node 2
+        let mut c: Container = Container::new();
+        c.add_element_between_nodes(CurrentSrc, 1., 1, 0);
+        c.add_element_between_nodes(CurrentSrc, 2., 3, 0);
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(Resistor, 2., 1, 2);
+        c.add_element_between_nodes(Resistor, 2., 2, 3);
+        c.add_element_between_nodes(Resistor, 1., 3, 0);
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let container: Rc<RefCell<Container>> = Rc::new(RefCell::new(c));
+
+        let mut solver: NodeStepSolver = Solver::new(container.clone());
+        assert_eq!(solver.solve().unwrap().len(), STEPS.len());
+        assert_eq!(
+            solver.connection_matrix,
+            DMatrix::from_row_slice(3, 3, &[1.5, -0.5, 0., -0.5, 1., -0.5, 0., -0.5, 1.5])
+        );
+        assert_eq!(solver.injections, DVector::from_vec(vec![1., 0., 2.]));
+
+        let mut matrix: NodeMatrixSolver = Solver::new(container);
+        matrix.solve().unwrap();
+        for (node, expected) in [(1, 7. / 6.), (2, 1.5), (3, 11. / 6.)] {
+            assert!((solver.node_voltage(node).unwrap() - expected).abs() < 1e-9);
+            assert!((matrix.node_voltage(node).unwrap() - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_reduced_system() {
+        let solver: NodeStepSolver = setup_mna_solver();
+        let supernodes: usize = solver.container.borrow().get_tools(SuperNode).len();
+        let n: usize = solver.container.borrow().nodes().len();
+        let (matrix, voltages, substitutions) = solver.reduced_system();
+        assert_eq!(supernodes, 1);
+        assert_eq!(matrix.shape(), (n - supernodes, n - supernodes));
+        assert_eq!(voltages.len(), n - supernodes);
+        assert_eq!(substitutions, vec![(1, 2, 32.0)]);
+
+        // The reduced system has the same solution for the remaining nodes
+        let remaining = matrix.try_inverse().unwrap() * voltages;
+        assert!((remaining[0] - 20.0).abs() < 1e-9);
+        assert!((remaining[1] + 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_format_supernode_equations() {
+        let mut c: Container = create_basic_supernode_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        // SRC(V)5 is grounded and sets Node 3 on its own
+        assert_eq!(
+            solver.format_supernode_equations(),
+            vec!["V_1 - V_2 = 10 (due to SRC(V)1)"]
+        );
+
+        // Matches the substitution of the reduced system
+        assert_eq!(
+            new_mna_solver().format_supernode_equations(),
+            vec!["V_2 - V_3 = 32 (due to SRC(V)4)"]
+        );
+    }
+
+    #[test]
+    fn test_gaussian_elimination() {
+        let a = DMatrix::from_row_slice(
+            3,
+            4,
+            &[2., 1., -1., 8., -3., -1., 2., -11., -2., 1., 2., -3.],
+        );
+        let steps = gaussian_elimination(a);
+        // One scale and two eliminations per column
+        assert_eq!(steps.len(), 9);
+        assert_eq!(steps[1].0, "Eliminate R_2 using pivot R_1");
+        assert_eq!(steps[1].1, "R_2 = R_2 + 3 R_1");
+        let expected =
+            DMatrix::from_row_slice(3, 4, &[1., 0., 0., 2., 0., 1., 0., 3., 0., 0., 1., -1.]);
+        assert_eq!(steps[8].2, expected);
+
+        // A zero pivot is swapped with the first row below that has one
+        let steps = gaussian_elimination(DMatrix::from_row_slice(2, 3, &[0., 1., 2., 1., 0., 3.]));
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].0, "Swap R_1 and R_2");
+
+        let solver: NodeStepSolver = setup_mna_solver();
+        let steps: Vec<Step> = solver.step_by_step_gaussian_elimination();
+        assert!(!steps.is_empty());
+        assert!(new_mna_solver()
+            .step_by_step_gaussian_elimination()
+            .is_empty());
+
+        // The last column of the reduced matrix solves the connection matrix
+        let augmented: DMatrix<f64> = solver.augmented_connection_matrix();
+        let reduced: DMatrix<f64> = gaussian_elimination(augmented.clone()).pop().unwrap().2;
+        let n: usize = solver.connection_matrix.nrows();
+        assert!((reduced.columns(0, n) - DMatrix::<f64>::identity(n, n)).norm() < 1e-12);
+        let residual = &solver.connection_matrix * reduced.column(n) - augmented.column(n);
+        assert!(residual.norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_back_substitution_trace() {
+        assert!(new_mna_solver().back_substitution_trace().is_empty());
+        let solver: NodeStepSolver = setup_mna_solver();
+        let steps: Vec<Step> = solver.back_substitution_trace();
+        let n: usize = solver.connection_matrix.nrows();
+        assert_eq!(steps.len(), n);
+        assert_eq!(steps[0].title(), Some(format!("From row {}", n)));
+
+        // The values solve the connection matrix, the last row is solved first
+        let x: DVector<f64> = DVector::from_iterator(
+            n,
+            steps
+                .iter()
+                .rev()
+                .map(|x| x.result.as_ref().unwrap().value()),
+        );
+        let voltages = solver.augmented_connection_matrix().column(n).into_owned();
+        assert!((&solver.connection_matrix * x - voltages).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_substitution_trace_two_nodes() {
+        let mut c: Container = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        c.add_element_between_nodes(Resistor, 2., 1, 2);
+        c.add_element_between_nodes(Resistor, 3., 2, 0);
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        let steps: Vec<Step> = solver.solve_with_substitution_trace().unwrap();
+        let trace: &[Step] = &steps[STEPS.len()..];
+        assert_eq!(trace.len(), 3);
+        assert_eq!(trace[0].title(), Some("Determinant".to_string()));
+        let a: &DMatrix<f64> = &solver.connection_matrix;
+        let determinant: f64 = trace[0].result.as_ref().unwrap().value();
+        assert!((determinant - a.determinant()).abs() < 1e-12);
+        assert_eq!(
+            trace[0].sub_steps[1].description,
+            Some(format!(
+                "det(A) = {}*{} - {}*{} = {}",
+                a[(0, 0)],
+                a[(1, 1)],
+                a[(0, 1)],
+                a[(1, 0)],
+                determinant
+            ))
+        );
+
+        for (i, step) in trace[1..].iter().enumerate() {
+            assert_eq!(step.title(), Some(format!("Solve for V_{}", i + 1)));
+            let voltage: f64 = step.result.as_ref().unwrap().value();
+            assert!((voltage - solver.node_voltages[i]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_substitution_trace_megaohm() {
+        // The determinant is about 1e-18, far below f64::EPSILON, yet the matrix is regular
+        let mut c: Container = Container::new();
+        c.add_element_between_nodes(CurrentSrc, 1e-6, 1, 0);
+        c.add_element_between_nodes(Resistor, 1e6, 1, 2);
+        c.add_element_between_nodes(Resistor, 1e6, 2, 3);
+        c.add_element_between_nodes(Resistor, 1e6, 3, 0);
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        let steps: Vec<Step> = solver.solve_with_substitution_trace().unwrap();
+        let trace: &[Step] = &steps[STEPS.len()..];
+        assert!(trace[0].result.as_ref().unwrap().value().abs() < f64::EPSILON);
+        for (step, expected) in trace[1..].iter().zip([3., 2., 1.]) {
+            assert!((step.result.as_ref().unwrap().value() - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_substitution_trace_three_nodes() {
+        let mut solver: NodeStepSolver = new_mna_solver();
+        let steps: Vec<Step> = solver.solve_with_substitution_trace().unwrap();
+        let trace: &[Step] = &steps[STEPS.len()..];
+        assert_eq!(trace.len(), 4);
+
+        // Three 2x2 minors followed by the expansion along the first row
+        let expansion = &trace[0].sub_steps;
+        assert_eq!(expansion.len(), 5);
+        assert!(expansion[1]
+            .description
+            .as_ref()
+            .unwrap()
+            .starts_with("det(A_M_11) = "));
+        assert!(expansion[4].description.as_ref().unwrap().starts_with(
+            "det(A) = A[1][1]*det(A_M_11) - A[1][2]*det(A_M_12) + A[1][3]*det(A_M_13) = "
+        ));
+        let determinant: f64 = trace[0].result.as_ref().unwrap().value();
+        assert!((determinant - solver.connection_matrix.determinant()).abs() < 1e-9);
+
+        let voltages: Vec<f64> = trace[1..]
+            .iter()
+            .map(|x| x.result.as_ref().unwrap().value())
+            .collect();
+        for (voltage, expected) in voltages.iter().zip([20.0, 24.0, -8.0]) {
+            assert!((voltage - expected).abs() < 1e-9);
+        }
+
+        // Larger systems show the Gaussian elimination instead
+        let mut c: Container = create_ladder_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
         let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        let steps: Vec<Step> = solver.solve_with_substitution_trace().unwrap();
+        let elimination: Vec<Step> = solver.step_by_step_gaussian_elimination();
+        assert!(!elimination.is_empty());
+        assert_eq!(steps.len(), STEPS.len() + elimination.len());
+        assert_eq!(
+            steps.last().unwrap().title,
+            elimination.last().unwrap().title
+        );
+    }
+
+    #[test]
+    fn test_solve_twice() {
+        let mut solver: NodeStepSolver = new_mna_solver();
+        let first = serde_json::to_string(&solver.solve().unwrap()).unwrap();
+        let sources: usize = solver.sources.len();
+        let second = serde_json::to_string(&solver.solve().unwrap()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(solver.sources.len(), sources);
+        assert_eq!(solver.solve_lazy().unwrap().len(), STEPS.len());
+
+        solver
+            .container
+            .borrow_mut()
+            .add_element_no_id(Element::new(Resistor, 1., vec![1], vec![0]));
+        assert_eq!(solver.solve().err(), Some(StatusError::StaleSolver));
+        assert_eq!(solver.render_step(0).err(), Some(StatusError::StaleSolver));
+    }
+
+    #[test]
+    fn test_referenced_elements() {
+        let mut c: Container = create_basic_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let node: Vec<usize> = c.node_by_id(2).borrow().member_ids();
+        let solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        // Node 2 joins R1 and R2, the only elements with a current in the KCL equations
+        let step: Step = solver.display_base_kcl_equations().unwrap();
+        let mut expected: Vec<usize> = node;
+        expected.sort();
+        assert_eq!(step.referenced_elements, expected);
+        assert_eq!(step.referenced_nodes, vec![1, 2]);
+
+        let json: String = serde_json::to_string(&step).unwrap();
+        assert!(json.contains(r#""referenced_elements":[1,2]"#));
+    }
+
+    fn setup_mna_solver() -> NodeStepSolver {
+        let mut solver: NodeStepSolver = new_mna_solver();
         solver.solve().expect("Unable to solve");
         solver
     }
+
+    fn new_mna_solver() -> NodeStepSolver {
+        let mut c: Container = create_mna_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes();
+        Solver::new(Rc::new(RefCell::new(c)))
+    }
 }
@@ -0,0 +1,194 @@
+use crate::validation::StatusError;
+use crate::validation::StatusError::Known;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Options shared by all solvers
+///
+/// Every field defaults to the historical behaviour so `SolveOptions::default()`
+/// can be used whenever the caller does not care.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolveOptions {
+    /// Point that reported node voltages are measured against.
+    pub reference: Reference,
+    /// Largest symbolic expression, counted in operation tree nodes, a step solver may build.
+    pub max_symbolic_terms: usize,
+    /// Which forms of a step are rendered, both are always serialized when available.
+    pub forms: FormDisplay,
+    /// Order of the node voltage unknowns in the matrix solvers.
+    pub row_order: RowOrder,
+    /// Abort the solve with `StrictModeViolation` when any warning was produced.
+    pub strict: bool,
+    /// Eliminate in the natural row order instead of swapping in the largest pivot.
+    pub no_pivoting: bool,
+    /// Model used for zero resistance branches such as wires and ammeters.
+    pub zero_ohm_strategy: ZeroOhmStrategy,
+    /// Sum in element id order with compensation, so every target produces bit identical
+    /// numbers. The matrix solvers always invert through `LuFactors`.
+    pub deterministic_fp: bool,
+    /// Let the solvers rewrite the circuit, e.g. prune self loops or replace wires. When
+    /// disabled a circuit that needs a transformation fails to solve instead.
+    pub allow_auto_transform: bool,
+    /// Accept resistors with a negative value, e.g. from small-signal models of active
+    /// devices. They deliver power, so the circuit is rejected unless this is set.
+    pub allow_negative_resistance: bool,
+    /// Accept inductors and solve them as short circuits. The solvers only analyse DC,
+    /// where an inductor has no voltage across it, so the circuit is rejected unless this is set.
+    pub allow_dc_inductors: bool,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        SolveOptions {
+            reference: Reference::default(),
+            max_symbolic_terms: 10_000,
+            forms: FormDisplay::default(),
+            row_order: RowOrder::default(),
+            strict: false,
+            no_pivoting: false,
+            zero_ohm_strategy: ZeroOhmStrategy::default(),
+            deterministic_fp: false,
+            allow_auto_transform: true,
+            allow_negative_resistance: false,
+            allow_dc_inductors: false,
+        }
+    }
+}
+
+/// Forms of a sub step rendered as operations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum FormDisplay {
+    /// Symbolic form first, then the numbers substituted.
+    #[default]
+    Both,
+    Symbolic,
+    Numeric,
+}
+
+/// Mapping of node voltages onto matrix rows
+///
+/// The order is applied to G, B, C, z and x alike, so the two orders produce
+/// permutations of the same system with identical solutions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RowOrder {
+    /// Lowest node id in the first row, as most textbooks write it.
+    Ascending,
+    /// Highest node id in the first row.
+    #[default]
+    Descending,
+}
+
+impl RowOrder {
+    /// Row of the `index`th of `n` nodes sorted by id.
+    pub(crate) fn row(&self, index: usize, n: usize) -> usize {
+        match self {
+            RowOrder::Ascending => index,
+            RowOrder::Descending => n - index - 1,
+        }
+    }
+}
+
+/// Treatment of resistors with a value of exactly 0 Ω
+///
+/// The strategy is applied to the container before its nodes are created, every
+/// solver therefore sees the same circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ZeroOhmStrategy {
+    /// Join the nodes at both ends, the current through the branch is lost.
+    Merge,
+    /// Replace the branch by a 0 V source, the matrix grows by one row but the
+    /// branch current is solved for like any source current.
+    #[default]
+    SenseBranch,
+    /// Replace the branch by a small resistance, which may leave the system ill conditioned.
+    Epsilon(f64),
+}
+
+impl ZeroOhmStrategy {
+    pub(crate) fn validate(&self) -> Result<(), StatusError> {
+        match self {
+            ZeroOhmStrategy::Epsilon(resistance) if *resistance <= 0.0 => Err(Known(format!(
+                "Epsilon resistance must be positive, got {}",
+                resistance
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Voltage reference used when reporting node voltages
+///
+/// The reference only shifts the reported voltages. The difference between
+/// any two nodes is identical for every choice.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Reference {
+    /// Use the ground element of the circuit.
+    #[default]
+    Auto,
+    /// Report voltages relative to the node with the given id.
+    Node(usize),
+    /// Shift voltages so that the midpoint of the two nodes sits at 0 V.
+    MidpointOf(usize, usize),
+}
+
+impl Reference {
+    /// Offset subtracted from every ground referenced node voltage.
+    ///
+    /// `raw` contains `(node id, voltage)` pairs, ground included as id 0.
+    pub(crate) fn offset(&self, raw: &[(usize, f64)]) -> Result<f64, StatusError> {
+        let lookup = |id: usize| -> Result<f64, StatusError> {
+            raw.iter()
+                .find(|(node, _)| *node == id)
+                .map(|(_, voltage)| *voltage)
+                .ok_or_else(|| Known(format!("Reference node {} does not exist", id)))
+        };
+
+        match self {
+            Reference::Auto => Ok(0.0),
+            Reference::Node(id) => lookup(*id),
+            Reference::MidpointOf(a, b) => Ok((lookup(*a)? + lookup(*b)?) / 2.0),
+        }
+    }
+
+    /// Shift ground referenced node voltages onto this reference.
+    pub(crate) fn apply(&self, raw: Vec<(usize, f64)>) -> Result<Vec<(usize, f64)>, StatusError> {
+        let offset: f64 = self.offset(&raw)?;
+        Ok(raw
+            .into_iter()
+            .map(|(id, voltage)| (id, voltage - offset))
+            .collect())
+    }
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reference::Auto => write!(f, "Ground"),
+            Reference::Node(id) => write!(f, "Node {}", id),
+            Reference::MidpointOf(a, b) => write!(f, "the midpoint of Node {} and Node {}", a, b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_offset() {
+        let raw = vec![(0, 0.0), (1, 20.0), (2, 24.0), (3, -8.0)];
+        assert_eq!(Reference::Auto.offset(&raw), Ok(0.0));
+        assert_eq!(Reference::Node(2).offset(&raw), Ok(24.0));
+        assert_eq!(Reference::MidpointOf(1, 3).offset(&raw), Ok(6.0));
+        assert!(Reference::Node(7).offset(&raw).is_err());
+    }
+
+    #[test]
+    fn test_row_order() {
+        assert_eq!(RowOrder::default(), RowOrder::Descending);
+        assert_eq!(RowOrder::Ascending.row(0, 3), 0);
+        assert_eq!(RowOrder::Descending.row(0, 3), 2);
+        assert_eq!(RowOrder::Descending.row(2, 3), 0);
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+/// Phases of a solve that are reported to a `ProgressSink`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Phase {
+    Validation,
+    NodeCreation,
+    MatrixFormation,
+    Factorization,
+    /// Iteration `k` of an iterative solver.
+    Iteration(usize),
+    Rendering,
+}
+
+/// Receives progress from long running operations
+///
+/// Every phase is reported with a fraction of 0.0 when it starts and 1.0 when it ends.
+/// Phases that do repeated work also report the fractions in between.
+pub trait ProgressSink {
+    fn report(&self, phase: Phase, fraction: f32);
+}
+
+impl<F: Fn(Phase, f32)> ProgressSink for F {
+    fn report(&self, phase: Phase, fraction: f32) {
+        self(phase, fraction)
+    }
+}
+
+/// Sink that discards all progress
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _phase: Phase, _fraction: f32) {}
+}
@@ -0,0 +1,359 @@
+use operations::math::EquationMember;
+use operations::operations::Operation;
+use operations::prelude::{Divide, Equal, Multiply, Negate, Power, Sum, Value};
+
+/// A single rewrite, `None` when the rule does not apply to the operation
+pub type RewriteRule = fn(&Operation) -> Option<Operation>;
+
+/// Passes after which a rule set that keeps rewriting is considered cyclic.
+const MAX_PASSES: usize = 64;
+
+/// Term rewriting over operation trees
+///
+/// Every pass rewrites the children before their parent, applying the first
+/// rule that matches at each node. Passes are repeated until none of the rules
+/// match anywhere in the tree.
+pub struct OperationSimplifier {
+    pub rules: Vec<RewriteRule>,
+}
+
+impl Default for OperationSimplifier {
+    fn default() -> Self {
+        OperationSimplifier {
+            rules: vec![
+                identity_elements,
+                double_negation,
+                constant_folding,
+                fraction_cancellation,
+            ],
+        }
+    }
+}
+
+impl OperationSimplifier {
+    pub fn new(rules: Vec<RewriteRule>) -> Self {
+        OperationSimplifier { rules }
+    }
+
+    /// Rewrite until a fixed point is reached.
+    pub fn simplify(&self, operation: Operation) -> Operation {
+        let mut operation: Operation = operation;
+        for _ in 0..MAX_PASSES {
+            let (next, changed) = self.pass(operation);
+            operation = next;
+            if !changed {
+                break;
+            }
+        }
+        operation
+    }
+
+    fn pass(&self, operation: Operation) -> (Operation, bool) {
+        let mut changed: bool = false;
+        let operation: Operation = match operation {
+            Sum(list) => Sum(self.pass_all(list, &mut changed)),
+            Multiply(list) => Multiply(self.pass_all(list, &mut changed)),
+            Divide(a, b) => Divide(
+                self.pass_boxed(a, &mut changed),
+                self.pass_boxed(b, &mut changed),
+            ),
+            Power(a, b) => Power(
+                self.pass_boxed(a, &mut changed),
+                self.pass_boxed(b, &mut changed),
+            ),
+            Equal(a, b) => Equal(
+                self.pass_boxed(a, &mut changed),
+                self.pass_boxed(b, &mut changed),
+            ),
+            Negate(a) => Negate(self.pass_boxed(a, &mut changed)),
+            other => other,
+        };
+
+        match self.rules.iter().find_map(|rule| rule(&operation)) {
+            Some(rewritten) => (rewritten, true),
+            None => (operation, changed),
+        }
+    }
+
+    fn pass_boxed(
+        &self,
+        operation: Option<Box<Operation>>,
+        changed: &mut bool,
+    ) -> Option<Box<Operation>> {
+        operation.map(|x| {
+            let (x, rewritten) = self.pass(*x);
+            *changed |= rewritten;
+            Box::new(x)
+        })
+    }
+
+    fn pass_all(&self, list: Vec<Operation>, changed: &mut bool) -> Vec<Operation> {
+        list.into_iter()
+            .map(|x| {
+                let (x, rewritten) = self.pass(x);
+                *changed |= rewritten;
+                x
+            })
+            .collect()
+    }
+}
+
+fn constant(operation: &Operation) -> Option<f64> {
+    match operation {
+        Value(x) => Some(*x),
+        _ => None,
+    }
+}
+
+fn boxed_constant(operation: &Option<Box<Operation>>) -> Option<f64> {
+    operation.as_deref().and_then(constant)
+}
+
+/// Collapse a term list, an empty list becomes `empty`.
+fn collapse(
+    mut list: Vec<Operation>,
+    empty: f64,
+    join: fn(Vec<Operation>) -> Operation,
+) -> Operation {
+    match list.len() {
+        0 => Value(empty),
+        1 => list.remove(0),
+        _ => join(list),
+    }
+}
+
+/// `x + 0`, `x * 1`, `x * 0`, `x / 1`, `x ^ 1` and `x ^ 0`, single term sums and products.
+pub fn identity_elements(operation: &Operation) -> Option<Operation> {
+    match operation {
+        Sum(list) => {
+            let kept: Vec<Operation> = list
+                .iter()
+                .filter(|x| constant(x) != Some(0.0))
+                .cloned()
+                .collect();
+            match kept.len() == list.len() && list.len() > 1 {
+                true => None,
+                false => Some(collapse(kept, 0.0, Sum)),
+            }
+        }
+        Multiply(list) => {
+            if list.iter().any(|x| constant(x) == Some(0.0)) {
+                return Some(Value(0.0));
+            }
+            let kept: Vec<Operation> = list
+                .iter()
+                .filter(|x| constant(x) != Some(1.0))
+                .cloned()
+                .collect();
+            match kept.len() == list.len() && list.len() > 1 {
+                true => None,
+                false => Some(collapse(kept, 1.0, Multiply)),
+            }
+        }
+        Divide(Some(a), b) if boxed_constant(b) == Some(1.0) => Some(*a.clone()),
+        Power(Some(a), b) if boxed_constant(b) == Some(1.0) => Some(*a.clone()),
+        Power(Some(_), b) if boxed_constant(b) == Some(0.0) => Some(Value(1.0)),
+        _ => None,
+    }
+}
+
+/// `-(-x)` to `x`.
+pub fn double_negation(operation: &Operation) -> Option<Operation> {
+    match operation {
+        Negate(Some(inner)) => match inner.as_ref() {
+            Negate(Some(x)) => Some(*x.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Evaluate operations whose operands are all numbers.
+///
+/// Sums and products fold their numeric terms into one and keep the rest.
+/// Division by zero is left untouched.
+pub fn constant_folding(operation: &Operation) -> Option<Operation> {
+    let fold = |list: &Vec<Operation>,
+                init: f64,
+                op: fn(f64, f64) -> f64,
+                join: fn(Vec<Operation>) -> Operation| {
+        let (numbers, rest): (Vec<&Operation>, Vec<&Operation>) =
+            list.iter().partition(|x| constant(x).is_some());
+        if numbers.len() < 2 {
+            return None;
+        }
+        let folded: f64 = numbers.iter().filter_map(|x| constant(x)).fold(init, op);
+        let mut terms: Vec<Operation> = vec![Value(folded)];
+        terms.extend(rest.into_iter().cloned());
+        Some(collapse(terms, init, join))
+    };
+
+    match operation {
+        Sum(list) => fold(list, 0.0, |a, b| a + b, Sum),
+        Multiply(list) => fold(list, 1.0, |a, b| a * b, Multiply),
+        Negate(a) => boxed_constant(a).map(|a| Value(-a)),
+        Divide(a, b) => match (boxed_constant(a), boxed_constant(b)) {
+            (Some(a), Some(b)) if b != 0.0 => Some(Value(a / b)),
+            _ => None,
+        },
+        Power(a, b) => match (boxed_constant(a), boxed_constant(b)) {
+            (Some(a), Some(b)) => Some(Value(a.powf(b))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Cancel factors shared by the numerator and denominator of a fraction.
+///
+/// Factors are matched on their equation representation, numeric factors are
+/// left to constant folding.
+pub fn fraction_cancellation(operation: &Operation) -> Option<Operation> {
+    let (numerator, denominator) = match operation {
+        Divide(Some(a), Some(b)) => (a.as_ref(), b.as_ref()),
+        _ => return None,
+    };
+    let factors = |x: &Operation| -> Vec<Operation> {
+        match x {
+            Multiply(list) => list.clone(),
+            x => vec![x.clone()],
+        }
+    };
+
+    let mut numerator: Vec<Operation> = factors(numerator);
+    let mut denominator: Vec<Operation> = factors(denominator);
+    let mut cancelled: bool = false;
+    numerator.retain(|x| {
+        if constant(x).is_some() {
+            return true;
+        }
+        let repr: String = x.equation_repr();
+        match denominator.iter().position(|y| y.equation_repr() == repr) {
+            Some(index) => {
+                denominator.remove(index);
+                cancelled = true;
+                false
+            }
+            None => true,
+        }
+    });
+    if !cancelled {
+        return None;
+    }
+
+    let numerator: Operation = collapse(numerator, 1.0, Multiply);
+    Some(match denominator.is_empty() {
+        true => numerator,
+        false => Divide(
+            Some(Box::new(numerator)),
+            Some(Box::new(collapse(denominator, 1.0, Multiply))),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solvers::simplifier::*;
+    use operations::prelude::Text;
+
+    fn text(name: &str) -> Operation {
+        Text(name.to_string())
+    }
+
+    fn boxed(operation: Operation) -> Option<Box<Operation>> {
+        Some(Box::new(operation))
+    }
+
+    fn only(rule: RewriteRule) -> OperationSimplifier {
+        OperationSimplifier::new(vec![rule])
+    }
+
+    #[test]
+    fn test_identity_elements() {
+        let simplifier = only(identity_elements);
+        let sum = Sum(vec![text("x"), Value(0.0)]);
+        assert_eq!(simplifier.simplify(sum).equation_repr(), "x");
+
+        let product = Multiply(vec![Value(1.0), text("x"), text("y")]);
+        let expected = Multiply(vec![text("x"), text("y")]).equation_repr();
+        assert_eq!(simplifier.simplify(product).equation_repr(), expected);
+
+        let zero = Multiply(vec![text("x"), Value(0.0)]);
+        assert_eq!(simplifier.simplify(zero).value(), 0.0);
+
+        let fraction = Divide(boxed(text("x")), boxed(Value(1.0)));
+        assert_eq!(simplifier.simplify(fraction).equation_repr(), "x");
+
+        let power = Power(boxed(text("x")), boxed(Value(0.0)));
+        assert_eq!(simplifier.simplify(power).value(), 1.0);
+
+        assert!(identity_elements(&Sum(vec![text("x"), text("y")])).is_none());
+    }
+
+    #[test]
+    fn test_double_negation() {
+        let simplifier = only(double_negation);
+        let twice = Negate(boxed(Negate(boxed(text("x")))));
+        assert_eq!(simplifier.simplify(twice).equation_repr(), "x");
+
+        // Applied until a fixed point, three negations leave one
+        let thrice = Negate(boxed(Negate(boxed(Negate(boxed(text("y")))))));
+        let expected = Negate(boxed(text("y"))).equation_repr();
+        assert_eq!(simplifier.simplify(thrice).equation_repr(), expected);
+
+        assert!(double_negation(&Negate(boxed(text("x")))).is_none());
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let simplifier = only(constant_folding);
+        let nested = Sum(vec![
+            Value(2.0),
+            Multiply(vec![Value(3.0), Value(4.0)]),
+            Negate(boxed(Value(1.0))),
+        ]);
+        assert_eq!(simplifier.simplify(nested).value(), 13.0);
+
+        let mixed = Sum(vec![Value(2.0), text("x"), Value(3.0)]);
+        let expected = Sum(vec![Value(5.0), text("x")]).equation_repr();
+        assert_eq!(simplifier.simplify(mixed).equation_repr(), expected);
+
+        let fraction = Divide(boxed(Value(1.0)), boxed(Value(4.0)));
+        assert_eq!(simplifier.simplify(fraction).value(), 0.25);
+        assert!(constant_folding(&Divide(boxed(Value(1.0)), boxed(Value(0.0)))).is_none());
+    }
+
+    #[test]
+    fn test_fraction_cancellation() {
+        let simplifier = only(fraction_cancellation);
+        let same = Divide(boxed(text("x")), boxed(text("x")));
+        assert_eq!(simplifier.simplify(same).value(), 1.0);
+
+        let common = Divide(
+            boxed(Multiply(vec![text("x"), text("y")])),
+            boxed(Multiply(vec![text("y"), text("z")])),
+        );
+        let expected = Divide(boxed(text("x")), boxed(text("z"))).equation_repr();
+        assert_eq!(simplifier.simplify(common).equation_repr(), expected);
+
+        assert!(fraction_cancellation(&Divide(boxed(text("x")), boxed(text("y")))).is_none());
+    }
+
+    #[test]
+    fn test_default_rules() {
+        // -(-(x * 1) * y) / (2 * y) + (3 - 3) reduces to x / 2
+        let expression = Sum(vec![
+            Divide(
+                boxed(Negate(boxed(Negate(boxed(Multiply(vec![
+                    Multiply(vec![text("x"), Value(1.0)]),
+                    text("y"),
+                ])))))),
+                boxed(Multiply(vec![Value(2.0), text("y")])),
+            ),
+            Sum(vec![Value(3.0), Value(-3.0)]),
+        ]);
+        let result = OperationSimplifier::default().simplify(expression);
+        let expected = Divide(boxed(text("x")), boxed(Value(2.0))).equation_repr();
+        assert_eq!(result.equation_repr(), expected);
+    }
+}
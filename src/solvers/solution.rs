@@ -0,0 +1,486 @@
+use crate::component::Component;
+use crate::component::Component::{CurrentSrc, Ground, Resistor, VoltageSrc};
+use crate::solvers::linear::{kahan_sum, solve_exact, Pivot, Ratio};
+use crate::solvers::solver::NodeSolver;
+use crate::validation::StatusError;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Terminal voltage, current and absorbed power of a single element
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ElementResult {
+    pub id: usize,
+    pub class: Component,
+    pub tags: Vec<String>,
+    /// Nodes at the positive and negative terminal.
+    pub nodes: (usize, usize),
+    /// Voltage of the positive terminal against the negative terminal.
+    pub voltage: f64,
+    /// Current flowing from the positive to the negative terminal.
+    pub current: f64,
+    /// Power absorbed by the element, negative when it delivers power.
+    pub power: f64,
+    /// The current is provably zero rather than rounded to a small value, the current
+    /// and power are then exactly 0.0.
+    pub exact: bool,
+    /// How the zero was proven, "no closed path" or "by symmetry".
+    pub exact_reason: Option<String>,
+}
+
+/// Aggregate results of the elements sharing a tag
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TagSummary {
+    /// Ids of the tagged elements.
+    pub elements: Vec<usize>,
+    /// Total power absorbed by the group.
+    pub power: f64,
+    /// Nodes connecting the group to the rest of the circuit.
+    pub boundary: Vec<usize>,
+    /// Resistance seen between the two boundary nodes
+    ///
+    /// Only available for purely resistive groups with exactly two boundary nodes.
+    pub equivalent_resistance: Option<f64>,
+}
+
+/// Per element results read off a solved node solver
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Solution {
+    pub elements: Vec<ElementResult>,
+    /// Row swaps and multipliers of the numeric elimination, in order.
+    pub pivots: Vec<Pivot>,
+    node_voltages: Vec<(usize, f64)>,
+}
+
+impl Solution {
+    /// Collect the results of every resistor and independent source.
+    ///
+    /// Fails if the solver has not been solved yet.
+    pub fn from_solver<S: NodeSolver + ?Sized>(solver: &S) -> Result<Solution, StatusError> {
+        let node_voltages: Vec<(usize, f64)> = solver.node_voltages()?;
+        let container = solver.container();
+        let container = container.borrow();
+
+        let mut elements: Vec<ElementResult> = Vec::new();
+        for element in container.get_elements().iter() {
+            let element = element.borrow();
+            if element.is_detached() {
+                continue;
+            }
+            let source_current: Option<f64> = match element.class {
+                Resistor => None,
+                VoltageSrc | CurrentSrc => Some(solver.current_through_source(element.id)?),
+                _ => continue,
+            };
+            let nodes: (usize, usize) = container.element_current_direction(element.id);
+            let voltage: f64 = solver.element_voltage(element.id)?;
+            let current: f64 = source_current.unwrap_or(voltage / element.value);
+            // Source values are not always oriented like the terminal lists. A voltage
+            // source current is solved against its nominal value and a current source
+            // injects its value into the node at its positive terminal.
+            let power: f64 = match element.class {
+                VoltageSrc => element.value * current,
+                CurrentSrc => -voltage * current,
+                _ => voltage * current,
+            };
+
+            elements.push(ElementResult {
+                id: element.id,
+                class: element.class.clone(),
+                tags: element.tags.clone(),
+                nodes,
+                voltage,
+                current,
+                power,
+                exact: false,
+                exact_reason: None,
+            });
+        }
+
+        let values: BTreeMap<usize, f64> = elements
+            .iter()
+            .map(|x| (x.id, container.get_element_by_id(x.id).borrow().value))
+            .collect();
+        // Other classes are not part of the exact system, leave those circuits alone
+        let supported: bool = container.get_elements().iter().all(|x| {
+            let x = x.borrow();
+            x.is_detached() || matches!(x.class, Ground | Resistor | VoltageSrc | CurrentSrc)
+        });
+        if supported {
+            let zeros: BTreeMap<usize, &'static str> = exact_zeros(&elements, &values);
+            for result in elements.iter_mut() {
+                if let Some(reason) = zeros.get(&result.id) {
+                    result.exact = true;
+                    result.exact_reason = Some(reason.to_string());
+                    result.current = 0.0;
+                    result.power = 0.0;
+                    if result.class == Resistor {
+                        result.voltage = 0.0;
+                    }
+                }
+            }
+        }
+
+        Ok(Solution {
+            elements,
+            pivots: solver.pivots(),
+            node_voltages,
+        })
+    }
+
+    /// Results aggregated per tag, an element with several tags counts towards each.
+    pub fn by_tag(&self) -> BTreeMap<String, TagSummary> {
+        let mut groups: BTreeMap<String, Vec<&ElementResult>> = BTreeMap::new();
+        for result in self.elements.iter() {
+            for tag in result.tags.iter() {
+                groups.entry(tag.clone()).or_default().push(result);
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(tag, members)| {
+                let summary = self.summarize(&tag, &members);
+                (tag, summary)
+            })
+            .collect()
+    }
+
+    fn summarize(&self, tag: &String, members: &[&ElementResult]) -> TagSummary {
+        // Members are in element id order, so the total does not depend on the target
+        let power: f64 = kahan_sum(members.iter().map(|x| x.power));
+
+        let mut boundary: Vec<usize> = members
+            .iter()
+            .flat_map(|x| [x.nodes.0, x.nodes.1])
+            .filter(|node| {
+                self.elements
+                    .iter()
+                    .filter(|x| !x.tags.contains(tag))
+                    .any(|x| x.nodes.0 == *node || x.nodes.1 == *node)
+            })
+            .collect();
+        boundary.sort();
+        boundary.dedup();
+
+        // The group absorbs V^2 / R_eq when driven across its two boundary nodes
+        let resistive: bool = members.iter().all(|x| x.class == Resistor);
+        let equivalent_resistance: Option<f64> = match boundary[..] {
+            // Negative resistors can make the power and the equivalent resistance negative
+            [a, b] if resistive && power != 0.0 => {
+                let voltage: f64 = self.node_voltage(a) - self.node_voltage(b);
+                Some(voltage * voltage / power)
+            }
+            _ => None,
+        };
+
+        TagSummary {
+            elements: members.iter().map(|x| x.id).collect(),
+            power,
+            boundary,
+            equivalent_resistance,
+        }
+    }
+
+    fn node_voltage(&self, id: usize) -> f64 {
+        self.node_voltages
+            .iter()
+            .find(|(node, _)| *node == id)
+            .map(|(_, voltage)| *voltage)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Unknowns of a solved circuit, the node voltages and the source currents
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SolvedValues {
+    /// Voltage of every node against the reference, keyed by node id.
+    pub node_voltages: BTreeMap<usize, f64>,
+    /// Current through every independent source from its positive to its negative
+    /// terminal, keyed by element id.
+    pub source_currents: BTreeMap<usize, f64>,
+}
+
+impl SolvedValues {
+    /// Fails if the solver has not been solved yet.
+    pub fn from_solver<S: NodeSolver + ?Sized>(solver: &S) -> Result<SolvedValues, StatusError> {
+        let node_voltages: BTreeMap<usize, f64> = solver.node_voltages()?.into_iter().collect();
+        let sources: Vec<usize> = solver
+            .container()
+            .borrow()
+            .get_elements()
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class.is_source() && !x.is_detached())
+            .map(|x| x.id)
+            .collect();
+
+        let mut source_currents: BTreeMap<usize, f64> = BTreeMap::new();
+        for id in sources {
+            source_currents.insert(id, solver.current_through_source(id)?);
+        }
+        Ok(SolvedValues {
+            node_voltages,
+            source_currents,
+        })
+    }
+}
+
+/// Elements whose current is exactly zero, with the reason
+///
+/// A branch no loop runs through carries no current, unless it is a current source which
+/// would make the circuit invalid. Every other branch is checked by
+/// solving the circuit again in fractions, reading each value as the shortest fraction
+/// within 1e-12 of it. That second check is skipped when a value has no short fraction,
+/// a fraction overflows or the exact voltages do not match the numeric ones.
+fn exact_zeros(
+    elements: &[ElementResult],
+    values: &BTreeMap<usize, f64>,
+) -> BTreeMap<usize, &'static str> {
+    let mut zeros: BTreeMap<usize, &'static str> = BTreeMap::new();
+    for result in elements.iter() {
+        let (a, b) = result.nodes;
+        if result.class != CurrentSrc && a != b && !connected(elements, result.id, a, b) {
+            zeros.insert(result.id, "no closed path");
+        }
+    }
+
+    if let Some(currents) = exact_currents(elements, values) {
+        for (id, current) in currents {
+            if current.is_zero() {
+                zeros.entry(id).or_insert("by symmetry");
+            }
+        }
+    }
+    zeros
+}
+
+/// Whether nodes `a` and `b` are joined by elements other than `skip`
+fn connected(elements: &[ElementResult], skip: usize, a: usize, b: usize) -> bool {
+    let mut seen: BTreeSet<usize> = BTreeSet::from([a]);
+    let mut queue: VecDeque<usize> = VecDeque::from([a]);
+    while let Some(node) = queue.pop_front() {
+        if node == b {
+            return true;
+        }
+        for x in elements.iter().filter(|x| x.id != skip) {
+            let next: usize = match x.nodes {
+                (from, to) if from == node => to,
+                (to, from) if from == node => to,
+                _ => continue,
+            };
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    false
+}
+
+/// Element currents of the circuit solved by modified nodal analysis in fractions
+fn exact_currents(
+    elements: &[ElementResult],
+    values: &BTreeMap<usize, f64>,
+) -> Option<BTreeMap<usize, Ratio>> {
+    let nodes: Vec<usize> = elements
+        .iter()
+        .flat_map(|x| [x.nodes.0, x.nodes.1])
+        .filter(|x| *x != 0)
+        .collect::<BTreeSet<usize>>()
+        .into_iter()
+        .collect();
+    let sources: Vec<&ElementResult> = elements.iter().filter(|x| x.class == VoltageSrc).collect();
+    let index = |node: usize| nodes.iter().position(|x| *x == node);
+    let size: usize = nodes.len() + sources.len();
+    let mut a: Vec<Vec<Ratio>> = vec![vec![Ratio::ZERO; size]; size];
+    let mut b: Vec<Ratio> = vec![Ratio::ZERO; size];
+    let one: Ratio = Ratio::new(1, 1)?;
+
+    for x in elements.iter() {
+        let value: Ratio = Ratio::from_f64(values[&x.id])?;
+        let (p, n) = (index(x.nodes.0), index(x.nodes.1));
+        match x.class {
+            Resistor => {
+                let conductance: Ratio = one.div(value)?;
+                for (row, other) in [(p, n), (n, p)] {
+                    if let Some(row) = row {
+                        a[row][row] = a[row][row].add(conductance)?;
+                        if let Some(other) = other {
+                            a[row][other] = a[row][other].sub(conductance)?;
+                        }
+                    }
+                }
+            }
+            CurrentSrc => {
+                // Same orientation as the power convention in `Solution::from_solver`
+                if let Some(row) = p {
+                    b[row] = b[row].add(value)?;
+                }
+                if let Some(row) = n {
+                    b[row] = b[row].sub(value)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Source currents flow from the positive to the negative terminal, the value is
+    // applied with the sign of the solved voltage
+    for (k, x) in sources.iter().enumerate() {
+        let row: usize = nodes.len() + k;
+        let value: Ratio = Ratio::from_f64(values[&x.id])?;
+        let value: Ratio = match x.voltage * values[&x.id] < 0.0 {
+            true => Ratio::ZERO.sub(value)?,
+            false => value,
+        };
+        for (node, sign) in [
+            (index(x.nodes.0), one),
+            (index(x.nodes.1), Ratio::ZERO.sub(one)?),
+        ] {
+            if let Some(node) = node {
+                a[node][row] = a[node][row].add(sign)?;
+                a[row][node] = a[row][node].add(sign)?;
+            }
+        }
+        b[row] = value;
+    }
+
+    let solved: Vec<Ratio> = solve_exact(a, b)?;
+    let voltage = |node: usize| index(node).map_or(Ratio::ZERO, |x| solved[x]);
+
+    let mut currents: BTreeMap<usize, Ratio> = BTreeMap::new();
+    for x in elements.iter() {
+        let across: Ratio = voltage(x.nodes.0).sub(voltage(x.nodes.1))?;
+        // Both solves describe the same circuit only if they agree
+        if (across.to_f64() - x.voltage).abs() > 1e-6 * x.voltage.abs().max(1.0) {
+            return None;
+        }
+        let current: Ratio = match x.class {
+            Resistor => across.div(Ratio::from_f64(values[&x.id])?)?,
+            VoltageSrc => solved[nodes.len() + sources.iter().position(|y| y.id == x.id)?],
+            _ => Ratio::from_f64(values[&x.id])?,
+        };
+        currents.insert(x.id, current);
+    }
+    Some(currents)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::Component::{Resistor, VoltageSrc};
+    use crate::container::Container;
+    use crate::solvers::node_matrix_solver::NodeMatrixSolver;
+    use crate::solvers::solution::ElementResult;
+    use crate::solvers::solution::{Solution, SolvedValues};
+    use crate::solvers::solver::{Solver, Step};
+    use crate::util::{bridge, create_mna_container};
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_by_tag() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        for (id, tags) in [(2, vec!["load"]), (3, vec!["load", "sense"])] {
+            c.get_element_by_id(id).borrow_mut().tags =
+                tags.iter().map(|x| x.to_string()).collect();
+        }
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert!(Solution::from_solver(&solver).is_err());
+        solver.solve().unwrap();
+
+        let solution: Solution = Solution::from_solver(&solver).unwrap();
+        let power = |id: usize| solution.elements.iter().find(|x| x.id == id).unwrap().power;
+        // R1, R2 and R3 dissipate 32 W, 4 W and 72 W
+        assert!((power(2) - 4.).abs() < 1e-9);
+        assert!((power(3) - 72.).abs() < 1e-9);
+        let total: f64 = solution.elements.iter().map(|x| x.power).sum();
+        assert!(total.abs() < 1e-9);
+
+        let groups = solution.by_tag();
+        assert_eq!(groups.len(), 2);
+        let load = &groups["load"];
+        assert_eq!(load.elements, vec![2, 3]);
+        assert!((load.power - (power(2) + power(3))).abs() < 1e-9);
+        assert_eq!(load.equivalent_resistance, None);
+
+        let sense = &groups["sense"];
+        assert_eq!(sense.boundary.len(), 2);
+        assert!((sense.equivalent_resistance.unwrap() - 8.).abs() < 1e-9);
+    }
+
+    fn solve(mut c: Container) -> Solution {
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        solver.solve().unwrap();
+        Solution::from_solver(&solver).unwrap()
+    }
+
+    fn result(solution: &Solution, id: usize) -> &ElementResult {
+        solution.elements.iter().find(|x| x.id == id).unwrap()
+    }
+
+    #[test]
+    fn test_exact_zeros() {
+        // r1 * r4 = r2 * r3, the galvanometer sits between two equal potentials
+        let balanced: Solution = solve(bridge(1., 2., 3., 6., 5., 10.));
+        let galvanometer = result(&balanced, 6);
+        assert!(galvanometer.exact);
+        assert_eq!(galvanometer.exact_reason, Some("by symmetry".to_string()));
+        assert_eq!(galvanometer.current, 0.0);
+        assert_eq!(galvanometer.voltage, 0.0);
+        assert!(balanced
+            .elements
+            .iter()
+            .filter(|x| x.id != 6)
+            .all(|x| !x.exact));
+
+        let unbalanced: Solution = solve(bridge(1., 2., 3., 6.01, 5., 10.));
+        let galvanometer = result(&unbalanced, 6);
+        assert!(!galvanometer.exact);
+        assert!(galvanometer.current.abs() > 0.0 && galvanometer.current.abs() < 1e-2);
+
+        // R3 hangs off the divider without a return path
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 0, 1);
+        c.add_element_between_nodes(Resistor, 5., 1, 0);
+        c.add_element_between_nodes(Resistor, 3., 2, 1);
+        let dangling: Solution = solve(c);
+        assert_eq!(
+            result(&dangling, 3).exact_reason,
+            Some("no closed path".to_string())
+        );
+        assert!(!result(&dangling, 2).exact);
+    }
+
+    #[test]
+    fn test_solved_values() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert!(SolvedValues::from_solver(&solver).is_err());
+        let steps: Vec<Step> = solver.solve().unwrap();
+
+        let values: SolvedValues = SolvedValues::from_solver(&solver).unwrap();
+        let close = |a: &BTreeMap<usize, f64>, b: Vec<(usize, f64)>| {
+            assert_eq!(
+                a.keys().copied().collect::<Vec<usize>>(),
+                b.iter().map(|x| x.0).collect::<Vec<usize>>()
+            );
+            assert!(a.values().zip(b).all(|(x, (_, y))| (x - y).abs() < 1e-9));
+        };
+        close(
+            &values.node_voltages,
+            vec![(0, 0.), (1, 20.), (2, 24.), (3, -8.)],
+        );
+        close(&values.source_currents, vec![(4, -4.), (5, 1.)]);
+
+        let last: &Step = steps.last().unwrap();
+        assert_eq!(last.title(), Some("Solved Values".to_string()));
+        assert_eq!(last.referenced_elements, vec![4, 5]);
+        assert_eq!(last.referenced_nodes, vec![1, 2, 3]);
+        let json: String = serde_json::to_string(last).unwrap();
+        assert!(json.contains("V_{3} = -8 V"));
+        assert!(json.contains("I_{SRC(V)4} = -4 A"));
+    }
+}
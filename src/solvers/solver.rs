@@ -1,9 +1,24 @@
-use crate::container::Container;
+use crate::component::Component::{Capacitor, CurrentSrc, Ground, Inductor, Resistor, VoltageSrc};
+use crate::container::{dc_inductor_message, Container};
+use crate::elements::Element;
+use crate::solvers::linear::{kahan_sum, LuFactors, Pivot};
+use crate::solvers::options::{FormDisplay, SolveOptions};
+use crate::solvers::progress::ProgressSink;
+use crate::solvers::solution::Solution;
+use crate::util::PrettyPrint;
 use crate::validation::StatusError;
+use crate::validation::StatusError::Known;
+use crate::validation::Warning;
+use nalgebra::DVector;
 use operations::prelude::*;
+use petgraph::graph::{NodeIndex, UnGraph};
+use petgraph::visit::EdgeRef;
+use rustworkx_core::connectivity;
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::Infallible;
 use std::fmt::Display;
 use std::rc::Rc;
 use wasm_bindgen::JsValue;
@@ -12,21 +27,555 @@ use wasm_bindgen::JsValue;
 /// KCL and KVL will be used to solve the circuit.
 
 pub trait Solver {
-    fn new(container: Rc<RefCell<Container>>) -> Self;
+    fn new(container: Rc<RefCell<Container>>) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new_with_options(container, SolveOptions::default())
+    }
+    fn new_with_options(container: Rc<RefCell<Container>>, options: SolveOptions) -> Self;
     fn solve(&mut self) -> Result<Vec<Step>, StatusError>;
+
+    /// Solve while reporting the phases of the solve to `progress`.
+    fn solve_with_progress(
+        &mut self,
+        _progress: &dyn ProgressSink,
+    ) -> Result<Vec<Step>, StatusError> {
+        self.solve()
+    }
+}
+
+/// Accessors shared by the nodal solvers once `solve` has been called.
+pub trait NodeSolver {
+    fn container(&self) -> Rc<RefCell<Container>>;
+
+    /// Node voltages keyed by node id relative to the configured reference.
+    ///
+    /// Ground is always included with id 0.
+    fn node_voltages(&self) -> Result<Vec<(usize, f64)>, StatusError>;
+
+    fn node_voltage(&self, node_id: usize) -> Result<f64, StatusError> {
+        self.node_voltages()?
+            .into_iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, voltage)| voltage)
+            .ok_or_else(|| Known(format!("Node {} does not exist", node_id)))
+    }
+
+    /// Voltage of node `a` measured against node `b`.
+    ///
+    /// This is independent of the reference the solver reports against.
+    fn voltage_between(&self, a: usize, b: usize) -> Result<f64, StatusError> {
+        Ok(self.node_voltage(a)? - self.node_voltage(b)?)
+    }
+
+    /// Voltage of an element's positive terminal against its negative terminal.
+    ///
+    /// Fails for ground, detached elements and unknown ids.
+    fn element_voltage(&self, element_id: usize) -> Result<f64, StatusError> {
+        let container = self.container();
+        let (a, b) = {
+            let container = container.borrow();
+            match container.get_elements().get(element_id) {
+                Some(x) if x.borrow().class != Ground && !x.borrow().is_detached() => {
+                    container.element_current_direction(element_id)
+                }
+                Some(_) => return Err(Known(format!("Element {} is not a branch", element_id))),
+                None => return Err(Known(format!("Element {} does not exist", element_id))),
+            }
+        };
+        self.voltage_between(a, b)
+    }
+
+    /// Nodes whose voltage differs from `other` by more than `tol`.
+    ///
+    /// Returns `(node id, voltage of self, voltage of other)` for each mismatch. Nodes
+    /// missing from `other` are reported with a NaN voltage, an unsolved `self` reports nothing.
+    fn compare_with(&self, other: &dyn NodeSolver, tol: f64) -> Vec<(usize, f64, f64)> {
+        let voltages: Vec<(usize, f64)> = self.node_voltages().unwrap_or_default();
+        let other_voltages: Vec<(usize, f64)> = other.node_voltages().unwrap_or_default();
+
+        voltages
+            .into_iter()
+            .filter_map(|(id, voltage)| {
+                let other_voltage: f64 = other_voltages
+                    .iter()
+                    .find(|(other_id, _)| *other_id == id)
+                    .map(|(_, x)| *x)
+                    .unwrap_or(f64::NAN);
+                if (voltage - other_voltage).abs() <= tol {
+                    return None;
+                }
+                Some((id, voltage, other_voltage))
+            })
+            .collect()
+    }
+
+    /// KVL residual around a closed loop given as ordered element ids.
+    ///
+    /// The loop is entered at the node the first element shares with the last, the
+    /// residual is the sum of the voltage drops along the walk. It is zero up to rounding
+    /// for a consistent solution.
+    fn check_kvl_around_mesh(&self, mesh: &[usize]) -> Result<f64, StatusError> {
+        if mesh.len() < 2 {
+            return Err(Known("A mesh needs at least two elements".to_string()));
+        }
+        let container = self.container();
+        let container = container.borrow();
+        let mut terminals: Vec<(usize, usize)> = Vec::new();
+        for id in mesh {
+            match container.get_elements().get(*id) {
+                Some(x) if x.borrow().class != Ground && !x.borrow().is_detached() => {
+                    terminals.push(container.element_current_direction(*id))
+                }
+                Some(_) => return Err(Known(format!("Element {} is not a branch", id))),
+                None => return Err(Known(format!("Element {} does not exist", id))),
+            }
+        }
+
+        let (first, last) = (terminals[0], terminals[terminals.len() - 1]);
+        let start: usize = match first {
+            (a, _) if a == last.0 || a == last.1 => a,
+            (_, b) if b == last.0 || b == last.1 => b,
+            _ => {
+                return Err(Known(format!(
+                    "Element {} and element {} do not share a node",
+                    mesh[0],
+                    mesh[mesh.len() - 1]
+                )))
+            }
+        };
+
+        let mut node: usize = start;
+        let mut residual: f64 = 0.0;
+        for (id, (from, to)) in mesh.iter().zip(terminals) {
+            let next: usize = match node {
+                x if x == from => to,
+                x if x == to => from,
+                _ => {
+                    return Err(Known(format!(
+                        "Element {} is not connected to node {} of the mesh",
+                        id, node
+                    )))
+                }
+            };
+            residual += self.voltage_between(node, next)?;
+            node = next;
+        }
+        if node != start {
+            return Err(Known(format!(
+                "Mesh ends at node {} instead of node {}",
+                node, start
+            )));
+        }
+        Ok(residual)
+    }
+
+    /// Check that no voltage sources form a loop among themselves
+    ///
+    /// Every source fixes the difference of two node voltages. Around a loop of sources the
+    /// last difference is already fixed by the others, so its MNA row is dependent and the
+    /// factorization fails with a singular matrix. Each loop is reported with its sources,
+    /// the one closing the loop last. Works before solving, the nodes have to exist.
+    fn check_causality(&self) -> Result<(), StatusError> {
+        let container = self.container();
+        let container = container.borrow();
+        let mut edges: Vec<(usize, usize, usize)> = Vec::new();
+        let mut errors: Vec<StatusError> = Vec::new();
+        for element in container.get_elements().iter().map(|x| x.borrow()) {
+            if element.class != VoltageSrc || element.is_detached() {
+                continue;
+            }
+            let (a, b) = match (
+                container.node_at(element.id, &element.positive),
+                container.node_at(element.id, &element.negative),
+            ) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+
+            // Sources closing a loop are left out, later loops are reported on their own
+            match source_path(&edges, a, b) {
+                None => edges.push((element.id, a, b)),
+                Some(path) if path.is_empty() => errors.push(Known(format!(
+                    "Voltage source {} has both terminals on Node {}, it loops onto itself",
+                    element.basic_string(),
+                    a
+                ))),
+                Some(path) => {
+                    let names: Vec<String> = path
+                        .iter()
+                        .map(|x| container.get_element_by_id(*x).basic_string())
+                        .chain([element.basic_string()])
+                        .collect();
+                    errors.push(Known(format!(
+                        "Voltage sources {} form a loop, the MNA equations are inconsistent",
+                        names.join(", ")
+                    )));
+                }
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(StatusError::Multiple(errors)),
+        }
+    }
+
+    /// Smallest set of elements whose removal splits the circuit in two
+    ///
+    /// Global minimum cut of the node graph, every element but ground is an edge between
+    /// the nodes at its terminals. Found with the Stoer-Wagner algorithm, which settles the
+    /// max-flow min-cut question for every pair of nodes at once. Losing all of the elements
+    /// isolates at least one node. Ids are ascending, empty if the circuit has fewer than
+    /// two nodes or is already split. Works before solving, the nodes have to exist.
+    fn minimum_cut_set(&self) -> Vec<usize> {
+        let container = self.container();
+        let container = container.borrow();
+        let branches: Vec<(usize, usize, usize)> = container
+            .get_elements()
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class != Ground && !x.is_detached())
+            .filter_map(|x| {
+                let a: usize = container.node_at(x.id, &x.positive)?;
+                let b: usize = container.node_at(x.id, &x.negative)?;
+                (a != b).then_some((x.id, a, b))
+            })
+            .collect();
+
+        // Parallel elements are cut together, they share one edge weighted by their count
+        let mut graph: UnGraph<usize, usize> = UnGraph::default();
+        let mut index: BTreeMap<usize, NodeIndex> = BTreeMap::new();
+        let mut weights: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for (_, a, b) in branches.iter() {
+            for node in [*a, *b] {
+                index.entry(node).or_insert_with(|| graph.add_node(node));
+            }
+            *weights.entry((*a.min(b), *a.max(b))).or_default() += 1;
+        }
+        for ((a, b), weight) in weights {
+            graph.add_edge(index[&a], index[&b], weight);
+        }
+
+        let cut =
+            connectivity::stoer_wagner_min_cut(&graph, |x| Ok::<usize, Infallible>(*x.weight()));
+        let side: Vec<usize> = match cut.unwrap_or(None) {
+            Some((_, side)) => side.into_iter().map(|x| graph[x]).collect(),
+            None => return vec![],
+        };
+        branches
+            .into_iter()
+            .filter(|(_, a, b)| side.contains(a) != side.contains(b))
+            .map(|(id, _, _)| id)
+            .collect()
+    }
+
+    /// KVL residual of every fundamental loop, keyed by the branch that closes the loop.
+    ///
+    /// See `Container::fundamental_loops`, an unsolved solver reports nothing.
+    fn kvl_residuals_all_meshes(&self) -> HashMap<usize, f64> {
+        let loops: Vec<Vec<usize>> = self.container().borrow().fundamental_loops();
+        loops
+            .iter()
+            .filter_map(|mesh| Some((mesh[0], self.check_kvl_around_mesh(mesh).ok()?)))
+            .collect()
+    }
+
+    /// Current through every co-tree branch, keyed by element id
+    ///
+    /// The co-tree holds the links of `Container::fundamental_loops`, the branches that close
+    /// a loop. A link is the only branch of its loop outside the spanning tree, so its current
+    /// is the fundamental loop current, the unknown `MeshMatrixSolver::loop_currents` solves
+    /// for. Currents flow from the positive to the negative terminal, capacitors carry none.
+    /// Links without a solved current, e.g. inductors, are left out, as is everything for an
+    /// unsolved solver.
+    fn co_tree_currents(&self) -> HashMap<usize, f64> {
+        let solution: Solution = match Solution::from_solver(self) {
+            Ok(solution) => solution,
+            Err(_) => return HashMap::new(),
+        };
+        let container = self.container();
+        let container = container.borrow();
+        container
+            .fundamental_loops()
+            .iter()
+            .filter_map(|mesh| {
+                let link: usize = mesh[0];
+                let current: f64 = match solution.elements.iter().find(|x| x.id == link) {
+                    Some(result) => result.current,
+                    None if container.get_element_by_id(link).borrow().class == Capacitor => 0.0,
+                    None => return None,
+                };
+                Some((link, current))
+            })
+            .collect()
+    }
+
+    /// Current through a voltage source as solved for by the solver.
+    ///
+    /// Solvers that do not carry source currents as unknowns keep this default.
+    fn voltage_source_current(&self, source_id: usize) -> Result<f64, StatusError> {
+        Err(Known(format!(
+            "Current through voltage source {} is not available from this solver",
+            source_id
+        )))
+    }
+
+    /// Elimination steps of the numeric solve, empty for solvers without a factorization.
+    fn pivots(&self) -> Vec<Pivot> {
+        vec![]
+    }
+
+    /// Factors of the numeric system and its right hand side, absent until solved.
+    ///
+    /// Solvers without a numeric system keep this default.
+    fn factored_system(&self) -> Option<(LuFactors, DVector<f64>)> {
+        None
+    }
+
+    /// Back substitution through the upper triangular factor, last unknown first
+    ///
+    /// `A` and `z` are the matrix and right hand side after forward elimination, rows and
+    /// unknowns are numbered from 1. Empty until the solver has been solved.
+    fn back_substitution_trace(&self) -> Vec<Step> {
+        let (factors, b) = match self.factored_system() {
+            Some(system) => system,
+            None => return vec![],
+        };
+        let n: usize = factors.size;
+        let at = |i: usize, j: usize| factors.packed[i * n + j];
+        let z: DVector<f64> = factors.forward_substitution(&b);
+        let equation = |z: String, terms: Vec<String>, diagonal: String| -> String {
+            match terms.is_empty() {
+                true => format!("{} / {}", z, diagonal),
+                false => format!("({} - {}) / {}", z, terms.join(" - "), diagonal),
+            }
+        };
+
+        let mut x: DVector<f64> = DVector::zeros(n);
+        let mut steps: Vec<Step> = Vec::new();
+        for i in (0..n).rev() {
+            let shift: f64 = (i + 1..n).map(|j| at(i, j) * x[j]).sum();
+            x[i] = (z[i] - shift) / at(i, i);
+
+            let row: usize = i + 1;
+            let symbolic: String = equation(
+                format!("z_{}", row),
+                (i + 1..n)
+                    .map(|j| format!("A[{}][{}]*V_{}", row, j + 1, j + 1))
+                    .collect(),
+                format!("A[{}][{}]", row, row),
+            );
+            let numeric: String = equation(
+                z[i].to_string(),
+                (i + 1..n)
+                    .map(|j| format!("{}*{}", at(i, j), x[j]))
+                    .collect(),
+                at(i, i).to_string(),
+            );
+            steps.push(Step {
+                title: Some(format!("From row {}", row)),
+                description: Some(format!("V_{} = {}", row, symbolic)),
+                result: Some(Value(x[i])),
+                sub_steps: vec![SubStep {
+                    description: Some(format!("V_{} = {} = {}", row, numeric, x[i])),
+                    result: Some(Value(x[i])),
+                    operations: vec![],
+                    forms: None,
+                }],
+                referenced_elements: vec![],
+                referenced_nodes: vec![],
+            });
+        }
+        steps
+    }
+
+    /// Checkpoint of the solver that `restore_state` can resume from.
+    ///
+    /// Solvers that cannot be checkpointed keep this default.
+    fn save_state(&self) -> Result<SolverState, StatusError> {
+        Err(Known(
+            "Checkpointing is not available from this solver".to_string(),
+        ))
+    }
+
+    /// Resume from a checkpoint, the solver is rebuilt on a new container.
+    fn restore_state(&mut self, _state: SolverState) -> Result<(), StatusError> {
+        Err(Known(
+            "Checkpointing is not available from this solver".to_string(),
+        ))
+    }
+
+    /// Change the value of independent sources, keyed by element id, and solve again
+    ///
+    /// Only the solver is updated, the container keeps the original values. The matrix of
+    /// a circuit does not depend on its sources, solvers that keep their factors only form
+    /// the right hand side again, the fast path for DC sweeps and Monte Carlo runs.
+    fn update_source_values(
+        &mut self,
+        _new_values: HashMap<usize, f64>,
+    ) -> Result<(), StatusError> {
+        Err(Known(
+            "Updating source values is not available from this solver".to_string(),
+        ))
+    }
+
+    /// Current through an independent source, flowing from its positive to its negative terminal.
+    ///
+    /// Current sources simply return their nominal value.
+    fn current_through_source(&self, source_id: usize) -> Result<f64, StatusError> {
+        let container = self.container();
+        let (class, value) = match container.borrow().get_elements().get(source_id) {
+            Some(element) => (element.borrow().class.clone(), element.borrow().value),
+            None => return Err(Known(format!("Element {} does not exist", source_id))),
+        };
+
+        match class {
+            CurrentSrc => Ok(value),
+            VoltageSrc => self.voltage_source_current(source_id),
+            _ => Err(Known(format!("Element {} is not a source", source_id))),
+        }
+    }
+
+    /// Total power delivered by the independent sources.
+    fn power_delivered_by_sources(&self) -> Result<f64, StatusError> {
+        let solution: Solution = Solution::from_solver(self)?;
+        Ok(-kahan_sum(
+            solution
+                .elements
+                .iter()
+                .filter(|x| x.class.is_source())
+                .map(|x| x.power),
+        ))
+    }
+
+    /// Total power dissipated by the resistors, negative resistors are left out.
+    fn power_absorbed_by_resistors(&self) -> Result<f64, StatusError> {
+        let solution: Solution = Solution::from_solver(self)?;
+        Ok(kahan_sum(
+            solution
+                .elements
+                .iter()
+                .filter(|x| x.class == Resistor && x.power >= 0.0)
+                .map(|x| x.power),
+        ))
+    }
+
+    /// Total power delivered by the negative resistors, zero without any.
+    fn power_generated_by_negative_resistors(&self) -> Result<f64, StatusError> {
+        let solution: Solution = Solution::from_solver(self)?;
+        Ok(-kahan_sum(
+            solution
+                .elements
+                .iter()
+                .filter(|x| x.class == Resistor && x.power < 0.0)
+                .map(|x| x.power),
+        ))
+    }
+
+    /// Absolute difference of the delivered and the absorbed power, zero up to rounding.
+    fn power_balance_error(&self) -> Result<f64, StatusError> {
+        let delivered: f64 =
+            self.power_delivered_by_sources()? + self.power_generated_by_negative_resistors()?;
+        Ok((delivered - self.power_absorbed_by_resistors()?).abs())
+    }
+
+    /// Results as ASCII tables, one row per resistor and source followed by one per node
+    ///
+    /// Numbers are rounded to `precision` decimal places and right aligned.
+    fn format_solution_as_table(&self, precision: usize) -> Result<String, StatusError> {
+        let solution: Solution = Solution::from_solver(self)?;
+        let number = |x: f64| -> String {
+            // Keep values that round to zero from printing as -0.000
+            match x.abs() < 0.5 * 10f64.powi(-(precision as i32)) {
+                true => format!("{:.*}", precision, 0.0),
+                false => format!("{:.*}", precision, x),
+            }
+        };
+
+        let container = self.container();
+        let elements: Vec<Vec<String>> = solution
+            .elements
+            .iter()
+            .map(|x| {
+                vec![
+                    container.borrow().get_element_by_id(x.id).basic_string(),
+                    number(x.voltage),
+                    number(x.current),
+                    number(x.power),
+                ]
+            })
+            .collect();
+        let nodes: Vec<Vec<String>> = self
+            .node_voltages()?
+            .into_iter()
+            .map(|(id, voltage)| vec![id.to_string(), number(voltage)])
+            .collect();
+
+        Ok(format!(
+            "{}{}",
+            ascii_table(
+                &["Element", "Voltage (V)", "Current (A)", "Power (W)"],
+                &elements
+            ),
+            ascii_table(&["Node", "Voltage (V)"], &nodes)
+        ))
+    }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+/// Serializable checkpoint of a solved node solver
+///
+/// The container is stored as its elements, nodes are rebuilt on restore.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SolverState {
+    pub elements: Vec<Element>,
+    pub options: SolveOptions,
+    /// Factors of the numeric system, absent until the solver has been solved.
+    pub factorization: Option<LuFactors>,
+    /// Numeric solution vector, empty until the solver has been solved.
+    pub solution: Vec<f64>,
+    /// Source values set by `NodeSolver::update_source_values`, keyed by element id.
+    #[serde(default)]
+    pub source_values: HashMap<usize, f64>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
 pub enum SolverType {
     NodeMatrix,
     NodeStep,
+    MeshMatrix,
+    MeshStep,
+}
+
+impl SolverType {
+    pub const ALL: [SolverType; 4] = [
+        SolverType::NodeMatrix,
+        SolverType::NodeStep,
+        SolverType::MeshMatrix,
+        SolverType::MeshStep,
+    ];
 }
 
+#[derive(Clone)]
 pub struct Step {
     pub title: Option<String>,
     pub description: Option<String>,
     pub result: Option<Operation>,
     pub sub_steps: Vec<SubStep>,
+    /// Ids of the elements the step mentions, sorted, for highlighting in the schematic.
+    pub referenced_elements: Vec<usize>,
+    /// Ids of the nodes the step mentions, sorted.
+    pub referenced_nodes: Vec<usize>,
+}
+
+/// Placeholder for a step that is rendered on demand
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StepStub {
+    pub key: String,
+    pub label: String,
+    pub id: usize,
 }
 
 #[derive(Clone)]
@@ -34,6 +583,14 @@ pub struct SubStep {
     pub description: Option<String>,
     pub result: Option<Operation>,
     pub operations: Vec<Operation>,
+    pub forms: Option<StepForms>,
+}
+
+/// Symbolic and numeric latex of the same content so the frontend can toggle between them
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StepForms {
+    pub symbolic: Option<String>,
+    pub numeric: Option<String>,
 }
 
 impl Step {
@@ -43,6 +600,8 @@ impl Step {
             description: None,
             sub_steps: vec![],
             result: None,
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
         }
     }
 
@@ -52,6 +611,25 @@ impl Step {
             description: None,
             result: None,
             sub_steps: steps,
+            referenced_elements: vec![],
+            referenced_nodes: vec![],
+        }
+    }
+
+    /// Record an element mentioned by the step.
+    pub fn reference_element(&mut self, id: usize) {
+        if let Err(index) = self.referenced_elements.binary_search(&id) {
+            self.referenced_elements.insert(index, id);
+        }
+    }
+
+    /// Record a node mentioned by the step, ground is never recorded.
+    pub fn reference_node(&mut self, id: usize) {
+        if id == 0 {
+            return;
+        }
+        if let Err(index) = self.referenced_nodes.binary_search(&id) {
+            self.referenced_nodes.insert(index, id);
         }
     }
 
@@ -74,6 +652,26 @@ impl SubStep {
             description: Some(label.to_string()),
             operations: vec![],
             result: None,
+            forms: None,
+        }
+    }
+
+    /// Sub step rendering the forms selected by `display`
+    pub fn new_with_forms(label: &str, forms: StepForms, display: FormDisplay) -> Self {
+        let rendered: Vec<&Option<String>> = match display {
+            FormDisplay::Both => vec![&forms.symbolic, &forms.numeric],
+            FormDisplay::Symbolic => vec![&forms.symbolic],
+            FormDisplay::Numeric => vec![&forms.numeric],
+        };
+        SubStep {
+            description: Some(label.to_string()),
+            operations: rendered
+                .into_iter()
+                .flatten()
+                .map(|x| Text(x.clone()))
+                .collect(),
+            result: None,
+            forms: Some(forms),
         }
     }
 
@@ -93,14 +691,16 @@ impl Serialize for Step {
     {
         let mut state: <S>::SerializeStruct;
         if &self.result == &None {
-            state = serializer.serialize_struct("Step", 3)?;
+            state = serializer.serialize_struct("Step", 5)?;
         } else {
-            state = serializer.serialize_struct("Step", 4)?;
+            state = serializer.serialize_struct("Step", 6)?;
             state.serialize_field("result", &latex_serialize(self.result.clone().unwrap()))?;
         }
         state.serialize_field("title", &self.title())?;
         state.serialize_field("description", &self.description())?;
         state.serialize_field("sub_steps", &self.get_steps())?;
+        state.serialize_field("referenced_elements", &self.referenced_elements)?;
+        state.serialize_field("referenced_nodes", &self.referenced_nodes)?;
         state.end()
     }
 }
@@ -110,12 +710,15 @@ impl Serialize for SubStep {
     where
         S: Serializer,
     {
-        let mut state: <S>::SerializeStruct;
-        if &self.result == &None {
-            state = serializer.serialize_struct("SubStep", 2)?;
-        } else {
-            state = serializer.serialize_struct("SubStep", 3)?;
-            state.serialize_field("result", &latex_serialize(self.result.clone().unwrap()))?;
+        let mut state: <S>::SerializeStruct = serializer.serialize_struct(
+            "SubStep",
+            2 + self.result.is_some() as usize + self.forms.is_some() as usize,
+        )?;
+        if let Some(result) = &self.result {
+            state.serialize_field("result", &latex_serialize(result.clone()))?;
+        }
+        if let Some(forms) = &self.forms {
+            state.serialize_field("forms", forms)?;
         }
         state.serialize_field("description", &self.description())?;
         state.serialize_field(
@@ -183,6 +786,227 @@ impl From<Step> for JsValue {
     }
 }
 
+/// Voltage sources leading from node `a` to node `b`, `edges` are `(source, node, node)`
+fn source_path(edges: &[(usize, usize, usize)], a: usize, b: usize) -> Option<Vec<usize>> {
+    let mut previous: HashMap<usize, (usize, usize)> = HashMap::new();
+    let mut queue: VecDeque<usize> = VecDeque::from([a]);
+    while let Some(node) = queue.pop_front() {
+        if node == b {
+            let mut path: Vec<usize> = Vec::new();
+            let mut node: usize = b;
+            while let Some((source, from)) = previous.get(&node) {
+                path.push(*source);
+                node = *from;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for (source, x, y) in edges.iter() {
+            let next: usize = match node {
+                node if node == *x => *y,
+                node if node == *y => *x,
+                _ => continue,
+            };
+            if next != a && !previous.contains_key(&next) {
+                previous.insert(next, (*source, node));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Fail with `StaleSolver` if the container changed since `generation` was recorded
+pub(crate) fn check_generation(
+    container: &Rc<RefCell<Container>>,
+    generation: u64,
+) -> Result<(), StatusError> {
+    match container.borrow().generation() == generation {
+        true => Ok(()),
+        false => Err(StatusError::StaleSolver),
+    }
+}
+
+/// Reject a strict solve once the container collected any warning.
+///
+/// All warnings are reported together rather than failing on the first.
+pub(crate) fn check_strict(
+    container: &Rc<RefCell<Container>>,
+    options: &SolveOptions,
+) -> Result<(), StatusError> {
+    let warnings: Vec<Warning> = container.borrow().warnings().clone();
+    match options.strict && !warnings.is_empty() {
+        true => Err(StatusError::StrictModeViolation(warnings)),
+        false => Ok(()),
+    }
+}
+
+/// Bordered table, the first column is left aligned and the others right aligned.
+fn ascii_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .fold(header.chars().count(), usize::max)
+        })
+        .collect();
+    let border: String = widths
+        .iter()
+        .map(|width| format!("+{}", "-".repeat(width + 2)))
+        .collect::<String>()
+        + "+\n";
+    let line = |cells: Vec<&str>| -> String {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(i, (cell, width))| match i {
+                0 => format!("| {:<width$} ", cell, width = width),
+                _ => format!("| {:>width$} ", cell, width = width),
+            })
+            .collect::<String>()
+            + "|\n"
+    };
+
+    let mut table: String = border.clone();
+    table.push_str(&line(headers.to_vec()));
+    table.push_str(&border);
+    for row in rows {
+        table.push_str(&line(row.iter().map(|x| x.as_str()).collect()));
+    }
+    table.push_str(&border);
+    table
+}
+
+/// Reject a circuit that needs a transformation when `allow_auto_transform` is off.
+pub(crate) fn check_auto_transform(
+    container: &Rc<RefCell<Container>>,
+    options: &SolveOptions,
+) -> Result<(), StatusError> {
+    let required: Vec<String> = container.borrow().required_transformations();
+    match options.allow_auto_transform || required.is_empty() {
+        true => Ok(()),
+        false => Err(Known(format!(
+            "Automatic transformations are disabled but the circuit needs them: {}",
+            required.join(", ")
+        ))),
+    }
+}
+
+/// Reject negative resistors unless `allow_negative_resistance` is set.
+pub(crate) fn check_negative_resistance(
+    container: &Rc<RefCell<Container>>,
+    options: &SolveOptions,
+) -> Result<(), StatusError> {
+    if options.allow_negative_resistance {
+        return Ok(());
+    }
+    match container
+        .borrow()
+        .get_elements()
+        .iter()
+        .find(|x| x.borrow().is_negative_resistance())
+    {
+        Some(element) => Err(Known(format!(
+            "Value cannot be zero or negative {}, enable allow_negative_resistance to model active devices",
+            element.pretty_string()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reject inductors unless `allow_dc_inductors` is set.
+pub(crate) fn check_dc_inductors(
+    container: &Rc<RefCell<Container>>,
+    options: &SolveOptions,
+) -> Result<(), StatusError> {
+    if options.allow_dc_inductors {
+        return Ok(());
+    }
+    match container
+        .borrow()
+        .get_elements()
+        .iter()
+        .find(|x| x.borrow().class == Inductor && !x.borrow().is_detached())
+    {
+        Some(element) => Err(Known(dc_inductor_message(&element.borrow()))),
+        None => Ok(()),
+    }
+}
+
+/// Reject dependent sources whose control voltage can not be read off the nodes.
+pub(crate) fn check_controls(container: &Rc<RefCell<Container>>) -> Result<(), StatusError> {
+    let container = container.borrow();
+    for element in container.get_elements().iter().map(|x| x.borrow()) {
+        if element.class.is_dependent() && !element.is_detached() {
+            container.control_nodes(element.id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Source limits need the outer iteration of the node matrix solver, see `Element::has_limit`.
+pub(crate) fn check_source_limits(container: &Rc<RefCell<Container>>) -> Result<(), StatusError> {
+    match container
+        .borrow()
+        .get_elements()
+        .iter()
+        .find(|x| x.borrow().has_limit())
+    {
+        Some(element) => Err(Known(format!(
+            "{} has a limit, switch to the node matrix solver to solve source limits",
+            element.pretty_string()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// What a source in its limiting model does instead of its nominal value
+pub(crate) fn limit_description(element: &Element) -> String {
+    match element.class {
+        VoltageSrc => format!(
+            "{} is a current source at its compliance limit, it holds {} V instead of driving {} A",
+            element.pretty_string(),
+            element.value.abs(),
+            element.current_limit.unwrap_or_default()
+        ),
+        _ => format!(
+            "{} is a voltage source in current limit, it delivers {} A instead of holding {} V",
+            element.pretty_string(),
+            element.value.abs(),
+            element.voltage_limit.unwrap_or_default()
+        ),
+    }
+}
+
+/// Note on every negative resistor, `None` if there are none.
+pub(crate) fn negative_resistance_sub_step(container: &Rc<RefCell<Container>>) -> Option<SubStep> {
+    let operations: Vec<Operation> = container
+        .borrow()
+        .get_elements()
+        .iter()
+        .filter(|x| x.borrow().is_negative_resistance())
+        .map(|x| {
+            Text(format!(
+                "{} has a negative resistance, it delivers power to the circuit instead of dissipating it",
+                x.pretty_string()
+            ))
+        })
+        .collect();
+    if operations.is_empty() {
+        return None;
+    }
+
+    Some(SubStep {
+        description: Some("Negative resistances".to_string()),
+        result: None,
+        operations,
+        forms: None,
+    })
+}
+
 pub fn serialize_steps(steps: Vec<Step>) -> Result<String, String> {
     match serde_json::to_string(&steps) {
         Ok(a) => Ok(a),
@@ -192,11 +1016,23 @@ pub fn serialize_steps(steps: Vec<Step>) -> Result<String, String> {
 
 #[cfg(test)]
 mod tests {
+    use crate::component::Component::{Ground, Resistor, VoltageSrc};
+    use crate::container::Container;
+    use crate::elements::Element;
+    use crate::solvers::mesh_matrix_solver::MeshMatrixSolver;
     use crate::solvers::node_matrix_solver::NodeMatrixSolver;
     use crate::solvers::node_step_solver::NodeStepSolver;
-    use crate::solvers::solver::Solver;
-    use crate::util::create_mna_container;
+    use crate::solvers::options::SolveOptions;
+    use crate::solvers::progress::Phase;
+    use crate::solvers::progress::Phase::{Factorization, MatrixFormation, Rendering};
+    use crate::solvers::solver::{NodeSolver, Solver};
+    use crate::util::{
+        bridge, create_ladder_container, create_mna_container, create_rlc_container,
+    };
+    use crate::validation::StatusError;
+    use crate::validation::WarningKind::DuplicateMembership;
     use std::cell::RefCell;
+    use std::collections::HashMap;
     use std::rc::Rc;
 
     #[test]
@@ -221,4 +1057,295 @@ mod tests {
             println!("---- Step ---- \n{}", i);
         }
     }
+
+    #[test]
+    fn test_compare_with() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let mut step: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        step.solve().unwrap();
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut matrix: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        matrix.solve().unwrap();
+
+        assert_eq!(matrix.compare_with(&step, 1e-9), vec![]);
+        assert_eq!(step.compare_with(&matrix, 1e-9), vec![]);
+
+        // Halving V5 moves every node
+        let mut c = create_mna_container();
+        c.get_element_by_id(5).borrow_mut().value = 10.;
+        c.create_nodes().unwrap();
+        let mut changed: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        changed.solve().unwrap();
+
+        let differences = matrix.compare_with(&changed, 1e-9);
+        assert_eq!(differences.len(), 3);
+        assert_eq!(differences[0].0, 1);
+        assert!((differences[0].1 - 20.).abs() < 1e-9);
+        assert!((differences[0].2 - 10.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_kvl_residuals() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let mut step: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+        step.solve().unwrap();
+
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut matrix: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert!(matrix.kvl_residuals_all_meshes().is_empty());
+        matrix.solve().unwrap();
+
+        // Five branches between four nodes leave two independent loops
+        for solver in [&step as &dyn NodeSolver, &matrix] {
+            let residuals = solver.kvl_residuals_all_meshes();
+            assert_eq!(residuals.len(), 2);
+            assert!(residuals.values().all(|x| x.abs() < 1e-9));
+        }
+
+        // V5, R2 and R3 form a loop through ground, R1 and R2 share no node
+        assert!(matrix.check_kvl_around_mesh(&[5, 2, 3]).unwrap().abs() < 1e-9);
+        assert!(matrix.check_kvl_around_mesh(&[1, 2]).is_err());
+        assert!(matrix.check_kvl_around_mesh(&[0, 1]).is_err());
+        assert!(matrix.check_kvl_around_mesh(&[5, 2]).is_err());
+    }
+
+    #[test]
+    fn test_co_tree_currents() {
+        // One link per independent loop, b - n + 1 with ground counted as a node
+        for create in [
+            create_mna_container,
+            create_ladder_container,
+            create_rlc_container,
+        ] {
+            let mut c = create();
+            c.create_nodes().unwrap();
+            let branches: usize = c
+                .get_elements()
+                .iter()
+                .filter(|x| x.borrow().class != Ground && !x.borrow().is_detached())
+                .count();
+            let nodes: usize = c.nodes().len() + 1;
+            let options = SolveOptions {
+                allow_dc_inductors: true,
+                ..SolveOptions::default()
+            };
+            let mut solver: NodeMatrixSolver =
+                Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+            assert!(solver.co_tree_currents().is_empty());
+            solver.solve().unwrap();
+            let currents: HashMap<usize, f64> = solver.co_tree_currents();
+            assert_eq!(currents.len(), branches - nodes + 1);
+        }
+
+        // R1 and C4 close the loops of the RLC circuit, C4 is open at DC
+        let mut c = create_rlc_container();
+        c.create_nodes().unwrap();
+        let options = SolveOptions {
+            allow_dc_inductors: true,
+            ..SolveOptions::default()
+        };
+        let mut solver: NodeMatrixSolver =
+            Solver::new_with_options(Rc::new(RefCell::new(c)), options);
+        solver.solve().unwrap();
+        let currents: HashMap<usize, f64> = solver.co_tree_currents();
+        assert!((currents[&1].abs() - 2.).abs() < 1e-9);
+        assert_eq!(currents[&4], 0.);
+
+        // The link currents are the loop currents of the mesh solver
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut node: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        node.solve().unwrap();
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let mut mesh: MeshMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        mesh.solve().unwrap();
+
+        let currents: HashMap<usize, f64> = node.co_tree_currents();
+        assert_eq!(currents.len(), 2);
+        for (mesh_loop, current) in mesh.loops().iter().zip(mesh.loop_currents().unwrap()) {
+            let link: usize = mesh_loop[0].0;
+            assert!((currents[&link].abs() - current.abs()).abs() < 1e-9);
+            assert!((currents[&link] - mesh.co_tree_currents()[&link]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_check_causality() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert_eq!(solver.check_causality(), Ok(()));
+
+        // SRC(V)3 closes a triangle with SRC(V)1 and SRC(V)2 through ground
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 5., 1, 0);
+        c.add_element_between_nodes(VoltageSrc, 2., 1, 2);
+        c.add_element_between_nodes(VoltageSrc, 3., 2, 0);
+        c.add_element_between_nodes(Resistor, 1., 1, 0);
+        c.add_element_between_nodes(Resistor, 1., 2, 0);
+        c.create_nodes().unwrap();
+        let expected = Err(StatusError::Known(
+            "Voltage sources SRC(V)2, SRC(V)1, SRC(V)3 form a loop, the MNA equations are inconsistent"
+                .to_string(),
+        ));
+        let container = Rc::new(RefCell::new(c));
+        let mut matrix: NodeMatrixSolver = Solver::new(container.clone());
+        assert_eq!(matrix.check_causality(), expected);
+        assert_eq!(matrix.solve().map(|_| ()), expected);
+        let mut step: NodeStepSolver = Solver::new(container);
+        assert_eq!(step.solve().map(|_| ()), expected);
+    }
+
+    #[test]
+    fn test_minimum_cut_set() {
+        // Every node of a bridge has three elements and no cut is smaller
+        let mut c = bridge(1., 2., 3., 4., 5., 10.);
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c.clone())));
+        let cut: Vec<usize> = solver.minimum_cut_set();
+        assert_eq!(cut.len(), 3);
+        let shared = |node: usize| {
+            cut.iter().all(|x| {
+                let (a, b) = c.element_current_direction(*x);
+                a == node || b == node
+            })
+        };
+        assert!([0, 1, 2, 3].into_iter().any(shared));
+
+        // R7 and R8 hang a node off the bridge, two cuts isolate it
+        let mut c = bridge(1., 2., 3., 4., 5., 10.);
+        c.add_element_between_nodes(Resistor, 1., 2, 4);
+        c.add_element_between_nodes(Resistor, 1., 4, 0);
+        c.create_nodes().unwrap();
+        let solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(c)));
+        assert_eq!(solver.minimum_cut_set(), vec![7, 8]);
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut c = create_mna_container();
+        c.create_nodes().unwrap();
+        c.create_super_nodes().unwrap();
+        let mut solver: NodeStepSolver = Solver::new(Rc::new(RefCell::new(c)));
+
+        let reports: RefCell<Vec<(Phase, f32)>> = RefCell::new(vec![]);
+        let steps = solver
+            .solve_with_progress(&|phase: Phase, fraction: f32| {
+                reports.borrow_mut().push((phase, fraction))
+            })
+            .unwrap();
+
+        assert_eq!(
+            reports.borrow()[..4],
+            [
+                (MatrixFormation, 0.0),
+                (MatrixFormation, 1.0),
+                (Factorization, 0.0),
+                (Factorization, 1.0)
+            ]
+        );
+        let rendering: Vec<f32> = reports.borrow()[4..]
+            .iter()
+            .map(|(phase, fraction)| {
+                assert_eq!(*phase, Rendering);
+                *fraction
+            })
+            .collect();
+        assert_eq!(rendering.len(), steps.len() + 1);
+        assert_eq!(rendering.first(), Some(&0.0));
+        assert_eq!(rendering.last(), Some(&1.0));
+        assert!(rendering.windows(2).all(|x| x[0] < x[1]));
+    }
+
+    #[test]
+    fn test_strict() {
+        // R2 lists R1 twice, the duplicate is dropped with a warning
+        let container = || {
+            let mut c = Container::new();
+            c.add_element_no_id(Element::new(Ground, 1.0, vec![3, 2], vec![]));
+            c.add_element_no_id(Element::new(Resistor, 1.0, vec![3], vec![2]));
+            c.add_element_no_id(Element::new(Resistor, 1.0, vec![1, 1], vec![0, 3]));
+            c.add_element_no_id(Element::new(VoltageSrc, 1.0, vec![2, 0], vec![1]));
+            c.create_nodes().unwrap();
+            Rc::new(RefCell::new(c))
+        };
+
+        let lenient = container();
+        let mut solver: NodeMatrixSolver = Solver::new(lenient.clone());
+        assert!(solver.solve().is_ok());
+        assert!(!lenient.borrow().warnings().is_empty());
+
+        let options = SolveOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let strict = container();
+        let mut solver: NodeMatrixSolver =
+            Solver::new_with_options(strict.clone(), options.clone());
+        let expected = StatusError::StrictModeViolation(strict.borrow().warnings().clone());
+        assert_eq!(solver.solve().err(), Some(expected.clone()));
+        assert!(solver.node_voltages().is_err());
+        match expected {
+            StatusError::StrictModeViolation(warnings) => {
+                assert!(warnings.iter().all(|x| x.kind == DuplicateMembership));
+                let codes = lenient
+                    .borrow()
+                    .warnings()
+                    .iter()
+                    .map(|x| x.kind)
+                    .collect::<Vec<_>>();
+                assert_eq!(warnings.iter().map(|x| x.kind).collect::<Vec<_>>(), codes);
+            }
+            _ => unreachable!(),
+        }
+
+        let mut solver: NodeStepSolver = Solver::new_with_options(container(), options);
+        assert!(matches!(
+            solver.solve(),
+            Err(StatusError::StrictModeViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_solution_as_table() {
+        let mut solver: NodeMatrixSolver =
+            Solver::new(Rc::new(RefCell::new(create_mna_container())));
+        assert!(solver.format_solution_as_table(3).is_err());
+        solver.solve().unwrap();
+
+        let expected: String = [
+            "+---------+-------------+-------------+-----------+",
+            "| Element | Voltage (V) | Current (A) | Power (W) |",
+            "+---------+-------------+-------------+-----------+",
+            "| R1      |       8.000 |       4.000 |    32.000 |",
+            "| R2      |      -4.000 |      -1.000 |     4.000 |",
+            "| R3      |      24.000 |       3.000 |    72.000 |",
+            "| SRC(V)4 |     -32.000 |      -4.000 |  -128.000 |",
+            "| SRC(V)5 |      20.000 |       1.000 |    20.000 |",
+            "+---------+-------------+-------------+-----------+",
+            "+------+-------------+",
+            "| Node | Voltage (V) |",
+            "+------+-------------+",
+            "| 0    |       0.000 |",
+            "| 1    |      20.000 |",
+            "| 2    |      24.000 |",
+            "| 3    |      -8.000 |",
+            "+------+-------------+",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(solver.format_solution_as_table(3).unwrap(), expected);
+        assert!(solver
+            .format_solution_as_table(0)
+            .unwrap()
+            .contains("| R1      |           8 |           4 |        32 |"));
+    }
 }
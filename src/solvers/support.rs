@@ -0,0 +1,285 @@
+use crate::component::Component;
+use crate::component::Component::{
+    Capacitor, CurrentSrc, DependentCurrent, DependentVoltage, Ground, Inductor, Resistor,
+    VoltageSrc,
+};
+use crate::container::Container;
+use crate::solvers::solver::SolverType;
+use crate::solvers::solver::SolverType::{MeshMatrix, MeshStep, NodeMatrix, NodeStep};
+use crate::validation::{StatusError, Warning, WarningKind};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// How well a solver handles a component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Support {
+    Full,
+    /// Solved with a simplified model, the note explains the simplification.
+    Approximated(&'static str),
+    Unsupported,
+}
+
+/// Capability table for every solver and component combination
+///
+/// Consulted by the solvers before solving and by `get_capabilities`.
+pub fn supports(solver: SolverType, component: &Component) -> Support {
+    match (solver, component) {
+        (_, Ground | Resistor | VoltageSrc) => Support::Full,
+        (NodeMatrix | MeshMatrix | MeshStep, CurrentSrc) => Support::Full,
+        (NodeMatrix, DependentVoltage | DependentCurrent) => Support::Full,
+        (NodeStep, CurrentSrc) => Support::Approximated(
+            "Current sources are only included in the KCL equations of the step solver when the circuit has no voltage sources",
+        ),
+        (_, Capacitor) => {
+            Support::Approximated("Capacitors are treated as open circuits in DC analysis")
+        }
+        (NodeMatrix, Inductor) => {
+            Support::Approximated("Inductors are treated as short circuits in DC analysis")
+        }
+        _ => Support::Unsupported,
+    }
+}
+
+/// Machine readable description of a solver, listed by `get_capabilities`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SolverDescriptor {
+    pub solver: SolverType,
+    pub display_name: &'static str,
+    pub description: &'static str,
+    /// Classes every circuit needs to be solved.
+    pub required: Vec<Component>,
+    /// Classes the capability table marks as unsupported.
+    pub forbidden: Vec<Component>,
+    /// The solve returns worked steps, e.g. the equations and substitutions.
+    pub produces_steps: bool,
+    /// Node voltages and currents can be read from the solver after the solve.
+    pub produces_values: bool,
+    pub complexity: &'static str,
+    /// Minimal `ContainerSetup` JSON the solver solves, a 10 V source across two 1 Ω resistors.
+    pub example: &'static str,
+}
+
+const DIVIDER_EXAMPLE: &str = r#"{"elements": [
+    {"id": 0, "value": 0.0, "class": "ground", "positive": [1, 3], "negative": []},
+    {"id": 1, "value": 10.0, "class": "voltage_src", "positive": [2], "negative": [0]},
+    {"id": 2, "value": 1.0, "class": "resistor", "positive": [1], "negative": [3]},
+    {"id": 3, "value": 1.0, "class": "resistor", "positive": [2], "negative": [0]}
+]}"#;
+
+impl SolverType {
+    /// Description of the solver for the frontend
+    ///
+    /// Every solver has to be matched here, a new solver without a descriptor does not compile.
+    pub fn descriptor(&self) -> SolverDescriptor {
+        let forbidden: Vec<Component> = Component::ALL
+            .iter()
+            .filter(|x| supports(*self, x) == Support::Unsupported)
+            .cloned()
+            .collect();
+        match self {
+            NodeMatrix => SolverDescriptor {
+                solver: *self,
+                display_name: "Node matrix (MNA)",
+                description: "Modified nodal analysis. Builds the A matrix from the conductances, the voltage source incidences and the gains of the voltage controlled sources, then solves A x = z for every node voltage and voltage source current at once. Shows the matrices rather than the algebra behind them.",
+                required: vec![Ground],
+                forbidden,
+                produces_steps: true,
+                produces_values: true,
+                complexity: "O((n + m)^3) for n nodes and m voltage sources, from the LU factorization",
+                example: DIVIDER_EXAMPLE,
+            },
+            NodeStep => SolverDescriptor {
+                solver: *self,
+                display_name: "Node steps",
+                description: "Nodal analysis worked by hand. Writes KCL at every node with Ohm's law for the branch currents and substitutes until each node voltage is known, the way a textbook solution reads.",
+                required: vec![Ground],
+                forbidden,
+                produces_steps: true,
+                produces_values: true,
+                complexity: "Symbolic expressions grow with every substitution, bounded by SolveOptions::max_symbolic_terms",
+                example: DIVIDER_EXAMPLE,
+            },
+            MeshMatrix => SolverDescriptor {
+                solver: *self,
+                display_name: "Mesh matrix",
+                description: "Loop analysis. Assigns a current to every independent loop, builds the A matrix from the loop resistances and solves A x = z for the loop currents. Current sources add their voltage as an unknown, so the loops through them are solved as a supermesh.",
+                required: vec![Ground],
+                forbidden,
+                produces_steps: true,
+                produces_values: true,
+                complexity: "O((l + k)^3) for l loops and k current sources, from the LU factorization",
+                example: DIVIDER_EXAMPLE,
+            },
+            MeshStep => SolverDescriptor {
+                solver: *self,
+                display_name: "Mesh steps",
+                description: "Loop analysis worked by hand. Writes KVL around every loop with the resistor drops in terms of the loop currents, collects the equations into one system and reads every branch current off the solved loop currents.",
+                required: vec![Ground],
+                forbidden,
+                produces_steps: true,
+                produces_values: true,
+                complexity: "O((l + k)^3) for l loops and k current sources, the system of the mesh matrix solver",
+                example: DIVIDER_EXAMPLE,
+            },
+        }
+    }
+}
+
+/// Check every element of the container against the capability table
+///
+/// Unsupported components fail with a list of the solvers that do support them,
+/// approximated components are recorded as warnings on the container.
+pub(crate) fn check_support(
+    solver: SolverType,
+    container: &Rc<RefCell<Container>>,
+) -> Result<(), StatusError> {
+    let mut unsupported: Vec<Component> = Vec::new();
+    let mut approximated: Vec<(Component, &'static str)> = Vec::new();
+    for element in container.borrow().get_elements() {
+        let class: Component = element.borrow().class.clone();
+        match supports(solver, &class) {
+            Support::Full => {}
+            Support::Approximated(note) => {
+                if !approximated.iter().any(|(x, _)| *x == class) {
+                    approximated.push((class, note));
+                }
+            }
+            Support::Unsupported => {
+                if !unsupported.contains(&class) {
+                    unsupported.push(class);
+                }
+            }
+        }
+    }
+
+    if !unsupported.is_empty() {
+        let alternatives: Vec<SolverType> = SolverType::ALL
+            .iter()
+            .filter(|x| {
+                unsupported
+                    .iter()
+                    .all(|class| supports(**x, class) != Support::Unsupported)
+            })
+            .copied()
+            .collect();
+        return Err(StatusError::Unsupported {
+            solver,
+            components: unsupported,
+            alternatives,
+        });
+    }
+
+    for (class, note) in approximated {
+        container.borrow_mut().add_warning(Warning::new(
+            WarningKind::Approximated,
+            format!("{:?}: {}", class, note),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::Element;
+    use crate::interfaces::ContainerSetup;
+    use crate::solvers::mesh_matrix_solver::MeshMatrixSolver;
+    use crate::solvers::mesh_step_solver::MeshStepSolver;
+    use crate::solvers::node_matrix_solver::NodeMatrixSolver;
+    use crate::solvers::node_step_solver::NodeStepSolver;
+    use crate::solvers::solver::{NodeSolver, Solver};
+    use crate::util::create_mna_container;
+    use crate::validation::Validation;
+
+    #[test]
+    fn test_supports() {
+        assert_eq!(
+            supports(NodeMatrix, &Capacitor),
+            Support::Approximated("Capacitors are treated as open circuits in DC analysis")
+        );
+        assert_eq!(
+            supports(NodeMatrix, &Inductor),
+            Support::Approximated("Inductors are treated as short circuits in DC analysis")
+        );
+        assert_eq!(supports(NodeStep, &Inductor), Support::Unsupported);
+        assert_eq!(supports(NodeStep, &Resistor), Support::Full);
+        assert_eq!(supports(MeshMatrix, &CurrentSrc), Support::Full);
+        assert_eq!(supports(MeshStep, &CurrentSrc), Support::Full);
+        assert_eq!(supports(NodeMatrix, &DependentVoltage), Support::Full);
+        assert_eq!(supports(NodeStep, &DependentCurrent), Support::Unsupported);
+    }
+
+    #[test]
+    fn test_descriptors() {
+        for solver in SolverType::ALL {
+            let descriptor: SolverDescriptor = solver.descriptor();
+            assert_eq!(descriptor.solver, solver);
+            assert!(!descriptor.display_name.is_empty() && !descriptor.description.is_empty());
+
+            // The example has to solve with its own solver
+            let setup: ContainerSetup = serde_json::from_str(descriptor.example).unwrap();
+            let mut container: Container = Container::from(setup);
+            let classes: Vec<Component> = container
+                .get_elements()
+                .iter()
+                .map(|x| x.borrow().class.clone())
+                .collect();
+            assert!(descriptor.required.iter().all(|x| classes.contains(x)));
+            assert!(!descriptor.forbidden.iter().any(|x| classes.contains(x)));
+            container.validate().unwrap();
+            container.create_nodes().unwrap();
+            container.create_super_nodes().unwrap();
+            let container = Rc::new(RefCell::new(container));
+            let voltage: f64 = match solver {
+                NodeMatrix => {
+                    let mut solver: NodeMatrixSolver = Solver::new(container);
+                    assert!(!solver.solve().unwrap().is_empty());
+                    solver.node_voltage(2).unwrap()
+                }
+                NodeStep => {
+                    let mut solver: NodeStepSolver = Solver::new(container);
+                    assert!(!solver.solve().unwrap().is_empty());
+                    solver.node_voltage(2).unwrap()
+                }
+                MeshMatrix => {
+                    let mut solver: MeshMatrixSolver = Solver::new(container);
+                    assert!(!solver.solve().unwrap().is_empty());
+                    solver.node_voltage(2).unwrap()
+                }
+                MeshStep => {
+                    let mut solver: MeshStepSolver = Solver::new(container);
+                    assert!(!solver.solve().unwrap().is_empty());
+                    solver.node_voltage(2).unwrap()
+                }
+            };
+            assert!((voltage.abs() - 5.0).abs() < 1e-9, "{:?}", solver);
+        }
+    }
+
+    #[test]
+    fn test_check_support() {
+        let mut container = create_mna_container();
+        container.add_element_no_id(Element::new(Capacitor, 1.0, vec![4], vec![0]));
+        let container = Rc::new(RefCell::new(container));
+        assert_eq!(check_support(NodeMatrix, &container), Ok(()));
+        assert_eq!(container.borrow().warnings().len(), 1);
+        assert_eq!(
+            container.borrow().warnings()[0].kind,
+            WarningKind::Approximated
+        );
+
+        container
+            .borrow_mut()
+            .add_element_no_id(Element::new(Inductor, 1.0, vec![4], vec![0]));
+        assert_eq!(check_support(NodeMatrix, &container), Ok(()));
+        assert_eq!(
+            check_support(NodeStep, &container),
+            Err(StatusError::Unsupported {
+                solver: NodeStep,
+                components: vec![Inductor],
+                alternatives: vec![NodeMatrix],
+            })
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use crate::component::Component::{CurrentSrc, Ground};
+use crate::container::{current_source_node_message, floating_node_message, Container};
+use crate::util::PrettyPrint;
+use crate::validation::StatusError::Known;
+use crate::validation::{Severity, StatusError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Machine readable fix for an issue, applied by the editor
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SuggestedAction {
+    /// Join two nodes, e.g. with a wire.
+    ConnectNodes { from: usize, to: usize },
+    /// Make the node the reference node by attaching a ground element.
+    AddGround { node: usize },
+    /// Give the current of a source a path by adding a resistor across it.
+    AddParallelResistor { element: usize },
+}
+
+/// Issue found by `Container::diagnose`, with a hint on how to fix it when one is known
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: StatusError,
+    pub suggestion: Option<String>,
+    pub suggested_action: Option<SuggestedAction>,
+}
+
+impl Container {
+    /// `validate_all` with suggestions for the issues that have an obvious fix
+    ///
+    /// * A floating node is matched with the node it most likely belongs to, the grounded
+    ///   node holding the element whose name is closest to one on the floating node. Ties
+    ///   go to the element with the closest id, elements added one after the other are
+    ///   usually drawn next to each other. Elements have no positions to compare.
+    /// * A circuit without ground gets the node with the most members as reference.
+    /// * A node that only connects current sources gets a resistor across its first source.
+    pub fn diagnose(&self) -> Vec<Diagnostic> {
+        let issues: Vec<(Severity, StatusError)> = self.validate_all();
+        let container: Option<Container> = self.with_nodes().ok();
+
+        let mut suggestions: Vec<(StatusError, String, SuggestedAction)> = Vec::new();
+        if let Some(container) = &container {
+            suggestions.extend(container.floating_node_suggestions());
+            suggestions.extend(container.current_source_suggestions());
+            suggestions.extend(container.ground_suggestion());
+        }
+
+        issues
+            .into_iter()
+            .map(|(severity, error)| {
+                let suggestion = suggestions.iter().find(|(x, _, _)| *x == error);
+                Diagnostic {
+                    severity,
+                    suggestion: suggestion.map(|(_, text, _)| text.clone()),
+                    suggested_action: suggestion.map(|(_, _, action)| action.clone()),
+                    error,
+                }
+            })
+            .collect()
+    }
+
+    /// Names of the elements at each node, ground is node 0
+    fn node_members(&self) -> BTreeMap<usize, Vec<(usize, String)>> {
+        let mut members: BTreeMap<usize, Vec<(usize, String)>> = BTreeMap::new();
+        for element in self.get_elements() {
+            let element = element.borrow();
+            if element.class == Ground || element.is_detached() {
+                continue;
+            }
+            for side in [&element.positive, &element.negative] {
+                if let Some(node) = self.node_at(element.id, side) {
+                    members
+                        .entry(node)
+                        .or_default()
+                        .push((element.id, element.basic_string()));
+                }
+            }
+        }
+        members
+    }
+
+    fn floating_node_suggestions(&self) -> Vec<(StatusError, String, SuggestedAction)> {
+        let grounded: Vec<usize> = self.grounded_nodes();
+        if grounded.len() < 2 {
+            return vec![];
+        }
+        let members = self.node_members();
+
+        let mut suggestions = Vec::new();
+        for node in self
+            .node_ids()
+            .into_iter()
+            .filter(|x| !grounded.contains(x))
+        {
+            // Closest pair of names, then of ids, between this node and a grounded one
+            let mut best: Option<((usize, usize), usize, &String, &String)> = None;
+            for target in grounded.iter().filter(|x| **x != 0) {
+                for (id, name) in members.get(&node).into_iter().flatten() {
+                    for (other_id, other) in members.get(target).into_iter().flatten() {
+                        let score = (edit_distance(name, other), id.abs_diff(*other_id));
+                        if best.map_or(true, |(x, _, _, _)| score < x) {
+                            best = Some((score, *target, name, other));
+                        }
+                    }
+                }
+            }
+
+            if let Some((_, target, name, other)) = best {
+                suggestions.push((
+                    Known(floating_node_message(node)),
+                    format!(
+                        "Did you mean to connect Node {} to Node {}? {} is next to {} there",
+                        node, target, name, other
+                    ),
+                    SuggestedAction::ConnectNodes {
+                        from: node,
+                        to: target,
+                    },
+                ));
+            }
+        }
+        suggestions
+    }
+
+    fn current_source_suggestions(&self) -> Vec<(StatusError, String, SuggestedAction)> {
+        let members = self.node_members();
+        self.current_source_nodes()
+            .into_iter()
+            .filter_map(|node| {
+                let (id, name) = members
+                    .get(&node)?
+                    .iter()
+                    .find(|(id, _)| self.get_elements()[*id].borrow().class == CurrentSrc)?;
+                Some((
+                    Known(current_source_node_message(node)),
+                    format!(
+                        "Add a resistor in parallel with {} so current can leave Node {}",
+                        name, node
+                    ),
+                    SuggestedAction::AddParallelResistor { element: *id },
+                ))
+            })
+            .collect()
+    }
+
+    fn ground_suggestion(&self) -> Option<(StatusError, String, SuggestedAction)> {
+        let grounds: usize = self
+            .get_elements()
+            .iter()
+            .filter(|x| x.borrow().class == Ground)
+            .count();
+        if grounds != 0 {
+            return None;
+        }
+
+        let (node, count) = self
+            .nodes()
+            .iter()
+            .filter_map(|x| x.upgrade())
+            .map(|x| (x.borrow().id, x.borrow().members.len()))
+            .fold(None, |best: Option<(usize, usize)>, x| match best {
+                Some(best) if best.1 >= x.1 => Some(best),
+                _ => Some(x),
+            })?;
+        Some((
+            Known("Multiple Grounds".to_string()),
+            format!(
+                "The circuit has no ground, add one at Node {} which connects the most elements ({})",
+                node, count
+            ),
+            SuggestedAction::AddGround { node },
+        ))
+    }
+}
+
+/// Levenshtein distance between two names, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current: Vec<usize> = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution: usize = previous[j] + usize::from(x != *y);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::Component::{CurrentSrc, Resistor, VoltageSrc};
+    use crate::container::Container;
+    use crate::elements::Element;
+    use crate::suggestion::SuggestedAction::{AddGround, AddParallelResistor, ConnectNodes};
+    use crate::suggestion::{edit_distance, Diagnostic};
+    use crate::validation::StatusError::Known;
+
+    fn find<'a>(diagnostics: &'a [Diagnostic], message: &str) -> &'a Diagnostic {
+        diagnostics
+            .iter()
+            .find(|x| x.error == Known(message.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("R3", "R3"), 0);
+        assert_eq!(edit_distance("R3", "R12"), 2);
+        assert_eq!(edit_distance("SRC(V)1", "SRC(C)1"), 1);
+        assert_eq!(edit_distance("", "R1"), 2);
+    }
+
+    #[test]
+    fn test_floating_node_suggestion() {
+        // A triangle of resistors that was never wired to the divider
+        let mut c = Container::new();
+        c.add_element_between_nodes(VoltageSrc, 10., 0, 1);
+        c.add_element_between_nodes(Resistor, 1., 1, 2);
+        c.add_element_between_nodes(Resistor, 1., 2, 0);
+        c.add_element_between_nodes(Resistor, 1., 3, 4);
+        c.add_element_between_nodes(Resistor, 1., 4, 5);
+        c.add_element_between_nodes(Resistor, 1., 5, 3);
+
+        let diagnostics: Vec<Diagnostic> = c.diagnose();
+        assert_eq!(diagnostics.len(), 3);
+        let diagnostic = find(
+            &diagnostics,
+            "Node 3 has no path to ground, its voltage is undefined",
+        );
+        assert_eq!(
+            diagnostic.suggestion,
+            Some("Did you mean to connect Node 3 to Node 2? R4 is next to R3 there".to_string())
+        );
+        assert_eq!(
+            diagnostic.suggested_action,
+            Some(ConnectNodes { from: 3, to: 2 })
+        );
+        assert!(diagnostics.iter().all(|x| x.suggested_action.is_some()));
+    }
+
+    #[test]
+    fn test_ground_suggestion() {
+        // Four nodes without a ground, node 1 joins four elements
+        let mut c = Container::new();
+        c.add_element_no_id(Element::new(Resistor, 1., vec![1, 2, 5], vec![3, 5]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![3, 4], vec![0, 2, 5]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![0, 1, 5], vec![4]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![0, 5], vec![1, 4]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![2], vec![1, 3]));
+        c.add_element_no_id(Element::new(VoltageSrc, 5., vec![0, 1, 2], vec![0, 3]));
+
+        let diagnostics: Vec<Diagnostic> = c.diagnose();
+        let diagnostic = find(&diagnostics, "Multiple Grounds");
+        assert_eq!(
+            diagnostic.suggestion,
+            Some(
+                "The circuit has no ground, add one at Node 1 which connects the most elements (4)"
+                    .to_string()
+            )
+        );
+        assert_eq!(diagnostic.suggested_action, Some(AddGround { node: 1 }));
+        assert!(!diagnostics
+            .iter()
+            .any(|x| matches!(x.suggested_action, Some(ConnectNodes { .. }))));
+    }
+
+    #[test]
+    fn test_current_source_suggestion() {
+        // Node 1 sits between two current sources in series
+        let mut c = Container::new();
+        c.add_element_between_nodes(CurrentSrc, 1., 0, 1);
+        c.add_element_between_nodes(CurrentSrc, 2., 1, 2);
+        c.add_element_between_nodes(Resistor, 3., 2, 0);
+
+        let diagnostics: Vec<Diagnostic> = c.diagnose();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].error,
+            Known(
+                "Node 1 only connects current sources, the currents into it cannot balance"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            diagnostics[0].suggestion,
+            Some("Add a resistor in parallel with SRC(C)1 so current can leave Node 1".to_string())
+        );
+        assert_eq!(
+            diagnostics[0].suggested_action,
+            Some(AddParallelResistor { element: 1 })
+        );
+    }
+}
@@ -78,6 +78,11 @@ impl Tool {
         Tool::create(SuperNode, elements)
     }
 
+    /// Create a supermesh from the elements
+    pub(crate) fn create_supermesh(elements: Vec<Weak<RefCell<Element>>>) -> Tool {
+        Tool::create(SuperMesh, elements)
+    }
+
     fn create(class: ToolType, elements: Vec<Weak<RefCell<Element>>>) -> Tool {
         let mut tool = Tool {
             id: 0,
@@ -96,6 +101,22 @@ impl Tool {
             .any(|e| e.upgrade().unwrap().id() == element.id())
     }
 
+    /// Remove repeated members, returning the ids of the dropped duplicates
+    pub(crate) fn dedup_members(&mut self) -> Vec<usize> {
+        let mut seen: Vec<usize> = Vec::new();
+        let mut removed: Vec<usize> = Vec::new();
+        self.members.retain(|x| {
+            let id: usize = x.upgrade().unwrap().borrow().id;
+            if seen.contains(&id) {
+                removed.push(id);
+                return false;
+            }
+            seen.push(id);
+            true
+        });
+        removed
+    }
+
     pub(crate) fn contains_all(&self, elements: &Vec<Weak<RefCell<Element>>>) -> bool {
         self.members
             .iter()
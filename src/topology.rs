@@ -0,0 +1,657 @@
+use crate::component::Component;
+use crate::component::Component::Ground;
+use crate::container::Container;
+use crate::tools::ToolType::{Mesh, Node, SuperMesh, SuperNode};
+use crate::tools::{Tool, ToolType};
+use crate::validation::StatusError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Owned copy of a tool, members are listed by element id
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ToolSummary {
+    pub id: usize,
+    pub members: Vec<usize>,
+}
+
+impl From<&Tool> for ToolSummary {
+    fn from(tool: &Tool) -> Self {
+        ToolSummary {
+            id: tool.id,
+            members: tool.member_ids(),
+        }
+    }
+}
+
+/// Counts describing the size of the circuit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TopologyStats {
+    /// Every element but ground and detached elements.
+    pub branches: usize,
+    /// Branches minus non ground nodes, the number of independent loops of a connected circuit.
+    pub independent_loops: usize,
+}
+
+/// Nodes, supernodes and meshes of a circuit without any reference into the container
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Topology {
+    pub nodes: Vec<ToolSummary>,
+    pub super_nodes: Vec<ToolSummary>,
+    pub meshes: Vec<ToolSummary>,
+    pub super_meshes: Vec<ToolSummary>,
+    /// Id of the ground element, node 0.
+    pub reference: Option<usize>,
+    pub stats: TopologyStats,
+}
+
+impl Topology {
+    /// Snapshot of the tools the container holds, nothing is created.
+    pub(crate) fn from_container(container: &Container) -> Topology {
+        let tools = |class: ToolType| -> Vec<ToolSummary> {
+            container
+                .get_tools(class)
+                .iter()
+                .filter_map(|x| x.upgrade())
+                .map(|x| ToolSummary::from(&*x.borrow()))
+                .collect()
+        };
+        let nodes: Vec<ToolSummary> = tools(Node);
+        let branches: usize = container.branch_count();
+
+        Topology {
+            stats: TopologyStats {
+                branches,
+                independent_loops: branches.saturating_sub(nodes.len()),
+            },
+            nodes,
+            super_nodes: tools(SuperNode),
+            meshes: tools(Mesh),
+            super_meshes: tools(SuperMesh),
+            reference: container
+                .get_elements()
+                .iter()
+                .find(|x| x.borrow().class == Ground)
+                .map(|x| x.borrow().id),
+        }
+    }
+}
+
+/// Nodes that changed with one edit of an `EditSession`
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct TopologyDelta {
+    /// Element id to `[positive terminal node, negative terminal node]` for every element
+    /// with a terminal that moved to another node. Ground maps to `[0]` and a removed
+    /// element to `[]`.
+    pub terminals: BTreeMap<usize, Vec<usize>>,
+    /// Whether the edit split a node and every node was computed again.
+    pub rebuilt: bool,
+}
+
+/// Node of every element terminal, kept up to date one edit at a time
+///
+/// Terminals are numbered `2 * id` for the positive and `2 * id + 1` for the negative side
+/// of an element and grouped through the connection lists, each group is a node. Ground
+/// is node 0, any other node is numbered after its lowest terminal plus one so its id is
+/// the same however the groups were built. The numbers are unrelated to the node ids of
+/// `Container::create_nodes`.
+///
+/// Adding an element only merges the groups its terminals touch. Removing one keeps the
+/// groups unless the element was the only link between the rest of a group, then the
+/// groups are marked stale. The container generation they belong to is recorded, stale
+/// groups and edits made around the topology are recomputed from scratch on the next `sync`.
+#[derive(Clone, Debug, Default)]
+pub struct IncrementalTopology {
+    group: Vec<Option<usize>>, // Terminal to the group holding it
+    groups: BTreeMap<usize, BTreeSet<usize>>, // Group to its terminals
+    next_group: usize,
+    ground: Option<usize>,   // Positive terminal of the ground element
+    generation: Option<u64>, // Container generation the groups belong to, None when stale
+}
+
+impl IncrementalTopology {
+    pub fn new(container: &Container) -> IncrementalTopology {
+        let mut topology: IncrementalTopology = IncrementalTopology::default();
+        topology.sync(container);
+        topology
+    }
+
+    /// Recompute every group if the container changed since they were built
+    ///
+    /// Returns true if the groups were recomputed.
+    pub fn sync(&mut self, container: &Container) -> bool {
+        if self.generation == Some(container.generation()) {
+            return false;
+        }
+        *self = IncrementalTopology::default();
+        for id in 0..container.get_elements().len() {
+            self.join(container, id);
+        }
+        self.generation = Some(container.generation());
+        true
+    }
+
+    /// Nodes of every element, in the format of `TopologyDelta::terminals`
+    pub fn terminals(&mut self, container: &Container) -> BTreeMap<usize, Vec<usize>> {
+        self.sync(container);
+        (0..container.get_elements().len())
+            .map(|id| (id, self.element_nodes(container, id)))
+            .collect()
+    }
+
+    /// Merge the groups touched by elements that were just added to the container
+    ///
+    /// The groups have to be in sync with the container from before the elements were added.
+    /// Connection lists are taken to be symmetric, every element listing an added element
+    /// is listed by it.
+    pub(crate) fn elements_added(&mut self, container: &Container, ids: &[usize]) -> TopologyDelta {
+        let touched: BTreeSet<usize> = ids
+            .iter()
+            .flat_map(|id| {
+                let element = container.get_elements()[*id].borrow();
+                element
+                    .positive
+                    .iter()
+                    .chain(element.negative.iter())
+                    .flat_map(|x| [2 * x, 2 * x + 1])
+                    .collect::<Vec<usize>>()
+            })
+            .filter_map(|x| self.group_of(x))
+            .collect();
+        let before: BTreeMap<usize, usize> = self.snapshot(&touched);
+
+        for id in ids {
+            self.join(container, *id);
+        }
+        self.generation = Some(container.generation());
+        self.delta(container, before, ids, false)
+    }
+
+    /// Drop the terminals of an element that was just removed from the container
+    ///
+    /// The groups have to be in sync with the container from before the removal. A group
+    /// the removal splits marks every group stale, they are recomputed before the delta
+    /// is taken.
+    pub(crate) fn element_removed(&mut self, container: &Container, id: usize) -> TopologyDelta {
+        let terminals: [usize; 2] = [2 * id, 2 * id + 1];
+        let touched: BTreeSet<usize> = terminals.iter().filter_map(|x| self.group_of(*x)).collect();
+        let before: BTreeMap<usize, usize> = self.snapshot(&touched);
+
+        for terminal in terminals {
+            let group: usize = match self.group_of(terminal) {
+                Some(group) => group,
+                None => continue,
+            };
+            self.group[terminal] = None;
+            let members: &mut BTreeSet<usize> = self.groups.get_mut(&group).unwrap();
+            members.remove(&terminal);
+            if members.is_empty() {
+                self.groups.remove(&group);
+            }
+        }
+
+        let split: bool = touched.iter().any(|group| match self.groups.get(group) {
+            Some(members) => !is_linked(container, members),
+            None => false,
+        });
+        self.generation = match split {
+            true => None,
+            false => Some(container.generation()),
+        };
+        let rebuilt: bool = self.sync(container);
+        self.delta(container, before, &[id], rebuilt)
+    }
+
+    /// Put the terminals of an element in groups, joined with the terminals they connect to
+    fn join(&mut self, container: &Container, id: usize) {
+        let element = container.get_elements()[id].borrow();
+        if element.class == Ground {
+            // Both sides of ground are the same node
+            self.union(2 * id, 2 * id + 1);
+            self.ground = Some(2 * id);
+        } else if element.is_detached() {
+            return;
+        }
+
+        for (terminal, side) in [(2 * id, &element.positive), (2 * id + 1, &element.negative)] {
+            self.insert(terminal);
+            for other in side {
+                if let Some(facing) = facing_terminal(container, *other, id) {
+                    self.union(terminal, facing);
+                }
+            }
+        }
+
+        // The links listed by the other side, they differ when it lists this element twice
+        let listed: BTreeSet<usize> = element
+            .positive
+            .iter()
+            .chain(element.negative.iter())
+            .copied()
+            .collect();
+        for other in listed {
+            let other_element = match container.get_elements().get(other) {
+                Some(x) if x.borrow().class == Ground || !x.borrow().is_detached() => x.borrow(),
+                _ => continue,
+            };
+            let sides = [
+                (2 * other, &other_element.positive),
+                (2 * other + 1, &other_element.negative),
+            ];
+            for (terminal, side) in sides {
+                if !side.contains(&id) {
+                    continue;
+                }
+                if let Some(facing) = facing_terminal(container, id, other) {
+                    self.union(terminal, facing);
+                }
+            }
+        }
+    }
+
+    /// Give a terminal its own group if it has none
+    fn insert(&mut self, terminal: usize) -> usize {
+        if self.group.len() <= terminal {
+            self.group.resize(terminal + 1, None);
+        }
+        if let Some(group) = self.group[terminal] {
+            return group;
+        }
+        let group: usize = self.next_group;
+        self.next_group += 1;
+        self.group[terminal] = Some(group);
+        self.groups.insert(group, BTreeSet::from([terminal]));
+        group
+    }
+
+    /// Merge the groups of two terminals, the smaller group is moved into the larger one
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.insert(a), self.insert(b));
+        if a == b {
+            return;
+        }
+        let (large, small) = match self.groups[&a].len() >= self.groups[&b].len() {
+            true => (a, b),
+            false => (b, a),
+        };
+        let moved: BTreeSet<usize> = self.groups.remove(&small).unwrap();
+        for terminal in moved.iter() {
+            self.group[*terminal] = Some(large);
+        }
+        self.groups.get_mut(&large).unwrap().extend(moved);
+    }
+
+    fn group_of(&self, terminal: usize) -> Option<usize> {
+        self.group.get(terminal).copied().flatten()
+    }
+
+    fn node_of(&self, terminal: usize) -> Option<usize> {
+        let members: &BTreeSet<usize> = &self.groups[&self.group_of(terminal)?];
+        match self.ground {
+            Some(ground) if members.contains(&ground) => Some(0),
+            _ => members.first().map(|x| x + 1),
+        }
+    }
+
+    fn element_nodes(&self, container: &Container, id: usize) -> Vec<usize> {
+        let element = container.get_elements()[id].borrow();
+        match (element.class, element.is_detached()) {
+            (Ground, _) => vec![0],
+            (_, true) => vec![],
+            _ => [2 * id, 2 * id + 1]
+                .iter()
+                .filter_map(|x| self.node_of(*x))
+                .collect(),
+        }
+    }
+
+    /// Node of every terminal in the groups
+    fn snapshot(&self, groups: &BTreeSet<usize>) -> BTreeMap<usize, usize> {
+        groups
+            .iter()
+            .flat_map(|x| self.groups[x].iter())
+            .map(|x| (*x, self.node_of(*x).unwrap()))
+            .collect()
+    }
+
+    /// Elements edited or with a terminal that moved away from its node in `before`
+    fn delta(
+        &self,
+        container: &Container,
+        before: BTreeMap<usize, usize>,
+        edited: &[usize],
+        rebuilt: bool,
+    ) -> TopologyDelta {
+        let mut elements: BTreeSet<usize> = edited.iter().copied().collect();
+        for (terminal, node) in before {
+            if self.node_of(terminal) != Some(node) {
+                elements.insert(terminal / 2);
+            }
+        }
+        TopologyDelta {
+            terminals: elements
+                .into_iter()
+                .map(|id| (id, self.element_nodes(container, id)))
+                .collect(),
+            rebuilt,
+        }
+    }
+}
+
+/// Terminal of `other` that connects to element `id`
+///
+/// That is the side of `other` listing `id`, or its positive side if it does not list it.
+/// `None` for a detached or missing element.
+fn facing_terminal(container: &Container, other: usize, id: usize) -> Option<usize> {
+    let element = container.get_elements().get(other)?.borrow();
+    if element.class != Ground && element.is_detached() {
+        return None;
+    }
+    match element.negative.contains(&id) && !element.positive.contains(&id) {
+        true => Some(2 * other + 1),
+        false => Some(2 * other),
+    }
+}
+
+/// Whether the terminals are connected through the connection lists without leaving them
+fn is_linked(container: &Container, terminals: &BTreeSet<usize>) -> bool {
+    // Lists are not always symmetric, collect the links in both directions first
+    let mut links: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for terminal in terminals {
+        let (id, positive) = (terminal / 2, terminal % 2 == 0);
+        let element = container.get_elements()[id].borrow();
+        let mut facing: Vec<usize> = Vec::new();
+        let side: &Vec<usize> = if positive {
+            &element.positive
+        } else {
+            &element.negative
+        };
+        if element.class == Ground {
+            // Both sides of ground are the same node
+            facing.push(terminal ^ 1);
+            facing.extend(
+                element
+                    .positive
+                    .iter()
+                    .chain(element.negative.iter())
+                    .filter_map(|x| facing_terminal(container, *x, id)),
+            );
+        } else {
+            facing.extend(
+                side.iter()
+                    .filter_map(|x| facing_terminal(container, *x, id)),
+            );
+        }
+        for other in facing.into_iter().filter(|x| terminals.contains(x)) {
+            links.entry(*terminal).or_default().push(other);
+            links.entry(other).or_default().push(*terminal);
+        }
+    }
+
+    let start: usize = match terminals.first() {
+        Some(start) => *start,
+        None => return true,
+    };
+    let mut seen: BTreeSet<usize> = BTreeSet::from([start]);
+    let mut queue: VecDeque<usize> = VecDeque::from([start]);
+    while let Some(terminal) = queue.pop_front() {
+        for next in links.get(&terminal).into_iter().flatten() {
+            if seen.insert(*next) {
+                queue.push_back(*next);
+            }
+        }
+    }
+    seen.len() == terminals.len()
+}
+
+/// A container edited one element at a time by an editor
+///
+/// Every edit returns the nodes it changed instead of the whole topology.
+pub struct EditSession {
+    container: Container,
+    topology: IncrementalTopology,
+}
+
+impl EditSession {
+    pub fn new(container: Container) -> EditSession {
+        let topology: IncrementalTopology = IncrementalTopology::new(&container);
+        EditSession {
+            container,
+            topology,
+        }
+    }
+
+    pub fn container(&self) -> &Container {
+        &self.container
+    }
+
+    /// Add an element between two node labels, see `Container::add_element_between_nodes`
+    ///
+    /// Returns the id of the element and the nodes that changed.
+    pub fn add_element_between_nodes(
+        &mut self,
+        class: Component,
+        value: f64,
+        node_a: usize,
+        node_b: usize,
+    ) -> (usize, TopologyDelta) {
+        self.topology.sync(&self.container);
+        let count: usize = self.container.get_elements().len();
+        let id: usize = self
+            .container
+            .add_element_between_nodes(class, value, node_a, node_b);
+        // The ground element is added along with the first element
+        let added: Vec<usize> = (count..self.container.get_elements().len()).collect();
+        (id, self.topology.elements_added(&self.container, &added))
+    }
+
+    /// Remove an element, see `Container::remove_element`
+    pub fn remove_element(&mut self, id: usize) -> Result<TopologyDelta, StatusError> {
+        self.topology.sync(&self.container);
+        self.container.remove_element(id)?;
+        Ok(self.topology.element_removed(&self.container, id))
+    }
+
+    /// Nodes of every element, in the format of `TopologyDelta::terminals`
+    pub fn terminals(&mut self) -> BTreeMap<usize, Vec<usize>> {
+        self.topology.terminals(&self.container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::Component::{Ground, Resistor, VoltageSrc};
+    use crate::container::Container;
+    use crate::elements::Element;
+    use crate::tools::ToolType;
+    use crate::tools::ToolType::{Mesh, Node, SuperNode};
+    use crate::topology::{EditSession, IncrementalTopology, ToolSummary, Topology, TopologyDelta};
+    use crate::util::*;
+    use std::collections::BTreeMap;
+
+    fn summaries(container: &Container, class: ToolType) -> Vec<ToolSummary> {
+        container
+            .get_tools(class)
+            .iter()
+            .map(|x| ToolSummary::from(&*x.upgrade().unwrap().borrow()))
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_topology() {
+        let fixtures: Vec<fn() -> Container> = vec![
+            create_basic_container,
+            create_basic_supernode_container,
+            create_basic_supermesh_container,
+            create_mna_container,
+            create_mna_container_2,
+            create_ladder_container,
+        ];
+        for fixture in fixtures {
+            let container: Container = fixture();
+            let topology: Topology = container.analyze_topology().unwrap();
+            // The container itself is left untouched
+            assert!(container.nodes().is_empty());
+
+            let mut expected: Container = fixture();
+            expected.create_nodes().unwrap();
+            expected.create_super_nodes().unwrap();
+            expected.create_meshes();
+            assert_eq!(topology.nodes, summaries(&expected, Node));
+            assert_eq!(topology.super_nodes, summaries(&expected, SuperNode));
+            assert_eq!(topology.meshes, summaries(&expected, Mesh));
+            assert!(topology.super_meshes.is_empty());
+            assert_eq!(topology.reference, Some(0));
+
+            // Tools that already exist are reused
+            assert_eq!(expected.analyze_topology().unwrap(), topology);
+
+            let json: String = serde_json::to_string(&topology).unwrap();
+            assert_eq!(serde_json::from_str::<Topology>(&json).unwrap(), topology);
+        }
+
+        let topology: Topology = create_mna_container().analyze_topology().unwrap();
+        assert_eq!(topology.stats.branches, 5);
+        assert_eq!(topology.stats.independent_loops, 2);
+    }
+
+    #[test]
+    fn test_edit_session() {
+        let mut session: EditSession = EditSession::new(Container::new());
+        let (id, delta) = session.add_element_between_nodes(VoltageSrc, 10., 1, 0);
+        assert_eq!(id, 1);
+        assert_eq!(
+            delta,
+            TopologyDelta {
+                terminals: BTreeMap::from([(0, vec![0]), (1, vec![3, 0])]),
+                rebuilt: false,
+            }
+        );
+        let (_, delta) = session.add_element_between_nodes(Resistor, 1., 1, 2);
+        assert_eq!(delta.terminals, BTreeMap::from([(2, vec![3, 6])]));
+        let (_, delta) = session.add_element_between_nodes(Resistor, 1., 2, 0);
+        assert_eq!(delta.terminals, BTreeMap::from([(3, vec![6, 0])]));
+
+        // R3 keeps its node but the node is numbered after its own terminal now
+        let delta: TopologyDelta = session.remove_element(2).unwrap();
+        assert_eq!(
+            delta,
+            TopologyDelta {
+                terminals: BTreeMap::from([(2, vec![]), (3, vec![7, 0])]),
+                rebuilt: false,
+            }
+        );
+        assert!(session.remove_element(2).is_err());
+        assert!(session.remove_element(0).is_err());
+        assert!(session.remove_element(9).is_err());
+
+        // R2 is the only link between R1 and R3
+        let mut c: Container = Container::new();
+        c.add_element_no_id(Element::new(Ground, 0., vec![1, 2, 3], vec![]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![2], vec![0]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![1, 3], vec![0]));
+        c.add_element_no_id(Element::new(Resistor, 1., vec![2], vec![0]));
+        let mut session: EditSession = EditSession::new(c);
+        assert_eq!(session.terminals()[&3], vec![3, 0]);
+        let delta: TopologyDelta = session.remove_element(2).unwrap();
+        assert!(delta.rebuilt);
+        assert_eq!(
+            delta.terminals,
+            BTreeMap::from([(2, vec![]), (3, vec![7, 0])])
+        );
+    }
+
+    /// Linear congruential generator, the edit sequences only need to be repeatable
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 33) as usize % n
+        }
+    }
+
+    /// Add a resistor linked to random terminals, the nodes do not follow any labels
+    fn add_linked(session: &mut EditSession, rng: &mut Lcg) -> TopologyDelta {
+        session.topology.sync(&session.container);
+        let candidates: Vec<usize> = session
+            .container
+            .get_elements()
+            .iter()
+            .map(|x| x.borrow())
+            .filter(|x| x.class == Ground || !x.is_detached())
+            .map(|x| x.id)
+            .collect();
+        let id: usize = session.container.get_elements().len();
+
+        let mut sides: [Vec<(usize, bool)>; 2] = [vec![], vec![]];
+        for side in sides.iter_mut() {
+            for _ in 0..rng.below(3) {
+                let other: usize = candidates[rng.below(candidates.len())];
+                let positive: bool = other == 0 || rng.below(2) == 0;
+                if !side.iter().any(|(x, _)| *x == other) {
+                    side.push((other, positive));
+                }
+            }
+        }
+        let ids =
+            |side: &Vec<(usize, bool)>| -> Vec<usize> { side.iter().map(|(x, _)| *x).collect() };
+        let element: Element = Element::new(Resistor, 1., ids(&sides[0]), ids(&sides[1]));
+        session.container.add_element_no_id(element);
+        for (other, positive) in sides.iter().flatten() {
+            let mut other = session.container.get_elements()[*other].borrow_mut();
+            match positive {
+                true => other.positive.push(id),
+                false => other.negative.push(id),
+            }
+        }
+        session.topology.elements_added(&session.container, &[id])
+    }
+
+    #[test]
+    fn test_incremental_topology() {
+        for seed in 0..8 {
+            let mut rng: Lcg = Lcg(seed);
+            let mut c: Container = Container::new();
+            c.add_element_no_id(Element::new(Ground, 0., vec![], vec![]));
+            let mut session: EditSession = EditSession::new(c);
+            let mut terminals: BTreeMap<usize, Vec<usize>> = session.terminals();
+            let mut splits: usize = 0;
+
+            for _ in 0..200 {
+                let live: Vec<usize> = session
+                    .container
+                    .get_elements()
+                    .iter()
+                    .map(|x| x.borrow())
+                    .filter(|x| x.class != Ground && !x.is_detached())
+                    .map(|x| x.id)
+                    .collect();
+                let delta: TopologyDelta = match rng.below(3) {
+                    0 if !live.is_empty() => {
+                        let id: usize = live[rng.below(live.len())];
+                        session.remove_element(id).unwrap()
+                    }
+                    1 => {
+                        let a: usize = rng.below(4);
+                        let b: usize = (a + 1 + rng.below(3)) % 4;
+                        session.add_element_between_nodes(Resistor, 1., a, b).1
+                    }
+                    _ => add_linked(&mut session, &mut rng),
+                };
+                splits += usize::from(delta.rebuilt);
+
+                // The incremental groups are current and the delta lists every change
+                assert!(!session.topology.sync(&session.container));
+                terminals.extend(delta.terminals);
+                assert_eq!(session.terminals(), terminals);
+                let rebuilt: BTreeMap<usize, Vec<usize>> =
+                    IncrementalTopology::new(&session.container).terminals(&session.container);
+                assert_eq!(terminals, rebuilt);
+            }
+            assert!(splits > 0);
+        }
+    }
+}
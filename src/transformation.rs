@@ -0,0 +1,49 @@
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Automatic rewrites of the circuit
+///
+/// Transformations change what is solved compared to the circuit the user drew. Each
+/// one is recorded on the container so the difference can be audited, and all of them
+/// can be turned off with `SolveOptions::allow_auto_transform`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TransformationKind {
+    /// An element with both terminals on the same node was taken out of the circuit.
+    SelfLoopPruned,
+    /// A zero resistance branch was removed and the nodes at its ends joined.
+    ZeroOhmMerged,
+    /// A zero resistance branch was replaced by a 0 V source.
+    ZeroOhmSenseBranch,
+    /// A zero resistance branch was replaced by a small resistance.
+    ZeroOhmEpsilon,
+    /// An element listed more than once in a node was only kept once.
+    DuplicateMembershipRemoved,
+}
+
+/// Record of one automatic transformation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Transformation {
+    pub kind: TransformationKind,
+    /// Ids of the elements the transformation changed or removed.
+    pub affected: Vec<usize>,
+    /// Ids of the elements added by the transformation, empty when it rewrote in place.
+    pub created: Vec<usize>,
+    pub description: String,
+}
+
+impl Transformation {
+    pub fn new(kind: TransformationKind, affected: Vec<usize>, description: String) -> Self {
+        Transformation {
+            kind,
+            affected,
+            created: vec![],
+            description,
+        }
+    }
+}
+
+impl Display for Transformation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Transformation ({:?}): {}", self.kind, self.description)
+    }
+}
@@ -1,6 +1,11 @@
-use crate::component::Component::{CurrentSrc, Ground, Resistor, VoltageSrc};
+use crate::component::Component::{Capacitor, CurrentSrc, Ground, Inductor, Resistor, VoltageSrc};
 use crate::container::Container;
 use crate::elements::Element;
+use crate::interfaces::ContainerSetup;
+use crate::validation::StatusError;
+use crate::validation::StatusError::Known;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 
 pub(crate) trait PrettyPrint {
     fn pretty_string(&self) -> String;
@@ -17,6 +22,20 @@ macro_rules! assert_known_error {
     };
 }
 
+/// Assert that two containers have the same printed summary, both are shown on failure
+#[macro_export]
+macro_rules! assert_circuit_eq {
+    ($left:expr, $right:expr) => {{
+        let (left, right) = ($left.to_string(), $right.to_string());
+        assert!(
+            left == right,
+            "Circuits differ\nleft:\n{}\nright:\n{}",
+            left,
+            right
+        )
+    }};
+}
+
 #[allow(dead_code)]
 pub fn create_basic_container() -> Container {
     let mut container = Container::new();
@@ -77,15 +96,239 @@ pub fn create_mna_container_2() -> Container {
     container
 }
 
+/// Voltage source driving R1 and R3 in series through the inductor L2, C4 across R3.
+///
+/// At DC L2 is a short and C4 an open circuit, node 1 sits at 10 V and nodes 2 and 3 at 6 V.
+#[allow(dead_code)]
+pub fn create_rlc_container() -> Container {
+    let mut container = Container::new();
+    container.add_element_no_id(Element::new(Ground, 0., vec![3, 4, 5], vec![]));
+    container.add_element_no_id(Element::new(Resistor, 2., vec![5], vec![2]));
+    container.add_element_no_id(Element::new(Inductor, 1., vec![1], vec![3, 4]));
+    container.add_element_no_id(Element::new(Resistor, 3., vec![2, 4], vec![0]));
+    container.add_element_no_id(Element::new(Capacitor, 1e-6, vec![2, 3], vec![0]));
+    container.add_element_no_id(Element::new(VoltageSrc, 10., vec![1], vec![0]));
+    container
+}
+
+/// Voltage source driving a chain of five resistors, one unknown per node.
+#[allow(dead_code)]
+pub fn create_ladder_container() -> Container {
+    let mut container = Container::new();
+    container.add_element_no_id(Element::new(Ground, 0., vec![1, 6], vec![]));
+    container.add_element_no_id(Element::new(VoltageSrc, 10., vec![2], vec![0]));
+    container.add_element_no_id(Element::new(Resistor, 1., vec![1], vec![3]));
+    container.add_element_no_id(Element::new(Resistor, 2., vec![2], vec![4]));
+    container.add_element_no_id(Element::new(Resistor, 3., vec![3], vec![5]));
+    container.add_element_no_id(Element::new(Resistor, 4., vec![4], vec![6]));
+    container.add_element_no_id(Element::new(Resistor, 5., vec![5], vec![0]));
+    container
+}
+
+/// Resistor ladder of `n_sections` sections driven by a voltage source
+///
+/// Section k (from 1) has a series resistor with id 2k from node k to node k + 1 and a
+/// shunt resistor with id 2k + 1 from node k + 1 to ground. The source has id 1 and holds
+/// node 1 at `vsrc`, node ids follow the sections.
+///
+/// The input resistance follows `R(1) = 2r` and `R(k) = r + r ‖ R(k - 1)`, the source
+/// delivers `vsrc / R(n_sections)`. It approaches `r (1 + √5) / 2` as the ladder grows.
+pub fn ladder(n_sections: usize, r: f64, vsrc: f64) -> Container {
+    let mut container = Container::new();
+    container.add_element_between_nodes(VoltageSrc, vsrc, 0, 1);
+    for k in 1..=n_sections {
+        container.add_element_between_nodes(Resistor, r, k, k + 1);
+        container.add_element_between_nodes(Resistor, r, k + 1, 0);
+    }
+    container
+}
+
+/// Wheatstone bridge with a galvanometer resistance across the middle
+///
+/// The source (id 1) holds node 1 at `vsrc`. `r1` (id 2) and `r2` (id 3) divide it down
+/// to node 2, `r3` (id 4) and `r4` (id 5) to node 3, and `r_gal` (id 6) runs from node 2
+/// to node 3.
+///
+/// From the Thevenin equivalent seen by the galvanometer its current is
+/// `vsrc (r2 / (r1 + r2) - r4 / (r3 + r4)) / (r1 ‖ r2 + r3 ‖ r4 + r_gal)`, which is zero
+/// when the bridge is balanced, `r1 r4 = r2 r3`.
+pub fn bridge(r1: f64, r2: f64, r3: f64, r4: f64, r_gal: f64, vsrc: f64) -> Container {
+    let mut container = Container::new();
+    container.add_element_between_nodes(VoltageSrc, vsrc, 0, 1);
+    container.add_element_between_nodes(Resistor, r1, 1, 2);
+    container.add_element_between_nodes(Resistor, r2, 2, 0);
+    container.add_element_between_nodes(Resistor, r3, 1, 3);
+    container.add_element_between_nodes(Resistor, r4, 3, 0);
+    container.add_element_between_nodes(Resistor, r_gal, 2, 3);
+    container
+}
+
+/// Current source feeding `n_branches` equal resistors from node 1 to ground
+///
+/// The source has id 1 and pushes `isrc` into node 1, the branches have ids 2 to
+/// `n_branches + 1`. Node 1 sits at `isrc r / n_branches` with `isrc / n_branches` in
+/// every branch.
+pub fn star(n_branches: usize, r: f64, isrc: f64) -> Container {
+    let mut container = Container::new();
+    container.add_element_between_nodes(CurrentSrc, isrc, 1, 0);
+    for _ in 0..n_branches {
+        container.add_element_between_nodes(Resistor, r, 1, 0);
+    }
+    container
+}
+
+/// RC chain of `n` sections driven by a voltage source
+///
+/// Laid out like `ladder` with a capacitor named C in place of every shunt resistor, so
+/// section k has the resistor 2k and the capacitor 2k + 1 at node k + 1.
+///
+/// At DC the capacitors are open and every node sits at `vsrc`. After a step the Elmore
+/// delay of the last node is `r c n (n + 1) / 2`, which for `n = 1` is the time constant.
+pub fn rc_chain(n: usize, r: f64, c: f64, vsrc: f64) -> Container {
+    let mut container = Container::new();
+    container.add_element_between_nodes(VoltageSrc, vsrc, 0, 1);
+    for k in 1..=n {
+        container.add_element_between_nodes(Resistor, r, k, k + 1);
+        let id: usize = container.add_element_between_nodes(Capacitor, c, k + 1, 0);
+        container
+            .get_element_by_id(id)
+            .borrow_mut()
+            .set_name("C".to_string());
+    }
+    container
+}
+
+/// Generators accepted by `generate_container` with the names of their parameters
+pub const GENERATORS: [(&str, &[&str]); 4] = [
+    ("ladder", &["n_sections", "r", "vsrc"]),
+    ("bridge", &["r1", "r2", "r3", "r4", "r_gal", "vsrc"]),
+    ("star", &["n_branches", "r", "isrc"]),
+    ("rc_chain", &["n", "r", "c", "vsrc"]),
+];
+
+/// Build a container with one of the generators by name
+///
+/// Parameters are passed in the order of `GENERATORS`, counts must be whole numbers.
+pub fn generate_container(name: &str, params: &[f64]) -> Result<Container, StatusError> {
+    let expected: usize = GENERATORS
+        .iter()
+        .find(|(x, _)| *x == name)
+        .map(|(_, names)| names.len())
+        .ok_or_else(|| Known(format!("Generator {} does not exist", name)))?;
+    if params.len() != expected {
+        return Err(Known(format!(
+            "Generator {} takes {} parameters, got {}",
+            name,
+            expected,
+            params.len()
+        )));
+    }
+    let count = |x: f64| -> Result<usize, StatusError> {
+        match x >= 0.0 && x.fract() == 0.0 {
+            true => Ok(x as usize),
+            false => Err(Known(format!(
+                "Generator {} needs a whole count, got {}",
+                name, x
+            ))),
+        }
+    };
+
+    Ok(match name {
+        "ladder" => ladder(count(params[0])?, params[1], params[2]),
+        "bridge" => bridge(
+            params[0], params[1], params[2], params[3], params[4], params[5],
+        ),
+        "star" => star(count(params[0])?, params[1], params[2]),
+        _ => rc_chain(count(params[0])?, params[1], params[2], params[3]),
+    })
+}
+
+/// Textbook circuit from `tests/problems` with its expected solution
+#[derive(Deserialize)]
+pub struct Problem {
+    pub name: String,
+    pub description: String,
+    pub container: ContainerSetup,
+    /// Expected voltage of every node but ground, keyed by node id.
+    pub node_voltages: Vec<(usize, f64)>,
+    /// Expected current through each resistor from its positive to its negative terminal.
+    pub currents: Vec<(usize, f64)>,
+    /// Largest accepted difference for any voltage or current.
+    pub tolerance: f64,
+    /// Solvers the problem cannot be solved with yet, with the reason.
+    #[serde(default)]
+    pub skip: BTreeMap<String, String>,
+}
+
+impl Problem {
+    /// A new container holding the circuit, nodes are not created yet.
+    pub fn create_container(&self) -> Container {
+        Container::from(self.container.clone())
+    }
+}
+
+/// Pairs every problem name with its JSON, the files are embedded so no file system is needed
+macro_rules! embed_problems {
+    ($($name:literal),* $(,)?) => {
+        [$(($name, include_str!(concat!("../tests/problems/", $name, ".json")))),*]
+    };
+}
+
+/// Problems of the textbook library, sorted by name
+const PROBLEMS: [(&str, &str); 20] = embed_problems![
+    "bridge_current_source",
+    "bridged_t",
+    "current_divider",
+    "delta_load",
+    "dependent_source_amplifier",
+    "ladder_network",
+    "norton_load",
+    "pi_network",
+    "r2r_ladder",
+    "series_parallel",
+    "stacked_sources",
+    "supermesh",
+    "supernode_current_source",
+    "supernode_two_sources",
+    "superposition",
+    "t_network",
+    "two_voltage_sources",
+    "voltage_divider",
+    "wheatstone_bridge_balanced",
+    "wheatstone_bridge_unbalanced",
+];
+
+/// Names accepted by `load_problem`, sorted.
+pub fn problem_names() -> Vec<&'static str> {
+    PROBLEMS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Load a problem of the textbook library by name
+pub fn load_problem(name: &str) -> Result<Problem, StatusError> {
+    let json: &str = PROBLEMS
+        .iter()
+        .find(|(x, _)| *x == name)
+        .map(|(_, json)| *json)
+        .ok_or_else(|| Known(format!("Problem {} does not exist", name)))?;
+    serde_json::from_str(json)
+        .map_err(|error| Known(format!("Problem {} is invalid: {}", name, error)))
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::assert_known_error;
     use crate::container::Container;
     use crate::elements::Element;
+    use crate::solvers::node_matrix_solver::NodeMatrixSolver;
+    use crate::solvers::solver::{NodeSolver, Solver};
     use crate::util::*;
     use crate::validation::Status::Valid;
+    use crate::validation::StatusError::Known;
     use crate::validation::Validation;
     use assert_json_diff::assert_json_include;
     use serde_json::json;
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     #[test]
     fn test_create_containers() {
@@ -119,7 +362,7 @@ mod tests {
             "name": "R1",
             "id": 1,
             "value": 1.0,
-            "class": "Resistor",
+            "class": "resistor",
             "positive": [2],
             "negative": [3]
         });
@@ -132,11 +375,120 @@ mod tests {
             class: Resistor,
             positive: vec![2],
             negative: vec![3],
+            tags: vec![],
+            current_limit: None,
+            voltage_limit: None,
+            limiting: false,
+            control: None,
         };
         assert_eq!(element.name, "R1");
         assert_json_include!(actual: element, expected: json);
     }
 
+    #[test]
+    fn test_load_problem() {
+        let names: Vec<&str> = problem_names();
+        assert_eq!(names.len(), 20);
+        for name in names {
+            let problem: Problem = load_problem(name).unwrap();
+            assert_eq!(problem.name, name);
+            assert!(!problem.node_voltages.is_empty());
+            assert_eq!(problem.create_container().validate(), Ok(Valid));
+        }
+        assert_known_error!(load_problem("missing"), "Problem missing does not exist");
+    }
+
+    /// Validate and solve a generated container with the node matrix solver
+    fn solve_generated(container: Container) -> NodeMatrixSolver {
+        assert_eq!(container.validate(), Ok(Valid));
+        let mut solver: NodeMatrixSolver = Solver::new(Rc::new(RefCell::new(container)));
+        solver.solve().unwrap();
+        solver
+    }
+
+    #[test]
+    fn test_ladder() {
+        let mut resistance: f64 = 2.0;
+        for n in 1..=6 {
+            if n > 1 {
+                resistance = 1.0 + resistance / (1.0 + resistance);
+            }
+            let solver = solve_generated(ladder(n, 1.0, 10.0));
+            assert!((solver.node_voltage(1).unwrap() - 10.0).abs() < 1e-9);
+            // The first series resistor carries the whole input current
+            let current: f64 = solver.element_voltage(2).unwrap();
+            assert!((current - 10.0 / resistance).abs() < 1e-9, "{} sections", n);
+        }
+        assert!((resistance - (1.0 + 5f64.sqrt()) / 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_bridge() {
+        let galvanometer = |r1: f64, r2: f64, r3: f64, r4: f64, r_gal: f64, vsrc: f64| {
+            let thevenin: f64 = vsrc * (r2 / (r1 + r2) - r4 / (r3 + r4));
+            let resistance: f64 = r1 * r2 / (r1 + r2) + r3 * r4 / (r3 + r4) + r_gal;
+            let solver = solve_generated(bridge(r1, r2, r3, r4, r_gal, vsrc));
+            let current: f64 = solver.element_voltage(6).unwrap() / r_gal;
+            assert!((current - thevenin / resistance).abs() < 1e-9);
+            current
+        };
+        assert!(galvanometer(1., 2., 3., 6., 5., 10.).abs() < 1e-9);
+        assert!((galvanometer(1., 2., 3., 4., 5., 10.) - 4. / 31.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_star() {
+        for n in 1..=5 {
+            let solver = solve_generated(star(n, 8.0, 2.0));
+            assert!((solver.node_voltage(1).unwrap() - 16.0 / n as f64).abs() < 1e-9);
+            for id in 2..n + 2 {
+                let current: f64 = solver.element_voltage(id).unwrap() / 8.0;
+                assert!((current - 2.0 / n as f64).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rc_chain() {
+        let container: Container = rc_chain(3, 1e3, 1e-6, 5.0);
+        assert_eq!(container.get_elements_by_type(Capacitor).len(), 3);
+        assert_eq!(
+            container.get_elements()[3].borrow().pretty_string(),
+            "C3: 0.000001 F"
+        );
+        let solver = solve_generated(container);
+        for node in 1..=4 {
+            assert!((solver.node_voltage(node).unwrap() - 5.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_generate_container() {
+        for (name, params) in GENERATORS {
+            let values: Vec<f64> = params.iter().map(|_| 2.0).collect();
+            let container: Container = generate_container(name, &values).unwrap();
+            assert_eq!(container.validate(), Ok(Valid));
+        }
+        assert_eq!(
+            generate_container("ladder", &[3., 1., 10.])
+                .unwrap()
+                .to_string(),
+            ladder(3, 1., 10.).to_string()
+        );
+        assert_known_error!(
+            generate_container("missing", &[]),
+            "Generator missing does not exist"
+        );
+        assert_known_error!(
+            generate_container("star", &[1., 2.]),
+            "Generator star takes 3 parameters, got 2"
+        );
+        assert_known_error!(
+            generate_container("ladder", &[1.5, 1., 10.]),
+            "Generator ladder needs a whole count, got 1.5"
+        );
+    }
+
     // #[test]
     // fn temporary_serialization() {
     //     for mut container in vec![
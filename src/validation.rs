@@ -1,3 +1,5 @@
+use crate::component::Component;
+use crate::solvers::solver::SolverType;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::fmt::{Debug, Display, Formatter};
@@ -24,10 +26,62 @@ pub enum StatusError {
     Unknown,
     Known(String),
     Multiple(Vec<StatusError>),
+    /// A symbolic expression grew past the given number of terms.
+    SymbolicLimit(usize),
+    /// The container has no elements at all.
+    EmptyCircuit,
+    /// The container has elements but none of them is a source.
+    NoSourcePresent,
+    /// The container changed after the solver was created.
+    StaleSolver,
+    /// The solver cannot handle some components, `alternatives` lists the solvers that can.
+    Unsupported {
+        solver: SolverType,
+        components: Vec<Component>,
+        alternatives: Vec<SolverType>,
+    },
+    /// Strict mode is on and the solve produced the listed warnings.
+    StrictModeViolation(Vec<Warning>),
 }
 
 pub type ValidationResult = Result<Status, StatusError>;
 
+/// Possible Warnings
+///
+/// Warnings describe issues that were corrected automatically and do not stop a solve.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum WarningKind {
+    DuplicateMembership,
+    /// A component is solved with a simplified model.
+    Approximated,
+    /// The system may be too poorly conditioned for an accurate numeric solve.
+    IllConditioned,
+    /// A source runs at its current or voltage limit instead of its nominal value.
+    SourceLimited,
+}
+
+/// How serious an issue reported by `Container::validate_all` is
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Severity {
+    /// The circuit can be solved but may not be what was intended.
+    Warning,
+    /// The circuit cannot be solved.
+    Error,
+}
+
+/// A non fatal issue found while building or solving a circuit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: String) -> Warning {
+        Warning { kind, message }
+    }
+}
+
 pub trait Validation {
     fn validate(&self) -> ValidationResult;
     fn clean(&mut self) -> &Self {
@@ -58,10 +112,48 @@ impl Display for StatusError {
             StatusError::Multiple(error_list) => {
                 write!(f, "Multiple Issues: {:?}", error_list)
             }
+            StatusError::SymbolicLimit(limit) => write!(
+                f,
+                "Symbolic expression exceeded {} terms, switch to numeric mode",
+                limit
+            ),
+            StatusError::EmptyCircuit => write!(f, "Circuit is empty, add elements to solve it"),
+            StatusError::NoSourcePresent => write!(
+                f,
+                "Circuit has no sources, add a voltage or current source to solve it"
+            ),
+            StatusError::StaleSolver => write!(
+                f,
+                "Circuit changed after the solver was created, create a new solver to solve it"
+            ),
+            StatusError::Unsupported {
+                solver,
+                components,
+                alternatives,
+            } => {
+                write!(f, "{:?} solver does not support {:?}", solver, components)?;
+                match alternatives.len() {
+                    0 => write!(f, ", no solver supports them yet"),
+                    _ => write!(f, ", switch to {:?}", alternatives),
+                }
+            }
+            StatusError::StrictModeViolation(warnings) => {
+                write!(f, "Strict mode rejected {} warning(s)", warnings.len())?;
+                for warning in warnings {
+                    write!(f, "\n{}", warning)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Warning ({:?}): {}", self.kind, self.message)
+    }
+}
+
 impl From<String> for StatusError {
     fn from(str: String) -> Self {
         StatusError::Known(str)
@@ -84,6 +176,7 @@ impl From<StatusError> for String {
                 .map(|x| format!("\"{}\"", x))
                 .collect::<Vec<String>>()
                 .join(", "),
+            other => format!("\"{}\"", other),
         };
 
         format!("{{\"errors\": [{contents}]}}")
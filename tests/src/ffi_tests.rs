@@ -0,0 +1,98 @@
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use circuit_solver_algorithms::elements::Element;
+use circuit_solver_algorithms::ffi::*;
+use circuit_solver_algorithms::interfaces::ContainerSetup;
+use circuit_solver_algorithms::util::create_mna_container;
+
+/// Call `css_solve_json` the way a C caller would, returning the code and the output.
+fn call_solve(input: Option<&str>) -> (i32, Option<String>) {
+    let input: Option<CString> = input.map(|x| CString::new(x).unwrap());
+    let input_ptr: *const c_char = input.as_ref().map_or(ptr::null(), |x| x.as_ptr());
+    let mut out: *mut c_char = ptr::null_mut();
+    let code: i32 = unsafe { css_solve_json(input_ptr, &mut out) };
+    let output: Option<String> = match out.is_null() {
+        true => None,
+        false => Some(unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_string()),
+    };
+    unsafe { css_free(out) };
+    (code, output)
+}
+
+#[test]
+fn test_ffi_solve() {
+    let elements: Vec<Element> = create_mna_container()
+        .get_elements()
+        .iter()
+        .map(|x| x.borrow().clone())
+        .collect();
+    let setup = ContainerSetup {
+        elements,
+        ports: vec![],
+    };
+    let (code, output) = call_solve(Some(&serde_json::to_string(&setup).unwrap()));
+    assert_eq!(code, CSS_OK);
+    let steps: serde_json::Value = serde_json::from_str(&output.unwrap()).unwrap();
+    assert!(steps.as_array().unwrap().len() > 2);
+}
+
+#[test]
+fn test_ffi_errors() {
+    let (code, output) = call_solve(Some(r#"{"elements": []}"#));
+    assert_eq!(code, CSS_EMPTY_CIRCUIT);
+    assert_eq!(
+        output.unwrap(),
+        "{\"errors\": [\"Circuit is empty, add elements to solve it\"]}"
+    );
+
+    let (code, output) = call_solve(Some("not a circuit"));
+    assert_eq!(code, CSS_INVALID_INPUT);
+    assert!(!output.unwrap().is_empty());
+
+    let (code, _) = call_solve(None);
+    assert_eq!(code, CSS_INVALID_INPUT);
+
+    // Null out pointers and null frees are tolerated
+    let input = CString::new(r#"{"elements": []}"#).unwrap();
+    assert_eq!(
+        unsafe { css_solve_json(input.as_ptr(), ptr::null_mut()) },
+        CSS_EMPTY_CIRCUIT
+    );
+    unsafe { css_free(ptr::null_mut()) };
+}
+
+#[test]
+fn test_ffi_version() {
+    let version: &str = unsafe { CStr::from_ptr(css_version()) }.to_str().unwrap();
+    assert_eq!(version.split('.').count(), 3);
+}
+
+#[test]
+fn test_ffi_header() {
+    let header: &str = include_str!("../../include/circuit_solver.h");
+    for declaration in [
+        "int32_t css_solve_json(const char *input, char **out);",
+        "void css_free(char *ptr);",
+        "const char *css_version(void);",
+    ] {
+        assert!(header.contains(declaration), "{}", declaration);
+    }
+    for (name, code) in [
+        ("CSS_OK", CSS_OK),
+        ("CSS_INVALID_INPUT", CSS_INVALID_INPUT),
+        ("CSS_UNKNOWN", CSS_UNKNOWN),
+        ("CSS_KNOWN", CSS_KNOWN),
+        ("CSS_MULTIPLE", CSS_MULTIPLE),
+        ("CSS_SYMBOLIC_LIMIT", CSS_SYMBOLIC_LIMIT),
+        ("CSS_EMPTY_CIRCUIT", CSS_EMPTY_CIRCUIT),
+        ("CSS_NO_SOURCE", CSS_NO_SOURCE),
+        ("CSS_STALE_SOLVER", CSS_STALE_SOLVER),
+        ("CSS_UNSUPPORTED", CSS_UNSUPPORTED),
+        ("CSS_STRICT_MODE", CSS_STRICT_MODE),
+        ("CSS_PANIC", CSS_PANIC),
+    ] {
+        let define: String = format!("#define {} {}\n", name, code);
+        assert!(header.contains(&define), "{}", define);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod wasm_tests;
-pub mod compare_test_case;
\ No newline at end of file
+pub mod compare_test_case;
+pub mod ffi_tests;
+pub mod problem_tests;
@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use circuit_solver_algorithms::container::Container;
+use circuit_solver_algorithms::interfaces::{
+    solve_generated_container, solve_test_problem, test_generator_names, test_problem_names,
+};
+use circuit_solver_algorithms::solvers::mesh_matrix_solver::MeshMatrixSolver;
+use circuit_solver_algorithms::solvers::mesh_step_solver::MeshStepSolver;
+use circuit_solver_algorithms::solvers::node_matrix_solver::NodeMatrixSolver;
+use circuit_solver_algorithms::solvers::node_step_solver::NodeStepSolver;
+use circuit_solver_algorithms::solvers::solver::{NodeSolver, Solver, SolverType};
+use circuit_solver_algorithms::util::{load_problem, problem_names, Problem};
+use circuit_solver_algorithms::validation::StatusError::Known;
+use operations::prelude::*;
+
+/// Create the nodes and solve the problem the same way `solve_nodal` does.
+fn solve(problem: &Problem, solver: SolverType) -> Result<Box<dyn NodeSolver>, String> {
+    let mut container: Container = problem.create_container();
+    container.create_nodes()?;
+    container.create_super_nodes()?;
+    let container: Rc<RefCell<Container>> = Rc::new(RefCell::new(container));
+
+    match solver {
+        SolverType::NodeMatrix => {
+            let mut solver: NodeMatrixSolver = Solver::new(container);
+            solver.solve()?;
+            Ok(Box::new(solver))
+        }
+        SolverType::NodeStep => {
+            let mut solver: NodeStepSolver = Solver::new(container);
+            solver.solve()?;
+            Ok(Box::new(solver))
+        }
+        SolverType::MeshMatrix => {
+            let mut solver: MeshMatrixSolver = Solver::new(container);
+            solver.solve()?;
+            Ok(Box::new(solver))
+        }
+        SolverType::MeshStep => {
+            let mut solver: MeshStepSolver = Solver::new(container);
+            solver.solve()?;
+            Ok(Box::new(solver))
+        }
+    }
+}
+
+/// Every voltage and current that is off by more than the tolerance of the problem.
+fn mismatches(problem: &Problem, solver: &dyn NodeSolver) -> Vec<String> {
+    let close = |actual: f64, expected: f64| (actual - expected).abs() <= problem.tolerance;
+    let mut mismatches: Vec<String> = Vec::new();
+
+    for (id, expected) in problem.node_voltages.iter() {
+        match solver.node_voltage(*id) {
+            Ok(actual) if close(actual, *expected) => {}
+            actual => mismatches.push(format!("node {}: {:?}, expected {}", id, actual, expected)),
+        }
+    }
+
+    let container = solver.container();
+    for (id, expected) in problem.currents.iter() {
+        let resistance: f64 = container.borrow().get_elements()[*id].borrow().value();
+        match solver.element_voltage(*id).map(|x| x / resistance) {
+            Ok(actual) if close(actual, *expected) => {}
+            actual => mismatches.push(format!(
+                "current {}: {:?}, expected {}",
+                id, actual, expected
+            )),
+        }
+    }
+    mismatches
+}
+
+#[test]
+fn test_problems() {
+    let mut failures: Vec<String> = Vec::new();
+
+    for name in problem_names() {
+        let problem: Problem = load_problem(name).unwrap();
+        for solver in SolverType::ALL {
+            if let Some(reason) = problem.skip.get(&format!("{:?}", solver)) {
+                println!("Skipped {} with {:?}: {}", name, solver, reason);
+                continue;
+            }
+            match solve(&problem, solver) {
+                Ok(solved) => failures.extend(
+                    mismatches(&problem, &*solved)
+                        .into_iter()
+                        .map(|x| format!("{} with {:?}: {}", name, solver, x)),
+                ),
+                Err(error) => failures.push(format!("{} with {:?}: {}", name, solver, error)),
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_problem_skips() {
+    let solvers: Vec<String> = SolverType::ALL.iter().map(|x| format!("{:?}", x)).collect();
+    for name in problem_names() {
+        let problem: Problem = load_problem(name).unwrap();
+        for (solver, reason) in problem.skip.iter() {
+            assert!(
+                solvers.contains(solver),
+                "{}: unknown solver {}",
+                name,
+                solver
+            );
+            assert!(!reason.is_empty(), "{}: no reason to skip {}", name, solver);
+        }
+    }
+}
+
+#[test]
+fn test_solve_test_problem() {
+    let names: Vec<String> = serde_json::from_str(&test_problem_names()).unwrap();
+    assert_eq!(names, problem_names());
+
+    let steps: serde_json::Value =
+        serde_json::from_str(&solve_test_problem("wheatstone_bridge_balanced").unwrap()).unwrap();
+    assert!(steps.as_array().unwrap().len() > 2);
+    assert_eq!(
+        solve_test_problem("missing").unwrap_err(),
+        String::from(Known("Problem missing does not exist".to_string()))
+    );
+}
+
+#[test]
+fn test_solve_generated_container() {
+    let generators: serde_json::Value = serde_json::from_str(&test_generator_names()).unwrap();
+    for generator in generators.as_array().unwrap() {
+        let name: &str = generator[0].as_str().unwrap();
+        let params: Vec<f64> = vec![3.0; generator[1].as_array().unwrap().len()];
+        let steps: serde_json::Value =
+            serde_json::from_str(&solve_generated_container(name, params).unwrap()).unwrap();
+        assert!(!steps.as_array().unwrap().is_empty(), "{}", name);
+    }
+    assert_eq!(
+        solve_generated_container("missing", vec![]).unwrap_err(),
+        String::from(Known("Generator missing does not exist".to_string()))
+    );
+}
@@ -7,12 +7,14 @@ use wasm_bindgen_test::wasm_bindgen_test;
 use circuit_solver_algorithms::component::Component::{Ground, Resistor, VoltageSrc};
 use circuit_solver_algorithms::container::Container;
 use circuit_solver_algorithms::elements::Element;
-use circuit_solver_algorithms::interfaces::{get_tools, load_wasm_container, ContainerSetup, solve};
+use circuit_solver_algorithms::interfaces::{circuit_info, close_session, diagnose, open_edit_session, session_add_element, session_remove_element, explain, get_capabilities, get_connectivity, get_tools, g_matrix_contributions, port_names, load_wasm_container, render_step, ContainerSetup, session_explain, solve, solve_lazy, solve_report};
+use circuit_solver_algorithms::interfaces::solve_nodal;
 use circuit_solver_algorithms::solvers::node_step_solver::NodeStepSolver;
+use circuit_solver_algorithms::solvers::progress::Phase;
 use circuit_solver_algorithms::solvers::solver::{Solver, Step};
-use circuit_solver_algorithms::util::create_mna_container;
+use circuit_solver_algorithms::util::{create_basic_supernode_container, create_mna_container};
 use circuit_solver_algorithms::validation::Status::Valid;
-use circuit_solver_algorithms::validation::StatusError::{Known, Multiple};
+use circuit_solver_algorithms::validation::StatusError::{EmptyCircuit, Known, Multiple, NoSourcePresent};
 use circuit_solver_algorithms::validation::{StatusError, Validation};
 
 use crate::compare_test_case::InputCaseSerde;
@@ -83,18 +85,13 @@ fn test_container_wasm() {
 
 #[wasm_bindgen_test]
 fn test_load() {
-    let c = ContainerSetup { elements: vec![] };
+    let c = ContainerSetup { elements: vec![], ports: vec![] };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
-    assert_eq!(
-        load_wasm_container(x),
-        Err(Multiple(vec![
-            Known("No Sources".parse().unwrap()),
-            Known("Multiple Grounds".parse().unwrap()),
-        ]))
-    );
+    assert_eq!(load_wasm_container(x), Err(EmptyCircuit));
 
     let c = ContainerSetup {
         elements: vec![Element::new(Ground, 0., vec![], vec![])],
+        ports: vec![],
     };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
     assert!(load_wasm_container(x).is_err());
@@ -104,12 +101,13 @@ fn test_load() {
             Element::new(Ground, 0., vec![1], vec![]),
             Element::new(Ground, 0., vec![0], vec![]),
         ],
+        ports: vec![],
     };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
     assert_eq!(
         load_wasm_container(x),
         Err(Multiple(vec![
-            Known("No Sources".to_string()),
+            NoSourcePresent,
             Known("Multiple Grounds".to_string()),
         ]))
     );
@@ -121,6 +119,7 @@ fn test_load() {
             Element::new(Resistor, 1.0, vec![1], vec![3]),
             Element::new(Resistor, 1.0, vec![2], vec![1, 0]),
         ],
+        ports: vec![],
     };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
     assert_eq!(
@@ -137,6 +136,7 @@ fn test_error() {
             Element::new(Resistor, 1.0, vec![1], vec![3]),
             Element::new(Resistor, 1.0, vec![2], vec![1, 0]),
         ],
+        ports: vec![],
     };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
     assert_eq!(
@@ -162,6 +162,7 @@ fn test_matrix_invert_error() {
             Element::new(Resistor, 10.0, vec![0, 1], vec![4]),
             Element::new(VoltageSrc, 10.0, vec![3], vec![2]),
         ],
+        ports: vec![],
     };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
     assert_eq!(
@@ -176,6 +177,7 @@ fn test_matrix_invert_error() {
             Element::new(VoltageSrc, 10.0, vec![4], vec![1]),
             Element::new(Resistor, 10.0, vec![2], vec![0, 1]),
         ],
+        ports: vec![],
     };
     let x: JsValue = serde_wasm_bindgen::to_value(&c).unwrap();
     assert_eq!(
@@ -248,4 +250,316 @@ pub fn test_malformed_ids_from_netlist() {
         solve(false, true, json),
         Err("{\"errors\": [\"Known Issue: Element cannot be connected to itself R1: 10 Ω\", \"Known Issue: Element cannot be connected to itself R2: 10 Ω\", \"Known Issue: Element cannot be connected to itself SRC(V)3: 10 V\", \"Known Issue: Multiple Grounds\"]}".to_string())
     );
-}
\ No newline at end of file
+}
+
+#[wasm_bindgen_test]
+fn test_connectivity() {
+    let container: Container = create_basic_supernode_container();
+    let connectivity: serde_json::Value = serde_json::from_str(
+        &get_connectivity(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        connectivity["terminals"],
+        serde_json::json!({
+            "0": [0],
+            "1": [1, 2],
+            "2": [2, 3],
+            "3": [2, 0],
+            "4": [1, 3],
+            "5": [3, 0],
+        })
+    );
+    assert_eq!(connectivity["ground"], serde_json::json!([0]));
+}
+
+#[wasm_bindgen_test]
+fn test_g_matrix_contributions() {
+    let container: Container = create_mna_container();
+    let rows: Vec<Vec<Vec<(usize, f64)>>> = serde_json::from_str(
+        &g_matrix_contributions(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1][1], vec![(2, 0.25), (3, 0.125)]);
+    assert_eq!(rows[1][2], vec![(2, -0.25)]);
+    assert!(rows[0][1].is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_explain() {
+    let container: Container = create_mna_container();
+    let steps: serde_json::Value = serde_json::from_str(
+        &explain(serde_wasm_bindgen::to_value(&container).unwrap(), 3).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(steps.as_array().unwrap().len(), 2);
+    assert_eq!(steps[0]["title"], "Element R3");
+    assert_eq!(
+        explain(serde_wasm_bindgen::to_value(&container).unwrap(), 0).unwrap_err(),
+        String::from(Known("Element 0 is not part of the solution".to_string()))
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_session_explain() {
+    let container: Container = create_mna_container();
+    let lazy: serde_json::Value = serde_json::from_str(
+        &solve_lazy(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    let session_id = lazy["session_id"].as_u64().unwrap() as u32;
+
+    // Same steps as explaining the container directly, for every element asked about
+    for id in [3, 5, 3] {
+        assert_eq!(
+            session_explain(session_id, id).unwrap(),
+            explain(serde_wasm_bindgen::to_value(&container).unwrap(), id).unwrap()
+        );
+    }
+    assert_eq!(
+        session_explain(session_id, 0).unwrap_err(),
+        String::from(Known("Element 0 is not part of the solution".to_string()))
+    );
+
+    assert!(close_session(session_id));
+    assert_eq!(
+        session_explain(session_id, 3).unwrap_err(),
+        String::from(Known(format!("Session {} does not exist", session_id)))
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_circuit_info() {
+    let container: Container = create_mna_container();
+    let info: serde_json::Value = serde_json::from_str(
+        &circuit_info(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        info,
+        serde_json::json!({"nodes": 4, "branches": 5, "loops": 2, "connected": true})
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_diagnose() {
+    // The polarity heuristic flags a source of mna, there is no fix to suggest for it
+    let container: Container = create_mna_container();
+    let diagnostics: serde_json::Value = serde_json::from_str(
+        &diagnose(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    let diagnostics: &Vec<serde_json::Value> = diagnostics.as_array().unwrap();
+    assert!(!diagnostics.is_empty());
+    for diagnostic in diagnostics {
+        assert_eq!(diagnostic["severity"], "Warning");
+        assert_eq!(diagnostic["suggestion"], serde_json::Value::Null);
+        assert_eq!(diagnostic["suggested_action"], serde_json::Value::Null);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_port_names() {
+    let mut container: Container = create_mna_container();
+    container.add_port("input".to_string(), 2, 0).unwrap();
+    container.add_port("output".to_string(), 1, 3).unwrap();
+    let names: serde_json::Value = serde_json::from_str(
+        &port_names(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(names, serde_json::json!(["input", "output"]));
+}
+
+#[wasm_bindgen_test]
+fn test_capabilities() {
+    let capabilities: serde_json::Value = serde_json::from_str(&get_capabilities()).unwrap();
+    let components = capabilities["components"].as_array().unwrap();
+    assert_eq!(components.len(), 10);
+    assert_eq!(components[1]["class"], "resistor");
+    assert_eq!(components[1]["legacy_class"], "Resistor");
+    assert_eq!(components[1]["code"], 1);
+    let solvers = capabilities["solvers"].as_array().unwrap();
+    assert_eq!(solvers.len(), 4);
+    assert_eq!(solvers[0]["solver"], "NodeMatrix");
+    assert!(!solvers[0]["forbidden"].as_array().unwrap().contains(&serde_json::json!("inductor")));
+    assert_eq!(solvers[1]["solver"], "NodeStep");
+    assert!(solvers[1]["forbidden"].as_array().unwrap().contains(&serde_json::json!("inductor")));
+}
+
+#[wasm_bindgen_test]
+fn test_solve_mesh() {
+    let container: Container = create_mna_container();
+    let steps: serde_json::Value = serde_json::from_str(
+        &solve(true, false, serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(steps[0]["title"], "Mesh Matrix Solver");
+    assert_eq!(steps[1]["title"], "Solved Values");
+
+    let steps: serde_json::Value = serde_json::from_str(
+        &solve(false, false, serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(steps[1]["title"], "KVL Equations");
+}
+
+#[wasm_bindgen_test]
+fn test_lazy_steps() {
+    let container: Container = create_mna_container();
+    let eager: serde_json::Value = serde_json::from_str(
+        &solve(false, true, serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+    let lazy: serde_json::Value = serde_json::from_str(
+        &solve_lazy(serde_wasm_bindgen::to_value(&container).unwrap()).unwrap(),
+    )
+    .unwrap();
+
+    let session_id = lazy["session_id"].as_u64().unwrap() as u32;
+    let stubs = lazy["steps"].as_array().unwrap();
+    let steps = eager.as_array().unwrap();
+    assert_eq!(stubs.len(), steps.len());
+
+    // Render out of order, only the requested step is materialized
+    for i in (0..stubs.len()).rev() {
+        assert_eq!(stubs[i]["label"], steps[i]["title"]);
+        let id = stubs[i]["id"].as_u64().unwrap() as usize;
+        let rendered: serde_json::Value =
+            serde_json::from_str(&render_step(session_id, id).unwrap()).unwrap();
+        assert_eq!(rendered["step"], steps[i]);
+        assert!(rendered["elapsed_ms"].as_f64().unwrap() >= 0.0);
+    }
+
+    assert!(close_session(session_id));
+    assert!(!close_session(session_id));
+    assert!(render_step(session_id, 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_edit_session() {
+    let session_id: u32 = open_edit_session();
+    let class = serde_wasm_bindgen::to_value(&Resistor).unwrap();
+    let added: serde_json::Value = serde_json::from_str(
+        &session_add_element(session_id, class.clone(), 2., 1, 0).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(added["id"], 1);
+    assert_eq!(added["delta"]["terminals"]["1"], serde_json::json!([3, 0]));
+
+    let delta: serde_json::Value =
+        serde_json::from_str(&session_remove_element(session_id, 1).unwrap()).unwrap();
+    assert_eq!(
+        delta,
+        serde_json::json!({"terminals": {"1": []}, "rebuilt": false})
+    );
+    assert!(session_remove_element(session_id, 1).is_err());
+
+    assert!(close_session(session_id));
+    assert!(session_add_element(session_id, class, 2., 1, 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_boundary_circuits() {
+    let empty = ContainerSetup { elements: vec![], ports: vec![] };
+    for matrix in [false, true] {
+        assert_eq!(
+            solve(matrix, true, serde_wasm_bindgen::to_value(&empty).unwrap()),
+            Err("{\"errors\": [\"Circuit is empty, add elements to solve it\"]}".to_string())
+        );
+    }
+
+    let no_source = ContainerSetup {
+        elements: vec![
+            Element::new(Ground, 0., vec![1], vec![]),
+            Element::new(Resistor, 1.0, vec![0], vec![0]),
+        ],
+        ports: vec![],
+    };
+    for matrix in [false, true] {
+        assert_eq!(
+            solve(matrix, true, serde_wasm_bindgen::to_value(&no_source).unwrap()),
+            Err("{\"errors\": [\"Circuit has no sources, add a voltage or current source to solve it\"]}".to_string())
+        );
+    }
+
+    // Smallest solvable circuit, a single source across a single resistor
+    let smallest = ContainerSetup {
+        elements: vec![
+            Element::new(Ground, 0., vec![1, 2], vec![]),
+            Element::new(VoltageSrc, 5.0, vec![2], vec![0]),
+            Element::new(Resistor, 10.0, vec![1], vec![0]),
+        ],
+        ports: vec![],
+    };
+    for matrix in [false, true] {
+        let steps = solve(matrix, true, serde_wasm_bindgen::to_value(&smallest).unwrap());
+        assert!(steps.is_ok());
+        assert!(steps.unwrap().len() > 2);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_nodal_progress() {
+    for matrix in [false, true] {
+        let phases: RefCell<Vec<Phase>> = RefCell::new(vec![]);
+        let record = |phase: Phase, fraction: f32| {
+            if fraction == 0.0 {
+                phases.borrow_mut().push(phase);
+            }
+        };
+        assert!(solve_nodal(create_mna_container(), matrix, &record).is_ok());
+
+        // The matrix solver forms its matrices while it is created
+        let expected: Vec<Phase> = if matrix {
+            vec![Phase::Validation, Phase::NodeCreation, Phase::Factorization, Phase::Rendering]
+        } else {
+            vec![
+                Phase::Validation,
+                Phase::NodeCreation,
+                Phase::MatrixFormation,
+                Phase::Factorization,
+                Phase::Rendering,
+            ]
+        };
+        assert_eq!(phases.into_inner(), expected);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_solve_report() {
+    // A triangle of resistors that is not connected to the rest of the circuit
+    let mut elements: Vec<Element> = create_mna_container()
+        .get_elements()
+        .iter()
+        .map(|x| x.borrow().clone())
+        .collect();
+    elements.push(Element::new(Resistor, 1.0, vec![7], vec![8]));
+    elements.push(Element::new(Resistor, 1.0, vec![8], vec![6]));
+    elements.push(Element::new(Resistor, 1.0, vec![6], vec![7]));
+    let floating = ContainerSetup { elements, ports: vec![] };
+
+    let report: serde_json::Value = serde_json::from_str(&solve_report(
+        true,
+        true,
+        serde_wasm_bindgen::to_value(&floating).unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(report["ok"], false);
+    assert!(!report["errors"].as_array().unwrap().is_empty());
+    assert!(report["result"].is_null());
+    assert_eq!(report["partial"]["topology"]["nodes"].as_array().unwrap().len(), 6);
+    assert_eq!(report["partial"]["stats"]["resistor"], 6);
+
+    let container: Container = create_mna_container();
+    let report: serde_json::Value = serde_json::from_str(&solve_report(
+        true,
+        true,
+        serde_wasm_bindgen::to_value(&container).unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(report["ok"], true);
+    assert!(report["errors"].as_array().unwrap().is_empty());
+    assert!(report["transformations"].as_array().unwrap().is_empty());
+    assert!(report["result"].as_array().unwrap().len() > 2);
+}